@@ -0,0 +1,5 @@
+//! `cargo bench` entry point for the simulator's benchmarks, the same ones the `bench` subcommand runs.
+
+fn main() {
+    compressed_cache_attack_sim::bench::run_benchmarks();
+}
@@ -0,0 +1,135 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::config::VictimConfig;
+use crate::victim::{BufferLayout, SecretPlacement, BUFFER_SIZE};
+
+/// A victim's buffer described region by region, so that new scenarios can be written as a layout file rather than as
+/// code. Offsets are buffer offsets; bytes no region covers are writable by the attacker. For example:
+///
+/// ```json
+/// {"name": "tagged record", "regions": [
+///   {"name": "header", "offset": 128, "bytes": 4, "access": {"ReadOnly": {"contents": [1, 0, 0, 0]}}},
+///   {"name": "key", "offset": 132, "bytes": 8, "align": 4, "access": {"Secret": {"placement": "Contiguous"}}}
+/// ]}
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AddressSpace {
+    #[serde(default)]
+    pub name: String,
+    /// How the buffer sits in memory. Contiguous unless given.
+    #[serde(default = "contiguous")]
+    pub buffer: BufferLayout,
+    pub regions: Vec<Region>
+}
+
+fn contiguous() -> BufferLayout {
+    return BufferLayout::Contiguous;
+}
+
+/// A named range of the buffer and who may write it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Region {
+    pub name: String,
+    pub offset: usize,
+    pub bytes: usize,
+    /// The offset must be a multiple of this. 1 unless given.
+    #[serde(default = "unaligned")]
+    pub align: usize,
+    pub access: RegionAccess
+}
+
+fn unaligned() -> usize {
+    return 1;
+}
+
+/// What a region holds, which decides what the attacker may do with it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RegionAccess {
+    /// Attacker-writable data, the same as leaving the bytes out. Useful for naming them and checking their alignment.
+    ReadWrite,
+    /// Public data the victim keeps fixed, one value per byte of the region. The attacker may read it but not write it.
+    ReadOnly {contents: Vec<u8>},
+    /// The secret, placed as given. Every layout has exactly one; its words must be word-aligned and stay within a line.
+    Secret {placement: SecretPlacement}
+}
+
+impl AddressSpace {
+    /// Reads a layout file, and checks that it describes a victim that can be built.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<AddressSpace> {
+        let text = fs::read_to_string(path)?;
+        let space: AddressSpace = serde_json::from_str(&text)?;
+        space.validate().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        return Ok(space);
+    }
+
+    /// Checks that every region fits the buffer, is aligned and overlaps no other, and that there is exactly one
+    /// secret, which fits in its line. Returns the first problem found.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut covered = [None::<&str>; BUFFER_SIZE];
+        for region in &self.regions {
+            if region.bytes == 0 || region.offset + region.bytes > BUFFER_SIZE {
+                return Err(format!("region {} ({} bytes at {}) does not fit in the {}-byte buffer", region.name, region.bytes, region.offset, BUFFER_SIZE));
+            }
+            if region.align == 0 || !region.offset.is_multiple_of(region.align) {
+                return Err(format!("region {} at {} is not aligned to {}", region.name, region.offset, region.align));
+            }
+            for owner in &mut covered[region.offset..region.offset + region.bytes] {
+                if let Some(other) = owner {return Err(format!("regions {} and {} overlap", other, region.name));}
+                *owner = Some(&region.name);
+            }
+            match &region.access {
+                RegionAccess::ReadOnly {contents} if contents.len() != region.bytes => {
+                    return Err(format!("read-only region {} has {} bytes but {} values", region.name, region.bytes, contents.len()));
+                },
+                RegionAccess::Secret {placement} => {
+                    let secret_size = if *placement == SecretPlacement::Interleaved {region.bytes / 2} else {region.bytes};
+                    if placement.span(secret_size) != region.bytes || !secret_size.is_multiple_of(4) {
+                        return Err(format!("secret region {} must hold whole words of secret", region.name));
+                    }
+                    if !region.offset.is_multiple_of(4) || region.offset / 64 != (region.offset + region.bytes - 1) / 64 {
+                        return Err(format!("secret region {} must start at a word and stay within one line", region.name));
+                    }
+                },
+                _ => ()
+            }
+        }
+        let secrets = self.regions.iter().filter(|region| matches!(region.access, RegionAccess::Secret {..})).count();
+        if secrets != 1 {return Err(format!("a layout needs exactly one secret region, found {}", secrets));}
+        return Ok(());
+    }
+
+    fn secret_region(&self) -> (&Region, SecretPlacement) {
+        return self.regions.iter().find_map(|region| match region.access {
+            RegionAccess::Secret {placement} => Some((region, placement)),
+            _ => None
+        }).expect("Validated layouts have a secret region");
+    }
+
+    /// The number of secret bytes the layout holds.
+    pub fn secret_size(&self) -> usize {
+        let (region, placement) = self.secret_region();
+        return if placement == SecretPlacement::Interleaved {region.bytes / 2} else {region.bytes};
+    }
+
+    /// Describes a victim with this layout, taking everything the layout does not cover (its interface, neighbors,
+    /// defenses and noise) from base. The layout replaces base's buffer layout, secret size, offset and placement, and
+    /// its read-only bytes.
+    pub fn victim_config(&self, base: &VictimConfig) -> VictimConfig {
+        let (secret, placement) = self.secret_region();
+        let read_only = self.regions.iter().flat_map(|region| match &region.access {
+            RegionAccess::ReadOnly {contents} => contents.iter().enumerate().map(|(i, &value)| (region.offset + i, value)).collect(),
+            _ => Vec::new()
+        }).collect();
+        return VictimConfig {
+            secret_size: self.secret_size(),
+            layout: self.buffer,
+            placement,
+            secret_offset: Some(secret.offset),
+            public_prefix: Vec::new(),
+            read_only,
+            ..base.clone()
+        };
+    }
+}
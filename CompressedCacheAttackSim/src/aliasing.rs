@@ -0,0 +1,114 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+use crate::victim::{buffer_address, BufferLayout, BUFFER_SIZE};
+
+/// The parts of a cache's geometry that decide which addresses alias each other.
+/// Sets are indexed by superblock, so all blocks of a superblock share a set.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CacheGeometry {
+    pub line_bytes: u64,
+    pub blocks_per_superblock: u64,
+    pub sets: u64
+}
+
+impl Default for CacheGeometry {
+    /// The geometry the simulator models: 64-byte lines, 4-block superblocks, and a single set.
+    fn default() -> CacheGeometry {
+        CacheGeometry {line_bytes: 64, blocks_per_superblock: 4, sets: 1}
+    }
+}
+
+impl CacheGeometry {
+    pub fn line(&self, addr: u64) -> u64 {
+        return addr / self.line_bytes;
+    }
+
+    pub fn superblock(&self, addr: u64) -> u64 {
+        return self.line(addr) / self.blocks_per_superblock;
+    }
+
+    pub fn set(&self, addr: u64) -> u64 {
+        return self.superblock(addr) % self.sets;
+    }
+}
+
+/// Which of the attacker's writable buffer bytes can influence the secret, and how.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AliasingReport {
+    pub geometry: CacheGeometry,
+    pub secret_line: u64,
+    pub secret_superblock: u64,
+    pub secret_set: u64,
+    /// Writable indices inside the secret's own line. These shape the line's compressed size.
+    pub secret_line_bytes: Vec<usize>,
+    /// The other lines of the secret's superblock, with the writable indices each one holds. Lines with no writable
+    /// bytes belong to the victim (or nobody), so the attacker cannot make them compressible.
+    pub companion_lines: Vec<(u64, Vec<usize>)>,
+    /// Lines in the secret's set but in other superblocks, with their writable indices. These can only help evict.
+    pub same_set_lines: Vec<(u64, Vec<usize>)>,
+    /// Writable indices that share nothing with the secret.
+    pub unrelated_bytes: usize
+}
+
+impl AliasingReport {
+    /// Companion lines in which the attacker can write every byte, and so can fill with zeros.
+    pub fn controllable_companions(&self) -> usize {
+        return self.companion_lines.iter().filter(|(_, bytes)| bytes.len() as u64 == self.geometry.line_bytes).count();
+    }
+
+    pub fn print(&self) {
+        println!("Secret line {:#x} in superblock {:#x}, set {}", self.secret_line, self.secret_superblock, self.secret_set);
+        println!("  {} writable bytes in the secret line", self.secret_line_bytes.len());
+        for (line, bytes) in &self.companion_lines {
+            println!("  Companion line {:#x}: {} writable bytes{}", line, bytes.len(), match (bytes.first(), bytes.last()) {
+                (Some(first), Some(last)) => format!(" (indices {}..={})", first, last),
+                _ => String::new()
+            });
+        }
+        println!("  {} of {} companion lines fully controllable", self.controllable_companions(), self.companion_lines.len());
+        println!("  {} other lines in the secret's set, holding {} writable bytes",
+                 self.same_set_lines.len(), self.same_set_lines.iter().map(|(_, bytes)| bytes.len()).sum::<usize>());
+        println!("  {} writable bytes alias nothing useful", self.unrelated_bytes);
+    }
+}
+
+/// Works out where every byte of a victim buffer at base lands relative to its secret (stored at the buffer offsets in
+/// private), skipping read-only bytes, which the attacker cannot write.
+pub fn analyze_aliasing(base: u64, layout: BufferLayout, private: Range<usize>, read_only: &[(usize, u8)], geometry: CacheGeometry) -> AliasingReport {
+    let secret_addr = buffer_address(base, layout, private.start);
+    let secret_line = geometry.line(secret_addr);
+    let secret_superblock = geometry.superblock(secret_addr);
+    let mut report = AliasingReport {
+        geometry,
+        secret_line,
+        secret_superblock,
+        secret_set: geometry.set(secret_addr),
+        secret_line_bytes: Vec::new(),
+        companion_lines: Vec::new(),
+        same_set_lines: Vec::new(),
+        unrelated_bytes: 0
+    };
+    let mut companions: BTreeMap<u64, Vec<usize>> = (0..geometry.blocks_per_superblock)
+        .map(|block| secret_superblock * geometry.blocks_per_superblock + block)
+        .filter(|&line| line != secret_line)
+        .map(|line| (line, Vec::new()))
+        .collect();
+    let mut same_set: BTreeMap<u64, Vec<usize>> = BTreeMap::new();
+    for index in 0..BUFFER_SIZE {
+        if private.contains(&index) || read_only.iter().any(|&(i, _)| i == index) {continue;}
+        let addr = buffer_address(base, layout, index);
+        let line = geometry.line(addr);
+        if line == secret_line {
+            report.secret_line_bytes.push(index);
+        } else if geometry.superblock(addr) == secret_superblock {
+            companions.entry(line).or_default().push(index);
+        } else if geometry.set(addr) == report.secret_set {
+            same_set.entry(line).or_default().push(index);
+        } else {
+            report.unrelated_bytes += 1;
+        }
+    }
+    report.companion_lines = companions.into_iter().collect();
+    report.same_set_lines = same_set.into_iter().collect();
+    return report;
+}
@@ -1,309 +1,2401 @@
+//! The attacks: attack string layouts, the prime-and-probe step, the pipelines of steps that recover a secret word by
+//! word, and the stats they report.
+
 use std::cmp::min;
-use std::collections::HashSet;
-use crate::structures::{AccessSpeed, ASSOCIATIVITY, Cache};
-use crate::victim::VictimProgramYACC;
+use std::collections::{BTreeMap, HashSet};
+use std::ops::Range;
+use std::time::{Duration, Instant};
+use crate::structures::{cpack_bits, cpack_words, AccessSpeed, Cache, Endianness, WayState, LINE_SIZE};
+use crate::victim::{AttackerPort, SecretBytes, SecretDistribution, SecretPlacement, VictimProgramYACC, BUFFER_SIZE};
+use crate::candidates::{log2_candidates, CandidateSet};
+use crate::keyschedule;
+use crate::statistics::CostDistributions;
+use crate::streams::RngStream;
+use crate::predictor::predict_success;
+use rand::SeedableRng;
+use rand::seq::SliceRandom;
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttackStats {
+    pub success: bool,
+    pub secret: Vec<u8>,
+    pub guesses_needed: usize,
+    pub bytes_written_to_victim: usize,
+    pub bytes_read_from_victim: usize,
+    pub attacker_cache_lines_loaded: usize,
+    pub set_evictions: usize,
+    /// Flush accesses that hit, i.e. lines left over from an earlier probe.
+    pub flush_hits: usize,
+    /// Calls made to the victim's API (reads, writes, whole-message stores and guesses), the cost unit for a remote victim.
+    pub victim_interactions: usize,
+    /// The victim calls that wrote to its buffer (byte writes, message stores and store reloads).
+    pub victim_write_calls: usize,
+    pub attempts: usize,
+    /// log2 of the number of secrets still consistent with what the attacker has learned, recorded after every probe.
+    pub candidate_log2: DecaySeries,
+    /// The co-allocation threshold (in bytes) found by differential probing, if the attack probed for one.
+    pub probed_threshold: Option<u64>,
+    /// The number of entries in the target set found by probing, if the attack calibrated the cache (see ThresholdSource::Calibrated).
+    #[serde(default)]
+    pub probed_capacity: Option<usize>,
+    /// Time the attacking thread spent in each phase, indexed by AttackPhase.
+    pub phase_time: [Duration; 4],
+    /// Probe outcomes in each phase, labeled against the true secret, indexed by AttackPhase.
+    pub probe_outcomes: [ProbeOutcomes; 4],
+    /// The secret line's compressed sizes after the probes of each phase, indexed by AttackPhase.
+    pub size_histograms: [SizeHistogram; 4],
+    /// What each phase cost, indexed by AttackPhase. Costs from before the first phase (calibration, migration) are left out.
+    #[serde(default)]
+    pub phase_costs: [PhaseCost; 4],
+    /// Simulated time the attacker spent waiting to stay within its stealth budget.
+    pub idle_ticks: u64,
+    /// Times the attacker had the victim relocate its buffer to get away from interference.
+    pub migrations: usize,
+    /// Times a read-back after a probe found the attack string wiped, i.e. the victim had moved its buffer (see AttackConfig::detect_relocation).
+    #[serde(default)]
+    pub relocations_detected: usize,
+    /// Candidate secrets, most likely first, left for a Judge to score (no-oracle builds only; otherwise guesses are validated directly).
+    pub answers: Vec<Vec<u8>>,
+    /// Probes skipped because the attack string could not be rearranged around the victim's read-only bytes.
+    pub unwritable_probes: usize,
+    /// Chunks allocated while grooming a heap-model victim.
+    pub heap_allocations: usize,
+    /// Dirty lines the cache wrote back to memory during the attack (always 0 under write-through).
+    pub writebacks: u64,
+    /// How the two oracles compared, if the attack read its probes through both.
+    pub oracle_report: OracleReport,
+    /// The seed of the victim attacked (see VictimProgramYACC::seed), to replay it if the attack failed.
+    pub victim_seed: Option<u64>,
+    /// What the last attempt learned about each secret word, in the order the words were found, with the probes spent on each part.
+    pub recovered_words: Vec<PartialWord>,
+    /// What each oracle said on the last probe read through both, until the probe is labeled.
+    last_readings: Option<(bool, bool)>,
+    /// The set's LRU state at every labeled probe, if the attack was configured to record it (see AttackConfig::record_lru).
+    pub probe_trace: Vec<ProbeRecord>,
+    /// What each phase's eliminations turned up, as the attacker saw it, indexed by AttackPhase.
+    pub signals: [EliminationSignals; 4],
+    /// Probes put to a vote whose rounds did not all agree.
+    pub split_votes: usize,
+    /// The chance of success predicted after the leading shorts of the last attempt that found them (see predict_success).
+    pub predicted_success: Option<f64>,
+    /// Attempts given up after the leading shorts because the predicted chance of success was too low (see AttackConfig::abort_below).
+    pub early_aborts: usize,
+    /// Whether the attack stopped because the victim refused a call over its access budget (see VictimProgramYACC::set_access_budget).
+    /// recovered_words then holds the shorts and bytes confirmed before the first refused call.
+    #[serde(default)]
+    pub budget_exhausted: bool,
+    /// The LRU state at the last probe, until the probe is labeled.
+    last_snapshot: Option<LruSnapshot>,
+    /// The stealth window the attacker is currently in, and the victim misses it expects to have caused in it.
+    pacing: (u64, u64),
+    /// The cost totals when the current phase started.
+    #[serde(default)]
+    phase_mark: PhaseCost
+}
+
+/// The phases every attack goes through, in order.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AttackPhase {
+    LeadingShort,
+    SecondByte,
+    LastByte,
+    Validation
+}
+
+impl AttackPhase {
+    pub const ALL: [AttackPhase; 4] = [AttackPhase::LeadingShort, AttackPhase::SecondByte, AttackPhase::LastByte, AttackPhase::Validation];
+}
+
+/// The costs an attack ran up within one phase, each counted as in the AttackStats total of the same name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PhaseCost {
+    pub bytes_written_to_victim: usize,
+    pub bytes_read_from_victim: usize,
+    pub victim_write_calls: usize,
+    pub victim_interactions: usize,
+    pub set_evictions: usize,
+    pub attacker_cache_lines_loaded: usize,
+    pub guesses: usize
+}
+
+impl std::ops::AddAssign for PhaseCost {
+    fn add_assign(&mut self, rhs: PhaseCost) {
+        self.bytes_written_to_victim += rhs.bytes_written_to_victim;
+        self.bytes_read_from_victim += rhs.bytes_read_from_victim;
+        self.victim_write_calls += rhs.victim_write_calls;
+        self.victim_interactions += rhs.victim_interactions;
+        self.set_evictions += rhs.set_evictions;
+        self.attacker_cache_lines_loaded += rhs.attacker_cache_lines_loaded;
+        self.guesses += rhs.guesses;
+    }
+}
+
+impl PhaseCost {
+    /// The costs run up since earlier, a snapshot of the same totals.
+    fn since(&self, earlier: &PhaseCost) -> PhaseCost {
+        return PhaseCost {
+            bytes_written_to_victim: self.bytes_written_to_victim - earlier.bytes_written_to_victim,
+            bytes_read_from_victim: self.bytes_read_from_victim - earlier.bytes_read_from_victim,
+            victim_write_calls: self.victim_write_calls - earlier.victim_write_calls,
+            victim_interactions: self.victim_interactions - earlier.victim_interactions,
+            set_evictions: self.set_evictions - earlier.set_evictions,
+            attacker_cache_lines_loaded: self.attacker_cache_lines_loaded - earlier.attacker_cache_lines_loaded,
+            guesses: self.guesses - earlier.guesses
+        };
+    }
+}
+
+/// Counts of probe outcomes, labeled by comparing what the probe saw (a hit means the candidates looked right)
+/// with whether the tested candidates actually contained the part of the secret being cracked.
+/// A false positive sends the attack down a dead end; a false negative throws the right answer away.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProbeOutcomes {
+    pub true_positives: usize,
+    pub false_positives: usize,
+    pub true_negatives: usize,
+    pub false_negatives: usize
+}
+
+impl ProbeOutcomes {
+    /// The fraction of probes over wrong candidates that still came back positive.
+    pub fn false_positive_rate(&self) -> f64 {
+        return self.false_positives as f64 / (self.false_positives + self.true_negatives).max(1) as f64;
+    }
+}
+
+impl std::ops::AddAssign for ProbeOutcomes {
+    fn add_assign(&mut self, rhs: ProbeOutcomes) {
+        self.true_positives += rhs.true_positives;
+        self.false_positives += rhs.false_positives;
+        self.true_negatives += rhs.true_negatives;
+        self.false_negatives += rhs.false_negatives;
+    }
+}
+
+impl ProbeOutcomes {
+    pub(crate) fn record(&mut self, hit: bool, actual: bool) {
+        match (hit, actual) {
+            (true, true) => self.true_positives += 1,
+            (true, false) => self.false_positives += 1,
+            (false, false) => self.true_negatives += 1,
+            (false, true) => self.false_negatives += 1
+        }
+    }
+
+    /// The fraction of labeled probes that came out right.
+    pub fn accuracy(&self) -> f64 {
+        let total = self.true_positives + self.false_positives + self.true_negatives + self.false_negatives;
+        return (self.true_positives + self.true_negatives) as f64 / total.max(1) as f64;
+    }
+}
+
+/// What the eliminations of one phase turned up, from the probe outcomes alone (no ground truth), for predicting
+/// whether the attack will succeed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct EliminationSignals {
+    /// Secret parts the eliminations looked for, one per word.
+    pub matches: usize,
+    /// Group probes, and how many came back positive.
+    pub group_probes: usize,
+    pub group_hits: usize,
+    /// Candidates probed one by one after the group probes, and how many of them came back positive.
+    pub single_probes: usize,
+    pub single_hits: usize,
+    /// Probes put to a vote whose rounds did not all agree.
+    pub split_votes: usize
+}
+
+impl EliminationSignals {
+    /// The signals gathered since earlier was taken.
+    pub fn since(&self, earlier: &EliminationSignals) -> EliminationSignals {
+        return EliminationSignals {
+            matches: self.matches - earlier.matches,
+            group_probes: self.group_probes - earlier.group_probes,
+            group_hits: self.group_hits - earlier.group_hits,
+            single_probes: self.single_probes - earlier.single_probes,
+            single_hits: self.single_hits - earlier.single_hits,
+            split_votes: self.split_votes - earlier.split_votes
+        };
+    }
+}
+
+impl std::ops::AddAssign for EliminationSignals {
+    fn add_assign(&mut self, rhs: EliminationSignals) {
+        self.matches += rhs.matches;
+        self.group_probes += rhs.group_probes;
+        self.group_hits += rhs.group_hits;
+        self.single_probes += rhs.single_probes;
+        self.single_hits += rhs.single_hits;
+        self.split_votes += rhs.split_votes;
+    }
+}
+
+/// How the latency and residency oracles compared on probes read through both (see ProbeOracle::Dual).
+/// Each oracle's outcomes are labeled against the true secret, as probe_outcomes are, so no-oracle builds leave them empty.
+/// With probe retries, only the last attempt of each probe is labeled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct OracleReport {
+    pub probes: usize,
+    pub disagreements: usize,
+    pub latency: ProbeOutcomes,
+    pub residency: ProbeOutcomes
+}
+
+impl std::ops::AddAssign for OracleReport {
+    fn add_assign(&mut self, rhs: OracleReport) {
+        self.probes += rhs.probes;
+        self.disagreements += rhs.disagreements;
+        self.latency += rhs.latency;
+        self.residency += rhs.residency;
+    }
+}
+
+/// The compressed sizes (in bits) the secret line took on right after each probe of a phase, read from the compressor,
+/// and how many of them fell outside the window that the phase's attack strings aim for.
+/// Sizes outside the window mean the strings do not compress the way the attack assumes they do.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SizeHistogram {
+    pub counts: BTreeMap<u64, usize>,
+    pub outside_window: usize
+}
+
+impl SizeHistogram {
+    /// Counts a size, checking it against the inclusive window.
+    #[cfg_attr(feature = "no-oracle", allow(dead_code))]
+    fn record(&mut self, bits: u64, window: (u64, u64)) {
+        *self.counts.entry(bits).or_insert(0) += 1;
+        if bits < window.0 || bits > window.1 {self.outside_window += 1;}
+    }
+
+    fn merge(&mut self, other: &SizeHistogram) {
+        for (&bits, &count) in &other.counts {*self.counts.entry(bits).or_insert(0) += count;}
+        self.outside_window += other.outside_window;
+    }
+
+    pub fn samples(&self) -> usize {
+        return self.counts.values().sum();
+    }
+}
+
+/// The set's LRU state right before a probe read its check line (ground truth, taken straight from the cache).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LruSnapshot {
+    /// Every way's age and lines, indexed by way.
+    pub ways: Vec<WayState>,
+    /// The age of the way holding the check line, or None if the line had already been evicted.
+    pub check_age: Option<usize>,
+    /// The age of the way holding the probed victim line, and whether that way also held other blocks of its superblock.
+    pub probed_line: Option<(usize, bool)>
+}
+
+impl LruSnapshot {
+    #[cfg(not(feature = "no-oracle"))]
+    fn take(victim: &VictimProgramYACC, line_offset: usize, check_addr: u64) -> LruSnapshot {
+        let ways = victim.way_states(line_offset);
+        let probed = victim.line_address(line_offset);
+        let age_of = |line: u64| ways.iter().find(|way| way.lines.contains(&line)).map(|way| (way.age.unwrap_or(0), way.lines.len() > 1));
+        let check_age = age_of(victim.line_of(check_addr)).map(|(age, _)| age);
+        let probed_line = age_of(probed);
+        return LruSnapshot {ways, check_age, probed_line};
+    }
+}
+
+/// Why a probe came out the way it did, read off its LRU snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub enum ProbeDiagnosis {
+    /// The probe saw what the truth says it should have.
+    Correct,
+    /// The probed line was co-allocated, but the check line was evicted anyway (e.g. by interference or a prefetch).
+    TargetEvicted,
+    /// The probed line should have been co-allocated but was not, so the check line was evicted as if nothing matched.
+    NoCompression,
+    /// The probed line was co-allocated although none of the tested candidates were right.
+    UnexpectedCompression,
+    /// The check line survived without the probed line being co-allocated, e.g. because another line left the set.
+    StaleCheckLine
+}
+
+/// One labeled probe and the LRU state it saw.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProbeRecord {
+    pub phase: AttackPhase,
+    pub hit: bool,
+    pub actual: bool,
+    pub lru: LruSnapshot,
+    /// The candidates the probe tested (bytes widened to shorts).
+    #[serde(default)]
+    pub tested: Vec<u16>,
+    /// The candidates still in the running once the probe's outcome was taken into account.
+    #[serde(default)]
+    pub remaining: Option<usize>
+}
+
+impl ProbeRecord {
+    pub fn diagnosis(&self) -> ProbeDiagnosis {
+        let compressed = self.lru.probed_line.is_some_and(|(_, shared)| shared);
+        return match (self.hit, self.actual) {
+            (hit, actual) if hit == actual => ProbeDiagnosis::Correct,
+            (false, _) if compressed => ProbeDiagnosis::TargetEvicted,
+            (false, _) => ProbeDiagnosis::NoCompression,
+            (true, _) if compressed => ProbeDiagnosis::UnexpectedCompression,
+            (true, _) => ProbeDiagnosis::StaleCheckLine
+        };
+    }
+}
+
+/// The victim's secret words, read through the simulator's ground-truth access so that probe outcomes can be labeled.
+/// The attack's decisions never depend on it, and no-oracle builds leave probes unlabeled.
+pub struct GroundTruth {
+    /// Each secret word as (leading short, second byte, last byte).
+    words: Vec<(u16, u8, u8)>
+}
+
+impl GroundTruth {
+    #[cfg(not(feature = "no-oracle"))]
+    fn of(victim: &VictimProgramYACC) -> Option<GroundTruth> {
+        let words = victim.ground_truth_words().iter()
+            .map(|&w| victim.endianness().word_bytes(w))
+            .map(|w| ((w[2] as u16) | ((w[3] as u16) << 8), w[1], w[0]))
+            .collect();
+        return Some(GroundTruth {words});
+    }
+
+    #[cfg(feature = "no-oracle")]
+    fn of(_victim: &VictimProgramYACC) -> Option<GroundTruth> {
+        return None;
+    }
+
+    pub fn has_short(&self, candidates: &[u16]) -> bool {
+        return self.words.iter().any(|w| candidates.contains(&w.0));
+    }
+
+    pub fn has_second_byte(&self, short: u16, candidates: &[u8]) -> bool {
+        return self.words.iter().any(|w| w.0 == short && candidates.contains(&w.1));
+    }
+
+    pub fn has_last_byte(&self, short: u16, second_byte: u8, candidates: &[u8]) -> bool {
+        return self.words.iter().any(|w| w.0 == short && w.1 == second_byte && candidates.contains(&w.2));
+    }
+}
+
+/// A per-probe series of log2(candidates).
+/// Debug output only summarizes the series, since a full attack records thousands of points.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct DecaySeries(pub Vec<f64>);
+
+impl DecaySeries {
+    fn push(&mut self, log2: f64) {self.0.push(log2);}
+}
+
+impl std::fmt::Debug for DecaySeries {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.0.first(), self.0.last()) {
+            (Some(first), Some(last)) => write!(f, "DecaySeries({} probes, {:.2} -> {:.2} bits)", self.0.len(), first, last),
+            _ => write!(f, "DecaySeries(empty)")
+        }
+    }
+}
+
+impl Default for AttackStats {
+    fn default() -> AttackStats {
+        AttackStats {
+            success: false,
+            secret: Vec::new(),
+            guesses_needed: 0,
+            bytes_written_to_victim: 0,
+            bytes_read_from_victim: 0,
+            attacker_cache_lines_loaded: 0,
+            set_evictions: 0,
+            flush_hits: 0,
+            victim_interactions: 0,
+            victim_write_calls: 0,
+            attempts: 0,
+            candidate_log2: DecaySeries::default(),
+            probed_threshold: None,
+            probed_capacity: None,
+            phase_time: [Duration::ZERO; 4],
+            probe_outcomes: [ProbeOutcomes::default(); 4],
+            size_histograms: Default::default(),
+            phase_costs: [PhaseCost::default(); 4],
+            idle_ticks: 0,
+            migrations: 0,
+            relocations_detected: 0,
+            answers: Vec::new(),
+            unwritable_probes: 0,
+            heap_allocations: 0,
+            writebacks: 0,
+            oracle_report: OracleReport::default(),
+            victim_seed: None,
+            recovered_words: Vec::new(),
+            last_readings: None,
+            probe_trace: Vec::new(),
+            signals: [EliminationSignals::default(); 4],
+            split_votes: 0,
+            predicted_success: None,
+            early_aborts: 0,
+            budget_exhausted: false,
+            last_snapshot: None,
+            pacing: (0, 0),
+            phase_mark: PhaseCost::default()
+        }
+    }
+}
+
+/// Adding stats treats rhs as a continuation of the same attack: costs, attempts and phase times add up,
+/// the candidate series are joined, and the attack counts as a success if either part succeeded
+/// (keeping the secret from the part that found it, preferring rhs).
+impl std::ops::AddAssign for AttackStats {
+    fn add_assign(&mut self, rhs: AttackStats) {
+        if rhs.success || !self.success {
+            self.success |= rhs.success;
+            if !rhs.secret.is_empty() {self.secret = rhs.secret;}
+        }
+        self.guesses_needed += rhs.guesses_needed;
+        self.bytes_written_to_victim += rhs.bytes_written_to_victim;
+        self.bytes_read_from_victim += rhs.bytes_read_from_victim;
+        self.attacker_cache_lines_loaded += rhs.attacker_cache_lines_loaded;
+        self.set_evictions += rhs.set_evictions;
+        self.flush_hits += rhs.flush_hits;
+        self.victim_interactions += rhs.victim_interactions;
+        self.victim_write_calls += rhs.victim_write_calls;
+        self.attempts += rhs.attempts;
+        self.candidate_log2.0.extend(rhs.candidate_log2.0);
+        self.probed_threshold = rhs.probed_threshold.or(self.probed_threshold);
+        self.probed_capacity = rhs.probed_capacity.or(self.probed_capacity);
+        self.idle_ticks += rhs.idle_ticks;
+        self.migrations += rhs.migrations;
+        self.relocations_detected += rhs.relocations_detected;
+        self.answers.extend(rhs.answers);
+        self.unwritable_probes += rhs.unwritable_probes;
+        self.heap_allocations += rhs.heap_allocations;
+        self.writebacks += rhs.writebacks;
+        self.oracle_report += rhs.oracle_report;
+        if !rhs.recovered_words.is_empty() {self.recovered_words = rhs.recovered_words;}
+        self.last_readings = rhs.last_readings;
+        self.probe_trace.extend(rhs.probe_trace);
+        self.split_votes += rhs.split_votes;
+        self.predicted_success = rhs.predicted_success.or(self.predicted_success);
+        self.early_aborts += rhs.early_aborts;
+        self.budget_exhausted |= rhs.budget_exhausted;
+        self.last_snapshot = rhs.last_snapshot;
+        self.pacing = rhs.pacing;
+        self.phase_mark = rhs.phase_mark;
+        for phase in AttackPhase::ALL {
+            self.phase_time[phase as usize] += rhs.phase_time[phase as usize];
+            self.phase_costs[phase as usize] += rhs.phase_costs[phase as usize];
+            self.probe_outcomes[phase as usize] += rhs.probe_outcomes[phase as usize];
+            self.size_histograms[phase as usize].merge(&rhs.size_histograms[phase as usize]);
+            self.signals[phase as usize] += rhs.signals[phase as usize];
+        }
+    }
+}
+
+impl std::ops::Add for AttackStats {
+    type Output = AttackStats;
+
+    fn add(mut self, rhs: AttackStats) -> AttackStats {
+        self += rhs;
+        return self;
+    }
+}
+
+/// Totals over a set of independent attacks (usually one per victim).
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Summary {
+    pub iterations: usize,
+    pub successes: usize,
+    pub first_try_successes: usize,
+    pub retry_successes: usize,
+    pub guesses_needed: usize,
+    pub bytes_written_to_victim: usize,
+    pub bytes_read_from_victim: usize,
+    pub attacker_cache_lines_loaded: usize,
+    pub set_evictions: usize,
+    pub flush_hits: usize,
+    pub victim_interactions: usize,
+    pub victim_write_calls: usize,
+    pub idle_ticks: u64,
+    pub unwritable_probes: usize,
+    pub heap_allocations: usize,
+    pub writebacks: u64,
+    pub early_aborts: usize,
+    pub budget_exhaustions: usize,
+    pub relocations_detected: usize,
+    pub oracle_report: OracleReport,
+    pub phase_time: [Duration; 4],
+    pub probe_outcomes: [ProbeOutcomes; 4],
+    pub size_histograms: [SizeHistogram; 4],
+    pub phase_costs: [PhaseCost; 4],
+    /// How the main costs spread over the attacks, beyond their totals.
+    pub distributions: CostDistributions
+}
+
+impl Summary {
+    pub fn success_rate(&self) -> f64 {
+        return self.successes as f64 / self.iterations.max(1) as f64;
+    }
+}
+
+/// Totals up the stats of independent attacks. Unlike adding AttackStats, this keeps per-attack success counts
+/// and drops the candidate series.
+pub fn summarize(results: &[AttackStats]) -> Summary {
+    let mut summary = Summary {iterations: results.len(), distributions: CostDistributions::of(results), ..Summary::default()};
+    for stats in results {
+        if stats.success {
+            summary.successes += 1;
+            if stats.attempts > 1 {summary.retry_successes += 1;} else {summary.first_try_successes += 1;}
+        }
+        summary.guesses_needed += stats.guesses_needed;
+        summary.bytes_written_to_victim += stats.bytes_written_to_victim;
+        summary.bytes_read_from_victim += stats.bytes_read_from_victim;
+        summary.attacker_cache_lines_loaded += stats.attacker_cache_lines_loaded;
+        summary.set_evictions += stats.set_evictions;
+        summary.flush_hits += stats.flush_hits;
+        summary.victim_interactions += stats.victim_interactions;
+        summary.victim_write_calls += stats.victim_write_calls;
+        summary.idle_ticks += stats.idle_ticks;
+        summary.unwritable_probes += stats.unwritable_probes;
+        summary.heap_allocations += stats.heap_allocations;
+        summary.writebacks += stats.writebacks;
+        summary.early_aborts += stats.early_aborts;
+        summary.budget_exhaustions += stats.budget_exhausted as usize;
+        summary.relocations_detected += stats.relocations_detected;
+        summary.oracle_report += stats.oracle_report;
+        for phase in AttackPhase::ALL {
+            summary.phase_time[phase as usize] += stats.phase_time[phase as usize];
+            summary.phase_costs[phase as usize] += stats.phase_costs[phase as usize];
+            summary.probe_outcomes[phase as usize] += stats.probe_outcomes[phase as usize];
+            summary.size_histograms[phase as usize].merge(&stats.size_histograms[phase as usize]);
+        }
+    }
+    return summary;
+}
+
+impl AttackStats {
+    /// Labels a probe outcome against whether the tested candidates really contained part of the secret.
+    fn record_probe(&mut self, phase: AttackPhase, hit: bool, actual: bool) {
+        self.probe_outcomes[phase as usize].record(hit, actual);
+        if let Some((latency, residency)) = self.last_readings.take() {
+            self.oracle_report.latency.record(latency, actual);
+            self.oracle_report.residency.record(residency, actual);
+        }
+        if let Some(lru) = self.last_snapshot.take() {
+            self.probe_trace.push(ProbeRecord {phase, hit, actual, lru, tested: Vec::new(), remaining: None});
+        }
+    }
+
+    /// Notes on the probe record_probe just traced (if it traced one) which candidates it tested, and how many remain.
+    fn trace_candidates<C: Copy + Into<u16>>(&mut self, tested: &[C], remaining: usize) {
+        let Some(record) = self.probe_trace.last_mut().filter(|record| record.remaining.is_none()) else {return};
+        record.tested = tested.iter().map(|&candidate| candidate.into()).collect();
+        record.remaining = Some(remaining);
+    }
+
+    /// Charges the time since phase_start and the costs since the last phase ended to a phase, then restarts the clock
+    /// for the next phase.
+    fn end_phase(&mut self, phase: AttackPhase, phase_start: &mut Instant) {
+        let now = Instant::now();
+        self.phase_time[phase as usize] += now - *phase_start;
+        *phase_start = now;
+        let totals = self.cost_totals();
+        self.phase_costs[phase as usize] += totals.since(&self.phase_mark);
+        self.phase_mark = totals;
+    }
+
+    /// The running totals that phase costs are taken from.
+    fn cost_totals(&self) -> PhaseCost {
+        return PhaseCost {
+            bytes_written_to_victim: self.bytes_written_to_victim,
+            bytes_read_from_victim: self.bytes_read_from_victim,
+            victim_write_calls: self.victim_write_calls,
+            victim_interactions: self.victim_interactions,
+            set_evictions: self.set_evictions,
+            attacker_cache_lines_loaded: self.attacker_cache_lines_loaded,
+            guesses: self.guesses_needed
+        };
+    }
+}
+
+/// log2 of the number of possible values of a single unknown byte (zero is never part of a secret).
+const BYTE_LOG2: f64 = 7.994353436858858;
+
+/// log2 of the values the attacker knows a secret byte can take.
+fn byte_log2(config: &AttackConfig) -> f64 {
+    if config.known_distribution == SecretDistribution::UniformBytes && config.secret_bytes == SecretBytes::UniqueNonZero {return BYTE_LOG2;}
+    return (config.known_distribution.alphabet_under(config.secret_bytes).len() as f64).log2();
+}
+
+/// Which of the victim's lines the probe reloads after the set has been flushed.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ProbeStrategy {
+    /// Reload the secret line first, then an all-zero companion line of its superblock.
+    SecretThenCompanion,
+    /// Reload only the secret line, for victims whose buffer does not share the secret's superblock and who bring in
+    /// a compressible companion (such as a header) on their own.
+    SecretOnly
+}
+
+/// How the attack strings pad out a test group that holds fewer candidates than the string has room for.
+/// A filler value that happens to match the secret produces a false positive, so the choice of filler matters,
+/// especially when the secret is allowed to take any value.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum FillerStrategy {
+    /// Counts up from 1, skipping included and excluded values (the original behavior).
+    SequentialLow,
+    /// Draws distinct uniformly random values, skipping included and excluded values.
+    HighEntropyRandom,
+    /// Counts up through a reserved range (inclusive) that the attacker believes cannot contain the secret.
+    ExcludedRange {start: u16, end: u16}
+}
+
+impl FillerStrategy {
+    /// Picks count distinct non-zero filler values no larger than max.
+    /// Panics if the strategy cannot supply enough values.
+    fn pick(&self, rng: &mut StdRng, count: usize, includes: &[u16], excludes: &CandidateSet<u16>, max: u16) -> Vec<u16> {
+        let usable = |x: &u16| !includes.contains(x) && !excludes.contains(x);
+        let filler: Vec<u16> = match *self {
+            FillerStrategy::SequentialLow => (1..=max).filter(usable).take(count).collect(),
+            FillerStrategy::HighEntropyRandom => {
+                let pool: Vec<u16> = (1..=max).filter(usable).collect();
+                assert!(pool.len() >= count, "Filler strategy {:?} ran out of values", self);
+                pool.choose_multiple(rng, count).copied().collect()
+            },
+            FillerStrategy::ExcludedRange {start, end} => (start.max(1)..=end.min(max)).filter(usable).take(count).collect()
+        };
+        assert_eq!(filler.len(), count, "Filler strategy {:?} ran out of values", self);
+        return filler;
+    }
+
+    /// Same as pick, but for byte-sized candidates.
+    fn pick_bytes(&self, rng: &mut StdRng, count: usize, includes: &[u8], excludes: &CandidateSet<u8>) -> Vec<u8> {
+        let includes: Vec<u16> = includes.iter().map(|&x| x as u16).collect();
+        let excludes: CandidateSet<u16> = excludes.iter().map(|&x| x as u16).collect();
+        return self.pick(rng, count, &includes, &excludes, 0xFF).into_iter().map(|x| x as u8).collect();
+    }
+}
+
+/// How the probe gets an attack string into the victim's secret line.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PrimeMethod {
+    /// Write only the bytes that differ from what is already there, one victim call per byte.
+    ByteWrites,
+    /// Hand the victim the whole attack string, which it copies into the line in a single call.
+    Memcpy
+}
+
+/// How the probe makes the victim bring its lines back into the cache after a flush.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ReloadMethod {
+    /// Ask the victim to read a byte from each line.
+    Read,
+    /// Ask the victim to store a byte into each line, rewriting a value the attacker already put there.
+    /// This works against store-only victims that never hand data back to the attacker.
+    Store
+}
+
+/// Where the superblocks holding the attacker's flush lines (and the line it checks afterwards) come from for each probe.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ProbeAddressPolicy {
+    /// The same superblocks, at the start of the attacker's address space, for every probe (the original behavior). Lines that survive one probe are
+    /// still cached when the next probe flushes with them.
+    Fixed,
+    /// Never-used superblocks for every probe, so no probe sees what an earlier probe left behind.
+    Fresh,
+    /// Cycles through a pool of size superblocks (at least as many as a flush set needs).
+    Pool {size: usize}
+}
+
+/// What the attacker keeps in its flush lines, which decides whether flush lines sharing a superblock get co-allocated.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum FlushLineContents {
+    /// Untouched memory, which compresses to 4 bytes (the original behavior).
+    Zeros,
+    /// A line built to compress to exactly bits bits, as calibration_line does. 512 bits does not compress at all.
+    Sized {bits: u64}
+}
+
+/// The lines the attacker reads to flush the set before every probe.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FlushSet {
+    /// How many lines are read, or None for one per way of the cache. The probe checks the line read second among the
+    /// last associativity lines, which (if each line takes an entry of its own) is the second least recently used once
+    /// the victim reloads.
+    pub lines: Option<usize>,
+    /// How many flush lines share each superblock (1 to the blocks per superblock, 4 by default). With more than one, compressible flush lines can be
+    /// co-allocated with each other, so they fill fewer entries of the set.
+    pub lines_per_superblock: usize,
+    pub contents: FlushLineContents
+}
+
+impl Default for FlushSet {
+    /// One all-zero line per way, each in a superblock of its own.
+    fn default() -> FlushSet {
+        FlushSet {lines: None, lines_per_superblock: 1, contents: FlushLineContents::Zeros}
+    }
+}
+
+impl FlushSet {
+    /// Returns how many lines are read to flush a set with the given associativity.
+    fn line_count(&self, associativity: usize) -> usize {
+        return self.lines.unwrap_or(associativity);
+    }
+
+    /// Returns the line the attacker checks after the victim reloads, as an index into the flush set.
+    fn check_index(&self, associativity: usize) -> usize {
+        return self.line_count(associativity).saturating_sub(associativity) + 1;
+    }
+
+    /// Returns the contents of every flush line, for lines of line_bytes bytes read in the given byte order.
+    fn line_contents(&self, line_bytes: usize, endianness: Endianness) -> Vec<u8> {
+        return match self.contents {
+            FlushLineContents::Zeros => vec![0u8; line_bytes],
+            FlushLineContents::Sized {bits} => calibration_line(bits, line_bytes, endianness).expect("No line compresses to that many bits")
+        };
+    }
+}
+
+/// The default start of the attacker's own address space, between the victim buffers and the co-tenant.
+pub const ATTACKER_REGION: u64 = 2 << 48;
+
+/// Hands out the attacker's superblocks in a target set, counting from the start of its address space, and checks every
+/// one against the machine's memory map, so that no flush or check line can ever alias the victim's or co-tenant's memory.
+pub struct AddressAllocator<'a> {
+    cache: &'a AttackerPort<'a>,
+    base: u64
+}
+
+impl<'a> AddressAllocator<'a> {
+    /// An allocator for an attacker whose address space starts at the given byte address.
+    pub fn new(cache: &'a AttackerPort<'a>, offset: u64) -> AddressAllocator<'a> {
+        return AddressAllocator {cache, base: offset / cache.superblock_bytes()};
+    }
+
+    /// Returns the k-th of the attacker's superblocks that maps to set.
+    /// Panics if it could overlap victim memory, which means the attacker's offset was set inside it.
+    pub fn superblock(&self, k: u64, set: usize) -> u64 {
+        let superblock = self.cache.superblock_in_set(self.base, k, set);
+        assert!(superblock >= self.base, "The attacker's address space wrapped around");
+        assert!(!self.cache.in_victim_memory(superblock), "Attacker superblock {:#X} may alias victim memory; move the attacker's address offset", superblock);
+        return superblock;
+    }
+}
+
+impl ProbeAddressPolicy {
+    /// Returns the addresses of the flush lines for the probe-th probe of an attack, in the order they are read.
+    /// Each address is the start of a line; lines_per_superblock consecutive lines share each superblock, and every
+    /// superblock maps to the target set (the k-th superblock of the policy becomes the k-th one in that set).
+    /// Superblocks come from an AddressAllocator starting at offset.
+    fn flush_set(&self, flush: &FlushSet, cache: &AttackerPort, associativity: usize, offset: u64, set: usize, probe: usize) -> Vec<u64> {
+        let lines = flush.line_count(associativity);
+        assert!(lines >= 2, "A flush set needs at least 2 lines");
+        let line_bytes = cache.line_size() as u64;
+        assert!((1..=(cache.superblock_bytes() / line_bytes) as usize).contains(&flush.lines_per_superblock), "A superblock holds 1 line up to its block count");
+        let needed = lines.div_ceil(flush.lines_per_superblock);
+        let allocator = AddressAllocator::new(cache, offset);
+        let superblocks: Vec<u64> = (0..needed).map(|i| match *self {
+            ProbeAddressPolicy::Fixed => allocator.superblock(i as u64, set),
+            ProbeAddressPolicy::Fresh => allocator.superblock((probe * needed + i) as u64, set),
+            ProbeAddressPolicy::Pool {size} => {
+                assert!(size >= needed, "A probe address pool needs at least {} superblocks", needed);
+                allocator.superblock(((probe * needed + i) % size) as u64, set)
+            }
+        }).collect();
+        let distinct: HashSet<u64> = superblocks.iter().copied().collect();
+        assert_eq!(distinct.len(), needed, "Flush lines must sit in distinct superblocks");
+        let superblock_bytes = cache.superblock_bytes();
+        return (0..lines).map(|i| superblocks[i / flush.lines_per_superblock] * superblock_bytes + (i % flush.lines_per_superblock) as u64 * line_bytes).collect();
+    }
+}
+
+/// How the attacker reads the outcome of a probe off its check line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum ProbeOracle {
+    /// Time a load of the check line (the original behavior). Subject to the victim's timing noise.
+    #[default]
+    Latency,
+    /// Ask whether the check line is held by the shared cache, without loading it, as a residency side channel
+    /// (such as a cache occupancy counter) would tell.
+    Residency,
+    /// Read both, residency first since it leaves the cache alone, and decide by latency. Every disagreement is counted
+    /// in the stats' oracle report, along with how reliable each oracle was.
+    Dual,
+    /// Read the attacker's miss counter before and after loading the check line, as with perf counters, and call it a hit
+    /// if the count did not move. Not subject to timing noise, but to the victim's counter noise.
+    MissCounter,
+    /// Skip the flush and reload altogether: time a victim read of the companion line, which takes longer when the
+    /// secret line shares its entry, since the companion then has to be decompressed. Only works on a victim whose
+    /// clock charges the cache's own cycles (see LatencyModel::cache_cycles), and is not subject to timing noise.
+    DecompressionLatency,
+    /// Evict+Reload on the companion line instead of prime and probe on a check line: evict the superblock, load the
+    /// companion through a mapping shared with the victim, have the victim reload its secret line, evict all but the
+    /// newest lines of the set, and time a reload of the companion. It only survives if the two lines shared an entry.
+    EvictReload
+}
+
+/// What the attacker knows about how the secret's words relate to each other.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum SecretStructure {
+    /// Every word is independent, so each one has to be recovered (the original assumption).
+    #[default]
+    Independent,
+    /// The secret is a 16-byte key schedule (see keyschedule::expand), so any one word gives away the rest.
+    KeySchedule
+}
+
+/// A pacing budget for a stealthy attacker: cause at most max_victim_misses victim misses in any window of
+/// simulated time (aligned to multiples of window ticks), waiting for the next window once the budget is spent.
+/// Matching these to an anomaly detector's settings keeps the attack below its alarm threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StealthBudget {
+    pub window: u64,
+    pub max_victim_misses: u64
+}
+
+/// How the attack copes with other processes touching the cache set while it probes.
+/// Interference between the flush and the check evicts the check line, so it turns positive probes into negatives.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum InterferenceMitigation {
+    /// Trust every probe.
+    None,
+    /// Repeat a negative probe up to attempts times in total, counting it as positive if any repeat is positive.
+    ProbeRetries {attempts: usize},
+    /// Before attacking, run canaries probes of a line that is known to be co-allocated. If any of them comes back
+    /// negative, ask the victim for a fresh buffer (hopefully in a quieter set) and check again, up to max_migrations times.
+    SetMigration {canaries: usize, max_migrations: usize}
+}
+
+/// Repeats every probe's flush, reload and check rounds times, and calls the probe positive if at least min_hits of the
+/// rounds were. Each round counts the flush lines the victim's reload evicted instead of checking one line (see vote_round),
+/// which does not depend on the replacement policy, but one round can still miss under random replacement.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ProbeVoting {
+    pub rounds: usize,
+    pub min_hits: usize
+}
+
+/// Where the attack gets the compressed size (in bytes) at which two blocks are co-allocated.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ThresholdSource {
+    /// Trust a known threshold, such as the 32 bytes of a stock YACC.
+    Assumed(u64),
+    /// Find the threshold by differential probing before the attack starts.
+    Probed,
+    /// Find the number of entries in the target set first (see probe_set_capacity), then the threshold as Probed does,
+    /// and attack with both, for a cache whose parameters the attacker does not know.
+    Calibrated
+}
+
+/// Knobs controlling how an attack builds and observes its probes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttackConfig {
+    pub filler: FillerStrategy,
+    pub probe: ProbeStrategy,
+    pub prime: PrimeMethod,
+    pub reload: ReloadMethod,
+    /// Walk an L1 eviction set before every flush, so the flush reaches the shared cache even if the attacker's
+    /// own lines are sitting in its private cache (only matters in the cross-core scenario).
+    pub evict_private_cache: bool,
+    pub threshold: ThresholdSource,
+    pub addresses: ProbeAddressPolicy,
+    /// Where the attacker's own address space starts, in bytes. Configurations saved before it was configurable get
+    /// ATTACKER_REGION (their flush lines used to start at 0, which could alias a victim buffer).
+    #[serde(default = "default_address_offset")]
+    pub address_offset: u64,
+    /// Configurations saved before flush sets were configurable get the original one.
+    #[serde(default)]
+    pub flush: FlushSet,
+    #[serde(default)]
+    pub oracle: ProbeOracle,
+    /// Snapshot the set's LRU state at every probe into the stats' probe trace, for diagnosing failed probes.
+    /// Ground truth, so no-oracle builds never record it.
+    #[serde(default)]
+    pub record_lru: bool,
+    #[serde(default)]
+    pub structure: SecretStructure,
+    /// Pace probes to stay under a detector's miss threshold. None probes as fast as possible.
+    pub stealth: Option<StealthBudget>,
+    /// How to cope with co-tenants disturbing the set while probing.
+    pub interference: InterferenceMitigation,
+    /// Put every probe to a vote over several rounds. None probes once.
+    #[serde(default)]
+    pub voting: Option<ProbeVoting>,
+    /// Give up on the attempt after the leading shorts if its predicted chance of success (see predict_success) is below
+    /// this. None never gives up early.
+    #[serde(default)]
+    pub abort_below: Option<f64>,
+    /// The values the attacker knows the secret's bytes are drawn from. Shorts and bytes outside it are never tested.
+    #[serde(default)]
+    pub known_distribution: SecretDistribution,
+    /// Whether the attacker allows for secret bytes that are zero or repeat. With SecretBytes::Arbitrary, zero values are
+    /// tested, every phase first calibrates how far the secret's words compress on their own (see calibrate_free_bits),
+    /// and words sharing a short or a second byte are all recovered.
+    #[serde(default)]
+    pub secret_bytes: SecretBytes,
+    /// The number of entries the attacker takes the target set to have, which decides how many lines flush it. None reads
+    /// it off the cache, as if the attacker knew it; ThresholdSource::Calibrated fills it in by probing.
+    #[serde(default)]
+    pub associativity: Option<usize>,
+    /// Whether to read back a byte of the attack string after every probe of the secret line, to catch the victim moving
+    /// its buffer (which wipes the string). A moved buffer is primed again from scratch and the probe repeated.
+    #[serde(default)]
+    pub detect_relocation: bool
+}
+
+fn default_address_offset() -> u64 {
+    return ATTACKER_REGION;
+}
+
+impl Default for AttackConfig {
+    fn default() -> AttackConfig {
+        AttackConfig {
+            filler: FillerStrategy::SequentialLow,
+            probe: ProbeStrategy::SecretThenCompanion,
+            prime: PrimeMethod::ByteWrites,
+            reload: ReloadMethod::Read,
+            evict_private_cache: false,
+            threshold: ThresholdSource::Assumed(32),
+            addresses: ProbeAddressPolicy::Fixed,
+            address_offset: ATTACKER_REGION,
+            flush: FlushSet::default(),
+            oracle: ProbeOracle::Latency,
+            record_lru: false,
+            structure: SecretStructure::Independent,
+            stealth: None,
+            interference: InterferenceMitigation::None,
+            voting: None,
+            abort_below: None,
+            known_distribution: SecretDistribution::UniformBytes,
+            secret_bytes: SecretBytes::UniqueNonZero,
+            associativity: None,
+            detect_relocation: false
+        }
+    }
+}
+
+/// Describes how a failed attack is retried before it is counted as a failure.
+/// The first attempt uses `first`; each entry of `alternates` is then tried in order against the same victim,
+/// without resetting the victim's buffer, until one of them recovers the secret.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    pub first: AttackConfig,
+    pub alternates: Vec<AttackConfig>
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    pub fn no_retries() -> RetryPolicy {
+        RetryPolicy {first: AttackConfig::default(), alternates: Vec::new()}
+    }
+}
+
+impl Default for RetryPolicy {
+    /// Retries once with random filler, in case a filler value matched the secret.
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            first: AttackConfig::default(),
+            alternates: vec![
+                AttackConfig {filler: FillerStrategy::HighEntropyRandom, ..AttackConfig::default()}
+            ]
+        }
+    }
+}
+
+/// The word makeup of one kind of attack string: candidate-testing words first, then distinct zero-extended bytes,
+/// then zero words. Strings laid out for a bounded C-PACK dictionary (see AttackLayout::with_dictionary) start with
+/// uncompressed padding words instead, and put the test words last.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct StringLayout {
+    pub tests: usize,
+    pub padding_words: usize,
+    pub byte_words: usize,
+    pub zero_words: usize,
+    /// Whether the test words come right before the secret, so that a bounded dictionary still holds them.
+    pub tests_last: bool,
+    /// The secret line's compressed size in bits when a test word matches the secret, and when none does.
+    pub bit_window: (u64, u64)
+}
+
+/// The attack string layouts for every phase of an attack, worked out from the co-allocation threshold.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttackLayout {
+    pub leading_short: StringLayout,
+    pub second_byte: StringLayout,
+    pub last_byte: StringLayout,
+    /// The leading shorts of the line's known words, if any. A test word with one of these makes the known word
+    /// compress against it whatever the secret is, so these shorts cannot be tested.
+    pub untestable_shorts: CandidateSet<u16>,
+    /// What the layouts were derived for: the threshold, the secret's size, the known words, the bits the secret's
+    /// other words were taken to save by compressing on their own (see with_free_bits), and the size of the C-PACK
+    /// dictionary if it is bounded (see with_dictionary).
+    pub threshold_bytes: u64,
+    pub secret_size: usize,
+    pub known: Vec<(usize, [u8; 4])>,
+    pub free_bits: u64,
+    pub dict_entries: Option<usize>,
+    /// The size of the secret line, which the attack string fills apart from the secret and the known words.
+    pub line_bytes: usize
+}
+
+impl AttackLayout {
+    /// Derives the layouts for a secret of secret_size bytes at the end of a line of the default LINE_SIZE bytes (see
+    /// derive_with_known for other line sizes), when lines of at most threshold_bytes compressed bytes are co-allocated. Returns None if some phase has no layout that works.
+    ///
+    /// In every phase, the secret word compresses one pattern smaller when a test word matches it
+    /// (xxxx to mmxx when cracking the leading short, mmxx to mmmx for the second byte, mmmx to mmmm for the last byte).
+    /// The attack string must make the line fit the threshold in the first case but not in the second, counting any
+    /// other secret word as uncompressed. The first test word of a phase is always uncompressed, and the rest compress
+    /// against it. As many test words as possible are used, then as few byte words as possible, and the rest are zeros.
+    /// With the default 32 bytes and 64-byte lines, this gives 6, 9 and 14 test words for 4-byte secrets, and 5, 7 and 12 for 8-byte secrets.
+    pub fn derive(threshold_bytes: u64, secret_size: usize) -> Option<AttackLayout> {
+        return AttackLayout::derive_with_known(threshold_bytes, secret_size, &[], LINE_SIZE);
+    }
+
+    /// Same as derive, but for a secret line of line_bytes bytes whose known words (given as (word index, value) in the
+    /// part of the line before the secret) hold fixed public data the attacker cannot write. Their compressed size is
+    /// charged against the threshold, and the layouts only cover the remaining words, which the attack string fills in order.
+    pub fn derive_with_known(threshold_bytes: u64, secret_size: usize, known: &[(usize, [u8; 4])], line_bytes: usize) -> Option<AttackLayout> {
+        return AttackLayout::derive_with_free_bits(threshold_bytes, secret_size, known, 0, None, line_bytes);
+    }
+
+    /// The same layouts, rederived for a secret whose words take up free_bits fewer bits than the layouts otherwise assume, because they compress against each other, against the strings' zero and byte words (which share the leading
+    /// short 0), or on their own. Secrets of unique non-zero bytes save nothing, but arbitrary secrets can save any amount.
+    pub fn with_free_bits(&self, free_bits: u64) -> Option<AttackLayout> {
+        return AttackLayout::derive_with_free_bits(self.threshold_bytes, self.secret_size, &self.known, free_bits, self.dict_entries, self.line_bytes);
+    }
+
+    /// The same layouts, rederived for a cache whose C-PACK dictionary only holds dict_entries words (None for an
+    /// unbounded one). The test words then go right before the secret, and there are never more of them than the
+    /// dictionary has room for next to the secret's other words and the known words, so none has been replaced by the
+    /// time the secret's word is compressed. With fewer test words the line compresses too well for the later phases, so
+    /// once the leading short is known the strings are padded with uncompressed words of other shorts, which cannot match
+    /// the secret's word and so may be replaced in the dictionary.
+    pub fn with_dictionary(&self, dict_entries: Option<usize>) -> Option<AttackLayout> {
+        return AttackLayout::derive_with_free_bits(self.threshold_bytes, self.secret_size, &self.known, self.free_bits, dict_entries, self.line_bytes);
+    }
+
+    fn derive_with_free_bits(threshold_bytes: u64, secret_size: usize, known: &[(usize, [u8; 4])], free_bits: u64, dict_entries: Option<usize>, line_bytes: usize) -> Option<AttackLayout> {
+        let words = (line_bytes - secret_size) / 4 - known.len();
+        let other_secret_bits = (secret_size as u64 / 4 - 1) * 34 + known_word_bits(known, line_bytes);
+        // Saving free_bits on the secret's words leaves that much more room for the attack string.
+        let threshold_bits = threshold_bytes * 8 + free_bits;
+        // Every other secret word and known word may take up a dictionary entry between the test words and the secret's word.
+        let most_tests = dict_entries.map_or(words, |entries| entries.saturating_sub(secret_size / 4 - 1 + known.len()).min(words));
+        let fit = |rest_test_bits: u64, hit_bits: u64, miss_bits: u64, short_known: bool| -> Option<StringLayout> {
+            let most_padding = if short_known && dict_entries.is_some() {words} else {0};
+            for tests in (1..=most_tests).rev() {
+                for padding_words in 0..=most_padding.min(words - tests) {
+                    for byte_words in 0..=(words - tests - padding_words) {
+                        let zero_words = words - tests - padding_words - byte_words;
+                        let total = 34 + (tests as u64 - 1) * rest_test_bits + 34 * padding_words as u64 + 12 * byte_words as u64 + 2 * zero_words as u64 + other_secret_bits;
+                        if total + hit_bits <= threshold_bits && total + miss_bits > threshold_bits {
+                            return Some(StringLayout {tests, padding_words, byte_words, zero_words, tests_last: dict_entries.is_some(),
+                                                      bit_window: ((total + hit_bits).saturating_sub(free_bits), (total + miss_bits).saturating_sub(free_bits))});
+                        }
+                    }
+                }
+            }
+            return None;
+        };
+        let untestable_shorts: HashSet<u16> = known.iter().map(|&(_, value)| u16::from_le_bytes([value[2], value[3]])).filter(|&short| short != 0).collect();
+        return Some(AttackLayout {
+            leading_short: fit(34, 24, 34, false)?,
+            second_byte: fit(24, 16, 24, true)?,
+            last_byte: fit(16, 6, 16, true)?,
+            untestable_shorts: untestable_shorts.into_iter().collect(),
+            threshold_bytes,
+            secret_size,
+            known: known.to_vec(),
+            free_bits,
+            dict_entries,
+            line_bytes
+        });
+    }
+}
+
+/// Returns the compressed size of the known words in bits, as if every other word of the line were zero.
+fn known_word_bits(known: &[(usize, [u8; 4])], line_bytes: usize) -> u64 {
+    if known.is_empty() {return 0;}
+    let mut line = vec![0u8; line_bytes];
+    for &(slot, value) in known {line[slot * 4..slot * 4 + 4].copy_from_slice(&value);}
+    let patterns = cpack_words(&line);
+    return known.iter().map(|&(slot, _)| patterns[slot].bits()).sum();
+}
+
+/// Returns the words around the secret in the secret line that are read-only in full, as (word index, value), with words
+/// indexed as in the attack string (which skips the secret's words).
+/// These are the known words that AttackLayout::derive_with_known accounts for, so their values are least significant
+/// byte first, as the layouts work with words.
+fn known_words(victim: &VictimProgramYACC, secret_size: usize) -> Vec<(usize, [u8; 4])> {
+    let words = whole_read_only_words(&writable_read_only(victim, secret_line(victim)), victim.line_size() - secret_size);
+    return words.into_iter().map(|(slot, value)| (slot, victim.endianness().word_bytes(value))).collect();
+}
+
+/// Returns the buffer offset of the line holding the victim's secret.
+fn secret_line(victim: &VictimProgramYACC) -> usize {
+    return victim.secret_start() / victim.line_size() * victim.line_size();
+}
+
+/// Returns the buffer offsets of the secret's words if the line at line_offset holds them, or else an empty range.
+/// Attack strings written to that line skip over them.
+fn secret_hole(victim: &VictimProgramYACC, line_offset: usize) -> Range<usize> {
+    let line_end = line_offset + victim.line_size();
+    if secret_line(victim) != line_offset {return line_end..line_end;}
+    let start = victim.secret_start();
+    return start..start + victim.secret_placement().span(victim.secret_size());
+}
+
+/// Returns the buffer offset of byte i of a string written to the line at line_offset, skipping the secret's words.
+fn string_offset(line_offset: usize, hole: &Range<usize>, i: usize) -> usize {
+    return if line_offset + i < hole.start {line_offset + i} else {line_offset + i + hole.len()};
+}
+
+/// Returns the read-only bytes of the line at line_offset as (offset within a string written there, value).
+fn writable_read_only(victim: &VictimProgramYACC, line_offset: usize) -> Vec<(usize, u8)> {
+    let hole = secret_hole(victim, line_offset);
+    return victim.read_only_bytes().iter()
+        .filter(|&&(index, _)| (line_offset..line_offset + victim.line_size()).contains(&index) && !hole.contains(&index))
+        .map(|&(index, value)| (index - line_offset - if index >= hole.end {hole.len()} else {0}, value))
+        .collect();
+}
+
+/// Returns the buffer offset of the companion line the victim reloads next to its secret line: the first line of the
+/// secret's superblock within the buffer, or its second if the secret is in the first.
+fn companion_offset(victim: &VictimProgramYACC) -> usize {
+    let superblock_start = secret_line(victim) / victim.superblock_bytes() as usize * victim.superblock_bytes() as usize;
+    return if secret_line(victim) == superblock_start {superblock_start + victim.line_size()} else {superblock_start};
+}
+
+/// Returns the buffer offset of a line that the attacker fully controls and that is neither the secret line nor the
+/// companion line (the buffer's second line, unless that one is taken). With 4-block superblocks or larger, it shares
+/// the secret's superblock; with 2-block ones, it shares a superblock with another attacker line instead. None if the
+/// buffer has no third line, as with 128-byte lines.
+fn spare_line(victim: &VictimProgramYACC) -> Option<usize> {
+    return (1..BUFFER_SIZE / victim.line_size()).map(|i| i * victim.line_size()).find(|&line| line != secret_line(victim) && line != companion_offset(victim));
+}
+
+/// Returns the word slots of a string of length bytes whose every byte is fixed (given as (offset, value) within the
+/// string), along with the values held there.
+fn whole_read_only_words(fixed: &[(usize, u8)], length: usize) -> Vec<(usize, [u8; 4])> {
+    return (0..length / 4).filter_map(|slot| {
+        let bytes: Vec<u8> = (0..4).filter_map(|i| fixed.iter().find(|&&(offset, _)| offset == slot * 4 + i).map(|&(_, value)| value)).collect();
+        let value: [u8; 4] = bytes.try_into().ok()?;
+        Some((slot, value))
+    }).collect();
+}
+
+/// Checks that a secret is a whole number of words that fits in the secret line.
+fn assert_secret_size(secret_size: usize, line_bytes: usize) {
+    assert!(secret_size > 0 && secret_size <= line_bytes && secret_size.is_multiple_of(4), "Bad secret size: {}", secret_size);
+}
+
+/// Attacks a victim with a secret of whole words, retrying according to the given policy if the attack fails.
+/// An interleaved secret (see SecretPlacement) only needs to take up whole words, and is attacked with
+/// AttackPipeline::interleaved whatever the configured structure.
+/// All attempts share one set of stats; `attempts` records how many configurations were tried.
+/// If any configuration asks for a probed threshold (or set capacity), it is probed once and reused by every attempt.
+/// Words of the secret line that the victim keeps read-only in full are treated as known words when deriving layouts.
+/// No-oracle builds cannot tell whether their answers are right, so they stop retrying once an attempt leaves any.
+pub fn attack_yacc_cpack_with_retries(victim: &mut VictimProgramYACC, secret_size: usize, policy: &RetryPolicy, verbose: bool) -> AttackStats {
+    return resume_attack(victim, secret_size, policy, None, verbose, |_| ());
+}
+
+/// How far an attack had got at the end of one of its phases: enough to carry it on against a victim rebuilt from the
+/// same seed (see resume_attack).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttackCheckpoint {
+    /// The retry policy's configuration being tried, counting the first as 0.
+    pub attempt: usize,
+    /// The last phase finished.
+    pub phase: AttackPhase,
+    pub words: Vec<PartialWord>,
+    /// What the attacker had written to the secret line.
+    pub buffer_state: Vec<u8>,
+    pub stats: AttackStats
+}
+
+/// Same as attack_yacc_cpack_with_retries, but hands a checkpoint to checkpoint at the end of every phase, and, given
+/// one, carries on from it instead of starting over. The victim must be rebuilt from the seed the checkpoint's attack
+/// ran on. A resumed attack rewrites its buffer, skips the phases already finished and starts from the words already
+/// found; it runs on a fresh cache, so its later probes need not match the uninterrupted attack's exactly.
+/// Attempts that migrate the victim or attack an interleaved secret cannot be picked up mid-way, and restart instead.
+pub fn resume_attack(victim: &mut VictimProgramYACC, secret_size: usize, policy: &RetryPolicy, mut from: Option<AttackCheckpoint>,
+                     verbose: bool, mut checkpoint: impl FnMut(AttackCheckpoint)) -> AttackStats {
+    let placement = victim.secret_placement();
+    let span = placement.span(secret_size);
+    assert_secret_size(span, victim.line_size());
+    let mut stats = AttackStats {victim_seed: Some(victim.seed()), ..AttackStats::default()};
+    let first_attempt = from.as_ref().map_or(0, |from| from.attempt);
+    if let Some(from) = &from {stats = from.stats.clone();}
+    let writebacks = victim.writebacks();
+    let mut buffer_state = vec![0u8; victim.line_size() - span];
+    let known = known_words(victim, span);
+    for (attempt, config) in std::iter::once(&policy.first).chain(policy.alternates.iter()).enumerate().skip(first_attempt) {
+        let resumed = from.take();
+        if resumed.is_none() {
+            if stats.attempts > 0 && verbose {println!("Retrying the attack with {:?}", config);}
+            stats.attempts += 1;
+        }
+        let calibrated;
+        let config = if config.threshold == ThresholdSource::Calibrated {
+            if stats.probed_capacity.is_none() {
+                stats.probed_capacity = probe_set_capacity(victim, config, &mut stats);
+                if verbose {println!("Probed set capacity: {:?} entries", stats.probed_capacity);}
+            }
+            calibrated = AttackConfig {associativity: stats.probed_capacity.or(config.associativity), ..config.clone()};
+            &calibrated
+        } else {config};
+        let threshold = attack_threshold(victim, config, &mut stats, verbose);
+        let migrates = matches!(config.interference, InterferenceMitigation::SetMigration {..});
+        if let InterferenceMitigation::SetMigration {canaries, max_migrations} = config.interference {
+            migrate_away_from_interference(victim, config, canaries, max_migrations, &mut buffer_state, &mut stats);
+        }
+        let layout = threshold.and_then(|t| AttackLayout::derive_with_known(t, span, &known, victim.line_size()));
+        let Some(layout) = layout.and_then(|layout| layout.with_dictionary(victim.dictionary_entries())) else {
+            if verbose {println!("No attack string layout works for a threshold of {:?} bytes", threshold);}
+            continue;
+        };
+        let mut pipeline = match placement {
+            SecretPlacement::Contiguous => AttackPipeline::for_structure(config.structure),
+            SecretPlacement::Interleaved => AttackPipeline::interleaved()
+        };
+        if let Some(resumed) = resumed.filter(|_| placement == SecretPlacement::Contiguous && !migrates) {
+            restore_secret_line(victim, &resumed.buffer_state);
+            buffer_state = resumed.buffer_state;
+            pipeline = pipeline.after(resumed.phase).starting_from(resumed.words);
+        }
+        pipeline.run_checkpointed(victim, config, &layout, &mut buffer_state, &mut stats, verbose, |phase, words, buffer_state, stats| {
+            checkpoint(AttackCheckpoint {attempt, phase, words: words.to_vec(), buffer_state: buffer_state.to_vec(), stats: stats.clone()});
+        });
+        if stats.success || !stats.answers.is_empty() || stats.budget_exhausted {break;}
+    }
+    if !stats.success && stats.answers.is_empty() && verbose {println!("Attack failed; the victim can be rebuilt from seed {}", victim.seed());}
+    stats.writebacks += victim.writebacks() - writebacks;
+    return stats;
+}
+
+/// Returns the co-allocation threshold (in bytes) the attack strings are laid out for, as the config's ThresholdSource
+/// gives it. A probed threshold is only probed once, and kept in stats for later attempts.
+fn attack_threshold(victim: &mut VictimProgramYACC, config: &AttackConfig, stats: &mut AttackStats, verbose: bool) -> Option<u64> {
+    return match config.threshold {
+        ThresholdSource::Assumed(bytes) => Some(bytes),
+        ThresholdSource::Probed | ThresholdSource::Calibrated => {
+            if stats.probed_threshold.is_none() {
+                stats.probed_threshold = probe_coallocation_threshold(victim, config, stats);
+                if verbose {println!("Probed co-allocation threshold: {:?} bytes", stats.probed_threshold);}
+            }
+            stats.probed_threshold
+        }
+    };
+}
+
+/// Writes what a checkpointed attack had written to the secret line back into a rebuilt victim's secret line.
+fn restore_secret_line(victim: &mut VictimProgramYACC, buffer_state: &[u8]) {
+    let line_offset = secret_line(victim);
+    let hole = secret_hole(victim, line_offset);
+    for (i, &byte) in buffer_state.iter().enumerate().filter(|&(_, &byte)| byte != 0) {
+        victim.write_byte(string_offset(line_offset, &hole, i), byte);
+    }
+}
+
+/// A baseline that cheats: reads the secret straight out of the simulator and submits it as a single guess.
+/// It makes no probes, so any probe or candidate statistics it reports point to a bug in the stats pipeline.
+#[cfg(not(feature = "no-oracle"))]
+pub fn attack_ground_truth(victim: &mut VictimProgramYACC) -> AttackStats {
+    let mut stats = AttackStats {attempts: 1, ..AttackStats::default()};
+    let mut phase_start = Instant::now();
+    let secret = victim.ground_truth_secret().to_vec();
+    stats.success = victim.validate_secret(&secret);
+    stats.guesses_needed = 1;
+    stats.victim_interactions = 1;
+    if stats.success {stats.secret = secret;}
+    stats.candidate_log2.push(0.0);
+    stats.end_phase(AttackPhase::Validation, &mut phase_start);
+    return stats;
+}
+
+/// A baseline with no side channel: submits guesses to validate_secret until one is right or max_guesses run out.
+/// Guesses follow the victim's assumptions (no zero bytes) and count up from the lowest address, so a secret whose last
+/// bytes are all 0x01 is found within 255^2 guesses.
+#[cfg(not(feature = "no-oracle"))]
+pub fn attack_brute_force(victim: &mut VictimProgramYACC, secret_size: usize, max_guesses: u64) -> AttackStats {
+    let mut stats = AttackStats {attempts: 1, ..AttackStats::default()};
+    let mut phase_start = Instant::now();
+    let space_log2 = secret_size as f64 * BYTE_LOG2;
+    for i in 0..max_guesses {
+        let guess: Vec<u8> = (0..secret_size).map(|b| ((i / 255u64.pow(b as u32)) % 255 + 1) as u8).collect();
+        stats.guesses_needed += 1;
+        stats.victim_interactions += 1;
+        if victim.validate_secret(&guess) {
+            stats.success = true;
+            stats.secret = guess;
+            stats.candidate_log2.push(0.0);
+            break;
+        }
+        stats.candidate_log2.push((space_log2.exp2() - (i + 1) as f64).max(1.0).log2());
+    }
+    stats.end_phase(AttackPhase::Validation, &mut phase_start);
+    return stats;
+}
+
+/// Attacks a victim with the following characteristics:
+/// * Secret is secret_len bytes (a whole number of words, up to a line) and placed at the end of the victim's
+///   BUFFER_SIZE-byte buffer
+/// * All other bytes in the buffer can be read/written by the attacker
+/// * The compressed cache is YACC w/ C-PACK
+/// * The cache replacement policy is LRU
+///
+/// The attack string budgets come from AttackLayout::derive_with_known, for the threshold of the default AttackConfig
+/// (see ThresholdSource) and the cache's line size and dictionary. Every other secret word counts against the budget
+/// as an uncompressed word, so long secrets leave too little room for any layout; the attack then gives up without
+/// probing. The recovered words are validated in every order, so guesses grow factorially with
+/// the number of words.
+pub fn attack_yacc_cpack_secret(victim: &mut VictimProgramYACC, secret_len: usize, verbose: bool) -> AttackStats {
+    assert_secret_size(secret_len, victim.line_size());
+    let mut stats = AttackStats {attempts: 1, victim_seed: Some(victim.seed()), ..AttackStats::default()};
+    let config = AttackConfig::default();
+    let threshold = attack_threshold(victim, &config, &mut stats, verbose);
+    let layout = threshold.and_then(|threshold| AttackLayout::derive_with_known(threshold, secret_len, &[], victim.line_size()));
+    let Some(layout) = layout.and_then(|layout| layout.with_dictionary(victim.dictionary_entries())) else {
+        if verbose {println!("No attack string layout fits a {}-byte secret", secret_len);}
+        return stats;
+    };
+    let mut buffer_state = vec![0u8; victim.line_size() - secret_len];
+    let writebacks = victim.writebacks();
+    AttackPipeline::standard().run(victim, &config, &layout, &mut buffer_state, &mut stats, verbose);
+    stats.writebacks = victim.writebacks() - writebacks;
+    return stats;
+}
+
+/// Attacks a victim with a 4-byte secret, as attack_yacc_cpack_secret.
+pub fn attack_yacc_cpack_4byte_secret(victim: &mut VictimProgramYACC, verbose: bool) -> AttackStats {
+    return attack_yacc_cpack_secret(victim, 4, verbose);
+}
+
+/// Attacks a victim with an 8-byte secret, as attack_yacc_cpack_secret.
+pub fn attack_yacc_cpack_8byte_secret(victim: &mut VictimProgramYACC, verbose: bool) -> AttackStats {
+    return attack_yacc_cpack_secret(victim, 8, verbose);
+}
+
+/// Attacks a victim as attack_yacc_cpack_with_retries does with the default retry policy, but reads every probe with
+/// Evict+Reload on the companion line (see ProbeOracle::EvictReload) instead of prime and probe, as a second channel
+/// to compare the first against.
+pub fn evict_and_reload_yacc_cpack_secret(victim: &mut VictimProgramYACC, secret_size: usize, verbose: bool) -> AttackStats {
+    let mut policy = RetryPolicy::default();
+    for config in std::iter::once(&mut policy.first).chain(policy.alternates.iter_mut()) {
+        config.oracle = ProbeOracle::EvictReload;
+    }
+    return attack_yacc_cpack_with_retries(victim, secret_size, &policy, verbose);
+}
+
+/// What the attack knows about one secret word so far. Words are listed in the order their leading shorts were found,
+/// which is not necessarily their order in the secret.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PartialWord {
+    pub short: Option<u16>,
+    pub second_byte: Option<u8>,
+    pub last_byte: Option<u8>,
+    /// Probes spent on the leading short, the second byte and the last byte. Leading shorts are found together, so
+    /// their probes are split evenly between the words.
+    pub probes: [usize; 3]
+}
+
+impl PartialWord {
+    /// The number of bytes of the word that are known.
+    pub fn known_bytes(&self) -> usize {
+        return 4 - self.unknown_bytes();
+    }
+
+    /// The number of bytes of the word that are still unknown.
+    fn unknown_bytes(&self) -> usize {
+        return 2 * self.short.is_none() as usize + self.second_byte.is_none() as usize + self.last_byte.is_none() as usize;
+    }
+
+    /// The word least significant byte first, if it is fully known.
+    fn bytes(&self) -> Option<[u8; 4]> {
+        let short = self.short?;
+        return Some([self.last_byte?, self.second_byte?, (short & 0xFF) as u8, ((short >> 8) & 0xFF) as u8]);
+    }
+}
+
+/// What the attacker knows at the end of a phase, handed to the observer of AttackPipeline::run_observed so that code
+/// outside the attack can apply its own stopping rules, or stop and hand the words over to another pipeline.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct KnowledgeState {
+    /// The phase that just ended.
+    pub phase: AttackPhase,
+    /// Every word found so far, with the bytes pinned in each, in the order they were found.
+    pub words: Vec<PartialWord>,
+    /// Bytes pinned over all words.
+    pub pinned_bytes: usize,
+    /// log2 of the number of secrets still consistent with what the attacker knows: the last point of the candidate
+    /// series, or every value of the secret if the attack has not recorded any.
+    pub candidate_log2: f64,
+    /// The chance that a single guess drawn from those candidates is right (2^-candidate_log2).
+    pub confidence: f64,
+    /// Probes spent so far, over every attempt.
+    pub probes: usize
+}
+
+/// What the observer of AttackPipeline::run_observed wants the pipeline to do after a phase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PipelineControl {
+    Continue,
+    /// Stop before the next step, leaving the words found so far in the stats' recovered words and validating nothing.
+    Stop
+}
+
+/// Everything a step of the attack works with: the victim, how to probe it, and the running stats.
+pub struct AttackContext<'a> {
+    victim: &'a mut VictimProgramYACC,
+    config: &'a AttackConfig,
+    layout: &'a AttackLayout,
+    buffer_state: &'a mut [u8],
+    stats: &'a mut AttackStats,
+    truth: Option<GroundTruth>,
+    /// The attacker's own random choices (such as random filler), seeded so that a victim's seed replays the whole attack.
+    rng: StdRng,
+    verbose: bool
+}
+
+impl AttackContext<'_> {
+    /// Returns what the attacker knows about the given words at the end of phase.
+    pub fn knowledge(&self, phase: AttackPhase, words: &[PartialWord]) -> KnowledgeState {
+        let pinned_bytes = words.iter().map(|word| 4 - word.unknown_bytes()).sum();
+        let candidate_log2 = self.stats.candidate_log2.0.last().copied().unwrap_or(8.0 * self.victim.secret_size() as f64);
+        return KnowledgeState {phase, words: words.to_vec(), pinned_bytes, candidate_log2, confidence: (-candidate_log2).exp2(), probes: self.stats.set_evictions};
+    }
+
+    /// Primes the secret line with an attack string and probes it. Returns true if the line was co-allocated.
+    pub fn probe(&mut self, attack_string: &[u8]) -> bool {
+        return prime_and_probe_yacc_lru(self.victim, self.config, attack_string, self.buffer_state, self.stats);
+    }
+
+    /// Probes as probe does, then records the secret line's compressed size against the window of the phase's strings
+    /// (unless the string could not be written).
+    fn probe_in_phase(&mut self, phase: AttackPhase, window: (u64, u64), attack_string: &[u8]) -> bool {
+        let unwritable_probes = self.stats.unwritable_probes;
+        let hit = self.probe(attack_string);
+        if self.stats.unwritable_probes == unwritable_probes {self.record_line_size(phase, window);}
+        return hit;
+    }
+
+    /// The size is ground truth, so no-oracle builds skip this.
+    #[cfg(not(feature = "no-oracle"))]
+    fn record_line_size(&mut self, phase: AttackPhase, window: (u64, u64)) {
+        self.stats.size_histograms[phase as usize].record(self.victim.secret_line_bits(), window);
+    }
+
+    #[cfg(feature = "no-oracle")]
+    fn record_line_size(&mut self, _phase: AttackPhase, _window: (u64, u64)) {}
+
+    /// How many of the secret's words could share what words[i] knows so far, going by how many words the other entries
+    /// account for: always 1, unless the attacker allows for arbitrary secrets. A short 0 guessed by LeadingShortStep
+    /// accounts for no word until one of its second bytes turns up, and gets 0 once the other entries account for all.
+    fn multiplicity(&self, words: &[PartialWord], i: usize) -> usize {
+        if self.config.secret_bytes == SecretBytes::UniqueNonZero {return 1;}
+        let secret_words = (self.victim.line_size() - self.buffer_state.len()) / 4;
+        let others = words.iter().enumerate().filter(|&(j, word)| j != i && (word.short != Some(0) || word.second_byte.is_some())).count();
+        return secret_words.saturating_sub(others);
+    }
+
+    /// log2 of the candidates for every unknown byte of every word, except the bytes_in_progress being cracked right now.
+    fn other_unknowns_log2(&self, words: &[PartialWord], bytes_in_progress: usize) -> f64 {
+        let unknown: usize = words.iter().map(PartialWord::unknown_bytes).sum();
+        return (unknown - bytes_in_progress) as f64 * byte_log2(self.config);
+    }
+}
+
+/// The candidates for one part of one secret word, and how to test a group of them with an attack string.
+pub trait CandidateSpace {
+    type Candidate: Copy + Eq + std::hash::Hash + std::fmt::Debug + Into<u16>;
+
+    fn phase(&self) -> AttackPhase;
+
+    /// Every possible value.
+    fn candidates(&self) -> CandidateSet<Self::Candidate>;
+
+    /// The layout of this space's attack strings.
+    fn string_layout<'a>(&self, layout: &'a AttackLayout) -> &'a StringLayout;
+
+    /// Builds an attack string that tests includes, padding with filler that avoids excludes, with its words in memory
+    /// order for a cache that reads them in the given byte order.
+    fn attack_string(&self, filler: &FillerStrategy, rng: &mut StdRng, layout: &StringLayout, includes: &[Self::Candidate], excludes: &CandidateSet<Self::Candidate>, endianness: Endianness) -> Vec<u8>;
+
+    /// Whether any of the candidates is really part of the secret, for labeling probe outcomes.
+    fn contains_secret(&self, truth: &GroundTruth, candidates: &[Self::Candidate]) -> bool;
+}
+
+/// The leading short of every secret word, except the untestable ones (see AttackLayout::untestable_shorts), which are
+/// never tested or used as filler, and those with a byte outside the secret's known distribution. The short 0 is never
+/// tested either: the strings' zero and byte words already match it.
+pub struct LeadingShorts {
+    pub untestable: CandidateSet<u16>,
+    pub distribution: SecretDistribution,
+    pub bytes: SecretBytes
+}
+
+impl CandidateSpace for LeadingShorts {
+    type Candidate = u16;
+
+    fn phase(&self) -> AttackPhase {
+        return AttackPhase::LeadingShort;
+    }
+
+    fn candidates(&self) -> CandidateSet<u16> {
+        return (0x0001..=0xFFFF).filter(|short: &u16| !self.untestable.contains(short) && short.to_le_bytes().iter().all(|&byte| self.distribution.allows_under(byte, self.bytes))).collect();
+    }
+
+    fn string_layout<'a>(&self, layout: &'a AttackLayout) -> &'a StringLayout {
+        return &layout.leading_short;
+    }
+
+    fn attack_string(&self, filler: &FillerStrategy, rng: &mut StdRng, layout: &StringLayout, includes: &[u16], excludes: &CandidateSet<u16>, endianness: Endianness) -> Vec<u8> {
+        let mut excludes = excludes.clone();
+        excludes.union(&self.untestable);
+        return endianness.line_bytes(&make_first_attack_string(filler, rng, layout, includes, &excludes));
+    }
+
+    fn contains_secret(&self, truth: &GroundTruth, candidates: &[u16]) -> bool {
+        return truth.has_short(candidates);
+    }
+}
+
+/// The second-to-least significant byte of the word starting with short, within the secret's known distribution.
+/// A word of three zero bytes compresses on its own whatever the attack string holds, so the short 0 is never tested
+/// with the second byte 0.
+pub struct SecondBytes {
+    pub short: u16,
+    pub distribution: SecretDistribution,
+    pub bytes: SecretBytes
+}
+
+impl CandidateSpace for SecondBytes {
+    type Candidate = u8;
+
+    fn phase(&self) -> AttackPhase {
+        return AttackPhase::SecondByte;
+    }
+
+    fn candidates(&self) -> CandidateSet<u8> {
+        return self.distribution.alphabet_under(self.bytes).into_iter().filter(|&byte| byte != 0 || self.short != 0).collect();
+    }
+
+    fn string_layout<'a>(&self, layout: &'a AttackLayout) -> &'a StringLayout {
+        return &layout.second_byte;
+    }
+
+    fn attack_string(&self, filler: &FillerStrategy, rng: &mut StdRng, layout: &StringLayout, includes: &[u8], excludes: &CandidateSet<u8>, endianness: Endianness) -> Vec<u8> {
+        return endianness.line_bytes(&make_second_attack_string(filler, rng, layout, self.short, includes, excludes));
+    }
 
-#[derive(Debug)]
-pub struct AttackStats {
-    pub success: bool,
-    pub secret: Vec<u8>,
-    pub guesses_needed: usize,
-    pub bytes_written_to_victim: usize,
-    pub bytes_read_from_victim: usize,
-    pub attacker_cache_lines_loaded: usize,
-    pub set_evictions: usize
+    fn contains_secret(&self, truth: &GroundTruth, candidates: &[u8]) -> bool {
+        return truth.has_second_byte(self.short, candidates);
+    }
 }
 
-impl AttackStats {
-    fn new() -> AttackStats {
-        AttackStats {
-            success: false,
-            secret: Vec::new(),
-            guesses_needed: 0,
-            bytes_written_to_victim: 0,
-            bytes_read_from_victim: 0,
-            attacker_cache_lines_loaded: 0,
-            set_evictions: 0
-        }
-    }
+/// The least significant byte of the word starting with short and second_byte, within the secret's known distribution.
+pub struct LastBytes {
+    pub short: u16,
+    pub second_byte: u8,
+    pub distribution: SecretDistribution,
+    pub bytes: SecretBytes
 }
 
-/// Attacks a victim with the following characteristics:
-/// * Secret is 4 bytes and placed at the end of a 256-byte superblock
-/// * All other bytes in the superblock can be read/written by the attacker
-/// * The compressed cache is YACC w/ C-PACK
-/// * The cache associativity is as defined in structures.rs (default: 8)
-/// * The cache replacement policy is LRU
-#[allow(dead_code)]
-pub fn attack_yacc_cpack_4byte_secret(victim: &mut VictimProgramYACC, verbose: bool) -> AttackStats {
-    let mut stats = AttackStats::new();
-    let mut buffer_state = [0u8;60];
-
-    // Step 1: crack the leading 2 bytes (bytes 2 and 3 of the secret).
-    let mut potential_shorts: Vec<u16> = (0x0001..=0xFFFF).collect();
-    // Step 1a: eliminate potential leading 2 bytes in groups of 6.
-    if verbose {println!("Cracking the leading short...")}
-    while potential_shorts.len() > 6 {
-        let mut shorts_to_test: Vec<u16> = Vec::new();
-        for _ in 0..6 {shorts_to_test.push(potential_shorts.pop().unwrap());}
-        let attack_string = make_first_attack_string(&shorts_to_test, &HashSet::new(), 4);
-        if prime_and_probe_yacc_lru(victim, &attack_string, &mut buffer_state, &mut stats) {
-            potential_shorts = shorts_to_test;
-        }
-    }
-    if verbose {println!("Determined that the leading short is one of the following: {:X?}", potential_shorts.as_slice());}
-    // Step 1b: once 6 or fewer candidates are found, find the one that fits.
-    let mut maybe_first_short: Option<u16> = None;
-    let excludes: HashSet<u16> = potential_shorts.iter().map(|&x|x).collect();
-    while !potential_shorts.is_empty() {
-        let short_to_test = potential_shorts.pop().unwrap();
-        let attack_string = make_first_attack_string(&vec![short_to_test], &excludes, 4);
-        if prime_and_probe_yacc_lru(victim, &attack_string, &mut buffer_state, &mut stats) {
-            maybe_first_short = Some(short_to_test);
-            potential_shorts.clear();
-        }
-    }
-    if maybe_first_short.is_none() {
-        // if verbose {
-            println!("Attack failed to find the first short");
-            victim.print_secret_line();
-        // }
-        return stats;
+impl CandidateSpace for LastBytes {
+    type Candidate = u8;
+
+    fn phase(&self) -> AttackPhase {
+        return AttackPhase::LastByte;
     }
-    let first_short = maybe_first_short.unwrap();
-    if verbose {println!("First short found: {:X?}", first_short);}
 
-    // Step 2: crack the second-to-least significant byte (byte 1 of the secret)
-    let maybe_second_byte = crack_second_byte(victim, 4, first_short, &mut buffer_state, &mut stats, verbose);
-    if maybe_second_byte.is_none() {
-        // if verbose {
-            println!("Attack failed to find the second-least byte (the first short is {:X?} though)", first_short);
-            victim.print_secret_line();
-        // }
-        return stats;
+    fn candidates(&self) -> CandidateSet<u8> {
+        return self.distribution.alphabet_under(self.bytes).into_iter().collect();
     }
-    let second_byte = maybe_second_byte.unwrap();
-    if verbose {println!("Second byte found: {:X?}", second_byte);}
 
-    // Step 3: crack the least significant byte (byte 0 of the secret)
-    let maybe_last_byte = crack_last_byte(victim, 4, first_short, second_byte, &mut buffer_state, &mut stats, verbose);
-    if maybe_last_byte.is_none() {
-        // if verbose {
-            println!("Attack failed to find the last byte (the first short and second byte are {:X?} and {:X?} though)", first_short, second_byte);
-            victim.print_secret_line();
-        // }
-        return stats;
+    fn string_layout<'a>(&self, layout: &'a AttackLayout) -> &'a StringLayout {
+        return &layout.last_byte;
+    }
+
+    fn attack_string(&self, filler: &FillerStrategy, rng: &mut StdRng, layout: &StringLayout, includes: &[u8], excludes: &CandidateSet<u8>, endianness: Endianness) -> Vec<u8> {
+        return endianness.line_bytes(&make_third_attack_string(filler, rng, layout, self.short, self.second_byte, includes, excludes));
     }
-    let last_byte = maybe_last_byte.unwrap();
-    if verbose {println!("Last byte found: {:X?}", last_byte);}
-
-    // Step 4: assemble and validate the secret
-    let secret = vec![last_byte, second_byte, (first_short & 0xFF) as u8, ((first_short >> 8) & 0xFF) as u8];
-    let correct = victim.validate_secret(&secret);
-    stats.guesses_needed += 1;
-    if verbose {println!("First guess: {:X?}", secret.as_slice());}
-    if correct {
-        stats.success = true;
-        stats.secret = secret;
-        if verbose {println!("Guess was correct!")}
-    } else if verbose {
-        println!("Guess was wrong")
+
+    fn contains_secret(&self, truth: &GroundTruth, candidates: &[u8]) -> bool {
+        return truth.has_last_byte(self.short, self.second_byte, candidates);
     }
-    return stats;
 }
 
-fn crack_second_byte(victim: &mut VictimProgramYACC, secret_size: usize, first_short: u16, buffer_state: &mut[u8], stats: &mut AttackStats, verbose: bool) -> Option<u8> {
-    let mut potential_second_bytes: Vec<u8> = (0x01..=0xFF).collect();
-    if verbose {println!("Cracking the second byte...")}
-    let throughput = match secret_size {
-        4 => 9,
-        8 => 7,
-        _ => panic!("Bad secret size")
+/// How a candidate space is whittled down by group probes.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum Eliminator {
+    /// For a single match: probe groups until one hits, then keep only that group, and finally probe its members one by one.
+    Narrow,
+    /// For several matches: probe every group, shortlist the members of every group that hits, and probe the shortlist
+    /// one by one until all matches are found.
+    Shortlist {matches: usize}
+}
+
+/// Finds the candidates in space that match the secret, probing with the given eliminator.
+/// other_unknowns_log2 is log2 of the candidates for every other unresolved part of the secret, used for candidate tracking.
+/// Returns the matches in the order they were found, which may be fewer than asked for if the attack went wrong.
+pub fn eliminate<S: CandidateSpace>(ctx: &mut AttackContext, space: &S, eliminator: Eliminator, other_unknowns_log2: f64) -> Vec<S::Candidate> {
+    let phase = space.phase();
+    let calibrated = match ctx.config.secret_bytes {
+        SecretBytes::UniqueNonZero => None,
+        SecretBytes::Arbitrary => match calibrate_free_bits(ctx, space) {
+            Some(layout) => Some(layout),
+            None => {
+                if ctx.verbose {println!("No layout for {:?} leaves a string of filler outside the threshold", phase);}
+                return Vec::new();
+            }
+        }
     };
-    while potential_second_bytes.len() > throughput {
-        let mut second_bytes_to_test: Vec<u8> = Vec::new();
-        for _ in 0..throughput {second_bytes_to_test.push(potential_second_bytes.pop().unwrap());}
-        let attack_string = make_second_attack_string(first_short, &second_bytes_to_test, &HashSet::new(), secret_size);
-        if prime_and_probe_yacc_lru(victim, &attack_string, buffer_state, stats) {
-            potential_second_bytes = second_bytes_to_test;
-        }
-    }
-    if verbose {println!("Determined that the second byte is one of the following: {:X?}", potential_second_bytes.as_slice());}
-    let mut maybe_second_byte: Option<u8> = None;
-    let excludes: HashSet<u8> = potential_second_bytes.iter().map(|&x|x).collect();
-    while !potential_second_bytes.is_empty() {
-        let second_byte_to_test = potential_second_bytes.pop().unwrap();
-        let attack_string = make_second_attack_string(first_short,&vec![second_byte_to_test], &excludes, secret_size);
-        if prime_and_probe_yacc_lru(victim, &attack_string, buffer_state, stats) {
-            maybe_second_byte = Some(second_byte_to_test);
-            potential_second_bytes.clear();
-        }
-    }
-    return maybe_second_byte;
-}
-
-fn crack_last_byte(victim: &mut VictimProgramYACC, secret_size: usize, first_short: u16, second_byte: u8, buffer_state: &mut[u8], stats: &mut AttackStats, verbose: bool) -> Option<u8> {
-    let mut potential_last_bytes: Vec<u8> = (0x01..=0xFF).collect();
-    if verbose {println!("Cracking the last byte...")}
-    let throughput = match secret_size {
-        4 => 14,
-        8 => 12,
-        _ => panic!("Bad secret size")
+    let layout = space.string_layout(calibrated.as_ref().unwrap_or(ctx.layout));
+    let group_size = layout.tests;
+    let matches = match eliminator {
+        Eliminator::Narrow => 1,
+        Eliminator::Shortlist {matches} => matches
     };
-    while potential_last_bytes.len() > throughput {
-        let mut last_bytes_to_test: Vec<u8> = Vec::new();
-        for _ in 0..throughput {last_bytes_to_test.push(potential_last_bytes.pop().unwrap());}
-        let attack_string = make_third_attack_string(first_short, second_byte, &last_bytes_to_test, &HashSet::new(), secret_size);
-        if prime_and_probe_yacc_lru(victim, &attack_string, buffer_state, stats) {
-            potential_last_bytes = last_bytes_to_test;
+    let mut candidates = space.candidates();
+    let mut signals = EliminationSignals {matches, ..EliminationSignals::default()};
+    let split_votes = ctx.stats.split_votes;
+    if ctx.verbose {println!("Cracking {:?}...", phase)}
+    // Step a: eliminate candidates in groups.
+    let mut shortlist: CandidateSet<S::Candidate> = CandidateSet::empty();
+    loop {
+        let remaining = match eliminator {
+            Eliminator::Narrow if candidates.len() > group_size => group_size,
+            Eliminator::Shortlist {..} if !candidates.is_empty() => min(group_size, candidates.len()),
+            _ => break
+        };
+        let group = candidates.split(remaining);
+        let attack_string = space.attack_string(&ctx.config.filler, &mut ctx.rng, layout, group.as_slice(), &CandidateSet::empty(), ctx.victim.endianness());
+        let hit = ctx.probe_in_phase(phase, layout.bit_window, &attack_string);
+        // A probe the victim stopped serving partway through tells nothing, so elimination ends with what it had confirmed.
+        if ctx.victim.access_budget_exhausted() {break;}
+        if let Some(truth) = &ctx.truth {ctx.stats.record_probe(phase, hit, space.contains_secret(truth, group.as_slice()));}
+        signals.group_probes += 1;
+        signals.group_hits += usize::from(hit);
+        let remaining = match (hit, eliminator) {
+            (true, Eliminator::Narrow) => group.len(),
+            (true, Eliminator::Shortlist {..}) => candidates.len() + shortlist.len() + group.len(),
+            (false, _) => candidates.len() + shortlist.len()
+        };
+        ctx.stats.trace_candidates(group.as_slice(), remaining);
+        if hit {
+            match eliminator {
+                Eliminator::Narrow => candidates = group,
+                Eliminator::Shortlist {..} => shortlist.union(&group)
+            }
         }
+        // The group and the shortlist never overlap, so the remaining candidates are both of them together.
+        ctx.stats.candidate_log2.push(matches as f64 * log2_candidates(candidates.len() + shortlist.len()) + other_unknowns_log2);
     }
-    if verbose {println!("Determined that the last byte is one of the following: {:X?}", potential_last_bytes.as_slice());}
-    let mut maybe_last_byte: Option<u8> = None;
-    let excludes: HashSet<u8> = potential_last_bytes.iter().map(|&x|x).collect();
-    while !potential_last_bytes.is_empty() {
-        let last_byte_to_test = potential_last_bytes.pop().unwrap();
-        let attack_string = make_third_attack_string(first_short, second_byte,&vec![last_byte_to_test], &excludes, secret_size);
-        if prime_and_probe_yacc_lru(victim, &attack_string, buffer_state, stats) {
-            maybe_last_byte = Some(last_byte_to_test);
-            potential_last_bytes.clear();
+    if eliminator == Eliminator::Narrow {shortlist = candidates;}
+    if ctx.verbose {println!("Determined that the {} {:?} matches are among the following: {:X?}", matches, phase, shortlist.as_slice());}
+    // Step b: once few enough candidates are left, find the ones that fit.
+    let mut found: Vec<S::Candidate> = Vec::new();
+    let excludes = shortlist.clone();
+    while found.len() < matches && !ctx.victim.access_budget_exhausted() {
+        let Some(candidate) = shortlist.pop() else {break};
+        let attack_string = space.attack_string(&ctx.config.filler, &mut ctx.rng, layout, &[candidate], &excludes, ctx.victim.endianness());
+        let hit = ctx.probe_in_phase(phase, layout.bit_window, &attack_string);
+        if ctx.victim.access_budget_exhausted() {break;}
+        if let Some(truth) = &ctx.truth {ctx.stats.record_probe(phase, hit, space.contains_secret(truth, &[candidate]));}
+        if hit {found.push(candidate);}
+        signals.single_probes += 1;
+        signals.single_hits += usize::from(hit);
+        let unresolved = matches - found.len();
+        ctx.stats.trace_candidates(&[candidate], shortlist.len() + found.len());
+        ctx.stats.candidate_log2.push(unresolved as f64 * shortlist.entropy() + other_unknowns_log2);
+    }
+    signals.split_votes = ctx.stats.split_votes - split_votes;
+    ctx.stats.signals[phase as usize] += signals;
+    return found;
+}
+
+/// For secrets of arbitrary bytes, finds the fewest bits the secret's words could be saving by compressing on their own
+/// (see AttackLayout::with_free_bits) for which a string of filler alone, which should match nothing, is not co-allocated,
+/// and returns the layouts allowing for them. Strings that match still fit the threshold with those layouts, since allowing
+/// for fewer bits than the secret really saves only leaves the line smaller.
+fn calibrate_free_bits<S: CandidateSpace>(ctx: &mut AttackContext, space: &S) -> Option<AttackLayout> {
+    let most = 32 * (ctx.layout.secret_size as u64 / 4);
+    let mut tried: Option<(usize, usize, usize, usize)> = None;
+    for free_bits in (0..=most).step_by(2) {
+        let Some(layout) = ctx.layout.with_free_bits(free_bits) else {continue};
+        let string = *space.string_layout(&layout);
+        if tried == Some((string.tests, string.padding_words, string.byte_words, string.zero_words)) {continue;}
+        tried = Some((string.tests, string.padding_words, string.byte_words, string.zero_words));
+        // Random filler, twice over, in case the first string happens to match the secret.
+        let missed = (0..2).any(|_| {
+            let attack_string = space.attack_string(&FillerStrategy::HighEntropyRandom, &mut ctx.rng, &string, &[], &CandidateSet::empty(), ctx.victim.endianness());
+            return !ctx.probe(&attack_string);
+        });
+        if missed {
+            if ctx.verbose {println!("Calibrated {:?}: the secret's words save {} bits", space.phase(), free_bits);}
+            return Some(layout);
         }
     }
-    return maybe_last_byte;
+    return None;
 }
 
-/// Attacks a victim with the following characteristics:
-/// * Secret is 8 bytes and placed at the end of a 256-byte superblock
-/// * All other bytes in the superblock can be read/written by the attacker
-/// * The compressed cache is YACC w/ C-PACK
-/// * The cache associativity is as defined in structures.rs (default: 8)
-/// * The cache replacement policy is LRU
-#[allow(dead_code)]
-pub fn attack_yacc_cpack_8byte_secret(victim: &mut VictimProgramYACC, verbose: bool) -> AttackStats {
-    let mut stats = AttackStats::new();
-    let mut buffer_state = [0u8;56];
-
-    // Step 1: crack the leading 2 bytes of each secret word (bytes 2 and 3 of the secret).
-    let mut potential_shorts: Vec<u16> = (0x0001..=0xFFFF).collect();
-    // Step 1a: eliminate potential leading 2 bytes in groups of 6.
-    if verbose {println!("Cracking the leading shorts...")}
-    let mut shorts_shortlist: Vec<u16> = Vec::new();
-    while !potential_shorts.is_empty() {
-        let mut shorts_to_test: Vec<u16> = Vec::new();
-        for _ in 0..min(5,potential_shorts.len()) {shorts_to_test.push(potential_shorts.pop().unwrap());}
-        let attack_string = make_first_attack_string(&shorts_to_test, &HashSet::new(), 8);
-        if prime_and_probe_yacc_lru(victim, &attack_string, &mut buffer_state, &mut stats) {
-            for s in shorts_to_test {shorts_shortlist.push(s);}
-        }
-    }
-    if verbose {println!("Determined that the leading shorts are two of the following: {:X?}", shorts_shortlist.as_slice());}
-
-    // Step 1b: Find the two shorts in the shortlist that start the two words.
-    let mut maybe_short1: Option<u16> = None;
-    let mut maybe_short2: Option<u16> = None;
-    let excludes: HashSet<u16> = shorts_shortlist.iter().map(|&x|x).collect();
-    while !shorts_shortlist.is_empty() && maybe_short2.is_none() {
-        let short_to_test = shorts_shortlist.pop().unwrap();
-        let attack_string = make_first_attack_string(&vec![short_to_test], &excludes, 8);
-        if prime_and_probe_yacc_lru(victim, &attack_string, &mut buffer_state, &mut stats) {
-            if maybe_short1.is_none() {maybe_short1 = Some(short_to_test);}
-            else {
-                maybe_short2 = Some(short_to_test);
+/// Narrow for a single match, and Shortlist for more.
+fn eliminator_for(matches: usize) -> Eliminator {
+    return if matches == 1 {Eliminator::Narrow} else {Eliminator::Shortlist {matches}};
+}
+
+/// One step of an attack pipeline. Each step fills in more of the partially known secret words, and returns
+/// a description of what went wrong if it could not.
+pub trait AttackStep {
+    /// The phase the step's time and probes are charged to.
+    fn phase(&self) -> AttackPhase;
+
+    fn run(&self, ctx: &mut AttackContext, words: &mut Vec<PartialWord>) -> Result<(), String>;
+
+    /// Describes what the step does, attacking with the given layout, when the steps before it found words words (or,
+    /// for the first step, when the secret has that many).
+    fn describe(&self, layout: &AttackLayout, words: usize) -> StepDescription;
+}
+
+/// What one step of an attack does, worked out from the same code that runs it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StepDescription {
+    pub phase: AttackPhase,
+    /// The part of each word the step recovers.
+    pub target: String,
+    /// How many of the secret's words the step recovers that part of.
+    pub words: usize,
+    /// The values the part can take.
+    pub candidates: usize,
+    pub eliminator: Eliminator,
+    /// The candidates each group probe tests at once, which is the number of test words in the step's attack strings.
+    pub group_size: usize,
+    pub string: StringLayout
+}
+
+impl StepDescription {
+    fn new<S: CandidateSpace>(space: &S, layout: &AttackLayout, target: &str, words: usize, eliminator: Eliminator) -> StepDescription {
+        let string = *space.string_layout(layout);
+        return StepDescription {phase: space.phase(), target: target.to_string(), words, candidates: space.candidates().len(), eliminator, group_size: string.tests, string};
+    }
+}
+
+/// Finds the leading short of every secret word, starting the list of words.
+pub struct LeadingShortStep;
+
+impl AttackStep for LeadingShortStep {
+    fn phase(&self) -> AttackPhase {
+        return AttackPhase::LeadingShort;
+    }
+
+    fn run(&self, ctx: &mut AttackContext, words: &mut Vec<PartialWord>) -> Result<(), String> {
+        let count = (ctx.victim.line_size() - ctx.buffer_state.len()) / 4;
+        let before = ctx.stats.set_evictions;
+        let space = LeadingShorts {untestable: ctx.layout.untestable_shorts.clone(), distribution: ctx.config.known_distribution, bytes: ctx.config.secret_bytes};
+        let mut shorts = eliminate(ctx, &space, eliminator_for(count), count as f64 * 2.0 * byte_log2(ctx.config));
+        if shorts.len() < count && !ctx.victim.access_budget_exhausted() {
+            if ctx.config.secret_bytes == SecretBytes::UniqueNonZero {return Err(format!("Attack failed to find the leading shorts (found {:X?})", shorts));}
+            // The words left over either repeat a short already found or start with the short 0, which cannot be tested.
+            // The short 0 is kept as a guess, and dropped by SecondByteStep if none of its second bytes turn up.
+            shorts.push(0);
+        }
+        if ctx.verbose {println!("Leading shorts found: {:X?}", shorts);}
+        let probes = ctx.stats.set_evictions - before;
+        words.extend(shorts.into_iter().enumerate().map(|(i, short)| {
+            let share = probes / count + usize::from(i < probes % count);
+            PartialWord {short: Some(short), probes: [share, 0, 0], ..PartialWord::default()}
+        }));
+        return Ok(());
+    }
+
+    fn describe(&self, layout: &AttackLayout, words: usize) -> StepDescription {
+        let eliminator = eliminator_for(words);
+        let space = LeadingShorts {untestable: layout.untestable_shorts.clone(), distribution: SecretDistribution::UniformBytes, bytes: SecretBytes::UniqueNonZero};
+        return StepDescription::new(&space, layout, "leading short", words, eliminator);
+    }
+}
+
+/// Finds the leading short of just one secret word, for secrets whose other words follow from any one of them.
+pub struct OneLeadingShortStep;
+
+impl AttackStep for OneLeadingShortStep {
+    fn phase(&self) -> AttackPhase {
+        return AttackPhase::LeadingShort;
+    }
+
+    fn run(&self, ctx: &mut AttackContext, words: &mut Vec<PartialWord>) -> Result<(), String> {
+        let space = LeadingShorts {untestable: ctx.layout.untestable_shorts.clone(), distribution: ctx.config.known_distribution, bytes: ctx.config.secret_bytes};
+        let before = ctx.stats.set_evictions;
+        let Some(&short) = eliminate(ctx, &space, Eliminator::Narrow, 2.0 * byte_log2(ctx.config)).first() else {
+            if ctx.victim.access_budget_exhausted() {return Ok(());}
+            return Err("Attack failed to find a leading short".to_string());
+        };
+        if ctx.verbose {println!("Leading short found: {:X?}", short);}
+        words.push(PartialWord {short: Some(short), probes: [ctx.stats.set_evictions - before, 0, 0], ..PartialWord::default()});
+        return Ok(());
+    }
+
+    fn describe(&self, layout: &AttackLayout, _words: usize) -> StepDescription {
+        let space = LeadingShorts {untestable: layout.untestable_shorts.clone(), distribution: SecretDistribution::UniformBytes, bytes: SecretBytes::UniqueNonZero};
+        return StepDescription::new(&space, layout, "leading short", 1, Eliminator::Narrow);
+    }
+}
+
+/// Writes a leading short of the attacker's choosing into the high half of every word of an interleaved secret, starting
+/// the list of words in memory order. Each word gets its own short, so the later steps test one word at a time (mmxx to
+/// mmmx, then mmmx to mmmm) and the words never have to be put in order. Spends no probes.
+pub struct ChosenShortStep;
+
+impl AttackStep for ChosenShortStep {
+    fn phase(&self) -> AttackPhase {
+        return AttackPhase::LeadingShort;
+    }
+
+    fn run(&self, ctx: &mut AttackContext, words: &mut Vec<PartialWord>) -> Result<(), String> {
+        let start = ctx.victim.secret_start();
+        let count = (ctx.victim.line_size() - ctx.buffer_state.len()) / 4;
+        let shorts: Vec<u16> = (0x0101u16..).filter(|short| !ctx.layout.untestable_shorts.contains(short)).take(count).collect();
+        for (i, &short) in shorts.iter().enumerate() {
+            for (j, byte) in short.to_le_bytes().into_iter().enumerate() {
+                let written = ctx.victim.write_byte(start + 4 * i + ctx.victim.endianness().byte_offset(2 + j), byte);
+                ctx.stats.victim_interactions += 1;
+                ctx.stats.victim_write_calls += 1;
+                if !written {return Err(format!("The victim would not take a leading short for word {}, so its secret is not interleaved", i));}
+                ctx.stats.bytes_written_to_victim += 1;
+            }
+            words.push(PartialWord {short: Some(short), ..PartialWord::default()});
+        }
+        // The secret words now hold the chosen shorts, which probe outcomes are labeled against.
+        ctx.truth = GroundTruth::of(ctx.victim);
+        if ctx.verbose {println!("Leading shorts written: {:X?}", shorts);}
+        return Ok(());
+    }
+
+    fn describe(&self, _layout: &AttackLayout, words: usize) -> StepDescription {
+        let string = StringLayout {tests: 0, padding_words: 0, byte_words: 0, zero_words: 0, tests_last: false, bit_window: (0, 0)};
+        return StepDescription {phase: AttackPhase::LeadingShort, target: "leading short (written by the attacker)".to_string(), words,
+                                candidates: 1, eliminator: Eliminator::Narrow, group_size: 0, string};
+    }
+}
+
+/// Finds the second-to-least significant byte of every word whose leading short is known.
+pub struct SecondByteStep;
+
+impl AttackStep for SecondByteStep {
+    fn phase(&self) -> AttackPhase {
+        return AttackPhase::SecondByte;
+    }
+
+    fn run(&self, ctx: &mut AttackContext, words: &mut Vec<PartialWord>) -> Result<(), String> {
+        let arbitrary = ctx.config.secret_bytes == SecretBytes::Arbitrary;
+        for i in 0..words.len() {
+            let Some(short) = words[i].short else {continue};
+            let other_unknowns_log2 = ctx.other_unknowns_log2(words, 1);
+            let before = ctx.stats.set_evictions;
+            let space = SecondBytes {short, distribution: ctx.config.known_distribution, bytes: ctx.config.secret_bytes};
+            let matches = ctx.multiplicity(words, i);
+            if matches == 0 {continue;}
+            let found = eliminate(ctx, &space, eliminator_for(matches), other_unknowns_log2);
+            words[i].probes[1] = ctx.stats.set_evictions - before;
+            if ctx.victim.access_budget_exhausted() && found.is_empty() {return Ok(());}
+            match found.split_first() {
+                Some((&byte, more)) => {
+                    words[i].second_byte = Some(byte);
+                    words.extend(more.iter().map(|&byte| PartialWord {short: Some(short), second_byte: Some(byte), ..PartialWord::default()}));
+                },
+                None if arbitrary && short == 0 => (),
+                None => return Err(format!("Attack failed to find the second-least byte of the word starting with {:X?}", short))
+            }
+        }
+        // A short 0 guessed by LeadingShortStep that no second byte turned up for was not part of the secret after all.
+        if arbitrary {words.retain(|word| word.short != Some(0) || word.second_byte.is_some());}
+        if ctx.verbose {println!("Second bytes found: {:X?}", words);}
+        return Ok(());
+    }
+
+    fn describe(&self, layout: &AttackLayout, words: usize) -> StepDescription {
+        return StepDescription::new(&SecondBytes {short: 0, distribution: SecretDistribution::UniformBytes, bytes: SecretBytes::UniqueNonZero}, layout, "second byte", words, Eliminator::Narrow);
+    }
+}
+
+/// Finds the least significant byte of every word whose leading short and second byte are known.
+pub struct LastByteStep;
+
+impl AttackStep for LastByteStep {
+    fn phase(&self) -> AttackPhase {
+        return AttackPhase::LastByte;
+    }
+
+    fn run(&self, ctx: &mut AttackContext, words: &mut Vec<PartialWord>) -> Result<(), String> {
+        for i in 0..words.len() {
+            let (Some(short), Some(second_byte)) = (words[i].short, words[i].second_byte) else {continue};
+            let other_unknowns_log2 = ctx.other_unknowns_log2(words, 1);
+            let before = ctx.stats.set_evictions;
+            let space = LastBytes {short, second_byte, distribution: ctx.config.known_distribution, bytes: ctx.config.secret_bytes};
+            let found = eliminate(ctx, &space, eliminator_for(ctx.multiplicity(words, i).max(1)), other_unknowns_log2);
+            words[i].probes[2] = ctx.stats.set_evictions - before;
+            if ctx.victim.access_budget_exhausted() && found.is_empty() {return Ok(());}
+            match found.split_first() {
+                Some((&byte, more)) => {
+                    words[i].last_byte = Some(byte);
+                    words.extend(more.iter().map(|&byte| PartialWord {short: Some(short), second_byte: Some(second_byte), last_byte: Some(byte), ..PartialWord::default()}));
+                },
+                None => return Err(format!("Attack failed to find the last byte of the word starting with {:X?} {:X?}", short, second_byte))
+            }
+        }
+        if ctx.verbose {println!("Last bytes found: {:X?}", words);}
+        return Ok(());
+    }
+
+    fn describe(&self, layout: &AttackLayout, words: usize) -> StepDescription {
+        return StepDescription::new(&LastBytes {short: 0, second_byte: 0, distribution: SecretDistribution::UniformBytes, bytes: SecretBytes::UniqueNonZero}, layout, "last byte", words, Eliminator::Narrow);
+    }
+}
+
+/// Turns the recovered words (least significant byte first) into the guesses to validate for a secret of the given
+/// number of words, most likely first, in memory order for the given byte order.
+pub type Assembler = fn(&[[u8; 4]], usize, Endianness) -> Vec<Vec<u8>>;
+
+/// The steps of an attack, run in order, followed by validation of the guesses assembled from the recovered words.
+pub struct AttackPipeline {
+    steps: Vec<Box<dyn AttackStep + Send + Sync>>,
+    assemble: Assembler,
+    /// Words some earlier attack already found, which the steps start from.
+    known: Vec<PartialWord>
+}
+
+impl AttackPipeline {
+    /// A pipeline with no steps, to register steps into. It guesses every ordering of the recovered words.
+    pub fn empty() -> AttackPipeline {
+        return AttackPipeline {steps: Vec::new(), assemble: every_order, known: Vec::new()};
+    }
+
+    /// The original attack: leading shorts, then second bytes, then last bytes.
+    pub fn standard() -> AttackPipeline {
+        return AttackPipeline {steps: vec![Box::new(LeadingShortStep), Box::new(SecondByteStep), Box::new(LastByteStep)], assemble: every_order, known: Vec::new()};
+    }
+
+    /// The attack on a key schedule victim: recovers a single word, then guesses the schedule through it at every position.
+    pub fn key_schedule() -> AttackPipeline {
+        return AttackPipeline {steps: vec![Box::new(OneLeadingShortStep), Box::new(SecondByteStep), Box::new(LastByteStep)], assemble: schedules_through, known: Vec::new()};
+    }
+
+    /// The attack on an interleaved secret: writes each word's leading short, then finds its second and last bytes, which
+    /// are the secret. Since each word has its own short, there is only one guess.
+    pub fn interleaved() -> AttackPipeline {
+        return AttackPipeline {steps: vec![Box::new(ChosenShortStep), Box::new(SecondByteStep), Box::new(LastByteStep)], assemble: low_halves, known: Vec::new()};
+    }
+
+    /// The pipeline that attacks secrets with the given structure.
+    pub fn for_structure(structure: SecretStructure) -> AttackPipeline {
+        return match structure {
+            SecretStructure::Independent => AttackPipeline::standard(),
+            SecretStructure::KeySchedule => AttackPipeline::key_schedule()
+        };
+    }
+
+    /// Describes every step of the pipeline against a secret of secret_words words, attacked with the given layout.
+    pub fn describe(&self, layout: &AttackLayout, secret_words: usize) -> Vec<StepDescription> {
+        let mut words = secret_words;
+        return self.steps.iter().map(|step| {
+            let description = step.describe(layout, words);
+            words = description.words;
+            description
+        }).collect();
+    }
+
+    /// Appends a step to the pipeline.
+    pub fn register(mut self, step: impl AttackStep + Send + Sync + 'static) -> AttackPipeline {
+        self.steps.push(Box::new(step));
+        return self;
+    }
+
+    /// Starts the steps from words an earlier attack already found (such as the words of the KnowledgeState another
+    /// pipeline stopped at), instead of from nothing.
+    pub fn starting_from(mut self, words: Vec<PartialWord>) -> AttackPipeline {
+        self.known = words;
+        return self;
+    }
+
+    /// Drops every step of the given phase or an earlier one, for carrying on an attack that already finished them.
+    pub fn after(mut self, phase: AttackPhase) -> AttackPipeline {
+        self.steps.retain(|step| step.phase() as usize > phase as usize);
+        return self;
+    }
+
+    /// Runs the pipeline, accumulating into existing stats.
+    /// buffer_state must reflect what the attacker has already written to the victim's secret line.
+    pub fn run(&self, victim: &mut VictimProgramYACC, config: &AttackConfig, layout: &AttackLayout, buffer_state: &mut [u8], stats: &mut AttackStats, verbose: bool) {
+        self.run_observed(victim, config, layout, buffer_state, stats, verbose, |_| PipelineControl::Continue);
+    }
+
+    /// Same as run, but hands what the attacker knows to observe after every step, and stops early if it says to.
+    #[allow(clippy::too_many_arguments)]
+    pub fn run_observed(&self, victim: &mut VictimProgramYACC, config: &AttackConfig, layout: &AttackLayout, buffer_state: &mut [u8], stats: &mut AttackStats,
+                        verbose: bool, mut observe: impl FnMut(&KnowledgeState) -> PipelineControl) {
+        self.run_with(victim, config, layout, buffer_state, stats, verbose, |state, _, _| observe(state));
+    }
+
+    /// Same as run, but hands the phase just finished, the words found so far, the buffer and the stats to checkpoint
+    /// after every step.
+    #[allow(clippy::too_many_arguments)]
+    pub fn run_checkpointed(&self, victim: &mut VictimProgramYACC, config: &AttackConfig, layout: &AttackLayout, buffer_state: &mut [u8], stats: &mut AttackStats,
+                            verbose: bool, mut checkpoint: impl FnMut(AttackPhase, &[PartialWord], &[u8], &AttackStats)) {
+        self.run_with(victim, config, layout, buffer_state, stats, verbose, |state, buffer_state, stats| {
+            checkpoint(state.phase, &state.words, buffer_state, stats);
+            return PipelineControl::Continue;
+        });
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn run_with(&self, victim: &mut VictimProgramYACC, config: &AttackConfig, layout: &AttackLayout, buffer_state: &mut [u8], stats: &mut AttackStats,
+                verbose: bool, mut observe: impl FnMut(&KnowledgeState, &[u8], &AttackStats) -> PipelineControl) {
+        let mut phase_start = Instant::now();
+        let truth = GroundTruth::of(victim);
+        let rng = StdRng::seed_from_u64(RngStream::Attacker.seed(victim.seed()) ^ stats.attempts as u64);
+        stats.phase_mark = stats.cost_totals();
+        let mut ctx = AttackContext {victim, config, layout, buffer_state, stats, truth, rng, verbose};
+        let mut words: Vec<PartialWord> = self.known.clone();
+        let earlier_signals = ctx.stats.signals[AttackPhase::LeadingShort as usize];
+        for (i, step) in self.steps.iter().enumerate() {
+            let result = step.run(&mut ctx, &mut words);
+            ctx.stats.end_phase(step.phase(), &mut phase_start);
+            if ctx.victim.access_budget_exhausted() {
+                // Elimination stops at the first probe the victim refused, so the words hold only what was confirmed before it.
+                if ctx.verbose {println!("The victim stopped serving calls during the {:?} phase; keeping the words found so far: {:X?}", step.phase(), words);}
+                ctx.stats.recovered_words = words;
+                ctx.stats.budget_exhausted = true;
+                return;
+            }
+            ctx.stats.recovered_words = words.clone();
+            if let Err(e) = result {
+                println!("{}", e);
+                #[cfg(not(feature = "no-oracle"))]
+                ctx.victim.print_secret_line();
+                return;
+            }
+            if step.phase() == AttackPhase::LeadingShort {
+                let remaining = &self.describe(ctx.layout, words.len())[i + 1..];
+                let predicted = predict_success(&ctx.stats.signals[AttackPhase::LeadingShort as usize].since(&earlier_signals), remaining);
+                ctx.stats.predicted_success = Some(predicted);
+                if ctx.config.abort_below.is_some_and(|threshold| predicted < threshold) {
+                    if ctx.verbose {println!("Giving up after the leading shorts: predicted a {:.1}% chance of success", predicted * 100.0);}
+                    ctx.stats.early_aborts += 1;
+                    return;
+                }
+            }
+            if observe(&ctx.knowledge(step.phase(), &words), ctx.buffer_state, ctx.stats) == PipelineControl::Stop {return;}
+        }
+        let Some(known) = words.iter().map(PartialWord::bytes).collect::<Option<Vec<[u8; 4]>>>() else {
+            println!("Attack finished without recovering every word: {:X?}", words);
+            return;
+        };
+        let secret_words = (ctx.victim.line_size() - ctx.buffer_state.len()) / 4;
+        let endianness = ctx.victim.endianness();
+        Self::validate(&mut ctx, (self.assemble)(&known, secret_words, endianness), phase_start);
+    }
+
+    /// Submits the guesses to the victim until one is right.
+    #[cfg(not(feature = "no-oracle"))]
+    fn validate(ctx: &mut AttackContext, guesses: Vec<Vec<u8>>, mut phase_start: Instant) {
+        let verbose = ctx.verbose;
+        for guess in guesses {
+            let correct = ctx.victim.validate_secret(&guess);
+            ctx.stats.guesses_needed += 1;
+            ctx.stats.victim_interactions += 1;
+            if verbose {println!("Guess {}: {:X?}", ctx.stats.guesses_needed, guess.as_slice());}
+            if correct {
+                ctx.stats.success = true;
+                ctx.stats.secret = guess;
+                if verbose {println!("Guess was correct!")}
                 break;
             }
         }
+        if !ctx.stats.success && verbose {println!("Every guess was wrong")}
+        ctx.stats.end_phase(AttackPhase::Validation, &mut phase_start);
     }
-    if maybe_short1.is_none() || maybe_short2.is_none() {
-        // if verbose {
-        println!("Attack failed to find the first shorts");
-        victim.print_secret_line();
-        // }
-        return stats;
+
+    /// Leaves every guess as an answer, since there is no oracle to check them against.
+    #[cfg(feature = "no-oracle")]
+    fn validate(ctx: &mut AttackContext, guesses: Vec<Vec<u8>>, mut phase_start: Instant) {
+        for answer in guesses {
+            if ctx.verbose {println!("Answer {}: {:X?}", ctx.stats.answers.len() + 1, answer.as_slice());}
+            ctx.stats.answers.push(answer);
+        }
+        ctx.stats.end_phase(AttackPhase::Validation, &mut phase_start);
     }
-    let short1 = maybe_short1.unwrap();
-    let short2 = maybe_short2.unwrap();
-    if verbose {println!("First shorts found: {:X?} {:X?}", short1, short2);}
-
-    // Step 2: crack the second-to-least significant bytes (byte 1 of the secret)
-    let maybe_second_byte1 = crack_second_byte(victim, 8, short1, &mut buffer_state, &mut stats, verbose);
-    let maybe_second_byte2 = crack_second_byte(victim, 8, short2, &mut buffer_state, &mut stats, verbose);
-    if maybe_second_byte1.is_none() || maybe_second_byte2.is_none() {
-        // if verbose {
-        println!("Attack failed to find the second-least bytes (the first shorts are {:X?} and {:X?} though)", short1, short2);
-        victim.print_secret_line();
-        // }
-        return stats;
+}
+
+/// Guesses every order of the words, since the probes cannot tell them apart. Fewer words than the secret has means
+/// some of them repeat (which only an attack on arbitrary secrets finds), so every arrangement using each word at least
+/// once is guessed instead, and after those, every arrangement that adds a word whose upper three bytes are zero, which
+/// compresses on its own whatever the attack string holds and so is never found.
+fn every_order(words: &[[u8; 4]], secret_words: usize, endianness: Endianness) -> Vec<Vec<u8>> {
+    let arrange = |words: &[[u8; 4]]| -> Vec<Vec<u8>> {
+        let orders = if words.len() < secret_words {covering_arrangements(words.len(), secret_words)} else {permutations(words.len())};
+        return orders.into_iter().map(|order| order.iter().flat_map(|&i| endianness.word_bytes(words[i])).collect()).collect();
+    };
+    let mut guesses = arrange(words);
+    if words.len() < secret_words {
+        for byte in 0x00..=0xFF {
+            let mut with_byte = words.to_vec();
+            with_byte.push([byte, 0, 0, 0]);
+            guesses.extend(arrange(&with_byte));
+        }
     }
-    let second_byte1 = maybe_second_byte1.unwrap();
-    let second_byte2 = maybe_second_byte2.unwrap();
-    if verbose {println!("Second bytes found: {:X?} {:X?}", second_byte1, second_byte2);}
-
-    // Step 3: crack the least significant bytes (byte 0 of the secret)
-    let maybe_last_byte1 = crack_last_byte(victim, 8, short1, second_byte1, &mut buffer_state, &mut stats, verbose);
-    let maybe_last_byte2 = crack_last_byte(victim, 8, short2, second_byte2, &mut buffer_state, &mut stats, verbose);
-    if maybe_last_byte1.is_none() || maybe_last_byte2.is_none() {
-        // if verbose {
-        println!("Attack failed to find the last bytes (the first shorts and second bytes are {:X?} {:X?} {:X?} {:X?} though)", short1, short2, second_byte1, second_byte2);
-        victim.print_secret_line();
-        // }
-        return stats;
+    return guesses;
+}
+
+/// Every sequence of length n over 0..k that holds each of 0..k at least once.
+fn covering_arrangements(k: usize, n: usize) -> Vec<Vec<usize>> {
+    fn extend(k: usize, n: usize, prefix: &mut Vec<usize>, out: &mut Vec<Vec<usize>>) {
+        let unused = (0..k).filter(|i| !prefix.contains(i)).count();
+        if prefix.len() == n {
+            if unused == 0 {out.push(prefix.clone());}
+            return;
+        }
+        if n - prefix.len() < unused {return;}
+        for i in 0..k {
+            prefix.push(i);
+            extend(k, n, prefix, out);
+            prefix.pop();
+        }
     }
-    let last_byte1 = maybe_last_byte1.unwrap();
-    let last_byte2 = maybe_last_byte2.unwrap();
-    if verbose {println!("Last bytes found: {:X?} {:X?}", last_byte1, last_byte2);}
-
-    // Step 4: assemble and validate the secret
-    let secret1 = vec![last_byte1, second_byte1, (short1 & 0xFF) as u8, ((short1 >> 8) & 0xFF) as u8, last_byte2, second_byte2, (short2 & 0xFF) as u8, ((short2 >> 8) & 0xFF) as u8];
-    let secret2 = vec![last_byte2, second_byte2, (short2 & 0xFF) as u8, ((short2 >> 8) & 0xFF) as u8, last_byte1, second_byte1, (short1 & 0xFF) as u8, ((short1 >> 8) & 0xFF) as u8];
-    let correct1 = victim.validate_secret(&secret1);
-    let correct2 = victim.validate_secret(&secret2);
-    if verbose {
-        println!("First guess: {:X?}", secret1.as_slice());
-        println!("Second guess (if needed): {:X?}", secret2.as_slice());
-    }
-    if correct1 { // We'll assume the first secret was guessed first
-        stats.success = true;
-        stats.secret = secret1;
-        stats.guesses_needed += 1;
-        if verbose {println!("First guess was correct!")}
-    } else if correct2 {
-        stats.success = true;
-        stats.secret = secret2;
-        stats.guesses_needed += 2;
-        if verbose {println!("Second guess was correct!")}
-    } else if verbose {
-        println!("Both guesses were wrong")
+    let mut out = Vec::new();
+    extend(k, n, &mut Vec::new(), &mut out);
+    return out;
+}
+
+/// Guesses the low halves of the words in order, which is where an interleaved secret keeps its bytes.
+fn low_halves(words: &[[u8; 4]], _secret_words: usize, endianness: Endianness) -> Vec<Vec<u8>> {
+    let low = endianness.byte_offset(0).min(endianness.byte_offset(1));
+    return vec![words.iter().flat_map(|&word| endianness.word_bytes(word)[low..low + 2].to_vec()).collect()];
+}
+
+/// Guesses the key schedule through the first word at each of the four positions it could hold, skipping repeats.
+fn schedules_through(words: &[[u8; 4]], _secret_words: usize, endianness: Endianness) -> Vec<Vec<u8>> {
+    let Some(word) = words.first().map(|&word| endianness.word_bytes(word)) else {return Vec::new()};
+    let mut guesses: Vec<Vec<u8>> = Vec::new();
+    for position in 0..keyschedule::SCHEDULE_SIZE / 4 {
+        let guess = keyschedule::schedule_through(word, position).to_vec();
+        if !guesses.contains(&guess) {guesses.push(guess);}
     }
-    return stats;
+    return guesses;
+}
+
+/// Every ordering of 0..n, starting with the identity.
+fn permutations(n: usize) -> Vec<Vec<usize>> {
+    if n == 0 {return vec![Vec::new()];}
+    let mut orders = Vec::new();
+    for first in 0..n {
+        for rest in permutations(n - 1) {
+            let mut order = vec![first];
+            order.extend(rest.into_iter().map(|i| if i >= first {i + 1} else {i}));
+            orders.push(order);
+        }
+    }
+    return orders;
 }
 
 /// Given a victim and attack string, determines if the attack string makes the victim's secret cache line compressible to 32B.
 /// Returns true if 32B compression occurred, false otherwise.
-fn prime_and_probe_yacc_lru(victim: &mut VictimProgramYACC, attack_string: &Vec<u8>, buffer_state: &mut [u8], stats: &mut AttackStats) -> bool {
-    // Step 1: prime the victim's secret cache line with the attack string (changing as few bytes as needed).
-    for i in 0..attack_string.len() {
-        if attack_string[i] != buffer_state[i] {
-            assert!(victim.write_byte(192 + i, attack_string[i])); // Make sure we're not writing OoB
-            buffer_state[i] = attack_string[i];
-            stats.bytes_written_to_victim += 1;
+/// With ProbeRetries, a negative result is re-probed (the string is already in place, so only the flush and reload repeat).
+/// With relocation detection, a probe after which the buffer turns out to have moved is primed again from scratch and
+/// repeated, up to MAX_RELOCATION_REPROBES times.
+fn prime_and_probe_yacc_lru(victim: &mut VictimProgramYACC, config: &AttackConfig, attack_string: &[u8], buffer_state: &mut [u8], stats: &mut AttackStats) -> bool {
+    let attempts = match config.interference {
+        InterferenceMitigation::ProbeRetries {attempts} => attempts.max(1),
+        _ => 1
+    };
+    let mut reprobes = 0;
+    loop {
+        let before = if config.detect_relocation {buffer_state.to_vec()} else {Vec::new()};
+        let hit = (0..attempts).any(|_| prime_and_probe_line(victim, config, secret_line(victim), attack_string, buffer_state, stats));
+        if !config.detect_relocation || reprobes == MAX_RELOCATION_REPROBES || !buffer_moved(victim, &before, buffer_state, stats) {return hit;}
+        buffer_state.fill(0);
+        reprobes += 1;
+    }
+}
+
+/// Probes repeated in a row after finding the victim's buffer moved, before taking the last one's result anyway.
+const MAX_RELOCATION_REPROBES: usize = 3;
+
+/// Reads back a non-zero byte of the secret line that the last probe left alone (so not a read-only byte, which a move
+/// keeps, nor one the probe just wrote, which would be there even in a moved buffer), given the line's state before and
+/// after the probe, and reports whether it was wiped, which means the victim moved its buffer. If the probe rewrote every
+/// non-zero byte, the line was primed in full anyway and there is nothing to check.
+fn buffer_moved(victim: &mut VictimProgramYACC, before: &[u8], buffer_state: &[u8], stats: &mut AttackStats) -> bool {
+    let line_offset = secret_line(victim);
+    let fixed = writable_read_only(victim, line_offset);
+    let untouched = |i: usize| before[i] != 0 && buffer_state[i] == before[i] && fixed.iter().all(|&(offset, _)| offset != i);
+    let Some(i) = (0..buffer_state.len()).find(|&i| untouched(i)) else {return false};
+    let read = victim.read_byte(string_offset(line_offset, &secret_hole(victim, line_offset), i));
+    stats.bytes_read_from_victim += 1;
+    stats.victim_interactions += 1;
+    if read.is_none_or(|byte| byte == buffer_state[i]) {return false;}
+    stats.relocations_detected += 1;
+    return true;
+}
+
+/// Checks for interference with canary probes of an all-zero line (which must always be co-allocated), and relocates the
+/// victim's buffer while they fail, up to max_migrations times. Resets buffer_state if the buffer moved.
+fn migrate_away_from_interference(victim: &mut VictimProgramYACC, config: &AttackConfig, canaries: usize, max_migrations: usize, buffer_state: &mut [u8], stats: &mut AttackStats) {
+    let zeros = vec![0u8; victim.line_size()];
+    let Some(line) = spare_line(victim) else {return};
+    for _ in 0..max_migrations {
+        let mut line_state = vec![0u8; victim.line_size()];
+        if (0..canaries).all(|_| prime_and_probe_line(victim, config, line, &zeros, &mut line_state, stats)) {return;}
+        victim.relocate_buffer();
+        stats.victim_interactions += 1;
+        stats.migrations += 1;
+        buffer_state.fill(0);
+    }
+}
+
+/// Grooms a heap-model victim: allocates chunks until one lands next to the secret, giving up after max_allocations.
+/// Each chunk is tested with a string that only overflows the co-allocation threshold if the rest of its line holds the
+/// (non-zero) secret, so a negative probe means the chunk shares the secret's line. That chunk is then zeroed again with
+/// one more probe, leaving the line the way the attack expects to find it. Returns whether grooming succeeded.
+pub fn groom_heap(victim: &mut VictimProgramYACC, config: &AttackConfig, threshold_bytes: u64, secret_size: usize, max_allocations: usize, stats: &mut AttackStats) -> bool {
+    let Some(string) = adjacency_string(threshold_bytes, secret_size, victim.line_size(), victim.endianness()) else {return false};
+    let mut line_state = vec![0u8; victim.line_size() - secret_size];
+    for _ in 0..max_allocations {
+        victim.allocate_chunk();
+        stats.heap_allocations += 1;
+        stats.victim_interactions += 1;
+        line_state.fill(0);
+        if !prime_and_probe_yacc_lru(victim, config, &string, &mut line_state, stats) {
+            prime_and_probe_yacc_lru(victim, config, &vec![0u8; victim.line_size() - secret_size], &mut line_state, stats);
+            return true;
         }
     }
-    // Step 2: flush all victim lines from the cache
-    for i in 0..ASSOCIATIVITY {
-        victim.cache().read_byte((i as u64) * 256); // Read from a different superblock each time to prevent compression
-        stats.attacker_cache_lines_loaded += 1;
+    return false;
+}
+
+/// Builds a string for the writable part of a line_bytes-byte secret line that fits the threshold when the rest of the line is zero,
+/// but not when it holds secret_size bytes of secret, even if every secret word compresses against one of the string's
+/// words (to mmmx at best, since the test words have distinct leading shorts). The string is in memory order for a cache
+/// that reads words in the given byte order.
+fn adjacency_string(threshold_bytes: u64, secret_size: usize, line_bytes: usize, endianness: Endianness) -> Option<Vec<u8>> {
+    let words = (line_bytes - secret_size) / 4;
+    let secret_words = secret_size as u64 / 4;
+    let threshold_bits = threshold_bytes * 8;
+    for tests in (0..=words).rev() {
+        for byte_words in 0..=(words - tests) {
+            let zero_words = words - tests - byte_words;
+            let total = 34 * tests as u64 + 12 * byte_words as u64 + 2 * zero_words as u64;
+            if total + 2 * secret_words <= threshold_bits && total + 16 * secret_words > threshold_bits {
+                let layout = StringLayout {tests, padding_words: 0, byte_words, zero_words, tests_last: false, bit_window: (total + 2 * secret_words, total + 34 * secret_words)};
+                return Some(endianness.line_bytes(&assemble_attack_string(&layout, (0..tests).map(|i| [0x11, 0x22, i as u8 + 1, 0xA0]), &[])));
+            }
+        }
     }
-    stats.set_evictions += 1;
-    // Step 3: reload the primed secret line and one of the other lines in the superblock (which should be all zeros, very compressible)
-    victim.read_byte(192);
-    victim.read_byte(0);
-    stats.bytes_read_from_victim += 2;
+    return None;
+}
+
+/// Same as prime_and_probe_yacc_lru, but primes the line of the victim's buffer starting at line_offset,
+/// whose current contents (as far as the attacker has written them) are line_state. If the line holds the secret, the
+/// attack string and line_state skip over the secret's words, wherever in the line they are.
+/// An attack string shorter than line_state leaves out the line's known words (those read-only in full), which are spliced
+/// back in around it. If the line holds other read-only bytes, the attack string is first rearranged around them; if that
+/// is impossible, or the victim
+/// refuses a write the attacker did not expect it to (as it does for canaries), the probe is skipped and counted as negative.
+fn prime_and_probe_line(victim: &mut VictimProgramYACC, config: &AttackConfig, line_offset: usize, attack_string: &[u8], line_state: &mut [u8], stats: &mut AttackStats) -> bool {
+    // Step 0: work around any bytes the victim will not let us write.
+    let hole = secret_hole(victim, line_offset);
+    let fixed = writable_read_only(victim, line_offset);
+    let spliced;
+    let attack_string = if attack_string.len() == line_state.len() {attack_string} else {
+        spliced = splice_known_words(attack_string, &fixed, line_state.len());
+        &spliced[..]
+    };
+    let routed;
+    let attack_string = if fixed.is_empty() {attack_string} else {
+        match route_around_read_only(attack_string, &fixed) {
+            Some(string) => {routed = string; &routed[..]},
+            None => {
+                stats.unwritable_probes += 1;
+                return false;
+            }
+        }
+    };
+    for &(offset, value) in &fixed {line_state[offset] = value;}
+    // Step 1: prime the victim's line with the attack string (changing as few bytes as needed).
+    match config.prime {
+        PrimeMethod::ByteWrites => {
+            for i in 0..attack_string.len() {
+                if attack_string[i] != line_state[i] {
+                    if !victim.write_byte(string_offset(line_offset, &hole, i), attack_string[i]) {
+                        stats.victim_interactions += 1;
+                        stats.victim_write_calls += 1;
+                        if !victim.access_budget_exhausted() {stats.unwritable_probes += 1;}
+                        return false;
+                    }
+                    line_state[i] = attack_string[i];
+                    stats.bytes_written_to_victim += 1;
+                    stats.victim_interactions += 1;
+                    stats.victim_write_calls += 1;
+                }
+            }
+        },
+        PrimeMethod::Memcpy => {
+            // One message for each part of the line on either side of the secret's words.
+            let split = (hole.start - line_offset).min(attack_string.len());
+            for part in [0..split, split..attack_string.len()] {
+                if part.is_empty() || attack_string[part.clone()] == line_state[part.clone()] {continue;}
+                if !victim.store_message(string_offset(line_offset, &hole, part.start), &attack_string[part.clone()]) {
+                    stats.victim_interactions += 1;
+                    stats.victim_write_calls += 1;
+                    if !victim.access_budget_exhausted() {stats.unwritable_probes += 1;}
+                    return false;
+                }
+                line_state[part.clone()].copy_from_slice(&attack_string[part.clone()]);
+                stats.bytes_written_to_victim += part.len();
+                stats.victim_interactions += 1;
+                stats.victim_write_calls += 1;
+            }
+        }
+    }
+    if config.oracle == ProbeOracle::DecompressionLatency {return decompression_latency_check(victim, stats);}
+    let primed = string_offset(line_offset, &hole, 0);
+    if config.oracle == ProbeOracle::EvictReload {return evict_reload_check(victim, config, primed, line_state[0], stats);}
+    // Steps 2 to 4 once, or over several rounds that are put to a vote.
+    let Some(voting) = config.voting else {return flush_reload_check(victim, config, primed, line_state[0], stats)};
+    let hits = (0..voting.rounds).filter(|_| vote_round(victim, config, primed, line_state[0], stats)).count();
+    if hits > 0 && hits < voting.rounds {stats.split_votes += 1;}
+    return hits >= voting.min_hits;
+}
+
+/// The probe step of prime_and_probe_line, with the line already primed: flushes the set, has the victim reload its line,
+/// and checks whether the attacker's check line survived. primed is the first offset the attacker wrote in the primed line,
+/// and first_byte the value there.
+fn flush_reload_check(victim: &mut VictimProgramYACC, config: &AttackConfig, primed: usize, first_byte: u8, stats: &mut AttackStats) -> bool {
+    let flush_set = flush_and_reload(victim, config, primed, first_byte, false, stats);
+    let associativity = set_capacity(victim, config);
     // Step 4: since we know the replacement algorithm is LRU, there is only a need to check the second-to-least recently used attacker block.
     // The least recently used block was definitely evicted, but the second-to-least might still be present if compression occurred.
     // So, if accessing the second-to-least recently used block is a hit, then compression occurred.
-    let time = victim.cache().read_byte(256).1;
+    let check = flush_set[config.flush.check_index(associativity)];
+    #[cfg(not(feature = "no-oracle"))]
+    if config.record_lru {stats.last_snapshot = Some(LruSnapshot::take(victim, primed, check));}
+    if config.oracle == ProbeOracle::MissCounter {
+        let before = victim.cache().read_miss_counter();
+        victim.cache().read_byte(check);
+        stats.attacker_cache_lines_loaded += 1;
+        return victim.cache().read_miss_counter() == before;
+    }
+    let resident = victim.cache().is_resident(check);
+    if config.oracle == ProbeOracle::Residency {return resident;}
+    let time = victim.cache().read_byte(check).1;
     stats.attacker_cache_lines_loaded += 1;
     let success = time == AccessSpeed::HIT;
+    if config.oracle == ProbeOracle::Dual {
+        stats.oracle_report.probes += 1;
+        if success != resident {stats.oracle_report.disagreements += 1;}
+        stats.last_readings = Some((success, resident));
+    }
     // if success {
     //     victim.print_secret_line();
     //     victim.print_compressibility();
@@ -311,230 +2403,342 @@ fn prime_and_probe_yacc_lru(victim: &mut VictimProgramYACC, attack_string: &Vec<
     return success;
 }
 
-/// Creates an attack string that helps deduce the upper two bytes in a 4-byte C-PACK word.
-/// includes: the set of shorts to target in the attack string. Should be 1-6 shorts for 4B secrets and 1-5 for 8B secrets.
-/// excludes: the set of shorts to explicitly avoid targeting in the attack string.
-/// secret_size: the size of the secret.
-fn make_first_attack_string(includes: &Vec<u16>, excludes: &HashSet<u16>, secret_size: usize) -> Vec<u8> {
-    if secret_size == 4 {
-        // Words to write: 15
-        // Bits to compress to: 224 to 232
-        // 224 bits is the minimum needed to make compression to 32B impossible if the last word is incompressible
-        // 232 bits is the maximum that allows compression to 32B if the last word compresses to 24 bits using the match-except-last-short rule
-        // 6 uncompressed short-testing words can be included (34 compressed bits each)
-        // Adding 8 zero words and 1 byte-word takes it up to 226, which is within the bounds
-        let mut attack_string: Vec<u8> = Vec::with_capacity(60);
-        if includes.len() < 1 || includes.len() > 6 {
-            panic!("Bad number of shorts to include")
-        }
-        for &include in includes { // Push all short-testing words
-            attack_string.push(0);
-            attack_string.push(0);
-            attack_string.push((include & 0xFF) as u8);
-            attack_string.push(((include >> 8) & 0xFF) as u8);
-        }
-        if includes.len() < 6 {
-            let mut valid_filler: Vec<u16> = (1u16..=100).filter(|x|!includes.contains(x) && !excludes.contains(x)).rev().collect();
-            for _ in 0..(6 - includes.len()) { // Push other short-testing words as filler
-                let short = valid_filler.pop().unwrap();
-                attack_string.push(0);
-                attack_string.push(0);
-                attack_string.push((short & 0xFF) as u8);
-                attack_string.push(((short >> 8) & 0xFF) as u8);
+/// The probe step of prime_and_probe_line for ProbeOracle::DecompressionLatency, with the line already primed. Writing the
+/// secret line re-inserted it, next to the companion line (see companion_offset) if both fit, so the victim's read of the
+/// companion hits either way, but only needs decompressing if they share an entry. A read that misses is retried once,
+/// since the companion is only resident after the first probe.
+fn decompression_latency_check(victim: &mut VictimProgramYACC, stats: &mut AttackStats) -> bool {
+    let (uncompressed, compressed) = victim.cache().hit_latencies();
+    for _ in 0..2 {
+        let start = victim.now();
+        victim.read_byte(companion_offset(victim));
+        stats.bytes_read_from_victim += 1;
+        stats.victim_interactions += 1;
+        let load = victim.now() - start - victim.latency_model().api_call;
+        if load <= compressed {return load > uncompressed;}
+    }
+    return false;
+}
+
+/// The probe step of prime_and_probe_line for ProbeOracle::EvictReload, with the line already primed. After the flush,
+/// the attacker loads the companion line itself and the victim reloads its secret line, which joins the companion's
+/// entry if both fit. Under LRU, re-reading all but the newest flush line then evicts the companion only if the secret
+/// line took an entry of its own, so the attacker's timed reload of the companion hits exactly when they were co-allocated.
+/// Counted as negative if the victim does not share the companion's line with the attacker.
+fn evict_reload_check(victim: &mut VictimProgramYACC, config: &AttackConfig, primed: usize, first_byte: u8, stats: &mut AttackStats) -> bool {
+    let Some(companion) = victim.shared_address(companion_offset(victim)) else {return false};
+    let flush_set = flush(victim, config, primed, false, stats);
+    victim.cache().read_byte(companion);
+    stats.attacker_cache_lines_loaded += 1;
+    reload(victim, config, &[(primed, first_byte)], stats);
+    let associativity = set_capacity(victim, config);
+    for &addr in &flush_set[flush_set.len().saturating_sub(associativity)..flush_set.len() - 1] {
+        victim.cache().read_byte(addr);
+        stats.attacker_cache_lines_loaded += 1;
+    }
+    stats.attacker_cache_lines_loaded += 1;
+    return victim.cache().read_byte(companion).1 == AccessSpeed::HIT;
+}
+
+/// The number of entries the attacker takes the target set to have (see AttackConfig::associativity).
+fn set_capacity(victim: &mut VictimProgramYACC, config: &AttackConfig) -> usize {
+    return config.associativity.unwrap_or_else(|| victim.cache().associativity());
+}
+
+/// The most passes over the flush lines a settling flush makes before giving up on the set holding only those lines.
+const MAX_SETTLE_PASSES: usize = 16;
+
+/// Steps 2 and 3 of a probe: flushes the set, then has the victim reload its line. With settle, the flush goes on until
+/// the set holds nothing but the last associativity flush lines, which random replacement does not guarantee after one
+/// pass. Returns the flush lines, in the order they were first read.
+fn flush_and_reload(victim: &mut VictimProgramYACC, config: &AttackConfig, primed: usize, first_byte: u8, settle: bool, stats: &mut AttackStats) -> Vec<u64> {
+    let flush_set = flush(victim, config, primed, settle, stats);
+    // Step 3: reload the primed line and (unless the victim brings its own) one of the other lines in the superblock (which should be all zeros, very compressible)
+    let companion = companion_offset(victim);
+    let order: Vec<(usize, u8)> = match config.probe {
+        ProbeStrategy::SecretThenCompanion => vec![(primed, first_byte), (companion, 0)],
+        ProbeStrategy::SecretOnly => vec![(primed, first_byte)]
+    };
+    reload(victim, config, &order, stats);
+    return flush_set;
+}
+
+/// Step 2 of a probe: flushes the set holding the primed line, settling it as flush_and_reload describes. Returns the
+/// flush lines, in the order they were first read.
+fn flush(victim: &mut VictimProgramYACC, config: &AttackConfig, primed: usize, settle: bool, stats: &mut AttackStats) -> Vec<u64> {
+    // Step 2: flush all victim lines from the cache
+    if config.evict_private_cache {
+        stats.attacker_cache_lines_loaded += victim.cache().evict_private_cache();
+    }
+    let set = victim.buffer_set(primed);
+    let associativity = set_capacity(victim, config);
+    let cache = victim.cache();
+    let flush_set = config.addresses.flush_set(&config.flush, &cache, associativity, config.address_offset, set, stats.set_evictions);
+    let contents = config.flush.line_contents(victim.line_size(), victim.endianness());
+    for &addr in &flush_set {
+        // With one line per superblock, flush lines cannot be co-allocated with each other, whatever their contents
+        let line = victim.line_of(addr);
+        victim.cache().initialize_line(line, &contents);
+        if victim.cache().read_byte(addr).1 == AccessSpeed::HIT {stats.flush_hits += 1;}
+        stats.attacker_cache_lines_loaded += 1;
+    }
+    if settle {
+        // Read the last associativity lines again until they all hit, so that they are all the set holds.
+        let last = &flush_set[flush_set.len().saturating_sub(associativity)..];
+        for _ in 0..MAX_SETTLE_PASSES {
+            let mut misses = 0;
+            for &addr in last {
+                if victim.cache().read_byte(addr).1 == AccessSpeed::MISS {misses += 1;}
+                stats.attacker_cache_lines_loaded += 1;
             }
+            if misses == 0 {break;}
+        }
+    }
+    stats.set_evictions += 1;
+    return flush_set;
+}
+
+/// Step 3 of a probe: has the victim reload each (buffer offset, value already there) in order, keeping to the stealth
+/// budget if there is one.
+fn reload(victim: &mut VictimProgramYACC, config: &AttackConfig, order: &[(usize, u8)], stats: &mut AttackStats) {
+    if let Some(budget) = config.stealth {
+        // Every reload right after a flush is a victim miss, so that is what each probe spends.
+        let cost = order.len() as u64;
+        let window = victim.now() / budget.window;
+        if stats.pacing.0 != window {stats.pacing = (window, 0);}
+        if stats.pacing.1 + cost > budget.max_victim_misses {
+            let wait = (window + 1) * budget.window - victim.now();
+            victim.idle(wait);
+            stats.idle_ticks += wait;
+            stats.pacing = (window + 1, 0);
         }
-        // Finally, push one word that's just a zero-extended byte, followed by 8 zero words
-        attack_string.push(0xFF);
-        for _ in 0..35 {attack_string.push(0);}
-
-        assert_eq!(attack_string.len(), 60);
-        return attack_string;
-    } else if secret_size == 8 {
-        // Words to write: 14
-        // Bits to compress to: 190 to 198
-        // 190 bits is the minimum needed to make compression to 32B impossible if the last two words are incompressible
-        // 198 bits is the max that allows 32B compression if one of the last two words compresses to 24 bits
-        // 5 uncompressed short-testing words can be included (34 compressed bits each)
-        // Adding 8 zero words and 1 byte-word takes it up to 192, which is within the bounds
-        let mut attack_string: Vec<u8> = Vec::with_capacity(56);
-        if includes.len() < 1 || includes.len() > 5 {
-            panic!("Bad number of shorts to include")
-        }
-        for &include in includes { // Push all short-testing words
-            attack_string.push(0);
-            attack_string.push(0);
-            attack_string.push((include & 0xFF) as u8);
-            attack_string.push(((include >> 8) & 0xFF) as u8);
-        }
-        if includes.len() < 5 {
-            let mut valid_filler: Vec<u16> = (1u16..=100).filter(|x|!includes.contains(x) && !excludes.contains(x)).rev().collect();
-            for _ in 0..(5 - includes.len()) { // Push other random short-testing words as filler
-                let short = valid_filler.pop().unwrap();
-                attack_string.push(0);
-                attack_string.push(0);
-                attack_string.push((short & 0xFF) as u8);
-                attack_string.push(((short >> 8) & 0xFF) as u8);
+        stats.pacing.1 += cost;
+    }
+    for &(index, known_value) in order {
+        match config.reload {
+            ReloadMethod::Read => {
+                victim.read_byte(index);
+                stats.bytes_read_from_victim += 1;
+            },
+            ReloadMethod::Store => {
+                victim.write_byte(index, known_value);
+                stats.bytes_written_to_victim += 1;
+                stats.victim_write_calls += 1;
             }
         }
-        // Finally, push one word that's just a zero-extended byte, followed by 8 zero words
-        attack_string.push(0xFF);
-        for _ in 0..35 {attack_string.push(0);}
+        stats.victim_interactions += 1;
+    }
+}
 
-        assert_eq!(attack_string.len(), 56);
-        return attack_string;
-    } else {
-        panic!("Bad secret size")
+/// One round of a voted probe. The single check of flush_reload_check relies on LRU order; this one does not. It settles
+/// the flush so that the set holds only the last associativity flush lines, and after the victim reloads, reads all of
+/// them back: co-allocated victim lines evict one of them, and separate ones at least two. Positive if at most one missed.
+/// The lines are always timed, whatever the configured oracle.
+fn vote_round(victim: &mut VictimProgramYACC, config: &AttackConfig, primed: usize, first_byte: u8, stats: &mut AttackStats) -> bool {
+    let flush_set = flush_and_reload(victim, config, primed, first_byte, true, stats);
+    let associativity = set_capacity(victim, config);
+    // Newest first, so that under LRU the reads of surviving lines evict nothing, and the first miss evicts a victim line.
+    let mut misses = 0;
+    for &addr in flush_set.iter().rev().take(associativity) {
+        if victim.cache().read_byte(addr).1 == AccessSpeed::MISS {misses += 1;}
+        stats.attacker_cache_lines_loaded += 1;
     }
+    return misses <= 1;
 }
 
-/// Creates an attack string that helps deduce the second-to-least significant bit of a 4-byte C-PACK word.
-/// short: the upper 2 bytes of the secret
-/// includes: the set of bytes to target in the attack string. Should be 1-9 bytes for 4B secrets and 1-7 for 8B secrets.
-/// excludes: the set of bytes to explicitly avoid targeting in the attack string.
-/// secret_size: the size of the secret.
-fn make_second_attack_string(short: u16, includes: &Vec<u8>, excludes: &HashSet<u8>, secret_size: usize) -> Vec<u8> {
-    if secret_size == 4 {
-        // Words to write: 15
-        // Bits to compress to: 234 to 240 (must allow 32B compression when last word compresses to 16 bits, but not when it's 24 bits)
-        // One byte-testing word at the front will be uncompressed (34 bits)
-        // Following byte-testing words will be compressed due to the match-except-the-last-short rule (24 bits each)
-        // 9 byte-testing words can be accommodated including the first one (total of 226 bits)
-        // This leaves 6 words which can be all zeros (12 bits for all) which totals 238 bits, within the bounds
-        let mut attack_string: Vec<u8> = Vec::with_capacity(60);
-        if includes.len() < 1 || includes.len() > 9 {
-            panic!("Bad number of bytes to include")
-        }
-        for &include in includes { // Push all byte-testing words
-            attack_string.push(0);
-            attack_string.push(include);
-            attack_string.push((short & 0xFF) as u8);
-            attack_string.push(((short >> 8) & 0xFF) as u8);
-        }
-        if includes.len() < 9 {
-            let mut valid_filler: Vec<u8> = (1u8..=100).filter(|x|!includes.contains(x) && !excludes.contains(x)).rev().collect();
-            for _ in 0..(9-includes.len()) { // Push other byte-testing words as filler
-                let byte = valid_filler.pop().unwrap();
-                attack_string.push(0);
-                attack_string.push(byte);
-                attack_string.push((short & 0xFF) as u8);
-                attack_string.push(((short >> 8) & 0xFF) as u8);
+/// Builds a full line of line_bytes bytes that C-PACK compresses to exactly bits bits, out of xxxx, mmxx, mmmx, zzzx,
+/// mmmm and zero words. Returns None if no such line exists (bit counts are always even, at least 2 per word, at most
+/// the uncompressed line, and some small counts are unreachable). The line is in memory order for a cache that reads
+/// words in the given byte order.
+fn calibration_line(bits: u64, line_bytes: usize, endianness: Endianness) -> Option<Vec<u8>> {
+    let words = line_bytes as u64 / 4;
+    if !(2 * words..=8 * line_bytes as u64).contains(&bits) || !bits.is_multiple_of(2) {return None;}
+    let extra = bits - 2 * words; // Bits on top of an all-zero line
+    for xxxx in 0..=words {
+        for mmxx in 0..=(words - xxxx) {
+            for mmmx in 0..=(words - xxxx - mmxx) {
+                for zzzx in 0..=(words - xxxx - mmxx - mmmx) {
+                    let used = 32 * xxxx + 22 * mmxx + 14 * mmmx + 10 * zzzx;
+                    if used > extra || !(extra - used).is_multiple_of(4) {continue;}
+                    let mmmm = (extra - used) / 4;
+                    if xxxx + mmxx + mmmx + zzzx + mmmm > words || (xxxx == 0 && mmxx + mmmx + mmmm > 0) {continue;}
+                    // Every matching word matches the first uncompressed word, 0x80A55A01.
+                    let mut line_words: Vec<[u8; 4]> = Vec::new();
+                    line_words.extend((0..xxxx).map(|i| [0x01, 0x5A, 0xA5, 0x80 + i as u8]));
+                    line_words.extend((0..mmxx).map(|i| [0x01, 0x10 + i as u8, 0xA5, 0x80]));
+                    line_words.extend((0..mmmx).map(|i| [0x02 + i as u8, 0x5A, 0xA5, 0x80]));
+                    line_words.extend((0..zzzx).map(|i| [0x01 + i as u8, 0, 0, 0]));
+                    line_words.extend((0..mmmm).map(|_| [0x01, 0x5A, 0xA5, 0x80]));
+                    let mut line = vec![0u8; line_bytes];
+                    for (i, word) in line_words.iter().enumerate() {line[i*4..i*4+4].copy_from_slice(word);}
+                    assert_eq!(cpack_bits(&line), bits);
+                    return Some(endianness.line_bytes(&line));
+                }
             }
         }
-        // Finally, push 6 zero words
-        for _ in 0..24 {attack_string.push(0);}
-
-        assert_eq!(attack_string.len(), 60);
-        return attack_string;
-    } else if secret_size == 8 {
-        // Words to write: 14
-        // Bits to compress to: 200 to 206 (must allow 32B compression when words compress to 34 and 16 bits, but not 34 and 24 bits)
-        // One byte-testing word at the front will be uncompressed (34 bits)
-        // Following byte-testing words will be compressed due to the match-except-the-last-short rule (24 bits each)
-        // 7 byte-testing words can be accommodated including the first one (total of 178 bits)
-        // This leaves 7 words which can be 1 zero-extended byte and 6 zero bytes to total 202 bits
-        let mut attack_string: Vec<u8> = Vec::with_capacity(56);
-        if includes.len() < 1 || includes.len() > 7 {
-            panic!("Bad number of bytes to include")
-        }
-        for &include in includes { // Push all byte-testing words
-            attack_string.push(0);
-            attack_string.push(include);
-            attack_string.push((short & 0xFF) as u8);
-            attack_string.push(((short >> 8) & 0xFF) as u8);
-        }
-        if includes.len() < 7 {
-            let mut valid_filler: Vec<u8> = (1u8..=100).filter(|x|!includes.contains(x) && !excludes.contains(x)).rev().collect();
-            for _ in 0..(7-includes.len()) { // Push other byte-testing words as filler
-                let byte = valid_filler.pop().unwrap();
-                attack_string.push(0);
-                attack_string.push(byte);
-                attack_string.push((short & 0xFF) as u8);
-                attack_string.push(((short >> 8) & 0xFF) as u8);
-            }
+    }
+    return None;
+}
+
+/// Differential probing: finds the largest compressed size (in bytes) at which a line is still co-allocated
+/// with the all-zero line at the start of its superblock, without knowing anything about the cache but its compressor.
+/// Binary searches over calibration lines of increasing compressed size, written to a line of the victim's buffer that
+/// the attacker fully controls (see spare_line), which is zeroed again afterwards.
+/// Returns None if not even an all-zero line is co-allocated.
+pub fn probe_coallocation_threshold(victim: &mut VictimProgramYACC, config: &AttackConfig, stats: &mut AttackStats) -> Option<u64> {
+    let line_bytes = victim.line_size();
+    let sizes: Vec<(u64, Vec<u8>)> = (0..=8 * line_bytes as u64).step_by(2).filter_map(|bits| calibration_line(bits, line_bytes, victim.endianness()).map(|line| (bits, line))).collect();
+    let mut line_state = vec![0u8; line_bytes];
+    let spare = spare_line(victim)?;
+    let mut probe = |line: &[u8], line_state: &mut [u8], stats: &mut AttackStats| prime_and_probe_line(victim, config, spare, line, line_state, stats);
+    if !probe(&sizes[0].1, &mut line_state, stats) {return None;}
+    // Invariant: sizes[low] is co-allocated, and sizes[high] is not (or is past the end).
+    let (mut low, mut high) = (0, sizes.len());
+    while high - low > 1 {
+        let mid = (low + high) / 2;
+        if probe(&sizes[mid].1, &mut line_state, stats) {low = mid;} else {high = mid;}
+    }
+    let zeros = vec![0u8; line_bytes];
+    probe(&zeros, &mut line_state, stats);
+    return Some(sizes[low].0.div_ceil(8));
+}
+
+/// The most entries probe_set_capacity looks for in a set.
+const MAX_SET_CAPACITY: usize = 64;
+
+/// Finds the number of entries in the set holding the victim's secret line without being told: reads k incompressible
+/// lines of its own into the set, each in a superblock of its own so that each takes an entry, then reads the first one
+/// again. Under LRU, it is only evicted once k exceeds the set's capacity, so the capacity is one less than the smallest
+/// such k. Returns None if even MAX_SET_CAPACITY lines do not evict it.
+pub fn probe_set_capacity(victim: &mut VictimProgramYACC, config: &AttackConfig, stats: &mut AttackStats) -> Option<usize> {
+    let set = victim.buffer_set(secret_line(victim));
+    let contents = calibration_line(8 * victim.line_size() as u64, victim.line_size(), victim.endianness()).expect("Some line does not compress at all");
+    let lines: Vec<u64> = {
+        let cache = victim.cache();
+        let allocator = AddressAllocator::new(&cache, config.address_offset);
+        (0..=MAX_SET_CAPACITY as u64).map(|k| allocator.superblock(k, set) * cache.superblock_bytes()).collect()
+    };
+    for &addr in &lines {
+        let line = victim.line_of(addr);
+        victim.cache().initialize_line(line, &contents);
+    }
+    for k in 2..=lines.len() {
+        for &addr in &lines[..k] {victim.cache().read_byte(addr);}
+        stats.attacker_cache_lines_loaded += k + 1;
+        if victim.cache().read_byte(lines[0]).1 == AccessSpeed::MISS {return Some(k - 1);}
+    }
+    return None;
+}
+
+/// Rearranges an attack string so that every read-only byte (given as (offset, value) within the string) already holds
+/// its value. The non-zero words keep their order, and only the all-zero words move, since a zero word compresses the
+/// same anywhere and never enters the C-PACK dictionary. Returns None if no such arrangement exists.
+fn route_around_read_only(attack_string: &[u8], fixed: &[(usize, u8)]) -> Option<Vec<u8>> {
+    let words: Vec<[u8; 4]> = attack_string.chunks(4).map(|w| w.try_into().expect("Attack strings are whole words")).collect();
+    let nonzero: Vec<[u8; 4]> = words.iter().copied().filter(|w| *w != [0; 4]).collect();
+    let fits = |slot: usize, word: &[u8; 4]| fixed.iter().all(|&(offset, value)| offset / 4 != slot || word[offset % 4] == value);
+    // reachable[slot][n]: the first slot words can hold the first n non-zero words and slot - n zero words.
+    let mut reachable = vec![vec![false; nonzero.len() + 1]; words.len() + 1];
+    reachable[0][0] = true;
+    for slot in 0..words.len() {
+        for n in 0..=nonzero.len().min(slot) {
+            if !reachable[slot][n] {continue;}
+            if n < nonzero.len() && fits(slot, &nonzero[n]) {reachable[slot + 1][n + 1] = true;}
+            if slot - n < words.len() - nonzero.len() && fits(slot, &[0; 4]) {reachable[slot + 1][n] = true;}
         }
-        // Finally, push a zero-extended-byte word and 6 zero words
-        attack_string.push(0xFF);
-        for _ in 0..27 {attack_string.push(0);}
+    }
+    if !reachable[words.len()][nonzero.len()] {return None;}
+    // Walk back from the end, preferring to place zero words late so the string stays close to its original shape.
+    let mut routed = vec![[0u8; 4]; words.len()];
+    let mut n = nonzero.len();
+    for slot in (0..words.len()).rev() {
+        if n <= slot && reachable[slot][n] && fits(slot, &[0; 4]) {continue;}
+        n -= 1;
+        routed[slot] = nonzero[n];
+    }
+    let routed: Vec<u8> = routed.concat();
+    assert_eq!(cpack_bits(&routed), cpack_bits(attack_string), "Moving zero words changed the compressed size");
+    return Some(routed);
+}
+
+/// Fills the word slots of a length-byte string in order with the words of attack_string, skipping the slots that are
+/// read-only in full, which get their fixed values instead.
+fn splice_known_words(attack_string: &[u8], fixed: &[(usize, u8)], length: usize) -> Vec<u8> {
+    let known = whole_read_only_words(fixed, length);
+    assert_eq!(attack_string.len() + 4 * known.len(), length, "Attack strings must leave out exactly the known words");
+    let mut words = attack_string.chunks(4);
+    return (0..length / 4).flat_map(|slot| match known.iter().find(|&&(s, _)| s == slot) {
+        Some(&(_, value)) => value.to_vec(),
+        None => words.next().expect("Attack strings are whole words").to_vec()
+    }).collect();
+}
 
-        assert_eq!(attack_string.len(), 56);
-        return attack_string;
-    } else {
-        panic!("Bad secret size")
+/// Builds an attack string with the given layout: the test words built from includes, then filler test words,
+/// then distinct zero-extended bytes counting down from 0xFF, then zero words. If the layout puts the test words last,
+/// the string starts with a padding word for each of padding_shorts instead, and ends with the test words. Every word is
+/// least significant byte first, as a little-endian cache reads it; Endianness::line_bytes puts the string in memory
+/// order for any other.
+fn assemble_attack_string(layout: &StringLayout, tests: impl Iterator<Item = [u8; 4]>, padding_shorts: &[u16]) -> Vec<u8> {
+    assert_eq!(padding_shorts.len(), layout.padding_words);
+    let tests: Vec<u8> = tests.flatten().collect();
+    assert_eq!(tests.len(), 4 * layout.tests);
+    let mut attack_string: Vec<u8> = Vec::with_capacity(4 * (layout.tests + layout.padding_words + layout.byte_words + layout.zero_words));
+    if !layout.tests_last {attack_string.extend(&tests);}
+    for &short in padding_shorts {attack_string.extend([0, 0, (short & 0xFF) as u8, (short >> 8) as u8]);}
+    for i in 0..layout.byte_words {attack_string.extend([0xFF - i as u8, 0, 0, 0]);}
+    attack_string.extend(vec![0u8; 4 * layout.zero_words]);
+    if layout.tests_last {attack_string.extend(&tests);}
+    return attack_string;
+}
+
+/// Creates an attack string that helps deduce the upper two bytes in a 4-byte C-PACK word.
+/// Each short-testing word is uncompressed (34 bits) unless the secret word shares its upper two bytes, in which case the
+/// secret word compresses to 24 bits and the line becomes small enough to be co-allocated.
+/// filler: how to pick filler values when fewer than the maximum number of candidates are included.
+/// layout: the makeup of the string. For the default threshold, this holds 6 shorts for 4B secrets and 5 for 8B secrets.
+/// includes: the set of shorts to target in the attack string. Should be at most layout.tests shorts.
+/// excludes: the set of shorts to explicitly avoid targeting in the attack string.
+fn make_first_attack_string(filler: &FillerStrategy, rng: &mut StdRng, layout: &StringLayout, includes: &[u16], excludes: &CandidateSet<u16>) -> Vec<u8> {
+    if includes.len() > layout.tests {
+        panic!("Bad number of shorts to include")
+    }
+    let filler_shorts = filler.pick(rng, layout.tests - includes.len(), includes, excludes, 0xFFFF);
+    let tests = includes.iter().chain(filler_shorts.iter()).map(|&short| [0, 0, (short & 0xFF) as u8, ((short >> 8) & 0xFF) as u8]);
+    return assemble_attack_string(layout, tests, &[]);
+}
+
+/// Creates an attack string that helps deduce the second-to-least significant bit of a 4-byte C-PACK word.
+/// The first byte-testing word is uncompressed (34 bits) and the rest match it except for the last short (24 bits).
+/// The secret word compresses to 16 bits instead of 24 if one of them also matches its second byte.
+/// filler: how to pick filler values when fewer than the maximum number of candidates are included.
+/// layout: the makeup of the string. For the default threshold, this holds 9 bytes for 4B secrets and 7 for 8B secrets.
+/// short: the upper 2 bytes of the secret
+/// includes: the set of bytes to target in the attack string. Should be at most layout.tests bytes.
+/// excludes: the set of bytes to explicitly avoid targeting in the attack string.
+fn make_second_attack_string(filler: &FillerStrategy, rng: &mut StdRng, layout: &StringLayout, short: u16, includes: &[u8], excludes: &CandidateSet<u8>) -> Vec<u8> {
+    if includes.len() > layout.tests {
+        panic!("Bad number of bytes to include")
     }
+    let filler_bytes = filler.pick_bytes(rng, layout.tests - includes.len(), includes, excludes);
+    let tests = includes.iter().chain(filler_bytes.iter()).map(|&byte| [0, byte, (short & 0xFF) as u8, ((short >> 8) & 0xFF) as u8]);
+    let padding = filler.pick(rng, layout.padding_words, &[short], &CandidateSet::empty(), 0xFFFF);
+    return assemble_attack_string(layout, tests, &padding);
 }
 
 /// Creates an attack string that helps deduce the least significant bit of a 4-byte C-PACK word.
+/// The first byte-testing word is uncompressed (34 bits) and the rest match it except for the last byte (16 bits).
+/// The secret word compresses to 6 bits instead of 16 if one of them matches it entirely.
+/// layout: the makeup of the string. For the default threshold, this holds 14 bytes for 4B secrets and 12 for 8B secrets.
 /// short: the upper 2 bytes of the secret
 /// second_byte: the second-to-least significant byte of the secret
-/// includes: the set of bytes to target in the attack string. Should be 1-14 bytes for 4B secrets and 1-12 for 8B secrets.
+/// includes: the set of bytes to target in the attack string. Should be at most layout.tests bytes.
 /// excludes: the set of bytes to explicitly avoid targeting in the attack string.
-/// secret_size: the size of the secret.
-fn make_third_attack_string(short: u16, second_byte: u8, includes: &Vec<u8>, excludes: &HashSet<u8>, secret_size: usize) -> Vec<u8> {
-    if secret_size == 4 {
-        // Words to write: 15
-        // Bits to compress to: 242 to 250 (must allow 32B compression when last word compresses to 6 bits, but not when it's 16 bits)
-        // One byte-testing word at the front will be uncompressed (34 bits)
-        // Following byte-testing words will be compressed due to the match-except-the-last-byte rule (16 bits each)
-        // 14 byte-testing words can be accommodated including the first one (total of 242 bits)
-        // This leaves 1 word which can be all zeros, bringing the total to 244, within the bounds.
-        let mut attack_string: Vec<u8> = Vec::with_capacity(60);
-        if includes.len() < 1 || includes.len() > 14 {
-            panic!("Bad number of bytes to include")
-        }
-        for &include in includes { // Push all byte-testing words
-            attack_string.push(include);
-            attack_string.push(second_byte);
-            attack_string.push((short & 0xFF) as u8);
-            attack_string.push(((short >> 8) & 0xFF) as u8);
-        }
-        if includes.len() < 14 {
-            let mut valid_filler: Vec<u8> = (1u8..=100).filter(|x|!includes.contains(x) && !excludes.contains(x)).rev().collect();
-            for _ in 0..(14-includes.len()) { // Push other random byte-testing words as filler
-                let first_byte = valid_filler.pop().unwrap();
-                attack_string.push(first_byte);
-                attack_string.push(second_byte);
-                attack_string.push((short & 0xFF) as u8);
-                attack_string.push(((short >> 8) & 0xFF) as u8);
-            }
-        }
-        // Finally, push a zero word
-        for _ in 0..4 {attack_string.push(0);}
-
-        assert_eq!(attack_string.len(), 60);
-        return attack_string;
-    } else if secret_size == 8 {
-        // Words to write: 14
-        // Bits to compress to: 208 to 216 (must allow 32B compression when words compress to 34 and 6, but not 34 and 16)
-        // One byte-testing word at the front will be uncompressed (34 bits)
-        // Following byte-testing words will be compressed due to the match-except-the-last-byte rule (16 bits each)
-        // 12 byte-testing words can be accommodated including the first one (total of 210 bits)
-        // This leaves 2 words which can be all zeros, bringing the total to 214, within the bounds.
-        let mut attack_string: Vec<u8> = Vec::with_capacity(56);
-        if includes.len() < 1 || includes.len() > 12 {
-            panic!("Bad number of bytes to include")
-        }
-        for &include in includes { // Push all byte-testing words
-            attack_string.push(include);
-            attack_string.push(second_byte);
-            attack_string.push((short & 0xFF) as u8);
-            attack_string.push(((short >> 8) & 0xFF) as u8);
-        }
-        if includes.len() < 12 {
-            let mut valid_filler: Vec<u8> = (1u8..=100).filter(|x|!includes.contains(x) && !excludes.contains(x)).rev().collect();
-            for _ in 0..(12-includes.len()) { // Push other random byte-testing words as filler
-                let first_byte = valid_filler.pop().unwrap();
-                attack_string.push(first_byte);
-                attack_string.push(second_byte);
-                attack_string.push((short & 0xFF) as u8);
-                attack_string.push(((short >> 8) & 0xFF) as u8);
-            }
-        }
-        // Finally, push 2 zero words
-        for _ in 0..8 {attack_string.push(0);}
-
-        assert_eq!(attack_string.len(), 56);
-        return attack_string;
-    } else {
-        panic!("Bad secret size")
+fn make_third_attack_string(filler: &FillerStrategy, rng: &mut StdRng, layout: &StringLayout, short: u16, second_byte: u8, includes: &[u8], excludes: &CandidateSet<u8>) -> Vec<u8> {
+    if includes.len() > layout.tests {
+        panic!("Bad number of bytes to include")
     }
-}
\ No newline at end of file
+    let filler_bytes = filler.pick_bytes(rng, layout.tests - includes.len(), includes, excludes);
+    let tests = includes.iter().chain(filler_bytes.iter()).map(|&first_byte| [first_byte, second_byte, (short & 0xFF) as u8, ((short >> 8) & 0xFF) as u8]);
+    let padding = filler.pick(rng, layout.padding_words, &[short], &CandidateSet::empty(), 0xFFFF);
+    return assemble_attack_string(layout, tests, &padding);
+}
@@ -0,0 +1,112 @@
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use crate::attacker::attack_yacc_cpack_4byte_secret;
+use crate::config::{CacheConfig, ExperimentConfig};
+use crate::experiment::Experiment;
+use crate::structures::{cpack_bits, ASSOCIATIVITY, Cache, Compressor, YACC};
+use crate::victim::VictimProgramYACC;
+
+/// Fixed inputs, so results are comparable across builds.
+const BENCH_SECRET: [u8; 4] = [0x3C, 0xA7, 0x51, 0xE9];
+const BENCH_SEED: u64 = 0x5EED_CAFE_F00D_BEEF;
+
+/// A tiny xorshift generator, used so the benchmark inputs never depend on the rand crate's algorithms.
+fn xorshift(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    return *state;
+}
+
+/// Makes a line whose words mix the patterns C-PACK cares about (zeros, small values, repeats, random words).
+fn bench_line(state: &mut u64) -> [u8; 64] {
+    let mut line = [0u8; 64];
+    for word in line.chunks_mut(4) {
+        let value = match xorshift(state) % 4 {
+            0 => 0u32,
+            1 => (xorshift(state) & 0xFF) as u32,
+            2 => 0xDEAD0000 | (xorshift(state) & 0xFF) as u32,
+            _ => xorshift(state) as u32
+        };
+        word.copy_from_slice(&value.to_le_bytes());
+    }
+    return line;
+}
+
+/// Runs f once to warm up, then takes samples of iters calls each and prints per-call statistics.
+fn bench<F: FnMut()>(name: &str, samples: usize, iters: usize, mut f: F) {
+    f();
+    let mut per_call: Vec<f64> = (0..samples).map(|_| {
+        let start = Instant::now();
+        for _ in 0..iters {f();}
+        start.elapsed().as_nanos() as f64 / iters as f64
+    }).collect();
+    per_call.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let mean = per_call.iter().sum::<f64>() / samples as f64;
+    let stddev = (per_call.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / samples as f64).sqrt();
+    println!("{:<28} mean {:>14} median {:>14} stddev {:>12} ({} samples x {} calls)",
+             name, format_nanos(mean), format_nanos(per_call[samples / 2]), format_nanos(stddev), samples, iters);
+}
+
+fn format_nanos(nanos: f64) -> String {
+    let d = Duration::from_nanos(nanos as u64);
+    return if nanos < 1e3 {format!("{:.1} ns", nanos)}
+        else if nanos < 1e6 {format!("{:.3} us", nanos / 1e3)}
+        else if nanos < 1e9 {format!("{:.3} ms", nanos / 1e6)}
+        else {format!("{:.3} s", d.as_secs_f64())};
+}
+
+/// Benchmarks the simulator's hot paths: compressing a line, a cache access, and a full single attack.
+pub fn run_benchmarks() {
+    let mut state = BENCH_SEED;
+    let lines: Vec<[u8; 64]> = (0..256).map(|_| bench_line(&mut state)).collect();
+    let mut i = 0;
+    bench("cpack_bits", 20, 10000, || {
+        black_box(cpack_bits(black_box(&lines[i % lines.len()])));
+        i += 1;
+    });
+
+    // Accesses cycle through 6 superblocks of 4 lines, which keeps the set busy co-allocating and evicting.
+    let mut cache = YACC::new(Compressor::CPACK, ASSOCIATIVITY);
+    for (n, line) in lines.iter().take(24).enumerate() {
+        for (offset, &byte) in line.iter().enumerate() {cache.write_byte(((n as u64) << 6) + offset as u64, byte);}
+    }
+    let mut addr = 0u64;
+    bench("YACC access", 20, 10000, || {
+        black_box(cache.read_byte(black_box(addr << 6)));
+        addr = (addr + 5) % 24;
+    });
+
+    // The same pattern over 96 superblocks on a 64-way set, to show how access time scales with associativity.
+    let mut wide = YACC::new(Compressor::CPACK, 64);
+    for n in 0..384u64 {
+        for (offset, &byte) in lines[n as usize % lines.len()].iter().enumerate() {wide.write_byte((n << 6) + offset as u64, byte);}
+    }
+    bench("YACC access (64 ways)", 20, 10000, || {
+        black_box(wide.read_byte(black_box(addr << 6)));
+        addr = (addr + 5) % 384;
+    });
+
+    // Cycling through twice as many superblocks as a 256-way set has ways makes every access a miss, so each one
+    // evicts the least recently used way and moves the new one to the other end of the LRU list.
+    let mut thrashed = YACC::new(Compressor::CPACK, 256);
+    for n in 0..512u64 {
+        for (offset, &byte) in lines[n as usize % lines.len()].iter().enumerate() {thrashed.write_byte((n << 8) + offset as u64, byte);}
+    }
+    let mut sb = 0u64;
+    bench("YACC LRU misses (256 ways)", 20, 10000, || {
+        black_box(thrashed.read_byte(black_box(sb << 8)));
+        sb = (sb + 1) % 512;
+    });
+
+    // A seeded 10-attack campaign on a wide cache, so that every sample attacks the same victims.
+    let campaign = ExperimentConfig {iterations: 10, cache: CacheConfig {associativity: 32, ..CacheConfig::default()}, ..ExperimentConfig::default()};
+    bench("Campaign x10 (32 ways)", 3, 1, || {
+        black_box(Experiment::from_config_seeded(&campaign, BENCH_SEED).run());
+    });
+
+    bench("4-byte attack (fixed secret)", 5, 1, || {
+        let mut victim = VictimProgramYACC::with_secret_seeded(BENCH_SECRET.to_vec(), YACC::new(Compressor::CPACK, ASSOCIATIVITY), BENCH_SEED, false);
+        black_box(attack_yacc_cpack_4byte_secret(&mut victim, false));
+    });
+}
@@ -0,0 +1,67 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use serde::Serialize;
+use crate::attacker::{summarize, AttackStats, ProbeOutcomes, Summary};
+use crate::config::ExperimentConfig;
+
+/// One attack's results, as stored in a bundle.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IterationRecord {
+    /// The victim's secret and what the attack recovered, in hex. None when the bundle is redacted.
+    pub secret: Option<String>,
+    pub recovered: Option<String>,
+    pub success: bool,
+    pub attempts: usize,
+    pub guesses_needed: usize,
+    pub set_evictions: usize,
+    pub victim_interactions: usize,
+    pub probe_outcomes: [ProbeOutcomes; 4]
+}
+
+impl IterationRecord {
+    fn new(secret: &[u8], stats: &AttackStats, redact: bool) -> IterationRecord {
+        let hex = |bytes: &[u8]| bytes.iter().map(|x| format!("{:02X}", x)).collect::<String>();
+        IterationRecord {
+            secret: if redact {None} else {Some(hex(secret))},
+            recovered: if redact || !stats.success {None} else {Some(hex(&stats.secret))},
+            success: stats.success,
+            attempts: stats.attempts,
+            guesses_needed: stats.guesses_needed,
+            set_evictions: stats.set_evictions,
+            victim_interactions: stats.victim_interactions,
+            probe_outcomes: stats.probe_outcomes
+        }
+    }
+}
+
+/// Everything needed to check or re-run a campaign, in a single JSON document for attaching to a paper's artifact.
+/// The victims' secrets come from an unseeded RNG, so they are recorded per iteration instead of as seeds
+/// (unless the bundle is redacted). The config section can be fed back through export-bundle --config-from.
+#[derive(Debug, Clone, Serialize)]
+pub struct ResultBundle {
+    pub software: String,
+    pub redacted: bool,
+    pub config: ExperimentConfig,
+    pub summary: Summary,
+    pub iterations: Vec<IterationRecord>
+}
+
+impl ResultBundle {
+    /// Runs a campaign and bundles its results. With redact, no secret (true or recovered) appears in the bundle.
+    pub fn record(config: &ExperimentConfig, redact: bool) -> ResultBundle {
+        let results = config.run_with_secrets();
+        let stats: Vec<AttackStats> = results.iter().map(|(_, stats)| stats.clone()).collect();
+        return ResultBundle {
+            software: format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+            redacted: redact,
+            config: config.clone(),
+            summary: summarize(&stats),
+            iterations: results.iter().map(|(secret, stats)| IterationRecord::new(secret, stats, redact)).collect()
+        };
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        return fs::write(path, serde_json::to_string_pretty(self)?);
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::HashSet;
+use std::hash::Hash;
+
+/// Returns log2 of a candidate count, treating an empty candidate set as fully resolved.
+pub fn log2_candidates(count: usize) -> f64 {
+    return if count == 0 {0.0} else {(count as f64).log2()};
+}
+
+/// An ordered pool of distinct candidate values for some part of the secret.
+/// Candidates are tested from the back, so the order decides which ones get probed first.
+/// Every operation keeps the relative order of the candidates it leaves in place.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CandidateSet<T> {
+    members: Vec<T>
+}
+
+impl<T: Copy + Eq + Hash> CandidateSet<T> {
+    /// Makes a set out of members, which must be distinct.
+    pub fn new(members: Vec<T>) -> CandidateSet<T> {
+        debug_assert_eq!(members.iter().collect::<HashSet<_>>().len(), members.len(), "Candidate sets cannot hold duplicates");
+        return CandidateSet {members};
+    }
+
+    pub fn empty() -> CandidateSet<T> {
+        return CandidateSet {members: Vec::new()};
+    }
+
+    pub fn len(&self) -> usize {
+        return self.members.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.members.is_empty();
+    }
+
+    pub fn contains(&self, candidate: &T) -> bool {
+        return self.members.contains(candidate);
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        return &self.members;
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        return self.members.iter();
+    }
+
+    /// Takes the next candidate to be tested.
+    pub fn pop(&mut self) -> Option<T> {
+        return self.members.pop();
+    }
+
+    /// Takes the next count candidates to be tested (or all of them, if there are fewer), listed in the order they were taken.
+    pub fn split(&mut self, count: usize) -> CandidateSet<T> {
+        let start = self.members.len().saturating_sub(count);
+        let mut taken = self.members.split_off(start);
+        taken.reverse();
+        return CandidateSet {members: taken};
+    }
+
+    /// Adds the candidates of other that are not already in the set, at the back, so they are tested first.
+    pub fn union(&mut self, other: &CandidateSet<T>) {
+        let present: HashSet<T> = self.members.iter().copied().collect();
+        self.members.extend(other.members.iter().copied().filter(|x| !present.contains(x)));
+    }
+
+    /// Removes every candidate that is also in other.
+    pub fn exclude(&mut self, other: &CandidateSet<T>) {
+        let other: HashSet<T> = other.members.iter().copied().collect();
+        self.members.retain(|x| !other.contains(x));
+    }
+
+    /// Keeps only the candidates that are also in other.
+    pub fn intersect(&mut self, other: &CandidateSet<T>) {
+        let other: HashSet<T> = other.members.iter().copied().collect();
+        self.members.retain(|x| other.contains(x));
+    }
+
+    /// log2 of the number of candidates, in bits: how much is left to learn if exactly one of them is right.
+    /// An empty set counts as resolved.
+    pub fn entropy(&self) -> f64 {
+        return log2_candidates(self.members.len());
+    }
+}
+
+impl<T: Copy + Eq + Hash> FromIterator<T> for CandidateSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> CandidateSet<T> {
+        return CandidateSet::new(iter.into_iter().collect());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(members: &[u8]) -> CandidateSet<u8> {
+        return CandidateSet::new(members.to_vec());
+    }
+
+    #[test]
+    fn pop_takes_from_the_back() {
+        let mut candidates = set(&[1, 2, 3]);
+        assert_eq!(candidates.pop(), Some(3));
+        assert_eq!(candidates.as_slice(), &[1, 2]);
+        assert_eq!(CandidateSet::<u8>::empty().pop(), None);
+    }
+
+    #[test]
+    fn split_takes_the_back_in_test_order() {
+        let mut candidates = set(&[1, 2, 3, 4, 5]);
+        let taken = candidates.split(2);
+        assert_eq!(taken.as_slice(), &[5, 4]);
+        assert_eq!(candidates.as_slice(), &[1, 2, 3]);
+    }
+
+    #[test]
+    fn split_past_the_end_takes_everything() {
+        let mut candidates = set(&[1, 2, 3]);
+        assert_eq!(candidates.split(10).as_slice(), &[3, 2, 1]);
+        assert!(candidates.is_empty());
+        assert!(candidates.split(1).is_empty());
+    }
+
+    #[test]
+    fn union_appends_new_members_in_order() {
+        let mut candidates = set(&[1, 2, 3]);
+        candidates.union(&set(&[4, 2, 5]));
+        assert_eq!(candidates.as_slice(), &[1, 2, 3, 4, 5]);
+        let mut empty = CandidateSet::empty();
+        empty.union(&set(&[7, 6]));
+        assert_eq!(empty.as_slice(), &[7, 6]);
+    }
+
+    #[test]
+    fn exclude_keeps_the_rest_in_order() {
+        let mut candidates = set(&[1, 2, 3, 4, 5]);
+        candidates.exclude(&set(&[4, 2, 9]));
+        assert_eq!(candidates.as_slice(), &[1, 3, 5]);
+        candidates.exclude(&CandidateSet::empty());
+        assert_eq!(candidates.as_slice(), &[1, 3, 5]);
+    }
+
+    #[test]
+    fn intersect_keeps_shared_members_in_order() {
+        let mut candidates = set(&[1, 2, 3, 4, 5]);
+        candidates.intersect(&set(&[5, 9, 1, 3]));
+        assert_eq!(candidates.as_slice(), &[1, 3, 5]);
+        candidates.intersect(&CandidateSet::empty());
+        assert!(candidates.is_empty());
+    }
+
+    #[test]
+    fn entropy_counts_bits_left() {
+        assert_eq!(CandidateSet::<u8>::empty().entropy(), 0.0);
+        assert_eq!(set(&[1]).entropy(), 0.0);
+        assert_eq!(set(&[1, 2, 3, 4]).entropy(), 2.0);
+        assert!(set(&[1, 2, 3]).contains(&2) && !set(&[1, 2, 3]).contains(&4));
+    }
+}
@@ -0,0 +1,683 @@
+use clap::{Args, Parser, Subcommand};
+use compressed_cache_attack_sim::structures::{Compressor, DictionaryReplacement, ReplacementPolicy};
+use compressed_cache_attack_sim::victim::SecretDistribution;
+
+/// Simulates cache timing attacks that leak secrets through the compressibility of a YACC compressed cache.
+#[derive(Debug, Parser)]
+#[command(version)]
+pub struct Cli {
+    /// Worker threads for parallel campaigns (defaults to one per core).
+    #[arg(long, global = true)]
+    pub threads: Option<usize>,
+    /// What to run. Without a subcommand, runs the default sweep (10000 attacks each on 4- and 8-byte secrets) and
+    /// writes it to sweep.csv.
+    #[command(subcommand)]
+    pub command: Option<Command>
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Runs a campaign of attacks on random secrets and writes its configuration and summary to a results file.
+    Simulate(SimulateArgs),
+    /// Re-runs the campaign recorded in a results file.
+    Rerun {
+        /// A results file written by simulate.
+        #[arg(long)]
+        config_from: String,
+        /// Also write every run's stats and the summary for post-processing, as JSON or CSV by extension.
+        #[arg(long)]
+        output: Option<String>
+    },
+    /// Runs one verbose attack against a chosen secret.
+    Attack {
+        /// The secret in hex, in memory order (e.g. DEADBEEF).
+        #[arg(long)]
+        secret: String
+    },
+    /// Attacks every secret in a file of hex secrets, one per line, and writes one CSV row per secret.
+    Batch {
+        #[arg(long)]
+        secrets_file: String,
+        #[arg(long, default_value = "batch_results.csv")]
+        out: String
+    },
+    /// Runs a campaign and writes a bundle of its configuration, per-iteration results and summary.
+    ExportBundle {
+        /// Take the configuration from a results file instead of running 100 default iterations.
+        #[arg(long)]
+        config_from: Option<String>,
+        #[arg(long, default_value = "bundle.json")]
+        out: String,
+        /// Leave the true and recovered secrets out of the bundle.
+        #[arg(long)]
+        redact_secrets: bool
+    },
+    /// Fingerprints every line of a raw memory dump and lists the lines that could hide a secret, best targets first.
+    Fingerprint {
+        #[arg(long)]
+        dump: String,
+        /// The address the dump starts at, in hex (e.g. 0x7FFF0000).
+        #[arg(long, default_value = "0", value_parser = parse_address)]
+        base: u64,
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        /// How many candidate lines to list.
+        #[arg(long, default_value_t = 20)]
+        top: usize,
+        /// Where to write every line's fingerprint.
+        #[arg(long, default_value = "fingerprints.csv")]
+        out: String
+    },
+    /// Times the simulator's hot paths.
+    Bench,
+    /// Runs the cache model conformance checks on every compressor and replacement policy.
+    Conformance,
+    /// Attacks one seeded victim and writes every probe as a frame of the set's state and the candidates it tested, as
+    /// an asciinema recording (.cast) or as JSON.
+    Replay {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 1)]
+        seed: u64,
+        #[arg(long, default_value = "replay.cast")]
+        out: String,
+        /// How long each frame stays up in a recording. Frames of probes that went wrong stay up five times as long.
+        #[arg(long, default_value_t = 0.05)]
+        seconds_per_frame: f64
+    },
+    /// Attacks one seeded victim and writes every cache access (address, read or write, hit or miss, the entry it
+    /// leaves behind and the line's compressed size) as newline-delimited JSON.
+    Trace {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 1)]
+        seed: u64,
+        #[arg(long, default_value = "trace.jsonl")]
+        out: String
+    },
+    /// Runs a campaign for every combination of the given parameters (each a comma-separated list), in parallel, and
+    /// writes one CSV row per combination.
+    Sweep {
+        #[arg(long, value_delimiter = ',', default_value = "4,8", value_parser = parse_secret_bytes)]
+        secret_bytes: Vec<usize>,
+        /// Any of cpack, bdi, fpc or none, or cpack:<entries>[:fifo|:lru] for C-PACK with a bounded dictionary.
+        #[arg(long, value_delimiter = ',', default_value = "cpack", value_parser = parse_compressor)]
+        compressors: Vec<Compressor>,
+        #[arg(long, value_delimiter = ',', default_value = "8", value_parser = parse_associativity)]
+        associativities: Vec<usize>,
+        /// Any of lru, random, srrip or brrip.
+        #[arg(long, value_delimiter = ',', default_value = "lru", value_parser = parse_replacement)]
+        replacements: Vec<ReplacementPolicy>,
+        #[arg(long, value_delimiter = ',', default_value = "10000")]
+        iterations: Vec<usize>,
+        #[arg(long, default_value = "sweep.csv")]
+        out: String
+    },
+    /// Attacks with each way of generating filler values for the attack strings.
+    Fillers {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize
+    },
+    /// Runs a batch of attacks and writes how the surviving candidate set shrinks with every probe, to decay_runs.csv
+    /// and decay_average.csv.
+    CandidateDecay {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        runs: usize
+    },
+    /// Runs one attack with the victim's audit log enabled and writes the log to audit_log.csv.
+    AuditTrace {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize
+    },
+    /// Runs one attack and writes the secret line's compressed size after every write to compressibility_timeline.csv.
+    CompressibilityTimeline {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize
+    },
+    /// Attacks across cores, with and without an inclusive last-level cache and the attacker's L1 eviction set.
+    CrossCore,
+    /// Estimates attack throughput against many tenants at once, with more and more tenants crowding the same sets.
+    MultiTenant,
+    /// Attacks victims that rotate their buffer or read their secret line on a timer.
+    ScheduledActivity {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks 4- and 8-byte secrets on a cache that re-encodes lines on writeback, with and without size padding.
+    Reencoding {
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks victims that clamp their secret line's compressibility with canaries, next to victims that do not.
+    ClampedVictims {
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks a cache that only co-allocates blocks with some probability, with an attacker that re-probes negative results.
+    ProbabilisticAdmission {
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks victims that mark the memory around their secret line as never compressed, at line, superblock and
+    /// page granularity, and reports what each granularity costs benign workloads.
+    UncompressedRegions {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks secrets placed at the start, middle and end of every line of the superblock.
+    SecretOffsets {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks heap-model victims end to end, grooming the heap first.
+    HeapGrooming {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Prints how the compressed size of a metadata block responds to the attacker's counter guesses.
+    MetadataLeak,
+    /// Attacks store-only victims, which never return buffer contents, by reloading lines with stores.
+    StoreOnly {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize
+    },
+    /// Compares priming the secret line byte by byte with handing the victim the whole attack string at once.
+    PrimeMethods {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize
+    },
+    /// Sweeps how many non-secret words of the secret line hold leftover victim data rather than zeros.
+    ResidualData {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize
+    },
+    /// Attacks a 4-byte secret for every leading short in a range, with the low short fixed, and reports every secret
+    /// that was not recovered.
+    ExhaustiveShorts {
+        /// The secret's low two bytes, as a little-endian hex short.
+        #[arg(long, default_value = "0x0201", value_parser = parse_short)]
+        low_short: u16,
+        #[arg(long, default_value = "0x0300", value_parser = parse_short)]
+        first_short: u16,
+        #[arg(long, default_value = "0x03FF", value_parser = parse_short)]
+        last_short: u16
+    },
+    /// Attacks caches with other co-allocation thresholds than 32 bytes, with an attacker that probes for the threshold.
+    UnknownThresholds {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks caches of other set sizes and thresholds, with an attacker assuming the stock ones and one that calibrates.
+    Uncalibrated {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks caches that reserve space for ECC in every compressed block.
+    EccOverhead {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks victims that keep protocol header fields in their secret line read-only.
+    ReadOnlyMasks {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks a cache that prefetches the neighboring line on every store, with each way of priming and reloading.
+    StorePrefetch {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks cache sets of 2 to 16 ways.
+    Associativity {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks LRU, random and RRIP caches, probing once and putting every probe to a vote.
+    ReplacementPolicies {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks secrets of every whole number of words the default threshold leaves room for.
+    SecretSizes {
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks 16-byte key schedules, word by word and by exploiting the schedule.
+    KeySchedule {
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks secrets kept two bytes to a word, next to the same secrets kept in whole words.
+    InterleavedSecrets {
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks caches with more than one set, with flush sets built from lines that map to the victim's set.
+    SetCounts {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Runs a noisy campaign and checks that every failed attack replays exactly from its seed.
+    ReplayFailures {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Sorts the failed probes of noisy setups by cause.
+    DiagnoseProbes {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Reads every probe through both the latency and the residency oracle, and reports how often they disagree.
+    Oracles {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks with flush and reload next to timing the decompression of the secret line's companion.
+    DecompressionTiming {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Reads probes through the latency oracle and through the miss counter under each kind of noise.
+    MissCounter {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks with the standard pipeline up to the leading shorts, and finishes with voted probes.
+    HandOff {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks with prime and probe and with Evict+Reload under each kind of interference.
+    EvictReload {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Runs the same seeded campaign with and without each source of noise, and compares the outcomes attack by attack.
+    PairedNoise {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize,
+        #[arg(long, default_value_t = 1)]
+        seed: u64
+    },
+    /// Attacks a write-through and a write-back cache.
+    WritePolicies {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Checks how well each attack's outcome is predicted after the leading shorts, and what giving up early saves.
+    PredictOutcomes {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks victims making more and more background accesses in the secret line's set, with each probe mode.
+    BackgroundNoise {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks secrets drawn from restricted alphabets, with and without an attacker that knows the alphabet.
+    SecretDistributions {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks secrets of unique non-zero bytes and of arbitrary bytes, with attackers assuming either.
+    SecretBytes {
+        /// One of uniform, ascii, hex or base64.
+        #[arg(long, default_value = "uniform", value_parser = parse_distribution)]
+        distribution: SecretDistribution,
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks caches with 2-, 4- and 8-block superblocks.
+    SuperblockSizes {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks caches with 32-, 64- and 128-byte lines.
+    LineSizes {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks caches with each compressor, and reports what each buys benign workloads.
+    Compressors {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks victims whose secret shares its line with a known public prefix.
+    PublicPrefix {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Prints which attacker-writable bytes alias the secret for contiguous and striped buffers.
+    BufferAliasing {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize
+    },
+    #[cfg(not(feature = "no-oracle"))]
+    /// Attacks next to a cheating and a brute-force baseline attacker.
+    Baselines {
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks victims whose buffer is striped across superblocks, for each kind of header and probe strategy.
+    StripedBuffers {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Compares probe address policies by flush hits and success rate.
+    ProbeAddresses {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Compares flush set shapes by success rate, flush hits and how the probes went wrong.
+    FlushSets {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Measures what an attack costs the victim's own hit rate.
+    VictimDisturbance {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks victims watched by a miss-rate detector, with the attacker pacing itself to tighter and tighter budgets.
+    Stealth {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks victims sharing their set with a noisy co-tenant, under each interference mitigation.
+    CoTenant {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Reports what compression buys benign workloads: hit rate, uplift over an uncompressed cache, effective capacity
+    /// and compressed sizes.
+    BenignPerformance {
+        /// Replay this trace file instead of the synthetic workloads.
+        #[arg(long)]
+        trace: Option<String>
+    },
+    /// Attacks YACC and DISH caches of the same geometry.
+    Architectures {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks little- and big-endian caches, with the threshold assumed and calibrated.
+    ByteOrder {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks caches whose C-PACK dictionary holds 16, 8 or 4 words, with FIFO and LRU replacement.
+    CpackDictionaries {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks victims that re-allocate their buffer every so many attacker operations, with and without an attacker that
+    /// detects the moves, and reports how well the attack holds up.
+    Rebasing {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Attacks victims that cap the attacker's buffer writes and reads at fractions of what an unlimited attack spends,
+    /// and reports what the attacks still recover.
+    AccessBudget {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Re-runs the standard attack against a cache with CEASER-style randomized set indexing, for several remap periods,
+    /// and reports how much the success rate and guess count degrade.
+    RandomizedIndexing {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Re-runs the standard attack against caches that quantize compressed sizes to coarse, randomly padded buckets,
+    /// and reports each phase's probe outcomes.
+    SizeQuantization {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize
+    },
+    /// Writes a JSON description of every attack strategy's steps, group sizes, bit windows and assumptions.
+    DescribeStrategies {
+        /// The co-allocation threshold the strategies are laid out for.
+        #[arg(long, default_value_t = 32)]
+        threshold: u64,
+        #[arg(long, default_value = "strategies.json")]
+        out: String
+    },
+    /// Runs a campaign and maps the probes spent on, and failures at, every byte position of the secret.
+    Heatmap {
+        #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+        secret_bytes: usize,
+        #[arg(long, default_value_t = 1000)]
+        iterations: usize,
+        /// Where to write one row per byte position.
+        #[arg(long, default_value = "heatmap.csv")]
+        out: String
+    },
+    /// Runs a seeded campaign serially and in parallel, and checks that every iteration came out identical.
+    SelfCheck {
+        #[arg(long, default_value_t = 1)]
+        seed: u64,
+        #[arg(long, default_value_t = 50)]
+        iterations: usize,
+        /// Check the campaign recorded in a results file instead of the default one.
+        #[arg(long)]
+        config_from: Option<String>
+    },
+    /// Runs a seeded campaign for millions of iterations, periodically checking for memory growth, broken cache
+    /// invariants and iterations that do not replay, and writes a health report.
+    Soak {
+        #[arg(long, default_value_t = 1)]
+        seed: u64,
+        #[arg(long, default_value_t = 1_000_000)]
+        iterations: usize,
+        /// How many iterations to run between health checks.
+        #[arg(long, default_value_t = 10_000)]
+        check_every: usize,
+        /// Soak the campaign recorded in a results file instead of the default one.
+        #[arg(long)]
+        config_from: Option<String>,
+        /// Where to write one row per health check.
+        #[arg(long, default_value = "soak.csv")]
+        out: String
+    },
+    /// Runs a seeded campaign that saves its progress to a session file after every phase, and picks the campaign up
+    /// where it left off if the session file already exists.
+    Session {
+        #[arg(long, default_value = "campaign.session")]
+        session: String,
+        #[arg(long, default_value_t = 1)]
+        seed: u64,
+        #[arg(long, default_value_t = 10000)]
+        iterations: usize,
+        /// Run the campaign recorded in a results file instead of the default one. Ignored when resuming.
+        #[arg(long)]
+        config_from: Option<String>,
+        /// Also write the per-iteration records to a JSON Lines (.jsonl) or CSV file.
+        #[arg(long)]
+        output: Option<String>
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct SimulateArgs {
+    /// The size of each secret, a whole number of words up to a line.
+    #[arg(long, default_value_t = 4, value_parser = parse_secret_bytes)]
+    pub secret_bytes: usize,
+    #[arg(long, default_value_t = 10000)]
+    pub iterations: usize,
+    /// One of cpack, bdi, fpc or none, or cpack:<entries>[:fifo|:lru] for C-PACK with a bounded dictionary.
+    #[arg(long, default_value = "cpack", value_parser = parse_compressor)]
+    pub compressor: Compressor,
+    /// The number of groups the iterations run in, one after another, reporting progress after each.
+    #[arg(long, default_value_t = 100)]
+    pub groups: usize,
+    /// The number of recent iterations whose success rate and cost are reported after every group.
+    #[arg(long, default_value_t = 1000)]
+    pub window: usize,
+    /// Stop after the first group at which the 95% confidence interval of the success rate is at most this wide
+    /// (say 0.02 for two percentage points), instead of running every iteration.
+    #[arg(long)]
+    pub stop_at_ci_width: Option<f64>,
+    /// The fewest iterations to run before stopping early.
+    #[arg(long, default_value_t = 100)]
+    pub min_iterations: usize,
+    /// Where to write the results file.
+    #[arg(long, default_value = "results.json")]
+    pub out: String,
+    /// Lay the victim's buffer out as described by a layout file (see AddressSpace). Its secret region sets the secret size.
+    #[arg(long)]
+    pub victim_layout: Option<String>,
+    /// Also write every run's stats and the summary for post-processing, as JSON (results.json) or CSV (results.csv,
+    /// with the summary in results_summary.csv).
+    #[arg(long)]
+    pub output: Option<String>
+}
+
+fn parse_secret_bytes(text: &str) -> Result<usize, String> {
+    let bytes: usize = text.parse().map_err(|e| format!("{}", e))?;
+    if bytes == 0 || bytes > 64 || !bytes.is_multiple_of(4) {
+        return Err(format!("secrets are whole words up to 64 bytes, got {}", bytes));
+    }
+    return Ok(bytes);
+}
+
+fn parse_associativity(text: &str) -> Result<usize, String> {
+    let ways: usize = text.parse().map_err(|e| format!("{}", e))?;
+    if ways == 0 {return Err("caches need at least one way".to_string());}
+    return Ok(ways);
+}
+
+fn parse_short(text: &str) -> Result<u16, String> {
+    return u16::from_str_radix(text.trim_start_matches("0x"), 16).map_err(|e| format!("{:?} is not a hex short: {}", text, e));
+}
+
+fn parse_address(text: &str) -> Result<u64, String> {
+    return u64::from_str_radix(text.trim_start_matches("0x"), 16).map_err(|e| format!("{:?} is not a hex address: {}", text, e));
+}
+
+fn parse_compressor(text: &str) -> Result<Compressor, String> {
+    let lower = text.to_ascii_lowercase();
+    if let Some(dictionary) = lower.strip_prefix("cpack:") {
+        let (entries, replacement) = dictionary.split_once(':').unwrap_or((dictionary, "fifo"));
+        let dict_entries: usize = entries.parse().map_err(|e| format!("{:?} is not a dictionary size: {}", entries, e))?;
+        if dict_entries == 0 {return Err("C-PACK dictionaries need at least one entry".to_string());}
+        let replacement = match replacement {
+            "fifo" => DictionaryReplacement::Fifo,
+            "lru" => DictionaryReplacement::Lru,
+            _ => return Err(format!("unknown dictionary replacement {:?} (expected fifo or lru)", replacement))
+        };
+        return Ok(Compressor::BoundedCPACK {dict_entries, replacement});
+    }
+    return match lower.as_str() {
+        "cpack" => Ok(Compressor::CPACK),
+        "bdi" => Ok(Compressor::BDI),
+        "fpc" => Ok(Compressor::FPC),
+        "none" => Ok(Compressor::NONE),
+        _ => Err(format!("unknown compressor {:?} (expected cpack, cpack:<entries>[:fifo|:lru], bdi, fpc or none)", text))
+    };
+}
+
+fn parse_replacement(text: &str) -> Result<ReplacementPolicy, String> {
+    return match text.to_ascii_lowercase().as_str() {
+        "lru" => Ok(ReplacementPolicy::Lru),
+        "random" => Ok(ReplacementPolicy::Random),
+        "srrip" => Ok(ReplacementPolicy::Srrip),
+        "brrip" => Ok(ReplacementPolicy::Brrip),
+        _ => Err(format!("unknown replacement policy {:?} (expected lru, random, srrip or brrip)", text))
+    };
+}
+
+fn parse_distribution(text: &str) -> Result<SecretDistribution, String> {
+    return match text.to_ascii_lowercase().as_str() {
+        "uniform" => Ok(SecretDistribution::UniformBytes),
+        "ascii" => Ok(SecretDistribution::PrintableAscii),
+        "hex" => Ok(SecretDistribution::HexDigits),
+        "base64" => Ok(SecretDistribution::Base64),
+        _ => Err(format!("unknown secret distribution {:?} (expected uniform, ascii, hex or base64)", text))
+    };
+}
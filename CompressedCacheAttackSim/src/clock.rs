@@ -0,0 +1,77 @@
+use crate::structures::AccessSpeed;
+use serde::{Deserialize, Serialize};
+
+/// How many ticks of simulated time each kind of event takes.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LatencyModel {
+    /// The overhead of one victim API call, on top of the accesses the victim makes to serve it.
+    pub api_call: u64,
+    pub hit: u64,
+    /// Misses in the shared cache. Stores are always charged as hits, since a store buffer hides their latency.
+    pub miss: u64,
+    /// Charge loads that reach the shared cache the cycles it reports (see YACC::read_byte_timed), which tell
+    /// co-allocated lines apart by their decompression, instead of hit and miss.
+    #[serde(default)]
+    pub cache_cycles: bool
+}
+
+impl LatencyModel {
+    /// One tick per victim API call, with cache accesses free: the time base the simulator has always used.
+    pub const CALLS: LatencyModel = LatencyModel {api_call: 1, hit: 0, miss: 0, cache_cycles: false};
+    /// Rough cycle counts for a system call and for hits and misses in a shared last-level cache.
+    pub const CYCLES: LatencyModel = LatencyModel {api_call: 1000, hit: 40, miss: 200, cache_cycles: false};
+    /// As CYCLES, with loads of the shared cache taking the cycles the cache reports.
+    pub const CACHE_CYCLES: LatencyModel = LatencyModel {api_call: 1000, hit: 40, miss: 200, cache_cycles: true};
+}
+
+impl Default for LatencyModel {
+    fn default() -> LatencyModel {
+        return LatencyModel::CALLS;
+    }
+}
+
+/// The simulation's single time base. Every victim API call and every access to the shared cache, by the victim or the
+/// attacker, advances it by the latency of that event; idle periods advance it directly.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SimClock {
+    now: u64,
+    latency: LatencyModel
+}
+
+impl SimClock {
+    pub fn new(latency: LatencyModel) -> SimClock {
+        return SimClock {now: 0, latency};
+    }
+
+    pub fn now(&self) -> u64 {
+        return self.now;
+    }
+
+    pub fn latency(&self) -> LatencyModel {
+        return self.latency;
+    }
+
+    pub fn advance(&mut self, ticks: u64) {
+        self.now += ticks;
+    }
+
+    /// Charges a load that hit or missed.
+    pub fn load(&mut self, speed: AccessSpeed) {
+        self.now += if speed == AccessSpeed::HIT {self.latency.hit} else {self.latency.miss};
+    }
+
+    /// Charges a load of the shared cache, which the cache reported as taking the given cycles.
+    pub fn load_timed(&mut self, speed: AccessSpeed, cycles: u64) {
+        if self.latency.cache_cycles {self.now += cycles;} else {self.load(speed);}
+    }
+
+    /// Charges a store.
+    pub fn store(&mut self) {
+        self.now += self.latency.hit;
+    }
+
+    /// Charges the overhead of a victim API call.
+    pub fn call(&mut self) {
+        self.now += self.latency.api_call;
+    }
+}
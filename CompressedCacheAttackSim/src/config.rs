@@ -0,0 +1,306 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use crate::attacker::{AttackStats, RetryPolicy, Summary};
+use crate::detector::MissRateDetector;
+use crate::experiment::Experiment;
+use crate::structures::{Compressor, Endianness, ReencodingDefense, RegionGranularity, ReplacementPolicy, WritePolicy, ASSOCIATIVITY, LINE_SIZE, SUPERBLOCK_BLOCKS, YACC};
+use crate::clock::LatencyModel;
+use crate::defenses::{RandomizedIndexing, SizeQuantization};
+use crate::dish::{CacheArchitecture, DISH};
+use crate::victim::{BackgroundNoiseConfig, BufferLayout, CoTenantConfig, CrossCoreConfig, ScheduledActivity, SecretBytes, SecretDistribution, SecretPlacement, VictimInterface, VictimProgramYACC};
+use serde::{Deserialize, Serialize};
+
+/// The shared compressed cache.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CacheConfig {
+    /// YACC, or DISH, which ignores the compressor and the thresholds and co-allocates by shared dictionaries.
+    #[serde(default)]
+    pub architecture: CacheArchitecture,
+    pub compressor: Compressor,
+    /// Co-allocation thresholds in bytes, as taken by YACC::set_coallocation_thresholds.
+    pub double_threshold: u64,
+    pub quad_threshold: u64,
+    /// ECC bytes stored with every compressed block, as taken by YACC::set_ecc_overhead.
+    pub ecc_bytes: u64,
+    #[serde(default)]
+    pub reencoding: Option<ReencodingDefense>,
+    /// The probability taken by YACC::set_admission_probability, if the admission defense is on.
+    #[serde(default)]
+    pub admission_probability: Option<f64>,
+    /// Keyed set-index randomization, as taken by YACC::enable_randomized_indexing, if the defense is on.
+    #[serde(default)]
+    pub randomized_indexing: Option<RandomizedIndexing>,
+    /// The buckets taken by YACC::set_size_quantization, if the defense is on.
+    #[serde(default)]
+    pub size_quantization: Option<SizeQuantization>,
+    /// The byte order the compressor reads words in, as taken by YACC::set_endianness.
+    #[serde(default)]
+    pub endianness: Endianness,
+    /// Whether stores also bring in the neighboring line, as in YACC::enable_store_prefetch.
+    #[serde(default)]
+    pub store_prefetch: bool,
+    /// How full sets pick the entry to evict, as taken by YACC::set_replacement_policy.
+    #[serde(default)]
+    pub replacement: ReplacementPolicy,
+    /// When stores reach memory, as taken by YACC::set_write_policy.
+    #[serde(default)]
+    pub write_policy: WritePolicy,
+    /// Entries in each cache set, as taken by YACC::new_with_geometry.
+    #[serde(default = "default_associativity")]
+    pub associativity: usize,
+    /// The number of sets. Configurations saved before the cache had more than one set get one.
+    #[serde(default = "default_sets")]
+    pub sets: usize,
+    /// Blocks per superblock, as taken by YACC::set_superblock_blocks.
+    #[serde(default = "default_superblock_blocks")]
+    pub superblock_blocks: usize,
+    /// Bytes per line, as taken by YACC::set_line_size. The thresholds are not scaled along with it.
+    #[serde(default = "default_line_size")]
+    pub line_size: usize
+}
+
+/// Configurations saved before associativity was configurable ran on the original 8-way set.
+fn default_associativity() -> usize {
+    return ASSOCIATIVITY;
+}
+
+fn default_sets() -> usize {
+    return 1;
+}
+
+fn default_superblock_blocks() -> usize {
+    return SUPERBLOCK_BLOCKS;
+}
+
+fn default_line_size() -> usize {
+    return LINE_SIZE;
+}
+
+impl CacheConfig {
+    /// Builds an empty cache with the configured architecture, compressor and geometry. The other settings are applied by configure.
+    pub fn build_cache(&self) -> YACC {
+        let mut cache = match self.architecture {
+            CacheArchitecture::Yacc => YACC::new_with_geometry(self.compressor, self.sets, self.associativity),
+            CacheArchitecture::Dish => DISH::new_with_geometry(self.sets, self.associativity).into_cache()
+        };
+        cache.set_superblock_blocks(self.superblock_blocks);
+        cache.set_line_size(self.line_size);
+        return cache;
+    }
+
+    /// Applies the settings that can change after the cache is built (everything but the compressor and associativity).
+    pub fn configure(&self, victim: &mut VictimProgramYACC) {
+        victim.set_superblock_blocks(self.superblock_blocks);
+        victim.set_line_size(self.line_size);
+        victim.set_coallocation_thresholds(self.double_threshold, self.quad_threshold);
+        victim.set_ecc_overhead(self.ecc_bytes);
+        if let Some(defense) = self.reencoding {victim.set_reencoding_defense(defense);}
+        if let Some(probability) = self.admission_probability {victim.set_admission_probability(probability);}
+        if let Some(defense) = self.randomized_indexing {victim.enable_randomized_indexing(defense);}
+        if let Some(defense) = self.size_quantization {victim.set_size_quantization(defense);}
+        victim.set_endianness(self.endianness);
+        if self.store_prefetch {victim.enable_store_prefetch();}
+        victim.set_replacement_policy(self.replacement);
+        victim.set_write_policy(self.write_policy);
+    }
+}
+
+impl Default for CacheConfig {
+    /// A stock YACC with C-PACK.
+    fn default() -> CacheConfig {
+        CacheConfig {architecture: CacheArchitecture::Yacc, compressor: Compressor::CPACK, double_threshold: 32, quad_threshold: 16, ecc_bytes: 0, reencoding: None, admission_probability: None, randomized_indexing: None, size_quantization: None, endianness: Endianness::Little, store_prefetch: false, replacement: ReplacementPolicy::Lru, write_policy: WritePolicy::WriteThrough, associativity: ASSOCIATIVITY, sets: 1, superblock_blocks: SUPERBLOCK_BLOCKS, line_size: LINE_SIZE}
+    }
+}
+
+/// Settings for a MissRateDetector watching the victim.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DetectorConfig {
+    pub window: u64,
+    pub max_misses: u64
+}
+
+/// The victim program and everything else running next to it: private caches, co-tenants and defenses.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VictimConfig {
+    pub secret_size: usize,
+    pub interface: VictimInterface,
+    pub layout: BufferLayout,
+    pub cross_core: Option<CrossCoreConfig>,
+    pub co_tenant: Option<CoTenantConfig>,
+    pub detector: Option<DetectorConfig>,
+    /// The victim's time base. Configurations saved before the clock existed get the default, one tick per API call.
+    #[serde(default)]
+    pub latency: LatencyModel,
+    #[serde(default)]
+    pub activities: Vec<ScheduledActivity>,
+    /// Public data kept right before the secret, as in VictimProgramYACC::set_public_prefix. Empty for none.
+    #[serde(default)]
+    pub public_prefix: Vec<u8>,
+    /// Buffer offsets the victim keeps fixed at the given values, as in VictimProgramYACC::protect_bytes.
+    #[serde(default)]
+    pub read_only: Vec<(usize, u8)>,
+    /// Whether the victim clamps its secret line's compressibility with canaries, as in VictimProgramYACC::clamp_compressibility.
+    #[serde(default)]
+    pub clamp_compressibility: bool,
+    /// The chance the attacker misreads each load's timing, as taken by VictimProgramYACC::set_timing_noise.
+    #[serde(default)]
+    pub timing_noise: f64,
+    /// The chance something else adds a miss to the attacker's miss counter, as taken by VictimProgramYACC::set_counter_noise.
+    #[serde(default)]
+    pub counter_noise: f64,
+    /// Whether the 16-byte secret is a key schedule, as set by VictimProgramYACC::refresh_key_schedule.
+    #[serde(default)]
+    pub key_schedule: bool,
+    /// Where the secret's bytes sit in its line, as set by VictimProgramYACC::interleave_secret.
+    #[serde(default)]
+    pub placement: SecretPlacement,
+    /// The buffer offset the secret's words start at, as set by VictimProgramYACC::set_secret_offset. None keeps the
+    /// secret at the end of the buffer.
+    #[serde(default)]
+    pub secret_offset: Option<usize>,
+    /// The granularity at which the secret line's memory is never compressed, as in VictimProgramYACC::enable_uncompressed_region.
+    #[serde(default)]
+    pub uncompressed_region: Option<RegionGranularity>,
+    /// The victim's background accesses to its own data, as taken by VictimProgramYACC::enable_background_noise.
+    #[serde(default)]
+    pub background_noise: Option<BackgroundNoiseConfig>,
+    /// The values the secret's bytes are drawn from, as taken by VictimProgramYACC::redraw_secret.
+    #[serde(default)]
+    pub secret_distribution: SecretDistribution,
+    /// Whether the secret's bytes may be zero or repeat, as taken by VictimProgramYACC::redraw_secret.
+    #[serde(default)]
+    pub secret_bytes: SecretBytes,
+    /// The most buffer writes the victim serves the attacker, as taken by VictimProgramYACC::set_access_budget. None for no limit.
+    #[serde(default)]
+    pub max_attacker_writes: Option<u64>,
+    /// The most buffer reads the victim serves the attacker, likewise.
+    #[serde(default)]
+    pub max_attacker_reads: Option<u64>,
+    /// How many attacker operations the victim serves before re-allocating its buffer, as taken by
+    /// VictimProgramYACC::set_rebase_period. None keeps the buffer where it is.
+    #[serde(default)]
+    pub rebase_every: Option<u64>
+}
+
+impl Default for VictimConfig {
+    /// A victim with a 4-byte secret in a contiguous buffer, alone on its core.
+    fn default() -> VictimConfig {
+        VictimConfig {
+            secret_size: 4,
+            interface: VictimInterface::ReadWrite,
+            layout: BufferLayout::Contiguous,
+            cross_core: None,
+            co_tenant: None,
+            detector: None,
+            latency: LatencyModel::CALLS,
+            activities: Vec::new(),
+            public_prefix: Vec::new(),
+            read_only: Vec::new(),
+            clamp_compressibility: false,
+            timing_noise: 0.0,
+            counter_noise: 0.0,
+            key_schedule: false,
+            placement: SecretPlacement::Contiguous,
+            secret_offset: None,
+            uncompressed_region: None,
+            background_noise: None,
+            secret_distribution: SecretDistribution::UniformBytes,
+            secret_bytes: SecretBytes::UniqueNonZero,
+            max_attacker_writes: None,
+            max_attacker_reads: None,
+            rebase_every: None
+        }
+    }
+}
+
+impl VictimConfig {
+    /// Makes a victim with a fresh random secret on the given cache, set up as described.
+    pub fn build(&self, cache: &CacheConfig) -> VictimProgramYACC {
+        return self.build_seeded(cache, rand::random());
+    }
+
+    /// Same as build, with every random choice the victim makes coming from the given seed (see VictimProgramYACC::seed).
+    pub fn build_seeded(&self, cache: &CacheConfig, seed: u64) -> VictimProgramYACC {
+        let mut victim = VictimProgramYACC::new_seeded_on(self.secret_size, cache.build_cache(), seed, false);
+        cache.configure(&mut victim);
+        self.configure(&mut victim);
+        return victim;
+    }
+
+    /// Sets a freshly built victim up as described, except for its secret size, which is chosen when the victim is made.
+    pub fn configure(&self, victim: &mut VictimProgramYACC) {
+        if let Some(offset) = self.secret_offset {victim.set_secret_offset(offset);}
+        if self.placement == SecretPlacement::Interleaved {victim.interleave_secret();}
+        if self.secret_distribution != SecretDistribution::UniformBytes || self.secret_bytes != SecretBytes::UniqueNonZero {
+            victim.redraw_secret(self.secret_distribution, self.secret_bytes);
+        }
+        if self.key_schedule {victim.refresh_key_schedule();}
+        victim.set_latency_model(self.latency);
+        victim.set_interface(self.interface);
+        victim.set_layout(self.layout);
+        if let Some(cross_core) = self.cross_core {victim.enable_cross_core(cross_core);}
+        if let Some(co_tenant) = self.co_tenant {victim.add_co_tenant(co_tenant);}
+        if let Some(detector) = self.detector {victim.attach_detector(MissRateDetector::new(detector.window, detector.max_misses));}
+        for &activity in &self.activities {victim.schedule_activity(activity);}
+        if !self.public_prefix.is_empty() {victim.set_public_prefix(&self.public_prefix);}
+        if !self.read_only.is_empty() {victim.protect_bytes(&self.read_only);}
+        if self.clamp_compressibility {victim.clamp_compressibility();}
+        if let Some(granularity) = self.uncompressed_region {victim.enable_uncompressed_region(granularity);}
+        if self.timing_noise > 0.0 {victim.set_timing_noise(self.timing_noise);}
+        if self.counter_noise > 0.0 {victim.set_counter_noise(self.counter_noise);}
+        if let Some(noise) = self.background_noise {victim.enable_background_noise(noise);}
+        if let Some(operations) = self.rebase_every {victim.set_rebase_period(operations);}
+        if self.max_attacker_writes.is_some() || self.max_attacker_reads.is_some() {
+            victim.set_access_budget(self.max_attacker_writes, self.max_attacker_reads);
+        }
+    }
+}
+
+/// Everything needed to re-run a campaign: the cache, the victim, the attack and the number of iterations.
+/// Only the secrets (and other random choices) differ between runs of the same configuration.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ExperimentConfig {
+    pub iterations: usize,
+    pub cache: CacheConfig,
+    pub victim: VictimConfig,
+    pub attack: RetryPolicy
+}
+
+impl ExperimentConfig {
+    /// Attacks iterations freshly built victims in parallel.
+    pub fn run(&self) -> Vec<AttackStats> {
+        return self.run_with_secrets().into_iter().map(|(_, stats)| stats).collect();
+    }
+
+    /// Same as run, but also returns each victim's true secret alongside its attack's stats.
+    pub fn run_with_secrets(&self) -> Vec<(Vec<u8>, AttackStats)> {
+        return Experiment::from_config(self).run().rows.into_iter().map(|row| (row.secret, row.stats)).collect();
+    }
+}
+
+/// A results file: the summary of a campaign, along with the configuration that produced it.
+#[derive(Serialize)]
+struct ResultsFile<'a> {
+    config: &'a ExperimentConfig,
+    summary: &'a Summary
+}
+
+/// The part of a results file needed to re-run it. Everything else in the file is ignored.
+#[derive(Deserialize)]
+struct ResultsConfig {
+    config: ExperimentConfig
+}
+
+/// Writes a campaign's configuration and summary to a JSON results file.
+pub fn write_results(path: impl AsRef<Path>, config: &ExperimentConfig, summary: &Summary) -> io::Result<()> {
+    let json = serde_json::to_string_pretty(&ResultsFile {config, summary})?;
+    return fs::write(path, json);
+}
+
+/// Reads the configuration back out of a JSON results file written by write_results.
+pub fn load_config(path: impl AsRef<Path>) -> io::Result<ExperimentConfig> {
+    let text = fs::read_to_string(path)?;
+    let results: ResultsConfig = serde_json::from_str(&text)?;
+    return Ok(results.config);
+}
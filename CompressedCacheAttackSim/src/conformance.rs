@@ -0,0 +1,211 @@
+use crate::aliasing::CacheGeometry;
+use crate::structures::{AccessSpeed, Cache, Compressor, ReplacementPolicy, YACC};
+
+/// A cache model the conformance checks can run on: a Cache whose memory can be set up without touching the cache, so
+/// that every scenario starts from a cold cache whatever the lines hold.
+pub trait ConformanceTarget: Cache {
+    /// Sets a line's contents in memory without an access.
+    fn initialize_line(&mut self, line_addr: u64, contents: &[u8; 64]);
+}
+
+impl ConformanceTarget for YACC {
+    fn initialize_line(&mut self, line_addr: u64, contents: &[u8; 64]) {
+        YACC::initialize_line(self, line_addr, contents);
+    }
+}
+
+/// What a cache model claims about itself, which decides what the checks expect of it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConformanceSpec {
+    pub geometry: CacheGeometry,
+    /// Entries in each set.
+    pub ways: usize,
+    /// The most blocks of a superblock that share one entry when all of them compress to nothing: 1 for a conventional
+    /// cache, up to blocks_per_superblock for a compressed one.
+    pub blocks_per_entry: usize,
+    /// Whether a full set evicts its least recently used entry. The eviction order check is skipped otherwise.
+    pub lru: bool
+}
+
+impl ConformanceSpec {
+    /// What a YACC (or DISH) cache with the default thresholds and no defenses claims.
+    pub fn of(cache: &YACC) -> ConformanceSpec {
+        return ConformanceSpec {
+            geometry: CacheGeometry {sets: cache.set_count() as u64, blocks_per_superblock: cache.superblock_blocks() as u64, ..CacheGeometry::default()},
+            ways: cache.associativity(),
+            blocks_per_entry: if cache.compressor() == Compressor::NONE && !cache.shares_dictionaries() {1} else {cache.superblock_blocks().min(4)},
+            lru: cache.replacement_policy() == ReplacementPolicy::Lru
+        };
+    }
+
+    /// The address of block b of the k-th superblock that maps to the given set.
+    fn line(&self, set: u64, k: u64, b: u64) -> u64 {
+        return (k * self.geometry.sets + set) * self.geometry.blocks_per_superblock + b;
+    }
+}
+
+/// How one check went.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CheckOutcome {
+    Passed,
+    Failed(String),
+    /// The check does not apply to the model, as the spec describes it.
+    Skipped(String)
+}
+
+/// The outcome of every check, in the order they ran.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConformanceReport {
+    pub checks: Vec<(&'static str, CheckOutcome)>
+}
+
+impl ConformanceReport {
+    /// Whether no check failed.
+    pub fn passed(&self) -> bool {
+        return !self.checks.iter().any(|(_, outcome)| matches!(outcome, CheckOutcome::Failed(_)));
+    }
+
+    pub fn print(&self) {
+        for (name, outcome) in &self.checks {
+            match outcome {
+                CheckOutcome::Passed => println!("  {}: passed", name),
+                CheckOutcome::Failed(why) => println!("  {}: FAILED ({})", name, why),
+                CheckOutcome::Skipped(why) => println!("  {}: skipped ({})", name, why)
+            }
+        }
+    }
+}
+
+type Check<C> = fn(&mut dyn FnMut() -> C, &ConformanceSpec) -> CheckOutcome;
+
+/// Runs every conformance check on fresh caches from make, and reports how each went. Every model the simulator
+/// attacks should pass, and so should any new one, given an honest spec:
+/// * fill: a cold set takes one superblock per way without evicting any, and reads return what memory holds;
+/// * conflict: a set cannot hold more incompressible superblocks than it has ways, and thrashing it leaves other sets alone;
+/// * co-allocation: two compressible blocks of a superblock share an entry, and two incompressible ones do not;
+/// * upgrade: an entry takes up to blocks_per_entry compressible blocks of its superblock, and no more;
+/// * eviction order: a full LRU set evicts the entry used longest ago.
+pub fn check_conformance<C: ConformanceTarget>(spec: &ConformanceSpec, mut make: impl FnMut() -> C) -> ConformanceReport {
+    let checks: [(&'static str, Check<C>); 5] = [
+        ("fill", check_fill),
+        ("conflict", check_conflict),
+        ("co-allocation", check_coallocation),
+        ("upgrade", check_upgrade),
+        ("eviction order", check_eviction_order)
+    ];
+    return ConformanceReport {checks: checks.into_iter().map(|(name, check)| (name, check(&mut make, spec))).collect()};
+}
+
+/// Contents no compressor can shrink to half a line: 64 bytes hashed from the line's address.
+fn incompressible(line_addr: u64) -> [u8; 64] {
+    let mut z = line_addr.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    return std::array::from_fn(|_| {
+        z = (z ^ (z >> 31)).wrapping_mul(0xBF58_476D_1CE4_E5B9).wrapping_add(0x9E37_79B9_7F4A_7C15);
+        (z >> 56) as u8
+    });
+}
+
+/// Gives every line incompressible contents.
+fn scramble(cache: &mut impl ConformanceTarget, lines: &[u64]) {
+    for &line in lines {cache.initialize_line(line, &incompressible(line));}
+}
+
+/// Reads the first byte of each line in order, and returns the lines that missed.
+fn misses(cache: &mut impl Cache, lines: &[u64]) -> Vec<u64> {
+    return lines.iter().copied().filter(|&line| cache.read_byte(line << 6).1 == AccessSpeed::MISS).collect();
+}
+
+/// The first block of the first count superblocks of set 0, after skip.
+fn superblocks(spec: &ConformanceSpec, skip: usize, count: usize) -> Vec<u64> {
+    return (skip..skip + count).map(|k| spec.line(0, k as u64, 0)).collect();
+}
+
+fn check_fill<C: ConformanceTarget>(make: &mut dyn FnMut() -> C, spec: &ConformanceSpec) -> CheckOutcome {
+    let mut cache = make();
+    let lines = superblocks(spec, 0, spec.ways);
+    scramble(&mut cache, &lines);
+    let cold = misses(&mut cache, &lines);
+    if cold.len() != lines.len() {return CheckOutcome::Failed(format!("{} of {} lines hit in a cold cache", lines.len() - cold.len(), lines.len()));}
+    let missed = misses(&mut cache, &lines);
+    if !missed.is_empty() {return CheckOutcome::Failed(format!("lines {:X?} were evicted from a set holding only its {} ways", missed, spec.ways));}
+    for &line in &lines {
+        let expected = incompressible(line);
+        if let Some(i) = (0..64).find(|&i| cache.read_byte((line << 6) + i as u64).0 != expected[i]) {
+            return CheckOutcome::Failed(format!("byte {} of line {:X} does not read back as initialized", i, line));
+        }
+    }
+    let addr = (lines[0] << 6) + 5;
+    let value = !incompressible(lines[0])[5];
+    cache.write_byte(addr, value);
+    if cache.read_byte(addr).0 != value {return CheckOutcome::Failed(format!("a byte written to {:X} does not read back", addr));}
+    return CheckOutcome::Passed;
+}
+
+fn check_conflict<C: ConformanceTarget>(make: &mut dyn FnMut() -> C, spec: &ConformanceSpec) -> CheckOutcome {
+    let mut cache = make();
+    let lines = superblocks(spec, 0, spec.ways + 1);
+    scramble(&mut cache, &lines);
+    misses(&mut cache, &lines);
+    if misses(&mut cache, &lines).is_empty() {
+        return CheckOutcome::Failed(format!("a set of {} ways held {} incompressible superblocks", spec.ways, lines.len()));
+    }
+    if spec.geometry.sets < 2 {return CheckOutcome::Passed;}
+    let mut cache = make();
+    let bystander = [spec.line(1, 0, 0)];
+    let thrash = superblocks(spec, 0, 2 * spec.ways);
+    scramble(&mut cache, &bystander);
+    scramble(&mut cache, &thrash);
+    misses(&mut cache, &bystander);
+    misses(&mut cache, &thrash);
+    if !misses(&mut cache, &bystander).is_empty() {return CheckOutcome::Failed("thrashing set 0 evicted a line of set 1".to_string());}
+    return CheckOutcome::Passed;
+}
+
+/// Accesses the given blocks of the first superblock of set 0 (scrambled first if asked), fills the other ways of
+/// the set with incompressible superblocks, and returns whether every one of those lines is still held.
+fn held_beside_full_set<C: ConformanceTarget>(make: &mut dyn FnMut() -> C, spec: &ConformanceSpec, blocks: u64, scrambled: bool) -> bool {
+    let mut cache = make();
+    let shared: Vec<u64> = (0..blocks).map(|b| spec.line(0, 0, b)).collect();
+    let rest = superblocks(spec, 1, spec.ways - 1);
+    if scrambled {scramble(&mut cache, &shared);}
+    scramble(&mut cache, &rest);
+    misses(&mut cache, &shared);
+    misses(&mut cache, &rest);
+    return misses(&mut cache, &shared).is_empty() && misses(&mut cache, &rest).is_empty();
+}
+
+fn check_coallocation<C: ConformanceTarget>(make: &mut dyn FnMut() -> C, spec: &ConformanceSpec) -> CheckOutcome {
+    if spec.geometry.blocks_per_superblock < 2 {return CheckOutcome::Skipped("superblocks of a single block".to_string());}
+    let shared = held_beside_full_set(make, spec, 2, false);
+    if shared != (spec.blocks_per_entry >= 2) {
+        return CheckOutcome::Failed(format!("two zeroed blocks of a superblock {} an entry", if shared {"shared"} else {"did not share"}));
+    }
+    if held_beside_full_set(make, spec, 2, true) {return CheckOutcome::Failed("two incompressible blocks of a superblock shared an entry".to_string());}
+    return CheckOutcome::Passed;
+}
+
+fn check_upgrade<C: ConformanceTarget>(make: &mut dyn FnMut() -> C, spec: &ConformanceSpec) -> CheckOutcome {
+    let most = spec.blocks_per_entry as u64;
+    if most < 2 {return CheckOutcome::Skipped("entries hold a single block".to_string());}
+    if !held_beside_full_set(make, spec, most, false) {return CheckOutcome::Failed(format!("{} zeroed blocks of a superblock did not share an entry", most));}
+    if most < spec.geometry.blocks_per_superblock && held_beside_full_set(make, spec, most + 1, false) {
+        return CheckOutcome::Failed(format!("{} zeroed blocks of a superblock shared an entry", most + 1));
+    }
+    return CheckOutcome::Passed;
+}
+
+fn check_eviction_order<C: ConformanceTarget>(make: &mut dyn FnMut() -> C, spec: &ConformanceSpec) -> CheckOutcome {
+    if !spec.lru {return CheckOutcome::Skipped("not LRU".to_string());}
+    if spec.ways < 2 {return CheckOutcome::Skipped("a single way".to_string());}
+    let mut cache = make();
+    let lines = superblocks(spec, 0, spec.ways + 1);
+    scramble(&mut cache, &lines);
+    misses(&mut cache, &lines[..spec.ways]);
+    misses(&mut cache, &lines[..1]);
+    misses(&mut cache, &lines[spec.ways..]);
+    let kept: Vec<u64> = lines.iter().copied().filter(|&line| line != lines[1]).collect();
+    let missed = misses(&mut cache, &kept);
+    if !missed.is_empty() {return CheckOutcome::Failed(format!("lines {:X?} were evicted instead of the least recently used", missed));}
+    if misses(&mut cache, &lines[1..2]).is_empty() {return CheckOutcome::Failed("the least recently used line was not evicted".to_string());}
+    return CheckOutcome::Passed;
+}
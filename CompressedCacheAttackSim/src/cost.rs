@@ -0,0 +1,80 @@
+use std::ops::{Add, Mul};
+use crate::attacker::Summary;
+
+/// The price of one action, or a total over many of them.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Cost {
+    pub cycles: u64,
+    pub syscalls: u64,
+    pub round_trips: u64
+}
+
+impl Add for Cost {
+    type Output = Cost;
+
+    fn add(self, rhs: Cost) -> Cost {
+        return Cost {cycles: self.cycles + rhs.cycles, syscalls: self.syscalls + rhs.syscalls, round_trips: self.round_trips + rhs.round_trips};
+    }
+}
+
+impl Mul<usize> for Cost {
+    type Output = Cost;
+
+    fn mul(self, count: usize) -> Cost {
+        let count = count as u64;
+        return Cost {cycles: self.cycles * count, syscalls: self.syscalls * count, round_trips: self.round_trips * count};
+    }
+}
+
+/// What each kind of attacker action costs in a given threat model.
+/// Victim calls that do not write (reads, guesses and buffer relocations) are all charged as reads.
+/// A probe is charged for its timing measurement only; its flush and check loads are charged as attacker loads.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CostModel {
+    pub name: &'static str,
+    pub victim_write: Cost,
+    pub victim_read: Cost,
+    pub attacker_load: Cost,
+    pub probe: Cost
+}
+
+impl CostModel {
+    /// An attacker on the same machine that reaches the victim through a local service, one syscall per call.
+    pub const CO_RESIDENT: CostModel = CostModel {
+        name: "co-resident",
+        victim_write: Cost {cycles: 2_000, syscalls: 1, round_trips: 0},
+        victim_read: Cost {cycles: 2_000, syscalls: 1, round_trips: 0},
+        attacker_load: Cost {cycles: 200, syscalls: 0, round_trips: 0},
+        probe: Cost {cycles: 100, syscalls: 0, round_trips: 0}
+    };
+
+    /// An attacker that talks to the victim over the network, and times the cache through a co-resident helper.
+    pub const REMOTE: CostModel = CostModel {
+        name: "remote",
+        victim_write: Cost {cycles: 20_000, syscalls: 2, round_trips: 1},
+        victim_read: Cost {cycles: 20_000, syscalls: 2, round_trips: 1},
+        attacker_load: Cost {cycles: 200, syscalls: 0, round_trips: 0},
+        probe: Cost {cycles: 100, syscalls: 0, round_trips: 0}
+    };
+
+    /// A sandboxed attacker (e.g. script in a browser) with cheap message passing to the victim but a coarse timer,
+    /// so each probe has to be amplified over many repetitions.
+    pub const SANDBOXED: CostModel = CostModel {
+        name: "sandboxed",
+        victim_write: Cost {cycles: 500, syscalls: 0, round_trips: 0},
+        victim_read: Cost {cycles: 500, syscalls: 0, round_trips: 0},
+        attacker_load: Cost {cycles: 400, syscalls: 0, round_trips: 0},
+        probe: Cost {cycles: 50_000, syscalls: 0, round_trips: 0}
+    };
+
+    pub const ALL: [CostModel; 3] = [CostModel::CO_RESIDENT, CostModel::REMOTE, CostModel::SANDBOXED];
+
+    /// Totals the cost of every action taken over a set of attacks.
+    pub fn total(&self, summary: &Summary) -> Cost {
+        let victim_reads = summary.victim_interactions - summary.victim_write_calls;
+        return self.victim_write * summary.victim_write_calls
+            + self.victim_read * victim_reads
+            + self.attacker_load * summary.attacker_cache_lines_loaded
+            + self.probe * summary.set_evictions;
+    }
+}
@@ -0,0 +1,90 @@
+//! Defenses that plug into the cache from outside its compression and replacement logic.
+
+use serde::{Deserialize, Serialize};
+
+/// Keyed set-index randomization in the style of CEASER: superblock addresses are encrypted under a secret key before
+/// picking a set, so the attacker's address arithmetic no longer tells which lines share the victim's set, and the key
+/// is replaced every remap_period accesses, moving every resident line to its set under the new key.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RandomizedIndexing {
+    /// Accesses to the cache between remaps, or None to keep the first key for good.
+    pub remap_period: Option<u64>
+}
+
+/// The running state of randomized indexing in one cache: its current key, and the accesses since the last remap.
+#[derive(Debug, Clone)]
+pub struct IndexRandomizer {
+    config: RandomizedIndexing,
+    key: u64,
+    accesses: u64,
+    remaps: u64
+}
+
+/// Size quantization: the cache only tells compressed sizes apart in whole buckets of bucket_bytes, rounding every
+/// line's stored size up to the next bucket, and on every write pads the line by one more bucket with probability
+/// pad_probability. Sizes that round into the same bucket are indistinguishable to the co-allocation logic.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SizeQuantization {
+    pub bucket_bytes: u64,
+    pub pad_probability: f64
+}
+
+impl SizeQuantization {
+    /// Rounds a stored size up to its bucket, plus one more bucket if the line was padded.
+    pub fn quantize(&self, bytes: u64, padded: bool) -> u64 {
+        return bytes.div_ceil(self.bucket_bytes) * self.bucket_bytes + if padded {self.bucket_bytes} else {0};
+    }
+}
+
+/// Rounds of the Feistel network that encrypts superblock addresses.
+const FEISTEL_ROUNDS: u64 = 4;
+
+impl IndexRandomizer {
+    pub fn new(config: RandomizedIndexing, key: u64) -> IndexRandomizer {
+        assert!(config.remap_period != Some(0), "Remap periods must be at least one access long");
+        IndexRandomizer {config, key, accesses: 0, remaps: 0}
+    }
+
+    /// Returns the set a superblock maps to under the current key. The encryption is a bijection on superblock
+    /// addresses, so every set still gets the same share of them.
+    pub fn set_of(&self, superblock: u64, sets: usize) -> usize {
+        return (encrypt(superblock, self.key) % sets as u64) as usize;
+    }
+
+    /// Counts an access. Returns whether the remap period just ran out, in which case the cache should rekey and remap.
+    pub fn tick(&mut self) -> bool {
+        let Some(period) = self.config.remap_period else {return false};
+        self.accesses += 1;
+        return self.accesses.is_multiple_of(period);
+    }
+
+    /// Replaces the key, as at the start of every remap period.
+    pub fn rekey(&mut self, key: u64) {
+        self.key = key;
+        self.remaps += 1;
+    }
+
+    /// Returns how many times the key was replaced.
+    pub fn remaps(&self) -> u64 {
+        return self.remaps;
+    }
+}
+
+/// Encrypts a superblock address with a Feistel network over its two 32-bit halves, standing in for CEASER's
+/// low-latency block cipher.
+fn encrypt(superblock: u64, key: u64) -> u64 {
+    let (mut left, mut right) = (superblock >> 32, superblock & 0xFFFF_FFFF);
+    for round in 0..FEISTEL_ROUNDS {
+        let mixed = round_function(right ^ key.rotate_left(16 * round as u32) ^ round);
+        (left, right) = (right, left ^ (mixed & 0xFFFF_FFFF));
+    }
+    return (left << 32) | right;
+}
+
+/// The SplitMix64 finalizer, used as the Feistel round function.
+fn round_function(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    return z ^ (z >> 31);
+}
@@ -0,0 +1,36 @@
+/// A simple anomaly detector watching the victim's cache misses: time is cut into fixed windows of simulated time,
+/// and an alarm is raised for every window in which the victim misses more than a threshold number of times.
+/// Prime+probe attacks make the victim miss on nearly every reload, so they trip this quickly unless they pace themselves.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MissRateDetector {
+    /// Length of a window, in ticks of the victim's clock.
+    pub window: u64,
+    /// The most misses a window may contain without raising an alarm.
+    pub max_misses: u64,
+    current_window: u64,
+    misses_in_window: u64,
+    alarms: u64
+}
+
+impl MissRateDetector {
+    pub fn new(window: u64, max_misses: u64) -> MissRateDetector {
+        assert!(window > 0, "Detector windows must be at least one tick long");
+        MissRateDetector {window, max_misses, current_window: 0, misses_in_window: 0, alarms: 0}
+    }
+
+    /// Records a victim miss at the given time.
+    pub fn observe_miss(&mut self, now: u64) {
+        let window = now / self.window;
+        if window != self.current_window {
+            self.current_window = window;
+            self.misses_in_window = 0;
+        }
+        self.misses_in_window += 1;
+        if self.misses_in_window == self.max_misses + 1 {self.alarms += 1;}
+    }
+
+    /// Returns the number of windows that raised an alarm so far.
+    pub fn alarms(&self) -> u64 {
+        return self.alarms;
+    }
+}
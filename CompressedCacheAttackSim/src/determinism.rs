@@ -0,0 +1,31 @@
+use crate::config::ExperimentConfig;
+use crate::experiment::{Experiment, IterationRow};
+
+/// An iteration whose results differed between two runs of the same seed, with both runs' results.
+#[derive(Debug, Clone)]
+pub struct Mismatch {
+    pub iteration: usize,
+    pub serial: String,
+    pub parallel: String
+}
+
+/// Runs the campaign described by config from seed twice, once serially and once in parallel, and returns every
+/// iteration whose results differ. Seeded replays, result bundles and failure replays all rely on this coming back empty.
+pub fn check_determinism(config: &ExperimentConfig, seed: u64) -> Vec<Mismatch> {
+    let experiment = Experiment::from_config_seeded(config, seed);
+    let serial = experiment.run_serially();
+    let parallel = experiment.run();
+    return serial.rows.iter().zip(&parallel.rows)
+        .map(|(a, b)| (a.iteration, outcome(a), outcome(b)))
+        .filter(|(_, a, b)| a != b)
+        .map(|(iteration, serial, parallel)| Mismatch {iteration, serial, parallel})
+        .collect();
+}
+
+/// Everything an iteration produced but its CPU time, which never replays. Floats are printed in full, so equal
+/// outcomes mean bit-identical results.
+pub(crate) fn outcome(row: &IterationRow) -> String {
+    let mut stats = row.stats.clone();
+    stats.phase_time = Default::default();
+    return format!("{:X?} {:?}", row.secret, stats);
+}
@@ -0,0 +1,120 @@
+//! DISH (Panda and Seznec, "Dictionary Sharing: An Efficient Cache Compression Scheme for Compressed Caches"): a
+//! compressed cache whose co-allocated blocks share one dictionary, instead of each being compressed on its own.
+
+use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use crate::structures::{Compressor, Endianness, YACC};
+
+/// Scheme I: the dictionary holds this many whole words, and every word of a block is a 3-bit pointer into it.
+pub const SCHEME_I_ENTRIES: usize = 8;
+
+/// Scheme II: the dictionary holds the upper 28 bits of this many words, and every word of a block is a 2-bit pointer
+/// plus its own low 4 bits.
+pub const SCHEME_II_ENTRIES: usize = 4;
+
+/// The cache organizations a victim can run on.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CacheArchitecture {
+    /// YACC: blocks of a superblock share an entry when each compresses under the entry's size threshold.
+    #[default]
+    Yacc,
+    /// DISH: blocks of a superblock share an entry when their words fit one shared dictionary.
+    Dish
+}
+
+/// Returns whether blocks can share one dictionary under either scheme: their distinct words number at most
+/// SCHEME_I_ENTRIES, or the distinct upper 28 bits of their words at most SCHEME_II_ENTRIES. Words are read in the given
+/// byte order.
+pub fn shared_dictionary_fits(blocks: &[&[u8]], endianness: Endianness) -> bool {
+    let words: HashSet<u32> = blocks.iter()
+        .flat_map(|block| block.chunks(4))
+        .map(|word| u32::from_le_bytes(endianness.word_bytes(word.try_into().expect("Lines are whole words"))))
+        .collect();
+    if words.len() <= SCHEME_I_ENTRIES {return true;}
+    return words.iter().map(|word| word >> 4).collect::<HashSet<u32>>().len() <= SCHEME_II_ENTRIES;
+}
+
+/// Builds DISH caches. DISH keeps YACC's superblock-tagged entries of up to four blocks, so it is not a cache type of
+/// its own but a mode of YACC, with dictionary sharing switched on (see YACC::share_dictionaries): a block joins an
+/// entry of its superblock only if the entry's dictionary still fits every block's words, and is never compressed on
+/// its own. The co-allocation thresholds and the size-based defenses do not apply. Callers take the YACC with
+/// into_cache.
+pub struct DISH {
+    cache: YACC
+}
+
+impl DISH {
+    /// Makes an empty DISH cache set with the given number of entries.
+    pub fn new(associativity: usize) -> DISH {
+        return DISH::new_with_geometry(1, associativity);
+    }
+
+    /// Makes an empty DISH cache of sets sets with ways entries each, indexed by superblock as YACC::new_with_geometry.
+    pub fn new_with_geometry(sets: usize, ways: usize) -> DISH {
+        // Blocks are stored as dictionary pointers, so no line compressor is involved.
+        let mut cache = YACC::new_with_geometry(Compressor::NONE, sets, ways);
+        cache.share_dictionaries();
+        return DISH {cache};
+    }
+
+    /// Returns the cache as the YACC it is built on, as the victim constructors take it.
+    pub fn into_cache(self) -> YACC {
+        return self.cache;
+    }
+}
+
+impl From<DISH> for YACC {
+    fn from(dish: DISH) -> YACC {
+        return dish.into_cache();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a block from its words, least significant byte first.
+    fn block(words: &[u32]) -> Vec<u8> {
+        return words.iter().flat_map(|word| word.to_le_bytes()).collect();
+    }
+
+    #[test]
+    fn scheme_i_fits_eight_distinct_words() {
+        // Words far apart, so that scheme II never applies.
+        let eight: Vec<u32> = (1..=8).map(|i| i << 24).collect();
+        let nine: Vec<u32> = (1..=9).map(|i| i << 24).collect();
+        assert!(shared_dictionary_fits(&[&block(&eight)], Endianness::Little));
+        assert!(!shared_dictionary_fits(&[&block(&nine)], Endianness::Little));
+        // Repeats take no extra entries.
+        let repeated: Vec<u32> = eight.iter().chain(eight.iter()).copied().collect();
+        assert!(shared_dictionary_fits(&[&block(&repeated)], Endianness::Little));
+    }
+
+    #[test]
+    fn scheme_ii_fits_four_distinct_upper_values() {
+        // 16 distinct words, but only 4 distinct upper 28 bits.
+        let four: Vec<u32> = (0..16).map(|i| ((i / 4) << 24) | (i % 4)).collect();
+        assert!(shared_dictionary_fits(&[&block(&four)], Endianness::Little));
+        // A fifth upper value needs a fifth entry.
+        let five: Vec<u32> = four.iter().copied().chain([4 << 24]).collect();
+        assert!(!shared_dictionary_fits(&[&block(&five)], Endianness::Little));
+    }
+
+    #[test]
+    fn blocks_share_one_dictionary() {
+        let first = block(&[1 << 24, 2 << 24, 3 << 24, 4 << 24, 5 << 24]);
+        let second = block(&[6 << 24, 7 << 24, 8 << 24, 9 << 24, 1 << 24]);
+        assert!(shared_dictionary_fits(&[&first], Endianness::Little));
+        assert!(shared_dictionary_fits(&[&second], Endianness::Little));
+        assert!(!shared_dictionary_fits(&[&first, &second], Endianness::Little));
+    }
+
+    #[test]
+    fn words_are_read_in_the_given_byte_order() {
+        // Little-endian, these are the words 0 to 15, which share the upper 28 bits. Big-endian, the varying byte is the
+        // most significant one, so the 16 words have 16 distinct upper values.
+        let bytes = block(&(0..16).collect::<Vec<u32>>());
+        assert!(shared_dictionary_fits(&[&bytes], Endianness::Little));
+        assert!(!shared_dictionary_fits(&[&bytes], Endianness::Big));
+    }
+}
@@ -0,0 +1,203 @@
+use std::fs::File;
+use std::io::Write;
+use std::time::{Duration, Instant};
+use crate::attacker::{attack_yacc_cpack_with_retries, summarize, AttackPhase, AttackStats, RetryPolicy, Summary};
+use crate::config::{CacheConfig, ExperimentConfig};
+use crate::statistics::{wilson_ci95, CampaignProgress, RollingWindow, StoppingRule};
+use crate::victim::VictimProgramYACC;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Makes the victim for each iteration, given the cache it should run on and the iteration's index.
+pub type VictimFactory = Box<dyn Fn(&CacheConfig, usize) -> VictimProgramYACC + Send + Sync>;
+
+/// How many iterations to run, split into groups that run one after another (each in parallel).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IterationPlan {
+    pub iterations: usize,
+    pub groups: usize
+}
+
+impl IterationPlan {
+    /// All iterations in a single parallel group.
+    pub fn single(iterations: usize) -> IterationPlan {
+        return IterationPlan {iterations, groups: 1};
+    }
+
+    /// Returns the iteration indices of each group. Earlier groups take one extra iteration if they do not divide evenly.
+    fn group_ranges(&self) -> Vec<std::ops::Range<usize>> {
+        let groups = self.groups.clamp(1, self.iterations.max(1));
+        let mut start = 0;
+        return (0..groups).map(|i| {
+            let size = self.iterations / groups + usize::from(i < self.iterations % groups);
+            start += size;
+            start - size..start
+        }).collect();
+    }
+}
+
+/// A campaign of attacks: who the victims are, how they are attacked, the cache they run on, and how many to run.
+pub struct Experiment {
+    pub cache: CacheConfig,
+    pub attack: RetryPolicy,
+    pub plan: IterationPlan,
+    victims: VictimFactory
+}
+
+/// The outcome of one iteration: the victim's true secret and its (scored) attack's stats.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IterationRow {
+    pub iteration: usize,
+    pub secret: Vec<u8>,
+    pub stats: AttackStats
+}
+
+/// Wall-clock and per-phase timing for an experiment, so users can judge how large a sweep is feasible
+/// and notice when the simulator itself gets slower.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExperimentMetadata {
+    pub iterations: usize,
+    pub threads: usize,
+    pub wall_clock: Duration,
+    pub phase_time: [Duration; 4]
+}
+
+/// Everything an experiment produced: the aggregate summary, one row per iteration (in iteration order) and its timing.
+#[derive(Debug, Clone)]
+pub struct ExperimentResult {
+    pub summary: Summary,
+    pub rows: Vec<IterationRow>,
+    pub metadata: ExperimentMetadata
+}
+
+impl Experiment {
+    /// Makes an experiment whose victims come from the given factory.
+    pub fn new(cache: CacheConfig, attack: RetryPolicy, plan: IterationPlan,
+               victims: impl Fn(&CacheConfig, usize) -> VictimProgramYACC + Send + Sync + 'static) -> Experiment {
+        return Experiment {cache, attack, plan, victims: Box::new(victims)};
+    }
+
+    /// Makes the experiment described by a configuration: every victim gets a fresh random secret, set up as in
+    /// VictimConfig::build, and all iterations run in a single group.
+    pub fn from_config(config: &ExperimentConfig) -> Experiment {
+        let victim = config.victim.clone();
+        return Experiment::new(config.cache.clone(), config.attack.clone(), IterationPlan::single(config.iterations), move |cache, _| victim.build(cache));
+    }
+
+    /// Same as from_config, but every random choice comes from seed: iteration i attacks the victim built from seed + i
+    /// (see VictimConfig::build_seeded), so running it again replays it exactly.
+    pub fn from_config_seeded(config: &ExperimentConfig, seed: u64) -> Experiment {
+        let victim = config.victim.clone();
+        return Experiment::new(config.cache.clone(), config.attack.clone(), IterationPlan::single(config.iterations),
+                               move |cache, iteration| victim.build_seeded(cache, seed.wrapping_add(iteration as u64)));
+    }
+
+    /// Runs every iteration.
+    pub fn run(&self) -> ExperimentResult {
+        return self.run_with_progress(|_, _| ());
+    }
+
+    /// Same as run, but calls progress with the number of groups finished so far and the total after every group.
+    pub fn run_with_progress(&self, mut progress: impl FnMut(usize, usize)) -> ExperimentResult {
+        let start = Instant::now();
+        let groups = self.plan.group_ranges();
+        let mut rows: Vec<IterationRow> = Vec::with_capacity(self.plan.iterations);
+        for (i, group) in groups.iter().enumerate() {
+            rows.par_extend(group.clone().into_par_iter().map(|iteration| self.run_iteration(iteration)));
+            progress(i + 1, groups.len());
+        }
+        return self.finish(rows, start, rayon::current_num_threads());
+    }
+
+    /// Same as run_with_progress, but keeps rolling statistics over the last window iterations, reports where the campaign
+    /// stands after every group, and skips the remaining groups once stop (if given) is met. Stopping can only happen
+    /// between groups, so it needs a plan of many groups to save much.
+    pub fn run_windowed(&self, window: usize, stop: Option<StoppingRule>, mut progress: impl FnMut(&CampaignProgress)) -> ExperimentResult {
+        let start = Instant::now();
+        let mut rows: Vec<IterationRow> = Vec::with_capacity(self.plan.iterations);
+        let mut recent = RollingWindow::new(window.max(1));
+        let mut successes = 0;
+        for group in self.plan.group_ranges() {
+            let first = rows.len();
+            rows.par_extend(group.into_par_iter().map(|iteration| self.run_iteration(iteration)));
+            for row in &rows[first..] {
+                recent.push(row.stats.success, row.stats.set_evictions as f64);
+                successes += usize::from(row.stats.success);
+            }
+            let stopping = stop.is_some_and(|rule| rule.is_met(successes, rows.len()));
+            progress(&CampaignProgress {
+                iterations: rows.len(),
+                successes,
+                success_ci95: wilson_ci95(successes, rows.len()),
+                window_iterations: recent.len(),
+                window_success_rate: recent.success_rate(),
+                window_cost: recent.cost(),
+                stopping
+            });
+            if stopping {break;}
+        }
+        return self.finish(rows, start, rayon::current_num_threads());
+    }
+
+    /// Same as run, but runs every iteration on the calling thread, one after another.
+    pub fn run_serially(&self) -> ExperimentResult {
+        let start = Instant::now();
+        let rows = (0..self.plan.iterations).map(|iteration| self.run_iteration(iteration)).collect();
+        return self.finish(rows, start, 1);
+    }
+
+    /// Summarizes the rows of an experiment that started at start and ran on threads threads.
+    fn finish(&self, rows: Vec<IterationRow>, start: Instant, threads: usize) -> ExperimentResult {
+        let stats: Vec<AttackStats> = rows.iter().map(|row| row.stats.clone()).collect();
+        let summary = summarize(&stats);
+        let metadata = ExperimentMetadata {
+            iterations: summary.iterations,
+            threads,
+            wall_clock: start.elapsed(),
+            phase_time: summary.phase_time
+        };
+        return ExperimentResult {summary, rows, metadata};
+    }
+
+    fn run_iteration(&self, iteration: usize) -> IterationRow {
+        return self.run_inspected(iteration, |_| ());
+    }
+
+    /// Runs a single iteration, handing its victim to inspect once the attack is over and before it is judged.
+    pub fn run_inspected(&self, iteration: usize, inspect: impl FnOnce(&VictimProgramYACC)) -> IterationRow {
+        let mut victim = (self.victims)(&self.cache, iteration);
+        let secret_size = victim.secret_size();
+        let mut stats = attack_yacc_cpack_with_retries(&mut victim, secret_size, &self.attack, false);
+        inspect(&victim);
+        let judge = victim.into_judge();
+        judge.score(&mut stats);
+        return IterationRow {iteration, secret: judge.secret().to_vec(), stats};
+    }
+}
+
+impl ExperimentMetadata {
+    pub fn iterations_per_second(&self) -> f64 {
+        return self.iterations as f64 / self.wall_clock.as_secs_f64();
+    }
+
+    pub fn print(&self) {
+        println!("Wall-clock time: {:.3} s on {} threads ({:.2} iterations/s)", self.wall_clock.as_secs_f64(), self.threads, self.iterations_per_second());
+        for phase in AttackPhase::ALL {
+            println!("CPU time in {:?} phase: {:.3} s", phase, self.phase_time[phase as usize].as_secs_f64());
+        }
+    }
+
+    /// Writes the metadata as key,value rows.
+    pub fn export(&self, path: &str) -> std::io::Result<()> {
+        let mut out = File::create(path)?;
+        writeln!(out, "key,value")?;
+        writeln!(out, "iterations,{}", self.iterations)?;
+        writeln!(out, "threads,{}", self.threads)?;
+        writeln!(out, "wall_clock_seconds,{}", self.wall_clock.as_secs_f64())?;
+        writeln!(out, "iterations_per_second,{}", self.iterations_per_second())?;
+        for phase in AttackPhase::ALL {
+            writeln!(out, "cpu_seconds_{:?},{}", phase, self.phase_time[phase as usize].as_secs_f64())?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+use crate::structures::{bdi_bits, cpack_bits, fpc_bits};
+
+/// A line's compressed size under every compressor, in bits: what an attacker could learn about the line by probing how
+/// it compresses, without reading it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Fingerprint {
+    pub cpack: u64,
+    pub bdi: u64,
+    pub fpc: u64
+}
+
+impl Fingerprint {
+    pub fn of(line: &[u8; 64]) -> Fingerprint {
+        return Fingerprint {cpack: cpack_bits(line), bdi: bdi_bits(line), fpc: fpc_bits(line)};
+    }
+
+    /// The fewest C-PACK bits of a line holding secret_words high-entropy words: those words take 34 bits each (they match
+    /// nothing) and, at best, every other word is zero.
+    fn min_cpack_bits(secret_words: usize) -> u64 {
+        return 34 * secret_words as u64 + 2 * (16 - secret_words) as u64;
+    }
+
+    /// Whether the sizes leave room for secret_words high-entropy words: C-PACK and FPC are at least what those words
+    /// cost on their own (34 and 35 bits each), and BDI did not find the line all zero or one repeated value.
+    pub fn could_hold(&self, secret_words: usize) -> bool {
+        return self.cpack >= Fingerprint::min_cpack_bits(secret_words) && self.fpc >= 35 * secret_words as u64 && self.bdi > 64;
+    }
+
+    /// The C-PACK bits beyond the fewest a line holding secret_words high-entropy words could take. The smaller it is, the
+    /// more the line looks like a pocket of secret in otherwise compressible data, which is what the attack needs.
+    pub fn slack(&self, secret_words: usize) -> u64 {
+        return self.cpack.saturating_sub(Fingerprint::min_cpack_bits(secret_words));
+    }
+}
+
+/// One line of a memory dump, by address.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineRecord {
+    pub address: u64,
+    pub fingerprint: Fingerprint
+}
+
+/// The fingerprints of every line of a memory dump, for picking out the lines worth attacking.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FingerprintDb {
+    pub lines: Vec<LineRecord>
+}
+
+impl FingerprintDb {
+    /// Fingerprints a raw dump that starts at base, one line per 64 bytes. A partial last line is padded with zeros.
+    pub fn from_dump(dump: &[u8], base: u64) -> FingerprintDb {
+        let lines = dump.chunks(64).enumerate().map(|(i, chunk)| {
+            let mut line = [0u8; 64];
+            line[..chunk.len()].copy_from_slice(chunk);
+            LineRecord {address: base + 64 * i as u64, fingerprint: Fingerprint::of(&line)}
+        }).collect();
+        return FingerprintDb {lines};
+    }
+
+    /// Reads and fingerprints a raw dump file, as from_dump.
+    pub fn load_dump(path: impl AsRef<Path>, base: u64) -> io::Result<FingerprintDb> {
+        return Ok(FingerprintDb::from_dump(&fs::read(path)?, base));
+    }
+
+    /// Returns the lines whose fingerprints could hide a secret of secret_size bytes (see Fingerprint::could_hold),
+    /// along with their slack, best targets first.
+    pub fn search(&self, secret_size: usize) -> Vec<(LineRecord, u64)> {
+        let words = secret_size.div_ceil(4);
+        let mut found: Vec<(LineRecord, u64)> = self.lines.iter()
+            .filter(|record| record.fingerprint.could_hold(words))
+            .map(|&record| (record, record.fingerprint.slack(words)))
+            .collect();
+        found.sort_by_key(|&(record, slack)| (slack, record.address));
+        return found;
+    }
+
+    /// Writes every line's fingerprint as CSV rows.
+    pub fn export(&self, path: &str) -> io::Result<()> {
+        let mut out = File::create(path)?;
+        writeln!(out, "address,cpack_bits,bdi_bits,fpc_bits")?;
+        for record in &self.lines {
+            let f = record.fingerprint;
+            writeln!(out, "{:#X},{},{},{}", record.address, f.cpack, f.bdi, f.fpc)?;
+        }
+        Ok(())
+    }
+}
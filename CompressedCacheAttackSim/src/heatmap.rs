@@ -0,0 +1,104 @@
+use std::fs::File;
+use std::io::{self, Write};
+use crate::attacker::PartialWord;
+use crate::experiment::IterationRow;
+
+/// Shades from lightest to darkest, for drawing heatmaps in a terminal.
+const SHADES: [char; 10] = [' ', '.', ':', '-', '=', '+', '*', '#', '%', '@'];
+
+/// Where a campaign's probes went and where it went wrong, per byte position of the secret (in memory order).
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LeakageHeatmap {
+    pub attacks: usize,
+    /// Probes spent on each byte position, summed over every attack's last attempt. A leading short's probes are split
+    /// evenly between its two bytes.
+    pub probes: Vec<f64>,
+    /// Attacks that did not recover each byte position correctly.
+    pub failures: Vec<usize>
+}
+
+impl LeakageHeatmap {
+    /// Tallies a campaign's rows against their true secrets. Recovered words are matched to secret words by leading
+    /// short; words whose short was wrong are matched to the remaining secret words in order.
+    pub fn from_rows(rows: &[IterationRow]) -> LeakageHeatmap {
+        let size = rows.iter().map(|row| row.secret.len()).max().unwrap_or(0);
+        let mut heatmap = LeakageHeatmap {attacks: rows.len(), probes: vec![0.0; size], failures: vec![0; size]};
+        for row in rows {
+            let words: Vec<&[u8]> = row.secret.chunks(4).collect();
+            let matched = match_words(&words, &row.stats.recovered_words);
+            for (j, word) in words.iter().enumerate() {
+                let recovered = matched[j].unwrap_or_default();
+                let short = u16::from_le_bytes([word[2], word[3]]);
+                let correct = [recovered.last_byte == Some(word[0]), recovered.second_byte == Some(word[1]),
+                               recovered.short == Some(short), recovered.short == Some(short)];
+                let probes = [recovered.probes[2] as f64, recovered.probes[1] as f64, recovered.probes[0] as f64 / 2.0, recovered.probes[0] as f64 / 2.0];
+                for byte in 0..4 {
+                    heatmap.probes[4 * j + byte] += probes[byte];
+                    if !correct[byte] {heatmap.failures[4 * j + byte] += 1;}
+                }
+            }
+        }
+        return heatmap;
+    }
+
+    pub fn mean_probes(&self, position: usize) -> f64 {
+        return self.probes[position] / self.attacks.max(1) as f64;
+    }
+
+    pub fn failure_rate(&self, position: usize) -> f64 {
+        return self.failures[position] as f64 / self.attacks.max(1) as f64;
+    }
+
+    /// Draws both maps with a row per secret word and a column per byte: mean probes, shaded on a log scale up to the
+    /// most probed byte (leading shorts take orders of magnitude more than the rest), then failure rates, shaded from 0 to 100%.
+    pub fn render(&self) -> String {
+        let positions = self.probes.len();
+        let most = (0..positions).map(|i| self.mean_probes(i).ln_1p()).fold(0.0, f64::max);
+        let shade = |fraction: f64| SHADES[((fraction * (SHADES.len() - 1) as f64).round() as usize).min(SHADES.len() - 1)];
+        let mut text = format!("Mean probes per byte over {} attacks (darker is more, on a log scale), then failure rate:\n", self.attacks);
+        text += &format!("{:8}{}  |{}\n", "", (0..4).map(|b| format!("{:>11}", format!("byte {}", b))).collect::<String>(),
+                         (0..4).map(|b| format!("{:>9}", format!("byte {}", b))).collect::<String>());
+        for word in 0..positions.div_ceil(4) {
+            text += &format!("word {:<3}", word);
+            let bytes = 4 * word..(4 * word + 4).min(positions);
+            for i in bytes.clone() {
+                let mean = self.mean_probes(i);
+                text += &format!("  {} {:>7.1}", shade(if most > 0.0 {mean.ln_1p() / most} else {0.0}), mean);
+            }
+            text += "  |";
+            for i in bytes {
+                let rate = self.failure_rate(i);
+                text += &format!("  {} {:>4.1}%", shade(rate), rate * 100.0);
+            }
+            text += "\n";
+        }
+        return text;
+    }
+
+    /// Writes one CSV row per byte position.
+    pub fn export(&self, path: &str) -> io::Result<()> {
+        let mut out = File::create(path)?;
+        writeln!(out, "position,word,byte,mean_probes,failure_rate")?;
+        for i in 0..self.probes.len() {
+            writeln!(out, "{},{},{},{},{}", i, i / 4, i % 4, self.mean_probes(i), self.failure_rate(i))?;
+        }
+        Ok(())
+    }
+}
+
+/// Pairs every secret word with the recovered word that has its leading short, then pairs the secret words left over
+/// with the recovered words left over, in order.
+fn match_words(words: &[&[u8]], recovered: &[PartialWord]) -> Vec<Option<PartialWord>> {
+    let mut used = vec![false; recovered.len()];
+    let mut matched: Vec<Option<PartialWord>> = words.iter().map(|word| {
+        let short = u16::from_le_bytes([word[2], word[3]]);
+        let i = (0..recovered.len()).find(|&i| !used[i] && recovered[i].short == Some(short))?;
+        used[i] = true;
+        Some(recovered[i])
+    }).collect();
+    let mut leftovers = (0..recovered.len()).filter(|&i| !used[i]);
+    for slot in matched.iter_mut().filter(|slot| slot.is_none()) {
+        *slot = leftovers.next().map(|i| recovered[i]);
+    }
+    return matched;
+}
@@ -0,0 +1,39 @@
+//! A simulator of cache timing attacks that leak secrets through the compressibility of a YACC compressed cache.
+//!
+//! [structures] models the compressed cache and its compressors, [victim] the program holding a secret and the interface
+//! it exposes to attackers, and [attacker] the attacks themselves. The other modules build experiments on top of them.
+
+#![allow(clippy::needless_return, clippy::upper_case_acronyms)]
+
+pub mod structures;
+pub mod victim;
+pub mod attacker;
+pub mod scenarios;
+pub mod bench;
+pub mod traces;
+pub mod detector;
+pub mod config;
+pub mod cost;
+pub mod aliasing;
+pub mod bundle;
+pub mod clock;
+pub mod candidates;
+pub mod experiment;
+pub mod keyschedule;
+pub mod fingerprint;
+pub mod output;
+pub mod determinism;
+pub mod heatmap;
+pub mod strategy;
+pub mod statistics;
+pub mod soak;
+pub mod address_space;
+pub mod predictor;
+pub mod streams;
+pub mod session;
+pub mod conformance;
+pub mod replay;
+pub mod defenses;
+pub mod dish;
+pub mod sweep;
+pub mod studies;
@@ -1,117 +1,152 @@
-use crate::attacker::{attack_yacc_cpack_4byte_secret, attack_yacc_cpack_8byte_secret, AttackStats};
-use crate::structures::Compressor;
-use crate::victim::VictimProgramYACC;
-use rayon::prelude::*;
+#![allow(clippy::needless_return, clippy::upper_case_acronyms)]
 
-mod structures;
-mod victim;
-mod attacker;
+use compressed_cache_attack_sim::address_space::AddressSpace;
+use compressed_cache_attack_sim::bench;
+use compressed_cache_attack_sim::config::{load_config, CacheConfig, ExperimentConfig, VictimConfig};
+use compressed_cache_attack_sim::statistics::StoppingRule;
+use compressed_cache_attack_sim::strategy::export_strategies;
+use compressed_cache_attack_sim::sweep::SweepGrid;
+use compressed_cache_attack_sim::studies::{attack_clamped_victim, attack_custom_secret, attack_ecc_overhead, attack_interleaved_secrets, attack_key_schedule, attack_probabilistic_admission, attack_public_prefix, attack_read_only_masks, attack_rebasing_victim, attack_reencoding_defense, attack_secrets_file, attack_store_prefetch, attack_uncalibrated_caches, attack_under_access_budget, attack_unknown_thresholds, check_cache_models, compare_cache_architectures, compare_compressors, compare_cpack_dictionaries, compare_decompression_timing, compare_endianness, compare_evict_reload, compare_filler_strategies, compare_flush_sets, compare_line_sizes, compare_miss_counter, compare_oracles, compare_prime_methods, compare_probe_addresses, compare_replacement_policies, compare_secret_bytes, compare_secret_distributions, compare_superblock_sizes, compare_write_policies, diagnose_failed_probes, evaluate_benign_performance, evaluate_randomized_indexing, evaluate_size_quantization, export_bundle, export_replay, fingerprint_dump, hand_off_after_leading_shorts, map_leakage, measure_victim_disturbance, paired_noise_comparison, parse_hex_secret, predict_attack_outcomes, quantify_background_noise, record_audit_trace, record_compressibility_timeline, replay_failed_attacks, report_buffer_aliasing, run_campaign, run_session, self_check, simulate_candidate_decay, simulate_co_tenant_interference, simulate_cross_core, simulate_heap_grooming, simulate_metadata_leak, simulate_multi_tenant_campaigns, simulate_scheduled_activity, simulate_store_only_victims, simulate_striped_buffers, soak_test, stealth_tradeoff, sweep_associativity, sweep_attacks, sweep_residual_data, sweep_secret_offsets, sweep_secret_sizes, sweep_set_counts, sweep_uncompressed_regions, trace_attack, validate_exhaustive_shorts};
+#[cfg(not(feature = "no-oracle"))]
+use compressed_cache_attack_sim::studies::compare_baseline_attackers;
+use crate::cli::{Cli, Command};
+use clap::Parser;
 
-fn main() {
-    simulate_4byte_attacks();
-    //simulate_8byte_attacks();
-}
-
-#[allow(dead_code)]
-fn test_4_byte_attack() {
-    let mut victim = VictimProgramYACC::new(4, Compressor::CPACK, true);
-    let results = attack_yacc_cpack_4byte_secret(&mut victim, true);
-    println!("{:#?}", results);
-}
-
-#[allow(dead_code)]
-fn test_8_byte_attack() {
-    let mut victim = VictimProgramYACC::new(8, Compressor::CPACK, true);
-    let results = attack_yacc_cpack_8byte_secret(&mut victim, true);
-    println!("{:#?}", results);
-}
-
-struct AggregateAttackStats {
-    successes: usize,
-    guesses_needed: usize,
-    bytes_written_to_victim: usize,
-    bytes_read_from_victim: usize,
-    attacker_cache_lines_loaded: usize,
-    set_evictions: usize
-}
+mod cli;
 
-impl AggregateAttackStats {
-    fn new() -> AggregateAttackStats {
-        AggregateAttackStats {
-            successes: 0,
-            guesses_needed: 0,
-            bytes_written_to_victim: 0,
-            bytes_read_from_victim: 0,
-            attacker_cache_lines_loaded: 0,
-            set_evictions: 0
-        }
-    }
-}
-
-#[allow(dead_code)]
-fn simulate_4byte_attacks() {
-    let iterations = 10000;
-    let subdivisions = 100;
-    let parallel_iterations = iterations / subdivisions;
-    println!("Running {} iterations in {} parallel groups of {}...", iterations, subdivisions, parallel_iterations);
-    let mut all_results: Vec<AttackStats> = Vec::new();
-    for i in 0..subdivisions {
-        let mut current_results: Vec<AttackStats> = (0..parallel_iterations).into_par_iter().map(|_|
-            attack_yacc_cpack_4byte_secret(&mut VictimProgramYACC::new(4, Compressor::CPACK, false), false)
-        ).collect();
-        println!("Group {} completed", i+1);
-        all_results.append(&mut current_results);
+fn main() {
+    let cli = Cli::parse();
+    if let Some(threads) = cli.threads {
+        if let Err(e) = rayon::ThreadPoolBuilder::new().num_threads(threads).build_global() {println!("Could not set up {} threads: {}", threads, e);}
     }
-    let results = all_results.into_iter().fold(AggregateAttackStats::new(),
-        |x,y| AggregateAttackStats {
-            successes: x.successes + if y.success {1} else {0},
-            guesses_needed: x.guesses_needed + y.guesses_needed,
-            bytes_written_to_victim: x.bytes_written_to_victim + y.bytes_written_to_victim,
-            bytes_read_from_victim: x.bytes_read_from_victim + y.bytes_read_from_victim,
-            attacker_cache_lines_loaded: x.attacker_cache_lines_loaded + y.attacker_cache_lines_loaded,
-            set_evictions: x.set_evictions + y.set_evictions
+    match cli.command {
+        None => sweep_attacks(&SweepGrid::default(), "sweep.csv"),
+        Some(Command::Sweep {secret_bytes, compressors, associativities, replacements, iterations, out}) => {
+            let grid = SweepGrid {secret_sizes: secret_bytes, compressors, associativities, replacements, iterations};
+            sweep_attacks(&grid, &out);
+        },
+        Some(Command::Simulate(args)) => {
+            let victim = VictimConfig {secret_size: args.secret_bytes, ..VictimConfig::default()};
+            let victim = match args.victim_layout.map(AddressSpace::load).transpose() {
+                Ok(space) => space.map_or(victim.clone(), |space| space.victim_config(&victim)),
+                Err(e) => {println!("Could not read a victim layout: {}", e); return;}
+            };
+            let config = ExperimentConfig {
+                iterations: args.iterations,
+                cache: CacheConfig {compressor: args.compressor, ..CacheConfig::default()},
+                victim,
+                ..ExperimentConfig::default()
+            };
+            let stop = args.stop_at_ci_width.map(|max_ci_width| StoppingRule {min_iterations: args.min_iterations, max_ci_width});
+            run_campaign(&config, args.groups, args.window, stop, &args.out, args.output.as_deref());
+        },
+        Some(Command::Rerun {config_from, output}) => match load_config(&config_from) {
+            Ok(config) => run_campaign(&config, 1, config.iterations.max(1), None, "rerun_results.json", output.as_deref()),
+            Err(e) => println!("Could not read a configuration from {}: {}", config_from, e)
+        },
+        Some(Command::Attack {secret}) => match parse_hex_secret(&secret) {
+            Ok(secret) => attack_custom_secret(secret),
+            Err(e) => println!("Bad --secret: {}", e)
+        },
+        Some(Command::Batch {secrets_file, out}) => {
+            if let Err(e) = attack_secrets_file(&secrets_file, &out) {println!("Batch run failed: {}", e);}
+        },
+        Some(Command::ExportBundle {config_from, out, redact_secrets}) => export_bundle(config_from.as_deref(), &out, redact_secrets),
+        Some(Command::Fingerprint {dump, base, secret_bytes, top, out}) => {
+            if let Err(e) = fingerprint_dump(&dump, base, secret_bytes, top, &out) {println!("Fingerprinting failed: {}", e);}
+        },
+        Some(Command::Bench) => bench::run_benchmarks(),
+        Some(Command::Conformance) => check_cache_models(),
+        Some(Command::Replay {secret_bytes, seed, out, seconds_per_frame}) => export_replay(secret_bytes, seed, &out, seconds_per_frame),
+        Some(Command::Trace {secret_bytes, seed, out}) => trace_attack(secret_bytes, seed, &out),
+        Some(Command::Fillers {secret_bytes}) => compare_filler_strategies(secret_bytes),
+        Some(Command::CandidateDecay {secret_bytes, runs}) => {
+            if let Err(e) = simulate_candidate_decay(secret_bytes, runs) {println!("Could not write the candidate decay: {}", e);}
+        },
+        Some(Command::AuditTrace {secret_bytes}) => {
+            if let Err(e) = record_audit_trace(secret_bytes) {println!("Could not write the audit log: {}", e);}
+        },
+        Some(Command::CompressibilityTimeline {secret_bytes}) => {
+            if let Err(e) = record_compressibility_timeline(secret_bytes) {println!("Could not write the timeline: {}", e);}
+        },
+        Some(Command::CrossCore) => simulate_cross_core(),
+        Some(Command::MultiTenant) => simulate_multi_tenant_campaigns(),
+        Some(Command::ScheduledActivity {secret_bytes, iterations}) => simulate_scheduled_activity(secret_bytes, iterations),
+        Some(Command::Reencoding {iterations}) => attack_reencoding_defense(iterations),
+        Some(Command::ClampedVictims {iterations}) => attack_clamped_victim(iterations),
+        Some(Command::ProbabilisticAdmission {iterations}) => attack_probabilistic_admission(iterations),
+        Some(Command::UncompressedRegions {secret_bytes, iterations}) => sweep_uncompressed_regions(secret_bytes, iterations),
+        Some(Command::SecretOffsets {secret_bytes, iterations}) => sweep_secret_offsets(secret_bytes, iterations),
+        Some(Command::HeapGrooming {secret_bytes, iterations}) => simulate_heap_grooming(secret_bytes, iterations),
+        Some(Command::MetadataLeak) => simulate_metadata_leak(),
+        Some(Command::StoreOnly {secret_bytes}) => simulate_store_only_victims(secret_bytes),
+        Some(Command::PrimeMethods {secret_bytes}) => compare_prime_methods(secret_bytes),
+        Some(Command::ResidualData {secret_bytes}) => sweep_residual_data(secret_bytes),
+        Some(Command::ExhaustiveShorts {low_short, first_short, last_short}) => {validate_exhaustive_shorts(low_short.to_le_bytes(), first_short..=last_short);},
+        Some(Command::UnknownThresholds {secret_bytes, iterations}) => attack_unknown_thresholds(secret_bytes, iterations),
+        Some(Command::Uncalibrated {secret_bytes, iterations}) => attack_uncalibrated_caches(secret_bytes, iterations),
+        Some(Command::EccOverhead {secret_bytes, iterations}) => attack_ecc_overhead(secret_bytes, iterations),
+        Some(Command::ReadOnlyMasks {secret_bytes, iterations}) => attack_read_only_masks(secret_bytes, iterations),
+        Some(Command::StorePrefetch {secret_bytes, iterations}) => attack_store_prefetch(secret_bytes, iterations),
+        Some(Command::Associativity {secret_bytes, iterations}) => sweep_associativity(secret_bytes, iterations),
+        Some(Command::ReplacementPolicies {secret_bytes, iterations}) => compare_replacement_policies(secret_bytes, iterations),
+        Some(Command::SecretSizes {iterations}) => sweep_secret_sizes(iterations),
+        Some(Command::KeySchedule {iterations}) => attack_key_schedule(iterations),
+        Some(Command::InterleavedSecrets {iterations}) => attack_interleaved_secrets(iterations),
+        Some(Command::SetCounts {secret_bytes, iterations}) => sweep_set_counts(secret_bytes, iterations),
+        Some(Command::ReplayFailures {secret_bytes, iterations}) => replay_failed_attacks(secret_bytes, iterations),
+        Some(Command::DiagnoseProbes {secret_bytes, iterations}) => diagnose_failed_probes(secret_bytes, iterations),
+        Some(Command::Oracles {secret_bytes, iterations}) => compare_oracles(secret_bytes, iterations),
+        Some(Command::DecompressionTiming {secret_bytes, iterations}) => compare_decompression_timing(secret_bytes, iterations),
+        Some(Command::MissCounter {secret_bytes, iterations}) => compare_miss_counter(secret_bytes, iterations),
+        Some(Command::HandOff {secret_bytes, iterations}) => hand_off_after_leading_shorts(secret_bytes, iterations),
+        Some(Command::EvictReload {secret_bytes, iterations}) => compare_evict_reload(secret_bytes, iterations),
+        Some(Command::PairedNoise {secret_bytes, iterations, seed}) => paired_noise_comparison(secret_bytes, iterations, seed),
+        Some(Command::WritePolicies {secret_bytes, iterations}) => compare_write_policies(secret_bytes, iterations),
+        Some(Command::PredictOutcomes {secret_bytes, iterations}) => predict_attack_outcomes(secret_bytes, iterations),
+        Some(Command::BackgroundNoise {secret_bytes, iterations}) => quantify_background_noise(secret_bytes, iterations),
+        Some(Command::SecretDistributions {secret_bytes, iterations}) => compare_secret_distributions(secret_bytes, iterations),
+        Some(Command::SecretBytes {distribution, secret_bytes, iterations}) => compare_secret_bytes(distribution, secret_bytes, iterations),
+        Some(Command::SuperblockSizes {secret_bytes, iterations}) => compare_superblock_sizes(secret_bytes, iterations),
+        Some(Command::LineSizes {secret_bytes, iterations}) => compare_line_sizes(secret_bytes, iterations),
+        Some(Command::Compressors {secret_bytes, iterations}) => compare_compressors(secret_bytes, iterations),
+        Some(Command::PublicPrefix {secret_bytes, iterations}) => attack_public_prefix(secret_bytes, iterations),
+        Some(Command::BufferAliasing {secret_bytes}) => report_buffer_aliasing(secret_bytes),
+        #[cfg(not(feature = "no-oracle"))]
+        Some(Command::Baselines {iterations}) => compare_baseline_attackers(iterations),
+        Some(Command::StripedBuffers {secret_bytes, iterations}) => simulate_striped_buffers(secret_bytes, iterations),
+        Some(Command::ProbeAddresses {secret_bytes, iterations}) => compare_probe_addresses(secret_bytes, iterations),
+        Some(Command::FlushSets {secret_bytes, iterations}) => compare_flush_sets(secret_bytes, iterations),
+        Some(Command::VictimDisturbance {secret_bytes, iterations}) => measure_victim_disturbance(secret_bytes, iterations),
+        Some(Command::Stealth {secret_bytes, iterations}) => stealth_tradeoff(secret_bytes, iterations),
+        Some(Command::CoTenant {secret_bytes, iterations}) => simulate_co_tenant_interference(secret_bytes, iterations),
+        Some(Command::BenignPerformance {trace}) => evaluate_benign_performance(trace.as_deref()),
+        Some(Command::Architectures {secret_bytes, iterations}) => compare_cache_architectures(secret_bytes, iterations),
+        Some(Command::ByteOrder {secret_bytes, iterations}) => compare_endianness(secret_bytes, iterations),
+        Some(Command::CpackDictionaries {secret_bytes, iterations}) => compare_cpack_dictionaries(secret_bytes, iterations),
+        Some(Command::Rebasing {secret_bytes, iterations}) => attack_rebasing_victim(secret_bytes, iterations),
+        Some(Command::AccessBudget {secret_bytes, iterations}) => attack_under_access_budget(secret_bytes, iterations),
+        Some(Command::RandomizedIndexing {secret_bytes, iterations}) => evaluate_randomized_indexing(secret_bytes, iterations),
+        Some(Command::SizeQuantization {secret_bytes, iterations}) => evaluate_size_quantization(secret_bytes, iterations),
+        Some(Command::DescribeStrategies {threshold, out}) => {
+            let sizes: Vec<usize> = (4..=64).step_by(4).collect();
+            match export_strategies(&out, &sizes, threshold) {
+                Ok(descriptions) => println!("Described {} strategies in {}", descriptions.len(), out),
+                Err(e) => println!("Could not write {}: {}", out, e)
+            }
+        },
+        Some(Command::Heatmap {secret_bytes, iterations, out}) => map_leakage(secret_bytes, iterations, &out),
+        Some(Command::SelfCheck {seed, iterations, config_from}) => match config_from.map(load_config).transpose() {
+            Ok(config) => self_check(config.unwrap_or_default(), seed, iterations),
+            Err(e) => println!("Could not read a configuration: {}", e)
+        },
+        Some(Command::Soak {seed, iterations, check_every, config_from, out}) => match config_from.map(load_config).transpose() {
+            Ok(config) => soak_test(config.unwrap_or_default(), seed, iterations, check_every, &out),
+            Err(e) => println!("Could not read a configuration: {}", e)
+        },
+        Some(Command::Session {session, seed, iterations, config_from, output}) => match config_from.map(load_config).transpose() {
+            Ok(config) => run_session(&session, config.unwrap_or(ExperimentConfig {iterations, ..ExperimentConfig::default()}), seed, output.as_deref()),
+            Err(e) => println!("Could not read a configuration: {}", e)
         }
-    );
-    println!();
-    println!("Iterations: {}", iterations);
-    println!("Successes: {}", results.successes);
-    println!("Guesses needed: {}", results.guesses_needed);
-    println!("Bytes written to the victim buffer: {}", results.bytes_written_to_victim);
-    println!("Bytes read from the victim buffer: {}", results.bytes_read_from_victim);
-    println!("Lines loaded directly by the attacker: {}", results.attacker_cache_lines_loaded);
-    println!("Number of set evictions performed by the attacker: {}", results.set_evictions);
-}
-
-#[allow(dead_code)]
-fn simulate_8byte_attacks() {
-    let iterations = 10000;
-    let subdivisions = 100;
-    let parallel_iterations = iterations / subdivisions;
-    println!("Running {} iterations in {} parallel groups of {}...", iterations, subdivisions, parallel_iterations);
-    let mut all_results: Vec<AttackStats> = Vec::new();
-    for i in 0..subdivisions {
-        let mut current_results: Vec<AttackStats> = (0..parallel_iterations).into_par_iter().map(|_|
-            attack_yacc_cpack_8byte_secret(&mut VictimProgramYACC::new(8, Compressor::CPACK, false), false)
-        ).collect();
-        println!("Group {} completed", i+1);
-        all_results.append(&mut current_results);
     }
-    let results = all_results.into_iter().fold(AggregateAttackStats::new(),
-       |x,y| AggregateAttackStats {
-           successes: x.successes + if y.success {1} else {0},
-           guesses_needed: x.guesses_needed + y.guesses_needed,
-           bytes_written_to_victim: x.bytes_written_to_victim + y.bytes_written_to_victim,
-           bytes_read_from_victim: x.bytes_read_from_victim + y.bytes_read_from_victim,
-           attacker_cache_lines_loaded: x.attacker_cache_lines_loaded + y.attacker_cache_lines_loaded,
-           set_evictions: x.set_evictions + y.set_evictions
-       }
-    );
-    println!();
-    println!("Iterations: {}", iterations);
-    println!("Successes: {}", results.successes);
-    println!("Guesses needed: {}", results.guesses_needed);
-    println!("Bytes written to the victim buffer: {}", results.bytes_written_to_victim);
-    println!("Bytes read from the victim buffer: {}", results.bytes_read_from_victim);
-    println!("Lines loaded directly by the attacker: {}", results.attacker_cache_lines_loaded);
-    println!("Number of set evictions performed by the attacker: {}", results.set_evictions);
 }
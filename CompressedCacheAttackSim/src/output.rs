@@ -0,0 +1,138 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+use serde::Serialize;
+use serde_json::Value;
+use crate::attacker::{AttackStats, PhaseCost, ProbeOutcomes, Summary};
+use crate::experiment::{ExperimentResult, IterationRow};
+use crate::structures::AccessRecord;
+
+/// One attack's stats, flattened to plain numbers for post-processing.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RunRecord {
+    pub iteration: usize,
+    pub victim_seed: Option<u64>,
+    pub success: bool,
+    pub attempts: usize,
+    pub guesses_needed: usize,
+    pub bytes_written_to_victim: usize,
+    pub bytes_read_from_victim: usize,
+    pub attacker_cache_lines_loaded: usize,
+    pub set_evictions: usize,
+    pub flush_hits: usize,
+    pub victim_interactions: usize,
+    pub victim_write_calls: usize,
+    pub idle_ticks: u64,
+    pub migrations: usize,
+    pub unwritable_probes: usize,
+    pub heap_allocations: usize,
+    pub writebacks: u64,
+    pub predicted_success: Option<f64>,
+    pub early_aborts: usize,
+    /// Probe outcomes over every phase, labeled against the true secret.
+    pub probe_outcomes: ProbeOutcomes,
+    /// Costs per phase, indexed by AttackPhase.
+    pub phase_costs: [PhaseCost; 4],
+    pub cpu_seconds: f64
+}
+
+impl RunRecord {
+    pub fn new(iteration: usize, stats: &AttackStats) -> RunRecord {
+        let mut outcomes = ProbeOutcomes::default();
+        for phase_outcomes in stats.probe_outcomes {outcomes += phase_outcomes;}
+        return RunRecord {
+            iteration,
+            victim_seed: stats.victim_seed,
+            success: stats.success,
+            attempts: stats.attempts,
+            guesses_needed: stats.guesses_needed,
+            bytes_written_to_victim: stats.bytes_written_to_victim,
+            bytes_read_from_victim: stats.bytes_read_from_victim,
+            attacker_cache_lines_loaded: stats.attacker_cache_lines_loaded,
+            set_evictions: stats.set_evictions,
+            flush_hits: stats.flush_hits,
+            victim_interactions: stats.victim_interactions,
+            victim_write_calls: stats.victim_write_calls,
+            idle_ticks: stats.idle_ticks,
+            migrations: stats.migrations,
+            unwritable_probes: stats.unwritable_probes,
+            heap_allocations: stats.heap_allocations,
+            writebacks: stats.writebacks,
+            predicted_success: stats.predicted_success,
+            early_aborts: stats.early_aborts,
+            probe_outcomes: outcomes,
+            phase_costs: stats.phase_costs,
+            cpu_seconds: stats.phase_time.iter().map(|t| t.as_secs_f64()).sum()
+        };
+    }
+
+    fn of(row: &IterationRow) -> RunRecord {
+        return RunRecord::new(row.iteration, &row.stats);
+    }
+}
+
+/// The JSON form of an experiment's results: the summary and one record per iteration.
+#[derive(Serialize)]
+struct ResultsDocument<'a> {
+    summary: &'a Summary,
+    runs: Vec<RunRecord>
+}
+
+/// Writes an experiment's results for post-processing, in the format the path's extension asks for.
+/// A .json path gets one document holding the summary and every run. A .csv path gets one row per run, and the summary
+/// goes to a second file next to it (results.csv puts it in results_summary.csv) as key,value rows.
+/// Writes a cache access trace as newline-delimited JSON, one access per line.
+pub fn export_access_trace(path: &str, trace: &[AccessRecord]) -> io::Result<()> {
+    let mut out = io::BufWriter::new(File::create(path)?);
+    for record in trace {
+        writeln!(out, "{}", serde_json::to_string(record)?)?;
+    }
+    return out.flush();
+}
+
+pub fn write_output(path: &str, result: &ExperimentResult) -> io::Result<()> {
+    let runs: Vec<RunRecord> = result.rows.iter().map(RunRecord::of).collect();
+    if path.ends_with(".json") {
+        return fs::write(path, serde_json::to_string_pretty(&ResultsDocument {summary: &result.summary, runs})?);
+    }
+    write_csv_rows(path, &runs)?;
+    let summary_path = Path::new(path).with_extension("").to_string_lossy().into_owned() + "_summary.csv";
+    let mut out = File::create(summary_path)?;
+    writeln!(out, "key,value")?;
+    for (key, value) in flatten(&serde_json::to_value(&result.summary)?) {
+        writeln!(out, "{},{}", key, value)?;
+    }
+    Ok(())
+}
+
+/// Writes records as CSV, one row each, with a column for every (flattened) field.
+pub(crate) fn write_csv_rows<T: Serialize>(path: &str, records: &[T]) -> io::Result<()> {
+    let mut out = File::create(path)?;
+    let rows: Vec<Vec<(String, String)>> = records.iter().map(|r| serde_json::to_value(r).map(|v| flatten(&v))).collect::<Result<_, _>>()?;
+    if let Some(first) = rows.first() {
+        writeln!(out, "{}", first.iter().map(|(key, _)| key.as_str()).collect::<Vec<_>>().join(","))?;
+    }
+    for row in &rows {
+        writeln!(out, "{}", row.iter().map(|(_, value)| value.as_str()).collect::<Vec<_>>().join(","))?;
+    }
+    Ok(())
+}
+
+/// Flattens a JSON value into (column, value) pairs: nested fields are joined with '.', array elements get their index,
+/// and missing values are left empty.
+fn flatten(value: &Value) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+    flatten_into(value, String::new(), &mut pairs);
+    return pairs;
+}
+
+fn flatten_into(value: &Value, prefix: String, pairs: &mut Vec<(String, String)>) {
+    let join = |key: &str| if prefix.is_empty() {key.to_string()} else {format!("{}.{}", prefix, key)};
+    match value {
+        Value::Object(fields) => for (key, field) in fields {flatten_into(field, join(key), pairs);},
+        Value::Array(items) => for (i, item) in items.iter().enumerate() {flatten_into(item, join(&i.to_string()), pairs);},
+        Value::Null => pairs.push((prefix, String::new())),
+        Value::String(text) => pairs.push((prefix, text.clone())),
+        other => pairs.push((prefix, other.to_string()))
+    }
+}
@@ -0,0 +1,56 @@
+use serde::Serialize;
+use crate::attacker::{AttackStats, EliminationSignals, Eliminator, ProbeOutcomes, StepDescription};
+
+/// Predicts the chance that an attack which has just found its leading shorts goes on to recover the secret, from what
+/// the attacker saw while finding them. The noise rate is the larger of two estimates: the share of group probes that
+/// came back positive beyond the one per word the secret accounts for, and the share of probes whose vote was split.
+/// The attack then succeeds if the single probes that picked the shorts, and every probe the remaining steps are
+/// expected to make, all read right.
+pub fn predict_success(signals: &EliminationSignals, remaining: &[StepDescription]) -> f64 {
+    let probes = signals.group_probes + signals.single_probes;
+    if probes == 0 {return 1.0;}
+    let excess_hits = signals.group_hits.saturating_sub(signals.matches);
+    let false_positive_rate = excess_hits as f64 / signals.group_probes.saturating_sub(signals.matches).max(1) as f64;
+    let split_rate = signals.split_votes as f64 / probes as f64;
+    let noise = false_positive_rate.max(split_rate).min(1.0);
+    let to_come: f64 = remaining.iter().map(expected_probes).sum();
+    return (1.0 - noise).powf(signals.single_probes as f64 + to_come);
+}
+
+/// The probes a step is expected to make: half its groups and half a group of singles per word when narrowing, or every
+/// group and a single per match when shortlisting.
+fn expected_probes(step: &StepDescription) -> f64 {
+    let groups = step.candidates as f64 / step.group_size.max(1) as f64;
+    return match step.eliminator {
+        Eliminator::Narrow => step.words as f64 * (groups + step.group_size as f64) / 2.0,
+        Eliminator::Shortlist {matches} => groups + matches as f64
+    };
+}
+
+/// How well the predictions of predict_success matched the outcomes of a campaign's attacks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct PredictorReport {
+    /// Attacks that got a prediction and were never given up early (the only ones whose outcome can be compared).
+    pub predicted: usize,
+    /// The mean squared difference between the predicted chance of success and the outcome. Always predicting 50% scores 0.25.
+    pub brier_score: f64,
+    /// Predictions of at least 50% taken as predicting success, labeled by whether the attack succeeded.
+    pub outcomes: ProbeOutcomes,
+    /// Attacks that gave up at least one attempt early.
+    pub aborted: usize
+}
+
+impl PredictorReport {
+    pub fn of(results: &[AttackStats]) -> PredictorReport {
+        let mut report = PredictorReport {aborted: results.iter().filter(|stats| stats.early_aborts > 0).count(), ..PredictorReport::default()};
+        let mut squared_error = 0.0;
+        for stats in results.iter().filter(|stats| stats.early_aborts == 0) {
+            let Some(predicted) = stats.predicted_success else {continue};
+            report.predicted += 1;
+            squared_error += (predicted - f64::from(u8::from(stats.success))).powi(2);
+            report.outcomes.record(predicted >= 0.5, stats.success);
+        }
+        report.brier_score = squared_error / report.predicted.max(1) as f64;
+        return report;
+    }
+}
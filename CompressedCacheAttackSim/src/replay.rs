@@ -0,0 +1,94 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use serde::Serialize;
+use crate::attacker::{AttackPhase, ProbeDiagnosis, ProbeRecord};
+use crate::structures::WayState;
+
+/// One probe of a recorded attack (see AttackConfig::record_lru), as a frame of a replay: what it tested, what the set
+/// looked like when it read its check line, and how many candidates it left.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReplayFrame {
+    pub probe: usize,
+    pub phase: AttackPhase,
+    pub tested: Vec<u16>,
+    pub remaining: Option<usize>,
+    pub hit: bool,
+    /// Whether the tested candidates really held part of the secret.
+    pub actual: bool,
+    pub diagnosis: ProbeDiagnosis,
+    pub ways: Vec<WayState>,
+    pub check_age: Option<usize>,
+    pub probed_line: Option<(usize, bool)>
+}
+
+impl ReplayFrame {
+    pub fn of(probe: usize, record: &ProbeRecord) -> ReplayFrame {
+        return ReplayFrame {
+            probe,
+            phase: record.phase,
+            tested: record.tested.clone(),
+            remaining: record.remaining,
+            hit: record.hit,
+            actual: record.actual,
+            diagnosis: record.diagnosis(),
+            ways: record.lru.ways.clone(),
+            check_age: record.lru.check_age,
+            probed_line: record.lru.probed_line
+        };
+    }
+
+    /// Draws the frame as text: a status line, then one row per way, oldest at the bottom, marking the ways holding the
+    /// probed line and the check line.
+    pub fn render(&self) -> String {
+        let mut text = format!("Probe {} ({:?}): testing {}\r\n", self.probe, self.phase, tested_list(&self.tested));
+        let verdict = if self.hit {"co-allocated"} else {"not co-allocated"};
+        let _ = write!(text, "  {} ({}, {:?}), {} candidates left\r\n\r\n", verdict, if self.hit == self.actual {"right"} else {"wrong"},
+                       self.diagnosis, self.remaining.map_or("?".to_string(), |remaining| remaining.to_string()));
+        let mut ways: Vec<(usize, &WayState)> = self.ways.iter().enumerate().collect();
+        ways.sort_by_key(|(_, way)| way.age.unwrap_or(usize::MAX));
+        for (index, way) in ways {
+            let mut marks = Vec::new();
+            if way.age.is_some() && way.age == self.probed_line.map(|(age, _)| age) {marks.push("probed line");}
+            if way.age.is_some() && way.age == self.check_age {marks.push("check line");}
+            let age = way.age.map_or("  -".to_string(), |age| format!("{:3}", age));
+            let lines = way.lines.iter().map(|line| format!("{:X}", line)).collect::<Vec<String>>().join(" ");
+            let _ = write!(text, "  way {:2}  age {}  {:<40}{}\r\n", index, age, lines, if marks.is_empty() {String::new()} else {format!(" <- {}", marks.join(", "))});
+        }
+        return text;
+    }
+}
+
+/// The first few tested candidates, and how many more there are.
+fn tested_list(tested: &[u16]) -> String {
+    const SHOWN: usize = 8;
+    let shown: Vec<String> = tested.iter().take(SHOWN).map(|candidate| format!("{:X}", candidate)).collect();
+    if tested.len() <= SHOWN {return shown.join(" ");}
+    return format!("{} and {} more", shown.join(" "), tested.len() - SHOWN);
+}
+
+/// Turns a probe trace into frames, one per probe.
+pub fn replay_frames(trace: &[ProbeRecord]) -> Vec<ReplayFrame> {
+    return trace.iter().enumerate().map(|(probe, record)| ReplayFrame::of(probe, record)).collect();
+}
+
+/// Writes the frames of a probe trace as a JSON array.
+pub fn export_frames(path: &str, trace: &[ProbeRecord]) -> io::Result<()> {
+    return fs::write(path, serde_json::to_string_pretty(&replay_frames(trace))?);
+}
+
+/// Writes a probe trace as an asciinema (v2) recording, showing each frame for seconds_per_frame, or longer when the
+/// probe went wrong.
+pub fn export_cast(path: &str, trace: &[ProbeRecord], seconds_per_frame: f64) -> io::Result<()> {
+    let frames = replay_frames(trace);
+    let height = frames.iter().map(|frame| frame.ways.len()).max().unwrap_or(0) + 4;
+    let mut cast = serde_json::json!({"version": 2, "width": 100, "height": height, "title": "Compressed cache attack replay"}).to_string();
+    let mut time = 0.0;
+    for frame in &frames {
+        cast.push('\n');
+        cast.push_str(&serde_json::to_string(&(time, "o", format!("\x1b[2J\x1b[H{}", frame.render())))?);
+        time += if frame.diagnosis == ProbeDiagnosis::Correct {seconds_per_frame} else {5.0 * seconds_per_frame};
+    }
+    cast.push('\n');
+    return fs::write(path, cast);
+}
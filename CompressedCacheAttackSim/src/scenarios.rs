@@ -0,0 +1,215 @@
+use rayon::prelude::*;
+use rand::Rng;
+use crate::attacker::{attack_yacc_cpack_with_retries, groom_heap, summarize, AttackConfig, AttackStats, RetryPolicy};
+use crate::cost::CostModel;
+use crate::structures::{ASSOCIATIVITY, Cache, Compressor, YACC};
+use crate::victim::{CoTenantConfig, CrossCoreConfig, HeapConfig, VictimProgramYACC};
+
+/// The cross-core threat model: the victim and the attacker run on different cores with private L1s,
+/// and only the compressed cache behind them is shared. Whether the attack still works depends on whether
+/// the shared cache is inclusive (so flushing it also flushes the victim's L1) and on whether the attacker
+/// walks an L1 eviction set before each flush (so its own flush accesses actually reach the shared cache).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CrossCoreScenario {
+    pub secret_size: usize,
+    pub compressor: Compressor,
+    pub caches: CrossCoreConfig,
+    pub evict_private_cache: bool,
+    pub iterations: usize
+}
+
+impl Default for CrossCoreScenario {
+    fn default() -> CrossCoreScenario {
+        CrossCoreScenario {
+            secret_size: 4,
+            compressor: Compressor::CPACK,
+            caches: CrossCoreConfig {l1_lines: 8, inclusive: true},
+            evict_private_cache: true,
+            iterations: 100
+        }
+    }
+}
+
+impl CrossCoreScenario {
+    /// Runs every iteration of the scenario in parallel, each against a fresh victim.
+    pub fn run(&self) -> Vec<AttackStats> {
+        let policy = RetryPolicy {
+            first: AttackConfig {evict_private_cache: self.evict_private_cache, ..AttackConfig::default()},
+            alternates: Vec::new()
+        };
+        return (0..self.iterations).into_par_iter().map(|_| {
+            let mut victim = VictimProgramYACC::new(self.secret_size, self.compressor, false);
+            victim.enable_cross_core(self.caches);
+            let mut stats = attack_yacc_cpack_with_retries(&mut victim, self.secret_size, &policy, false);
+            victim.into_judge().score(&mut stats);
+            stats
+        }).collect();
+    }
+
+    /// Runs the scenario and prints its success rate and average cost.
+    pub fn run_and_report(&self) {
+        let summary = summarize(&self.run());
+        println!("Cross-core ({} L1 lines, {}, {}): {} of {} attacks succeeded, {:.1} attacker lines loaded on average",
+                 self.caches.l1_lines,
+                 if self.caches.inclusive {"inclusive"} else {"non-inclusive"},
+                 if self.evict_private_cache {"with L1 eviction set"} else {"without L1 eviction set"},
+                 summary.successes, self.iterations, summary.attacker_cache_lines_loaded as f64 / self.iterations as f64);
+    }
+}
+
+/// A campaign against many victims (tenants) at once, each with its own secret, spread over the sets of a shared cache.
+/// One attacker works through them round-robin, one probe at a time, so its time is split between all of them.
+/// Tenants that land in different sets never disturb each other and are simulated independently; a tenant sharing its set
+/// with others sees their traffic as a co-tenant, touching the set on any of the attacker's calls with probability
+/// 1 - (1 - tenant_activity)^others.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultiTenantCampaign {
+    pub tenants: usize,
+    pub sets: u64,
+    pub secret_size: usize,
+    /// The chance that a tenant touches the cache during any one of the attacker's calls.
+    pub tenant_activity: f64,
+    pub policy: RetryPolicy,
+    /// Prices the attacker's actions, to turn the campaign into wall-clock time.
+    pub cost: CostModel,
+    pub clock_ghz: f64
+}
+
+impl Default for MultiTenantCampaign {
+    fn default() -> MultiTenantCampaign {
+        MultiTenantCampaign {
+            tenants: 16,
+            sets: 64,
+            secret_size: 4,
+            tenant_activity: 0.05,
+            policy: RetryPolicy::default(),
+            cost: CostModel::CO_RESIDENT,
+            clock_ghz: 3.0
+        }
+    }
+}
+
+/// The outcome of a multi-tenant campaign.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CampaignThroughput {
+    pub tenants: usize,
+    pub successes: usize,
+    /// Tenants that shared their set with at least one other tenant.
+    pub contended: usize,
+    pub contended_successes: usize,
+    /// Time for the attacker to work through every tenant, in seconds.
+    pub seconds: f64
+}
+
+impl CampaignThroughput {
+    pub fn secrets_per_hour(&self) -> f64 {
+        return self.successes as f64 / (self.seconds / 3600.0);
+    }
+}
+
+impl MultiTenantCampaign {
+    /// Places the tenants, attacks all of them, and totals up the attacker's time.
+    /// Since the attacker interleaves its probes, the campaign takes as long as all the attacks put together.
+    pub fn run(&self) -> CampaignThroughput {
+        let mut rng = rand::thread_rng();
+        let sets: Vec<u64> = (0..self.tenants).map(|_| rng.gen_range(0..self.sets)).collect();
+        let others: Vec<usize> = sets.iter().map(|set| sets.iter().filter(|x| *x == set).count() - 1).collect();
+        let results: Vec<AttackStats> = others.par_iter().map(|&others| {
+            let mut victim = VictimProgramYACC::new(self.secret_size, Compressor::CPACK, false);
+            if others > 0 {
+                let access_probability = 1.0 - (1.0 - self.tenant_activity).powi(others as i32);
+                victim.add_co_tenant(CoTenantConfig {access_probability, coverage: self.tenants as f64 / self.sets as f64});
+            }
+            let mut stats = attack_yacc_cpack_with_retries(&mut victim, self.secret_size, &self.policy, false);
+            victim.into_judge().score(&mut stats);
+            stats
+        }).collect();
+        let cycles = self.cost.total(&summarize(&results)).cycles as f64;
+        let contended: Vec<&AttackStats> = results.iter().zip(&others).filter(|(_, &others)| others > 0).map(|(x, _)| x).collect();
+        return CampaignThroughput {
+            tenants: self.tenants,
+            successes: results.iter().filter(|x| x.success).count(),
+            contended: contended.len(),
+            contended_successes: contended.iter().filter(|x| x.success).count(),
+            seconds: cycles / (self.clock_ghz * 1e9)
+        };
+    }
+
+    /// Runs the campaign and prints its throughput.
+    pub fn run_and_report(&self) {
+        let result = self.run();
+        println!("{} tenants in {} sets ({} attacker): {} of {} secrets recovered ({} of {} contended tenants) in {:.1} s, {:.0} secrets/hour",
+                 self.tenants, self.sets, self.cost.name, result.successes, result.tenants, result.contended_successes, result.contended,
+                 result.seconds, result.secrets_per_hour());
+    }
+}
+
+/// The heap threat model, end to end: the attacker first has to groom the victim's heap until one of its chunks lands
+/// next to the secret allocation (spending up to max_allocations chunks), and only then runs the attack.
+/// Grooming assumes the co-allocation threshold is threshold_bytes, since it cannot probe for it before it shares a line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeapScenario {
+    pub secret_size: usize,
+    pub heap: HeapConfig,
+    pub max_allocations: usize,
+    pub threshold_bytes: u64,
+    pub policy: RetryPolicy,
+    pub iterations: usize
+}
+
+impl Default for HeapScenario {
+    fn default() -> HeapScenario {
+        HeapScenario {
+            secret_size: 4,
+            heap: HeapConfig {adjacency_probability: 0.1},
+            max_allocations: 100,
+            threshold_bytes: 32,
+            policy: RetryPolicy::default(),
+            iterations: 100
+        }
+    }
+}
+
+impl HeapScenario {
+    /// Runs every iteration of the scenario in parallel, each against a fresh victim.
+    /// Attacks whose grooming failed have no attempts.
+    pub fn run(&self) -> Vec<AttackStats> {
+        return (0..self.iterations).into_par_iter().map(|_| {
+            let mut victim = VictimProgramYACC::new(self.secret_size, Compressor::CPACK, false);
+            victim.enable_heap(self.heap);
+            let mut stats = AttackStats::default();
+            if groom_heap(&mut victim, &self.policy.first, self.threshold_bytes, self.secret_size, self.max_allocations, &mut stats) {
+                stats += attack_yacc_cpack_with_retries(&mut victim, self.secret_size, &self.policy, false);
+            }
+            victim.into_judge().score(&mut stats);
+            stats
+        }).collect();
+    }
+
+    /// Runs the scenario and prints how often grooming and the whole attack succeeded, and what grooming cost.
+    pub fn run_and_report(&self) {
+        let results = self.run();
+        let groomed = results.iter().filter(|x| x.attempts > 0).count();
+        let summary = summarize(&results);
+        println!("Heap (adjacency probability {}, up to {} chunks): groomed {} of {}, {} attacks succeeded, {:.1} chunks allocated on average",
+                 self.heap.adjacency_probability, self.max_allocations, groomed, self.iterations, summary.successes,
+                 summary.heap_allocations as f64 / self.iterations as f64);
+    }
+}
+
+/// Explores whether the C-PACK match primitive used by the attack also leaks through compressed metadata.
+/// The victim stores to its line a secret number of times (secret_writes). The attacker stores to a line that shares
+/// the victim's metadata block guess times, for every guess in 1..=max_guess, each time on a fresh cache.
+/// Returns (guess, compressed bits of the shared metadata block); a correct guess makes the two counters identical,
+/// which C-PACK encodes as a full match, so it shows up as a dip in the block's compressed size.
+pub fn metadata_counter_leak(compressor: Compressor, secret_writes: u32, max_guess: u32) -> Vec<(u32, u64)> {
+    let victim_line: u64 = 0x1000;
+    let attacker_line: u64 = victim_line + 1;
+    return (1..=max_guess).map(|guess| {
+        let mut cache = YACC::new(compressor, ASSOCIATIVITY);
+        cache.enable_metadata(compressor);
+        for _ in 0..secret_writes {cache.write_byte(victim_line << 6, 0xAA);}
+        for _ in 0..guess {cache.write_byte(attacker_line << 6, 0x55);}
+        (guess, cache.metadata().unwrap().block_bits(victim_line))
+    }).collect();
+}
@@ -0,0 +1,148 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use serde::{Deserialize, Serialize};
+use crate::attacker::{resume_attack, summarize, AttackCheckpoint, AttackStats, DecaySeries};
+use crate::config::ExperimentConfig;
+use crate::experiment::{ExperimentMetadata, ExperimentResult, IterationRow};
+
+/// A seeded campaign that can be saved and picked up again, so that a long sweep killed part way through loses at most
+/// the phase it was in. Iteration i attacks the victim built from seed + i, as in Experiment::from_config_seeded.
+///
+/// A session is kept in two files: the session file itself, holding the configuration and seed on its first line and one
+/// finished iteration per line after it, and a sidecar (the same path with `.in_flight` added) holding the checkpoint of
+/// the iteration being attacked, if any. Finished iterations are kept without their candidate decay series, which runs to
+/// thousands of points per attack and would otherwise make up nearly all of the session file.
+#[derive(Debug, Clone)]
+pub struct AttackSession {
+    pub config: ExperimentConfig,
+    pub seed: u64,
+    /// Every iteration finished so far, in order.
+    pub finished: Vec<IterationRow>,
+    /// How far the attack on the next iteration had got, if it was interrupted.
+    pub in_flight: Option<AttackCheckpoint>
+}
+
+#[derive(Serialize, Deserialize)]
+struct SessionHeader {
+    config: ExperimentConfig,
+    seed: u64
+}
+
+#[derive(Serialize, Deserialize)]
+struct InFlight {
+    iteration: usize,
+    checkpoint: AttackCheckpoint
+}
+
+fn in_flight_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".in_flight");
+    return PathBuf::from(name);
+}
+
+/// Writes contents to path through a temporary file, so that a crash part way through leaves the old contents.
+fn write_atomically(path: &Path, contents: &str) -> io::Result<()> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    fs::write(&temporary, contents)?;
+    return fs::rename(&temporary, path);
+}
+
+impl AttackSession {
+    /// A session that has not run any iterations yet.
+    pub fn new(config: ExperimentConfig, seed: u64) -> AttackSession {
+        return AttackSession {config, seed, finished: Vec::new(), in_flight: None};
+    }
+
+    /// Whether every iteration has finished.
+    pub fn is_done(&self) -> bool {
+        return self.finished.len() >= self.config.iterations;
+    }
+
+    /// Writes the whole session to path and its sidecar.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut text = serde_json::to_string(&SessionHeader {config: self.config.clone(), seed: self.seed})?;
+        for row in &self.finished {
+            text.push('\n');
+            text.push_str(&serde_json::to_string(row)?);
+        }
+        text.push('\n');
+        write_atomically(path, &text)?;
+        return self.save_in_flight(path);
+    }
+
+    fn save_in_flight(&self, path: &Path) -> io::Result<()> {
+        let sidecar = in_flight_path(path);
+        return match &self.in_flight {
+            Some(checkpoint) => {
+                let in_flight = InFlight {iteration: self.finished.len(), checkpoint: checkpoint.clone()};
+                write_atomically(&sidecar, &serde_json::to_string(&in_flight)?)
+            },
+            None if sidecar.exists() => fs::remove_file(sidecar),
+            None => Ok(())
+        };
+    }
+
+    /// Reads a session back from path and its sidecar. A last line cut short by a crash is dropped, as is a sidecar left
+    /// over from an iteration that had already finished.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<AttackSession> {
+        let path = path.as_ref();
+        let mut lines = BufReader::new(File::open(path)?).lines();
+        let header = lines.next().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "empty session file"))??;
+        let header: SessionHeader = serde_json::from_str(&header)?;
+        let lines: Vec<String> = lines.collect::<io::Result<_>>()?;
+        let mut finished = Vec::with_capacity(lines.len());
+        for (i, line) in lines.iter().enumerate() {
+            match serde_json::from_str::<IterationRow>(line) {
+                Ok(row) => finished.push(row),
+                Err(_) if i + 1 == lines.len() => break,
+                Err(e) => return Err(e.into())
+            }
+        }
+        let sidecar = in_flight_path(path);
+        let in_flight = if sidecar.exists() {
+            let in_flight: InFlight = serde_json::from_str(&fs::read_to_string(sidecar)?)?;
+            Some(in_flight).filter(|in_flight| in_flight.iteration == finished.len()).map(|in_flight| in_flight.checkpoint)
+        } else {None};
+        return Ok(AttackSession {config: header.config, seed: header.seed, finished, in_flight});
+    }
+
+    /// Runs the remaining iterations one after another, saving to path as it goes: the checkpoint of the attack in flight
+    /// after every phase, and each iteration once it finishes. Calls progress with the number of iterations finished so
+    /// far and the total after every iteration.
+    pub fn run(&mut self, path: impl AsRef<Path>, mut progress: impl FnMut(usize, usize)) -> io::Result<ExperimentResult> {
+        let path = path.as_ref();
+        let start = Instant::now();
+        self.save(path)?;
+        let mut out = OpenOptions::new().append(true).open(path)?;
+        while !self.is_done() {
+            let iteration = self.finished.len();
+            let mut victim = self.config.victim.build_seeded(&self.config.cache, self.seed.wrapping_add(iteration as u64));
+            let secret_size = victim.secret_size();
+            let mut saved = Ok(());
+            let mut stats = resume_attack(&mut victim, secret_size, &self.config.attack, self.in_flight.take(), false, |checkpoint| {
+                if saved.is_ok() {
+                    saved = serde_json::to_string(&InFlight {iteration, checkpoint}).map_err(io::Error::from)
+                        .and_then(|text| write_atomically(&in_flight_path(path), &text));
+                }
+            });
+            saved?;
+            let judge = victim.into_judge();
+            judge.score(&mut stats);
+            stats.candidate_log2 = DecaySeries::default();
+            let row = IterationRow {iteration, secret: judge.secret().to_vec(), stats};
+            writeln!(out, "{}", serde_json::to_string(&row)?)?;
+            out.flush()?;
+            self.finished.push(row);
+            self.save_in_flight(path)?;
+            progress(self.finished.len(), self.config.iterations);
+        }
+        let stats: Vec<AttackStats> = self.finished.iter().map(|row| row.stats.clone()).collect();
+        let summary = summarize(&stats);
+        let metadata = ExperimentMetadata {iterations: summary.iterations, threads: 1, wall_clock: start.elapsed(), phase_time: summary.phase_time};
+        return Ok(ExperimentResult {summary, rows: self.finished.clone(), metadata});
+    }
+}
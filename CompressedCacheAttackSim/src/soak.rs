@@ -0,0 +1,138 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::time::Instant;
+use rayon::prelude::*;
+use crate::config::ExperimentConfig;
+use crate::determinism::outcome;
+use crate::experiment::Experiment;
+use crate::structures::Footprint;
+
+/// How far a footprint or the resident memory may grow past the first check's before the soak calls it a leak.
+const GROWTH_TOLERANCE: f64 = 1.5;
+
+/// The page size /proc/self/statm counts resident memory in.
+const PAGE_BYTES: u64 = 4096;
+
+/// One periodic check of a soak run, covering the iterations since the previous check.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthCheck {
+    /// Iterations run so far, this check's included.
+    pub iterations: usize,
+    pub elapsed_seconds: f64,
+    pub successes: usize,
+    /// The largest memory and bookkeeping any one victim held on to after its attack (each maximized separately).
+    pub largest_footprint: Footprint,
+    /// The process's resident memory in bytes, where the platform reports it.
+    pub resident_bytes: Option<u64>,
+    /// Victims that failed their invariant checks, and the first failure.
+    pub invariant_failures: usize,
+    pub first_failure: Option<String>,
+    /// Whether the check's first iteration came out the same when replayed on its own afterwards.
+    pub replayed: bool
+}
+
+/// Every check of a soak run, in order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoakReport {
+    pub seed: u64,
+    pub checks: Vec<HealthCheck>
+}
+
+/// Runs iterations of the campaign described by config from seed (iteration i attacks the victim built from seed + i,
+/// as in Experiment::from_config_seeded), checking its health every check_every iterations and handing each check to
+/// on_check as it is made. Only one check's worth of results is held at a time, so millions of iterations fit in memory.
+pub fn soak(config: &ExperimentConfig, seed: u64, iterations: usize, check_every: usize, mut on_check: impl FnMut(&HealthCheck)) -> SoakReport {
+    let experiment = Experiment::from_config_seeded(config, seed);
+    let start = Instant::now();
+    let mut checks = Vec::new();
+    for first in (0..iterations).step_by(check_every.max(1)) {
+        let last = (first + check_every.max(1)).min(iterations);
+        let batch: Vec<_> = (first..last).into_par_iter().map(|iteration| {
+            let mut footprint = Footprint::default();
+            let mut health = Ok(());
+            let row = experiment.run_inspected(iteration, |victim| {
+                footprint = victim.footprint();
+                health = victim.check_invariants();
+            });
+            (row, footprint, health)
+        }).collect();
+        let failures: Vec<&String> = batch.iter().filter_map(|(_, _, health)| health.as_ref().err()).collect();
+        let check = HealthCheck {
+            iterations: last,
+            elapsed_seconds: start.elapsed().as_secs_f64(),
+            successes: batch.iter().filter(|(row, _, _)| row.stats.success).count(),
+            largest_footprint: Footprint {
+                memory_lines: batch.iter().map(|(_, footprint, _)| footprint.memory_lines).max().unwrap_or(0),
+                reserved_slots: batch.iter().map(|(_, footprint, _)| footprint.reserved_slots).max().unwrap_or(0)
+            },
+            resident_bytes: resident_bytes(),
+            invariant_failures: failures.len(),
+            first_failure: failures.first().map(|failure| failure.to_string()),
+            replayed: outcome(&experiment.run_inspected(first, |_| ())) == outcome(&batch[0].0)
+        };
+        on_check(&check);
+        checks.push(check);
+    }
+    return SoakReport {seed, checks};
+}
+
+/// Reads the process's resident memory from /proc, which only Linux has.
+fn resident_bytes() -> Option<u64> {
+    let statm = fs::read_to_string("/proc/self/statm").ok()?;
+    let pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+    return Some(pages * PAGE_BYTES);
+}
+
+impl HealthCheck {
+    /// Describes the check on one line.
+    pub fn render(&self) -> String {
+        let resident = self.resident_bytes.map_or("unknown".to_string(), |bytes| format!("{:.1} MiB", bytes as f64 / (1 << 20) as f64));
+        return format!("{:>10} iterations, {:>8.1} s: {} successes, at most {} memory lines and {} reserved slots per victim, \
+                        {} resident, {} invariant failures, {}",
+                       self.iterations, self.elapsed_seconds, self.successes, self.largest_footprint.memory_lines,
+                       self.largest_footprint.reserved_slots, resident, self.invariant_failures,
+                       if self.replayed {"replays identically"} else {"DOES NOT REPLAY"});
+    }
+}
+
+impl SoakReport {
+    /// Everything that went wrong: invariant failures, iterations that did not replay, and victim footprints or resident
+    /// memory that grew past GROWTH_TOLERANCE times the first check's.
+    pub fn problems(&self) -> Vec<String> {
+        let Some(baseline) = self.checks.first() else {return Vec::new();};
+        let grew = |now: usize, then: usize| now as f64 > then.max(1) as f64 * GROWTH_TOLERANCE;
+        let mut problems = Vec::new();
+        for check in &self.checks {
+            if let Some(failure) = &check.first_failure {
+                problems.push(format!("{} invariant failures by iteration {}, first: {}", check.invariant_failures, check.iterations, failure));
+            }
+            if !check.replayed {problems.push(format!("the first iteration checked at {} did not replay identically", check.iterations));}
+            if grew(check.largest_footprint.memory_lines, baseline.largest_footprint.memory_lines)
+                || grew(check.largest_footprint.reserved_slots, baseline.largest_footprint.reserved_slots) {
+                problems.push(format!("victim footprints grew from {:?} to {:?} by iteration {}", baseline.largest_footprint, check.largest_footprint, check.iterations));
+            }
+            if let (Some(now), Some(then)) = (check.resident_bytes, baseline.resident_bytes) {
+                if grew(now as usize, then as usize) {
+                    problems.push(format!("resident memory grew from {} to {} bytes by iteration {}", then, now, check.iterations));
+                }
+            }
+        }
+        return problems;
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        return self.problems().is_empty();
+    }
+
+    /// Writes one CSV row per check.
+    pub fn export(&self, path: &str) -> io::Result<()> {
+        let mut out = File::create(path)?;
+        writeln!(out, "iterations,elapsed_seconds,successes,memory_lines,reserved_slots,resident_bytes,invariant_failures,replayed")?;
+        for check in &self.checks {
+            writeln!(out, "{},{},{},{},{},{},{},{}", check.iterations, check.elapsed_seconds, check.successes, check.largest_footprint.memory_lines,
+                     check.largest_footprint.reserved_slots, check.resident_bytes.map_or(String::new(), |bytes| bytes.to_string()),
+                     check.invariant_failures, check.replayed)?;
+        }
+        Ok(())
+    }
+}
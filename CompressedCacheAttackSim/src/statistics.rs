@@ -0,0 +1,156 @@
+use std::collections::VecDeque;
+use serde::Serialize;
+use crate::attacker::AttackStats;
+
+/// The z-score of a two-sided 95% confidence interval under the normal approximation.
+const Z_95: f64 = 1.959964;
+
+/// How one per-attack quantity spread over a campaign.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Distribution {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub median: f64,
+    /// The sample standard deviation (0 with fewer than two samples).
+    pub stddev: f64,
+    /// A 95% confidence interval for the mean, from the normal approximation (mean +- 1.96 standard errors).
+    pub ci95: (f64, f64)
+}
+
+impl Distribution {
+    /// Describes a sample. An empty sample is all zeros.
+    pub fn of(values: &[f64]) -> Distribution {
+        if values.is_empty() {return Distribution::default();}
+        let n = values.len() as f64;
+        let mut sorted = values.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let middle = sorted.len() / 2;
+        let median = if sorted.len().is_multiple_of(2) {(sorted[middle - 1] + sorted[middle]) / 2.0} else {sorted[middle]};
+        let mean = values.iter().sum::<f64>() / n;
+        let variance = if values.len() < 2 {0.0} else {values.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / (n - 1.0)};
+        let stddev = variance.sqrt();
+        let margin = Z_95 * stddev / n.sqrt();
+        return Distribution {min: sorted[0], max: sorted[sorted.len() - 1], mean, median, stddev, ci95: (mean - margin, mean + margin)};
+    }
+}
+
+/// Per-attack distributions of the costs a Summary only totals.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct CostDistributions {
+    pub guesses_needed: Distribution,
+    pub bytes_written_to_victim: Distribution,
+    pub bytes_read_from_victim: Distribution,
+    pub attacker_cache_lines_loaded: Distribution,
+    pub set_evictions: Distribution
+}
+
+impl CostDistributions {
+    pub fn of(results: &[AttackStats]) -> CostDistributions {
+        let spread = |metric: fn(&AttackStats) -> usize| Distribution::of(&results.iter().map(|stats| metric(stats) as f64).collect::<Vec<f64>>());
+        return CostDistributions {
+            guesses_needed: spread(|stats| stats.guesses_needed),
+            bytes_written_to_victim: spread(|stats| stats.bytes_written_to_victim),
+            bytes_read_from_victim: spread(|stats| stats.bytes_read_from_victim),
+            attacker_cache_lines_loaded: spread(|stats| stats.attacker_cache_lines_loaded),
+            set_evictions: spread(|stats| stats.set_evictions)
+        };
+    }
+
+    /// Every distribution, with the name the summary prints it under.
+    pub fn named(&self) -> [(&'static str, &Distribution); 5] {
+        return [
+            ("Guesses needed", &self.guesses_needed),
+            ("Bytes written to the victim buffer", &self.bytes_written_to_victim),
+            ("Bytes read from the victim buffer", &self.bytes_read_from_victim),
+            ("Lines loaded directly by the attacker", &self.attacker_cache_lines_loaded),
+            ("Set evictions", &self.set_evictions)
+        ];
+    }
+}
+
+/// A Wilson score 95% confidence interval for a rate of successes out of trials. Unlike the normal approximation, it stays
+/// within [0, 1] and does not collapse to a point when every trial succeeds. (0, 1) with no trials.
+pub fn wilson_ci95(successes: usize, trials: usize) -> (f64, f64) {
+    if trials == 0 {return (0.0, 1.0);}
+    let n = trials as f64;
+    let p = successes as f64 / n;
+    let z2 = Z_95 * Z_95;
+    let center = (p + z2 / (2.0 * n)) / (1.0 + z2 / n);
+    let margin = Z_95 / (1.0 + z2 / n) * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt();
+    return ((center - margin).max(0.0), (center + margin).min(1.0));
+}
+
+/// Whether each of the last few iterations of a campaign succeeded, and what it cost, oldest first.
+#[derive(Debug, Clone)]
+pub struct RollingWindow {
+    capacity: usize,
+    recent: VecDeque<(bool, f64)>
+}
+
+impl RollingWindow {
+    /// An empty window over the last capacity iterations.
+    pub fn new(capacity: usize) -> RollingWindow {
+        assert!(capacity > 0, "A window needs room for at least one iteration");
+        return RollingWindow {capacity, recent: VecDeque::with_capacity(capacity)};
+    }
+
+    /// Adds an iteration, dropping the oldest if the window is full.
+    pub fn push(&mut self, success: bool, cost: f64) {
+        if self.recent.len() == self.capacity {self.recent.pop_front();}
+        self.recent.push_back((success, cost));
+    }
+
+    pub fn len(&self) -> usize {
+        return self.recent.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.recent.is_empty();
+    }
+
+    /// The fraction of the window's iterations that succeeded (0 if it is empty).
+    pub fn success_rate(&self) -> f64 {
+        return self.recent.iter().filter(|(success, _)| *success).count() as f64 / self.recent.len().max(1) as f64;
+    }
+
+    pub fn cost(&self) -> Distribution {
+        return Distribution::of(&self.recent.iter().map(|&(_, cost)| cost).collect::<Vec<f64>>());
+    }
+}
+
+/// When a campaign has run enough iterations: once it has run at least min_iterations, and the 95% confidence interval
+/// of its success rate (see wilson_ci95) is at most max_ci_width wide.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StoppingRule {
+    pub min_iterations: usize,
+    pub max_ci_width: f64
+}
+
+impl StoppingRule {
+    pub fn is_met(&self, successes: usize, trials: usize) -> bool {
+        let (low, high) = wilson_ci95(successes, trials);
+        return trials >= self.min_iterations && high - low <= self.max_ci_width;
+    }
+}
+
+/// Where a campaign stands: its success rate so far, and its success rate and cost (set evictions) over a recent window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CampaignProgress {
+    pub iterations: usize,
+    pub successes: usize,
+    pub success_ci95: (f64, f64),
+    pub window_iterations: usize,
+    pub window_success_rate: f64,
+    pub window_cost: Distribution,
+    /// Whether the campaign's stopping rule was met, so it stops here.
+    pub stopping: bool
+}
+
+impl CampaignProgress {
+    pub fn render(&self) -> String {
+        return format!("{} iterations, {:.2}% succeeded (95% CI {:.2}% to {:.2}%); last {}: {:.2}% succeeded, {:.1} set evictions per attack{}",
+                       self.iterations, 100.0 * self.successes as f64 / self.iterations.max(1) as f64, 100.0 * self.success_ci95.0, 100.0 * self.success_ci95.1,
+                       self.window_iterations, 100.0 * self.window_success_rate, self.window_cost.mean, if self.stopping {"; stopping"} else {""});
+    }
+}
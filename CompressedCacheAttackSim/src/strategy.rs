@@ -0,0 +1,55 @@
+use std::fs;
+use std::io;
+use serde::Serialize;
+use crate::attacker::{AttackLayout, AttackPipeline, SecretStructure, StepDescription};
+
+/// Everything an attack strategy does to a secret of one size, generated from the layouts and pipelines the simulator
+/// runs, so that papers and artifacts can state exactly what was simulated.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StrategyDescription {
+    pub structure: SecretStructure,
+    pub secret_size: usize,
+    /// The compressed size at which two blocks of a superblock are co-allocated, which every bit window is placed around.
+    pub threshold_bytes: u64,
+    pub steps: Vec<StepDescription>,
+    /// How the recovered words are turned into guesses.
+    pub guesses: String,
+    pub assumptions: Vec<String>
+}
+
+/// What every strategy takes for granted about the cache, the victim and the attacker.
+const COMMON_ASSUMPTIONS: [&str; 5] = [
+    "The cache compresses with C-PACK and co-allocates two blocks of a superblock when both fit in threshold_bytes.",
+    "The secret sits at a known offset in a 64-byte line, and the attacker can write the rest of the line and have the victim read it back.",
+    "No secret word has a zero leading short, second byte or last byte (zero is never a candidate).",
+    "With the default probe, replacement is LRU, so the check line survives exactly when the victim's lines were co-allocated.",
+    "Bit windows count every secret word outside the one being cracked as uncompressed (34 bits)."
+];
+
+impl StrategyDescription {
+    /// Describes the strategy for secrets with the given structure, or None if no attack string layout fits.
+    pub fn new(structure: SecretStructure, secret_size: usize, threshold_bytes: u64) -> Option<StrategyDescription> {
+        let layout = AttackLayout::derive(threshold_bytes, secret_size)?;
+        let mut assumptions: Vec<String> = COMMON_ASSUMPTIONS.iter().map(|a| a.to_string()).collect();
+        let guesses = match structure {
+            SecretStructure::Independent => "Every ordering of the recovered words, since the attack does not learn where each word sits.",
+            SecretStructure::KeySchedule => {
+                if secret_size != 16 {return None;}
+                assumptions.push("The secret is a 16-byte key schedule, which any one of its words determines.".to_string());
+                "The key schedule through the recovered word, with the word at each of the four positions."
+            }
+        };
+        let steps = AttackPipeline::for_structure(structure).describe(&layout, secret_size / 4);
+        return Some(StrategyDescription {structure, secret_size, threshold_bytes, steps, guesses: guesses.to_string(), assumptions});
+    }
+}
+
+/// Describes every strategy that applies to each secret size, and writes the descriptions to a JSON file.
+pub fn export_strategies(path: &str, secret_sizes: &[usize], threshold_bytes: u64) -> io::Result<Vec<StrategyDescription>> {
+    let descriptions: Vec<StrategyDescription> = secret_sizes.iter()
+        .flat_map(|&size| [SecretStructure::Independent, SecretStructure::KeySchedule].map(|structure| StrategyDescription::new(structure, size, threshold_bytes)))
+        .flatten()
+        .collect();
+    fs::write(path, serde_json::to_string_pretty(&descriptions)?)?;
+    return Ok(descriptions);
+}
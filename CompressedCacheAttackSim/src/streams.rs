@@ -0,0 +1,44 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+/// The components of a simulation that draw random numbers. Each draws from its own stream derived from the master
+/// seed, so enabling one source of randomness (say, timing noise) leaves the draws of every other component unchanged,
+/// and runs of different configurations from the same seed stay paired.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RngStream {
+    /// The secret, the buffer base and the victim's own choices (relocation, heap chunks, canaries, residual data).
+    Victim,
+    /// The cache's admission, re-encoding and random replacement choices.
+    Cache,
+    /// Timing and miss-counter noise in what the attacker observes.
+    Noise,
+    /// Whether the co-tenant shares the victim's set, and when it touches it.
+    CoTenant,
+    /// The attacker's own choices.
+    Attacker,
+    /// The victim's background accesses to its own data.
+    Background
+}
+
+impl RngStream {
+    pub const ALL: [RngStream; 6] = [RngStream::Victim, RngStream::Cache, RngStream::Noise, RngStream::CoTenant, RngStream::Attacker, RngStream::Background];
+
+    /// The seed of this stream under the given master seed. The victim stream is seeded with the master seed itself.
+    pub fn seed(self, master_seed: u64) -> u64 {
+        if self == RngStream::Victim {return master_seed;}
+        return splitmix64(master_seed ^ (self as u64).wrapping_mul(0xD6E8_FEB8_6659_FD93));
+    }
+
+    /// A fresh generator for this stream under the given master seed.
+    pub fn rng(self, master_seed: u64) -> StdRng {
+        return StdRng::seed_from_u64(self.seed(master_seed));
+    }
+}
+
+/// The SplitMix64 finalizer, which spreads nearby inputs to unrelated outputs.
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    return z ^ (z >> 31);
+}
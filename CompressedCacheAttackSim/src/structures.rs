@@ -1,31 +1,290 @@
+//! The compressed cache: YACC and its replacement policies and defenses, the compressors that size its lines, and the
+//! private caches and metadata structures that can sit around it.
+
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::ops::Range;
+use serde::{Deserialize, Serialize};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use crate::defenses::{IndexRandomizer, RandomizedIndexing, SizeQuantization};
+use crate::dish::shared_dictionary_fits;
 
+/// The associativity of the modeled cache set, unless another is given to YACC::new.
 pub const ASSOCIATIVITY: usize = 8;
 
-pub fn cpack_bits(line: &[u8;64]) -> u64 {
+/// The blocks in each superblock, unless another count is given to YACC::set_superblock_blocks.
+pub const SUPERBLOCK_BLOCKS: usize = 4;
+
+/// The line size in bytes, unless another is given to YACC::set_line_size.
+pub const LINE_SIZE: usize = 64;
+
+/// The line sizes the cache can be built with.
+pub const LINE_SIZES: [usize; 3] = [32, 64, 128];
+
+/// The order in which the compressors assemble a 4-byte word from its bytes in memory.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum Endianness {
+    /// The least significant byte comes first, as on x86 and most ARM systems.
+    #[default]
+    Little,
+    /// The most significant byte comes first.
+    Big
+}
+
+impl Endianness {
+    /// Converts a word between least-significant-byte-first order and memory order. Converting twice gives the word back.
+    pub fn word_bytes(&self, word: [u8; 4]) -> [u8; 4] {
+        return match self {
+            Endianness::Little => word,
+            Endianness::Big => [word[3], word[2], word[1], word[0]]
+        };
+    }
+
+    /// Converts every word of a line with word_bytes, so that a line in memory order can be compressed as if it were
+    /// little-endian, and a little-endian line put into memory order.
+    pub fn line_bytes(&self, line: &[u8]) -> Vec<u8> {
+        return line.chunks(4).flat_map(|word| self.word_bytes(word.try_into().expect("Lines are whole words"))).collect();
+    }
+
+    /// Returns the offset within a word in memory of the byte of the given significance (0 for the least significant).
+    pub fn byte_offset(&self, significance: usize) -> usize {
+        return match self {
+            Endianness::Little => significance,
+            Endianness::Big => 3 - significance
+        };
+    }
+}
+
+/// The C-PACK pattern chosen for a single 4-byte word.
+/// z = zero byte, m = byte matched from the dictionary, x = unmatched byte (most significant byte first).
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum PatternCode {
+    /// zzzz: the word is all zeros.
+    ZZZZ,
+    /// mmmm: the word fully matches a previous word.
+    MMMM,
+    /// zzzx: the word is a zero-extended byte.
+    ZZZX,
+    /// mmmx: the upper 3 bytes match a previous word.
+    MMMX,
+    /// mmxx: the upper 2 bytes match a previous word.
+    MMXX,
+    /// xxxx: the word is stored uncompressed.
+    XXXX
+}
+
+impl PatternCode {
+    /// Returns the number of bits the pattern takes up in the compressed line, including its code.
+    pub fn bits(&self) -> u64 {
+        return match self {
+            PatternCode::ZZZZ => 2,
+            PatternCode::MMMM => 6,
+            PatternCode::ZZZX => 12,
+            PatternCode::MMMX => 16,
+            PatternCode::MMXX => 24,
+            PatternCode::XXXX => 34
+        };
+    }
+}
+
+/// Returns the C-PACK pattern chosen for each of the words in a line (16 in a 64-byte line).
+pub fn cpack_words(line: &[u8]) -> Vec<PatternCode> {
     let mut history: HashSet<u32> = HashSet::new();
     let mut no_byte_history: HashSet<u32> = HashSet::new();
     let mut no_short_history: HashSet<u32> = HashSet::new();
-    let mut bits = 0u64;
-    for i in 0..16 {
+    let mut patterns = vec![PatternCode::XXXX; line.len() / 4];
+    for i in 0..patterns.len() {
         // Little-endian conversion
         let word = (line[i*4] as u32) | ((line[i*4+1] as u32) << 8) | ((line[i*4+2] as u32) << 16) | ((line[i*4+3] as u32) << 24);
-        if word == 0 {bits += 2}
-        else if history.contains(&word) {bits += 6;}
-        else if word & 0x0FF == word {bits += 12;}
-        else if no_byte_history.contains(&(word & 0xFFFFFF00)) {bits += 16;}
-        else if no_short_history.contains(&(word & 0xFFFF0000)) {bits += 24;}
-        else {bits += 34;}
+        if word == 0 {patterns[i] = PatternCode::ZZZZ;}
+        else if history.contains(&word) {patterns[i] = PatternCode::MMMM;}
+        else if word & 0x0FF == word {patterns[i] = PatternCode::ZZZX;}
+        else if no_byte_history.contains(&(word & 0xFFFFFF00)) {patterns[i] = PatternCode::MMMX;}
+        else if no_short_history.contains(&(word & 0xFFFF0000)) {patterns[i] = PatternCode::MMXX;}
         history.insert(word);
         no_byte_history.insert(word & 0xFFFFFF00);
         no_short_history.insert(word & 0xFFFF0000);
     }
-    return bits;
+    return patterns;
+}
+
+pub fn cpack_bits(line: &[u8]) -> u64 {
+    return cpack_words(line).iter().map(|p| p.bits()).sum();
+}
+
+pub fn cpack_bytes(line: &[u8]) -> u64 {
+    return cpack_bits(line).div_ceil(8);
+}
+
+/// Which dictionary entry a bounded C-PACK dictionary replaces when a new word is pushed into it while full.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum DictionaryReplacement {
+    /// Replaces the entry pushed longest ago, as in the C-PACK hardware.
+    Fifo,
+    /// Replaces the entry matched or pushed longest ago.
+    Lru
+}
+
+/// Returns the C-PACK pattern chosen for each of the words in a line, with the dictionary held to dict_entries words
+/// as in hardware. Every word that is not a full match is pushed into the dictionary (zero words included, as in
+/// cpack_words), and a full match refreshes its entry under Lru. With at least as many entries as the line has words,
+/// this gives the same patterns as cpack_words.
+pub fn bounded_cpack_words(line: &[u8], dict_entries: usize, replacement: DictionaryReplacement) -> Vec<PatternCode> {
+    assert!(dict_entries > 0, "C-PACK dictionaries need at least one entry");
+    // Oldest entry first
+    let mut dictionary: Vec<u32> = Vec::with_capacity(dict_entries);
+    let mut patterns = vec![PatternCode::XXXX; line.len() / 4];
+    for i in 0..patterns.len() {
+        let word = u32::from_le_bytes([line[i*4], line[i*4+1], line[i*4+2], line[i*4+3]]);
+        let matching = |mask: u32| dictionary.iter().any(|&entry| entry & mask == word & mask);
+        if word == 0 {patterns[i] = PatternCode::ZZZZ;}
+        else if matching(0xFFFFFFFF) {patterns[i] = PatternCode::MMMM;}
+        else if word & 0x0FF == word {patterns[i] = PatternCode::ZZZX;}
+        else if matching(0xFFFFFF00) {patterns[i] = PatternCode::MMMX;}
+        else if matching(0xFFFF0000) {patterns[i] = PatternCode::MMXX;}
+        if let Some(entry) = dictionary.iter().position(|&entry| entry == word) {
+            if replacement == DictionaryReplacement::Lru {
+                dictionary.remove(entry);
+                dictionary.push(word);
+            }
+            continue;
+        }
+        if dictionary.len() == dict_entries {dictionary.remove(0);}
+        dictionary.push(word);
+    }
+    return patterns;
+}
+
+pub fn bounded_cpack_bits(line: &[u8], dict_entries: usize, replacement: DictionaryReplacement) -> u64 {
+    return bounded_cpack_words(line, dict_entries, replacement).iter().map(|p| p.bits()).sum();
+}
+
+/// The Frequent Pattern Compression pattern chosen for a single 4-byte word. Every pattern starts with a 3-bit prefix.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum FpcPattern {
+    /// 000: a run of up to 8 zero words, whose length is stored with the run's first word.
+    ZeroRun,
+    /// A zero word that continues a run, taking no space of its own.
+    ZeroRunContinued,
+    /// 001: a 4-bit value, sign-extended.
+    SignExtended4,
+    /// 010: a byte, sign-extended.
+    SignExtended8,
+    /// 011: a halfword, sign-extended.
+    SignExtended16,
+    /// 100: a halfword padded with a zero halfword below it.
+    PaddedHalfword,
+    /// 101: two halfwords, each a sign-extended byte.
+    TwoSignExtendedBytes,
+    /// 110: the same byte repeated four times.
+    RepeatedBytes,
+    /// 111: the word stored uncompressed.
+    Uncompressed
+}
+
+impl FpcPattern {
+    /// Returns the number of bits the pattern takes up in the compressed line, including its prefix.
+    pub fn bits(&self) -> u64 {
+        return match self {
+            FpcPattern::ZeroRun => 6,
+            FpcPattern::ZeroRunContinued => 0,
+            FpcPattern::SignExtended4 => 7,
+            FpcPattern::SignExtended8 | FpcPattern::RepeatedBytes => 11,
+            FpcPattern::SignExtended16 | FpcPattern::PaddedHalfword | FpcPattern::TwoSignExtendedBytes => 19,
+            FpcPattern::Uncompressed => 35
+        };
+    }
+}
+
+/// Returns the FPC pattern chosen for each of the words in a line, taking the smallest pattern that fits each word.
+/// Attack strings aimed at FPC caches can be sized with this, as C-PACK strings are with cpack_words.
+pub fn fpc_words(line: &[u8]) -> Vec<FpcPattern> {
+    let fits = |value: i32, bits: u32| value >> (bits - 1) == 0 || value >> (bits - 1) == -1;
+    let mut patterns = vec![FpcPattern::Uncompressed; line.len() / 4];
+    let mut run = 0;
+    for i in 0..patterns.len() {
+        // Little-endian conversion, as in cpack_words
+        let word = u32::from_le_bytes([line[i*4], line[i*4+1], line[i*4+2], line[i*4+3]]);
+        let signed = word as i32;
+        if word != 0 {run = 0;}
+        patterns[i] = if word == 0 {
+            run = if run == 8 {1} else {run + 1};
+            if run == 1 {FpcPattern::ZeroRun} else {FpcPattern::ZeroRunContinued}
+        }
+        else if fits(signed, 4) {FpcPattern::SignExtended4}
+        else if fits(signed, 8) {FpcPattern::SignExtended8}
+        else if word.to_le_bytes().iter().all(|&b| b == word as u8) {FpcPattern::RepeatedBytes}
+        else if fits(signed, 16) {FpcPattern::SignExtended16}
+        else if word & 0xFFFF == 0 {FpcPattern::PaddedHalfword}
+        else if fits(word as u16 as i16 as i32, 8) && fits((word >> 16) as u16 as i16 as i32, 8) {FpcPattern::TwoSignExtendedBytes}
+        else {FpcPattern::Uncompressed};
+    }
+    return patterns;
+}
+
+pub fn fpc_bits(line: &[u8]) -> u64 {
+    return fpc_words(line).iter().map(|p| p.bits()).sum();
 }
 
-pub fn cpack_bytes(line: &[u8;64]) -> u64 {
-    return (cpack_bits(line) + 7) / 8;
+pub fn fpc_bytes(line: &[u8]) -> u64 {
+    return fpc_bits(line).div_ceil(8);
+}
+
+/// The base-delta encodings BDI tries on every line, as (base size, delta size) in bytes.
+const BDI_ENCODINGS: [(usize, usize); 6] = [(8, 1), (8, 2), (8, 4), (4, 1), (4, 2), (2, 1)];
+
+/// Returns the size of a line compressed with Base-Delta-Immediate, in bits.
+/// An all-zero line takes a byte and a line of one repeated 8-byte value takes 8 bytes. Otherwise, the line is split into
+/// values of the base size, and every value is stored as a delta from either zero (an immediate) or the line's single
+/// explicit base, plus one bit per value saying which. The smallest encoding whose deltas all fit wins; lines that no
+/// encoding fits are stored uncompressed.
+pub fn bdi_bits(line: &[u8]) -> u64 {
+    return bdi_bits_in(line, Endianness::Little);
+}
+
+/// Same as bdi_bits, but with every value read in the given byte order.
+pub fn bdi_bits_in(line: &[u8], endianness: Endianness) -> u64 {
+    if line.iter().all(|&b| b == 0) {return 8;}
+    let words = bdi_values(line, 8, endianness);
+    if words.iter().all(|&w| w == words[0]) {return 64;}
+    let values = |base: usize| line.len() / base;
+    return BDI_ENCODINGS.iter()
+        .filter(|&&(base, delta)| bdi_fits(line, base, delta, endianness))
+        .map(|&(base, delta)| (8 * (base + values(base) * delta) + values(base)) as u64)
+        .min()
+        .unwrap_or(8 * line.len() as u64);
+}
+
+pub fn bdi_bytes(line: &[u8]) -> u64 {
+    return bdi_bits(line).div_ceil(8);
+}
+
+/// Splits a line into values of size bytes, in the given byte order.
+fn bdi_values(line: &[u8], size: usize, endianness: Endianness) -> Vec<u64> {
+    let value = |c: &[u8]| -> u64 {
+        return match endianness {
+            Endianness::Little => c.iter().rev().fold(0u64, |acc, &b| (acc << 8) | b as u64),
+            Endianness::Big => c.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64)
+        };
+    };
+    return line.chunks(size).map(value).collect();
+}
+
+/// Checks whether every base-byte value of a line is within a signed delta-byte delta of either zero or the explicit base,
+/// which is the first value that is not within reach of zero.
+fn bdi_fits(line: &[u8], base: usize, delta: usize, endianness: Endianness) -> bool {
+    let width = 8 * base as u32;
+    let mask = if width == 64 {u64::MAX} else {(1u64 << width) - 1};
+    let fits = |value: u64| {
+        let signed = ((value << (64 - width)) as i64) >> (64 - width);
+        let limit = 1i64 << (8 * delta - 1);
+        (-limit..limit).contains(&signed)
+    };
+    let values = bdi_values(line, base, endianness);
+    let explicit = values.iter().copied().find(|&v| !fits(v));
+    return values.iter().all(|&v| fits(v) || explicit.is_some_and(|b| fits(v.wrapping_sub(b) & mask)));
 }
 
 #[derive(PartialEq, Clone, Copy)]
@@ -42,120 +301,921 @@ pub trait Cache {
     fn write_byte(&mut self, byte_addr: u64, data: u8);
 }
 
-const EMPTY_LINE: [u8; 64] = [0u8;64];
+const EMPTY_LINE: [u8; 128] = [0u8;128];
 
 struct MainMemory {
-    memory_map: HashMap<u64, [u8;64]>
+    line_bytes: usize,
+    memory_map: HashMap<u64, Box<[u8]>>,
+    /// The compressed size in bits of every line compressed since it was last written, so that lines probed over and
+    /// over are only compressed once. Filled in through shared references, hence the RefCell.
+    compressed_bits: RefCell<HashMap<u64, u64>>
 }
 
 impl MainMemory {
-    fn new() -> MainMemory {
+    fn new(line_bytes: usize) -> MainMemory {
         MainMemory {
-            memory_map: HashMap::new()
+            line_bytes,
+            memory_map: HashMap::new(),
+            compressed_bits: RefCell::new(HashMap::new())
         }
     }
 
+    /// Returns a line's compressed size in bits, from the memo if the line has not been written since it was last
+    /// compressed, or else by running compress on it (which must be the same compressor every time).
+    fn compressed_bits(&self, line_addr: u64, compress: impl Fn(&[u8]) -> u64) -> u64 {
+        if let Some(&bits) = self.compressed_bits.borrow().get(&line_addr) {return bits;}
+        let bits = compress(self.get_line(line_addr));
+        self.compressed_bits.borrow_mut().insert(line_addr, bits);
+        return bits;
+    }
+
     /// Gets a reference to a line.
-    /// If line_addr does not yet exist, a pointer to a default EMPTY_LINE is returned.
-    fn get_line(&self, line_addr: u64) -> &[u8; 64] {
-        self.memory_map.get(&line_addr).unwrap_or(&EMPTY_LINE)
+    /// If line_addr does not yet exist, a slice of the default EMPTY_LINE is returned.
+    fn get_line(&self, line_addr: u64) -> &[u8] {
+        return self.memory_map.get(&line_addr).map_or(&EMPTY_LINE[..self.line_bytes], |line| line);
     }
-    /// Gets a mutable reference to a line.
+    /// Gets a mutable reference to a line, forgetting its memoized compressed size.
     /// If line_addr does not yet exist, a new all-zeros line is created.
-    fn get_line_mut(&mut self, line_addr: u64) -> &mut[u8; 64] {
-        if !self.memory_map.contains_key(&line_addr) {
-            self.memory_map.insert(line_addr, EMPTY_LINE.clone());
+    fn get_line_mut(&mut self, line_addr: u64) -> &mut [u8] {
+        self.compressed_bits.get_mut().remove(&line_addr);
+        let line_bytes = self.line_bytes;
+        return self.memory_map.entry(line_addr).or_insert_with(|| vec![0u8; line_bytes].into_boxed_slice());
+    }
+
+    /// Regroups every materialized byte into lines of a new size, keeping each byte at its address.
+    fn resize_lines(&mut self, line_bytes: usize) {
+        let old_bytes = self.line_bytes as u64;
+        let mut resized = MainMemory::new(line_bytes);
+        for (line_addr, line) in self.memory_map.drain() {
+            for (i, &byte) in line.iter().enumerate().filter(|&(_, &byte)| byte != 0) {
+                let byte_addr = line_addr * old_bytes + i as u64;
+                resized.get_line_mut(byte_addr / line_bytes as u64)[(byte_addr % line_bytes as u64) as usize] = byte;
+            }
         }
-        return self.memory_map.get_mut(&line_addr).expect("empty line didn't get inserted?");
+        *self = resized;
     }
 }
 
+/// An entry holds up to four blocks of one superblock, whatever the superblock's size: all of them in a 4-block
+/// superblock, both of a 2-block one, and any four of an 8-block one.
 #[derive(PartialEq, Clone, Copy)]
 enum YACCEntry {
     INVALID,
     SINGLE {line_addr: u64},
     DOUBLE {sb_addr: u64, block0: u64, block1: u64},
     TRIO {sb_addr: u64, block0: u64, block1: u64, block2: u64},
-    QUAD {sb_addr: u64}
+    QUAD {sb_addr: u64, block0: u64, block1: u64, block2: u64, block3: u64}
 }
 
-#[derive(PartialEq, Clone, Copy)]
+impl YACCEntry {
+    /// Returns the addresses of every line held by the entry, with sb_shift the log2 of the blocks per superblock.
+    fn lines(&self, sb_shift: u32) -> Vec<u64> {
+        let line = |sb_addr: u64, block: u64| (sb_addr << sb_shift) | block;
+        return match *self {
+            YACCEntry::INVALID => Vec::new(),
+            YACCEntry::SINGLE {line_addr} => vec![line_addr],
+            YACCEntry::DOUBLE {sb_addr, block0, block1} => vec![line(sb_addr, block0), line(sb_addr, block1)],
+            YACCEntry::TRIO {sb_addr, block0, block1, block2} => vec![line(sb_addr, block0), line(sb_addr, block1), line(sb_addr, block2)],
+            YACCEntry::QUAD {sb_addr, block0, block1, block2, block3} => vec![line(sb_addr, block0), line(sb_addr, block1), line(sb_addr, block2), line(sb_addr, block3)]
+        };
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum Compressor {
-    CPACK
+    CPACK,
+    /// C-PACK with a dictionary of dict_entries words, as computed by bounded_cpack_bits. CPACK's dictionary never fills.
+    BoundedCPACK {dict_entries: usize, replacement: DictionaryReplacement},
+    /// Base-Delta-Immediate, as computed by bdi_bits.
+    BDI,
+    /// Frequent Pattern Compression, as computed by fpc_bits.
+    FPC,
+    /// Stores every line uncompressed, so the cache behaves like a conventional one. Used as a performance baseline.
+    NONE
+}
+
+impl Compressor {
+    /// Cycles spent decompressing a line on a hit, as reported for hardware implementations of each compressor.
+    pub fn decompression_cycles(&self) -> u64 {
+        return match self {
+            Compressor::CPACK | Compressor::BoundedCPACK {..} => 8,
+            Compressor::BDI => 1,
+            Compressor::FPC => 5,
+            Compressor::NONE => 0
+        };
+    }
+
+    /// Returns how many words the compressor's dictionary holds, if it is a bounded C-PACK dictionary.
+    pub fn dictionary_entries(&self) -> Option<usize> {
+        return match self {
+            Compressor::BoundedCPACK {dict_entries, ..} => Some(*dict_entries),
+            _ => None
+        };
+    }
+}
+
+/// Cycle costs of an access to the shared cache, on top of any decompression (see YACC::read_byte_timed).
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub struct AccessTiming {
+    /// Paid by every access.
+    pub tag_lookup: u64,
+    /// Paid by misses, to fetch the line from memory.
+    pub memory: u64
+}
+
+impl Default for AccessTiming {
+    /// Matches the hit and miss latencies of LatencyModel::CYCLES.
+    fn default() -> AccessTiming {
+        return AccessTiming {tag_lookup: 40, memory: 160};
+    }
+}
+
+/// One way of the set at some moment, for debugging: how recently it was used and which lines it holds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WayState {
+    /// 0 for the most recently used way, up to associativity - 1 for the least. None if the way holds nothing.
+    pub age: Option<usize>,
+    pub lines: Vec<u64>
+}
+
+/// Whether an access read or wrote.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AccessKind {
+    Read,
+    Write
+}
+
+/// One access to the cache, as recorded by the access trace (see YACC::enable_access_trace).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccessRecord {
+    pub byte_addr: u64,
+    pub kind: AccessKind,
+    /// Whether the line was held before the access.
+    pub hit: bool,
+    /// The way holding the line after the access and that way's state, or None if the line is not held.
+    pub way: Option<usize>,
+    pub entry: Option<WayState>,
+    /// The space the line takes up in a co-allocated entry after the access, in bytes, ECC and padding included.
+    pub stored_bytes: u64
+}
+
+/// How much memory a cache (or a victim with its cache) holds on to, for spotting leaks over long runs.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Footprint {
+    /// Lines of main memory that have been materialized.
+    pub memory_lines: usize,
+    /// Slots reserved by growable bookkeeping: the eviction log, padded lines, logs and traces.
+    pub reserved_slots: usize
+}
+
+/// A defense that re-encodes every line as it is written back into the cache.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum ReencodingDefense {
+    /// Each write re-encodes the line under a fresh nonce, which changes where its block sits within a co-allocated entry,
+    /// but leaves its size class (and so every co-allocation decision) as it was.
+    RotatingNonce,
+    /// On top of the nonce, each write pads the line's compressed size up to the next size class with probability
+    /// pad_probability (a quad-sized line is stored as double-sized, and a double-sized line as uncompressed).
+    PaddedSizes {pad_probability: f64}
+}
+
+/// The units in which a defense can mark memory as never compressed: a tag bit per line or per superblock, or a page
+/// table bit per 4 KiB page.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum RegionGranularity {
+    Line,
+    Superblock,
+    Page
+}
+
+impl RegionGranularity {
+    pub const ALL: [RegionGranularity; 3] = [RegionGranularity::Line, RegionGranularity::Superblock, RegionGranularity::Page];
+
+    /// The size of one unit in the given cache, in bytes. Lines and superblocks take their size from the cache's geometry.
+    pub fn bytes(&self, cache: &YACC) -> u64 {
+        return match self {
+            RegionGranularity::Line => cache.line_size() as u64,
+            RegionGranularity::Superblock => cache.superblock_bytes(),
+            RegionGranularity::Page => 4096
+        };
+    }
+
+    /// The smallest run of whole units of the given cache that covers the given byte addresses.
+    pub fn cover(&self, bytes: Range<u64>, cache: &YACC) -> Range<u64> {
+        let unit = self.bytes(cache);
+        return bytes.start / unit * unit..bytes.end.div_ceil(unit) * unit;
+    }
+}
+
+/// How a full set picks the entry to evict.
+#[derive(Debug, PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum ReplacementPolicy {
+    /// Evicts the least recently used entry.
+    #[default]
+    Lru,
+    /// Evicts an entry drawn uniformly at random.
+    Random,
+    /// Static RRIP: every entry has a re-reference prediction value (RRPV) of RRPV_MAX or less. Hits predict a near
+    /// re-reference (0), fills a long one (RRPV_MAX - 1), and the entry evicted is one predicted distant (RRPV_MAX),
+    /// after aging the whole set until there is one.
+    Srrip,
+    /// Bimodal RRIP: as Srrip, but fills predict a distant re-reference, except for one in BRRIP_LONG_FILLS.
+    Brrip
+}
+
+/// When stores reach memory.
+#[derive(Debug, PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum WritePolicy {
+    /// Every store goes straight to memory, and the stored line is taken out and re-inserted, so it is recompressed and
+    /// co-allocated afresh on every store.
+    #[default]
+    WriteThrough,
+    /// Stores update the cached line and mark it dirty, and it reaches memory only when it is evicted. A stored line is
+    /// recompressed in place: it keeps its entry as long as it still fits there, and is only moved out when it outgrows it.
+    WriteBack
+}
+
+/// The largest re-reference prediction value of the RRIP policies, which use two bits per entry.
+pub const RRPV_MAX: u8 = 3;
+
+/// Under Brrip, one fill in this many gets a long re-reference prediction instead of a distant one.
+pub const BRRIP_LONG_FILLS: u32 = 32;
+
+/// What an access did to an entry, as far as the replacement policy is concerned.
+#[derive(Clone, Copy, PartialEq)]
+enum EntryAccess {
+    /// The line was already in the entry.
+    Hit,
+    /// The line was co-allocated into an entry that already held other blocks of its superblock.
+    Join,
+    /// The line took the entry for itself.
+    Fill
+}
+
+/// One set of the cache: its entries, their indices in LRU order, and their RRPVs.
+struct CacheSet {
+    entries: Vec<YACCEntry>,
+    lru_state: LruList,
+    rrpv: Vec<u8>
+}
+
+/// Marks the end of an LruList's links.
+const NIL: usize = usize::MAX;
+
+/// The ways of a set in LRU order, as a doubly-linked list threaded through per-way links, so that moving a way to the
+/// most recently used end and finding the least recently used way both take constant time. Only ways that have been
+/// touched since the list was last cleared are on it.
+struct LruList {
+    prev: Vec<usize>,
+    next: Vec<usize>,
+    /// The least and the most recently used way, or NIL when the list is empty.
+    head: usize,
+    tail: usize,
+    len: usize
+}
+
+impl LruList {
+    fn new(ways: usize) -> LruList {
+        return LruList {prev: vec![NIL; ways], next: vec![NIL; ways], head: NIL, tail: NIL, len: 0};
+    }
+
+    /// Whether a way is on the list.
+    fn contains(&self, way: usize) -> bool {
+        return self.prev[way] != NIL || self.head == way;
+    }
+
+    /// Makes a way the most recently used, adding it to the list if it is not on it yet.
+    fn touch(&mut self, way: usize) {
+        if self.tail == way {return;}
+        if self.contains(way) {
+            let (prev, next) = (self.prev[way], self.next[way]);
+            if prev == NIL {self.head = next;} else {self.next[prev] = next;}
+            self.prev[next] = prev; // way is not the tail, so next is a way
+        } else {
+            self.len += 1;
+        }
+        self.prev[way] = self.tail;
+        self.next[way] = NIL;
+        if self.tail == NIL {self.head = way;} else {self.next[self.tail] = way;}
+        self.tail = way;
+    }
+
+    /// The least recently used way, if any way is on the list.
+    fn lru(&self) -> Option<usize> {
+        return if self.head == NIL {None} else {Some(self.head)};
+    }
+
+    /// The ways on the list, least recently used first.
+    fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        return std::iter::successors(self.lru(), |&way| if self.next[way] == NIL {None} else {Some(self.next[way])}).take(self.len);
+    }
+
+    /// How many ways were touched more recently than the given one, if it is on the list.
+    fn age(&self, way: usize) -> Option<usize> {
+        if !self.contains(way) {return None;}
+        return std::iter::successors(Some(way), |&way| if self.next[way] == NIL {None} else {Some(self.next[way])}).count().checked_sub(1);
+    }
+
+    fn len(&self) -> usize {
+        return self.len;
+    }
+
+    fn clear(&mut self) {
+        self.prev.fill(NIL);
+        self.next.fill(NIL);
+        (self.head, self.tail, self.len) = (NIL, NIL, 0);
+    }
+
+    /// Checks that the links form one list of len distinct ways, each linked back to the way before it.
+    fn check(&self) -> Result<(), String> {
+        let ways = self.prev.len();
+        let mut seen = vec![false; ways];
+        let (mut way, mut prev, mut count) = (self.head, NIL, 0);
+        while way != NIL {
+            if way >= ways {return Err(format!("links to way {} of {}", way, ways));}
+            if seen[way] {return Err(format!("lists way {} twice", way));}
+            if self.prev[way] != prev {return Err(format!("links way {} back to {} instead of {}", way, self.prev[way], prev));}
+            seen[way] = true;
+            (prev, way, count) = (way, self.next[way], count + 1);
+        }
+        if prev != self.tail || count != self.len {return Err(format!("ends at way {} after {} ways, but its tail is {} and its length {}", prev, count, self.tail, self.len));}
+        return Ok(());
+    }
 }
 
 pub struct YACC {
-    entries: [YACCEntry;ASSOCIATIVITY],
-    lru_state: Vec<usize>,
+    sets: Vec<CacheSet>,
+    /// The log2 of the blocks per superblock, and of the bytes per line.
+    sb_shift: u32,
+    line_shift: u32,
     memory: MainMemory,
-    compressor: Compressor
+    compressor: Compressor,
+    /// The byte order the compressor reads words in.
+    endianness: Endianness,
+    evictions: Option<Vec<u64>>,
+    metadata: Option<Box<CompressedMetadata>>,
+    /// Largest compressed size (in bytes) at which two blocks of a superblock share an entry.
+    double_threshold: u64,
+    /// Largest compressed size (in bytes) at which three or four blocks of a superblock share an entry.
+    quad_threshold: u64,
+    /// Bytes of ECC (or other per-line metadata) stored next to every compressed block, counted against the thresholds.
+    ecc_bytes: u64,
+    reencoding: Option<ReencodingDefense>,
+    /// The nonce of the last re-encoding, and the lines whose last re-encoding padded them to the next size class.
+    nonce: u64,
+    padded_lines: HashSet<u64>,
+    /// The chance that a compressible block joins an existing entry of its superblock instead of taking an entry alone.
+    admission_probability: f64,
+    /// Whether every store also brings in the adjacent line of its aligned two-line pair, as adjacent-line prefetchers do.
+    store_prefetch: bool,
+    replacement: ReplacementPolicy,
+    /// Byte address ranges whose lines are never compressed, so they never share an entry.
+    uncompressed_regions: Vec<Range<u64>>,
+    timing: AccessTiming,
+    write_policy: WritePolicy,
+    /// Cached lines written since they were last in memory (write-back only), and how many lines were written back so far.
+    dirty_lines: HashSet<u64>,
+    writebacks: u64,
+    /// Every access since the trace was enabled, if it was.
+    trace: Option<Vec<AccessRecord>>,
+    /// Keyed set-index randomization, if the defense is on.
+    randomizer: Option<IndexRandomizer>,
+    /// The size quantization defense, if enabled, and the lines its last draw padded by an extra bucket.
+    quantization: Option<SizeQuantization>,
+    quantized_padding: HashSet<u64>,
+    /// Whether the blocks of an entry share one DISH dictionary, instead of each fitting the entry's size threshold.
+    shared_dictionaries: bool,
+    /// Where the defenses and random replacement draw from. Seeded from entropy unless reseeded.
+    rng: StdRng
 }
 
 impl YACC {
-    pub fn new(comp: Compressor) -> YACC {
+    /// Makes an empty cache set with the given number of entries.
+    pub fn new(comp: Compressor, associativity: usize) -> YACC {
+        return YACC::new_with_geometry(comp, 1, associativity);
+    }
+
+    /// Makes an empty cache of sets sets with ways entries each. Sets are indexed by superblock (the superblock address
+    /// modulo the set count), so all blocks of a superblock share a set and can still be co-allocated.
+    pub fn new_with_geometry(comp: Compressor, sets: usize, ways: usize) -> YACC {
+        assert!(sets > 0, "A cache needs at least one set");
+        assert!(ways > 0, "A cache set needs at least one entry");
         YACC {
-            entries: [YACCEntry::INVALID; 8],
-            lru_state: Vec::new(),
-            memory: MainMemory::new(),
-            compressor: comp
+            sets: (0..sets).map(|_| CacheSet {entries: vec![YACCEntry::INVALID; ways], lru_state: LruList::new(ways), rrpv: vec![RRPV_MAX; ways]}).collect(),
+            sb_shift: SUPERBLOCK_BLOCKS.trailing_zeros(),
+            line_shift: LINE_SIZE.trailing_zeros(),
+            memory: MainMemory::new(LINE_SIZE),
+            compressor: comp,
+            endianness: Endianness::Little,
+            evictions: None,
+            metadata: None,
+            double_threshold: 32,
+            quad_threshold: 16,
+            ecc_bytes: 0,
+            reencoding: None,
+            nonce: 0,
+            padded_lines: HashSet::new(),
+            admission_probability: 1.0,
+            store_prefetch: false,
+            replacement: ReplacementPolicy::Lru,
+            uncompressed_regions: Vec::new(),
+            timing: AccessTiming::default(),
+            write_policy: WritePolicy::WriteThrough,
+            dirty_lines: HashSet::new(),
+            writebacks: 0,
+            trace: None,
+            randomizer: None,
+            quantization: None,
+            quantized_padding: HashSet::new(),
+            shared_dictionaries: false,
+            rng: StdRng::from_entropy()
         }
     }
 
+    /// Changes the compressed sizes (in bytes) below which blocks are co-allocated. The defaults are 32 and 16.
+    pub fn set_coallocation_thresholds(&mut self, double_bytes: u64, quad_bytes: u64) {
+        self.double_threshold = double_bytes;
+        self.quad_threshold = quad_bytes;
+    }
+
+    /// Changes the number of blocks per superblock (2, 4 or 8; the default is 4). Sets are still indexed by superblock,
+    /// and an entry still holds at most four blocks, so smaller superblocks leave fewer blocks to co-allocate and larger
+    /// ones more. The cache is emptied first, since its entries were laid out for the old superblocks.
+    pub fn set_superblock_blocks(&mut self, blocks: usize) {
+        assert!([2, 4, 8].contains(&blocks), "Superblocks hold 2, 4 or 8 blocks");
+        if blocks.trailing_zeros() == self.sb_shift {return;}
+        self.empty();
+        self.sb_shift = blocks.trailing_zeros();
+    }
+
+    /// Changes the line size (one of LINE_SIZES; the default is LINE_SIZE). Memory keeps every byte at its address,
+    /// regrouped into lines of the new size. The co-allocation thresholds stay as they were, so they usually want
+    /// scaling along with the line. The cache is emptied first, since its entries held lines of the old size.
+    pub fn set_line_size(&mut self, bytes: usize) {
+        assert!(LINE_SIZES.contains(&bytes), "Lines are 32, 64 or 128 bytes");
+        if bytes.trailing_zeros() == self.line_shift {return;}
+        self.empty();
+        self.padded_lines.clear();
+        self.quantized_padding.clear();
+        self.memory.resize_lines(bytes);
+        self.line_shift = bytes.trailing_zeros();
+    }
+
+    /// Changes the byte order the compressor reads words in (the default is little-endian). The cache is emptied first,
+    /// since its entries were co-allocated by sizes under the old order.
+    pub fn set_endianness(&mut self, endianness: Endianness) {
+        if endianness == self.endianness {return;}
+        self.empty();
+        self.memory.compressed_bits.get_mut().clear();
+        self.endianness = endianness;
+    }
+
+    /// Returns the byte order the compressor reads words in.
+    pub fn endianness(&self) -> Endianness {
+        return self.endianness;
+    }
+
+    /// Returns the line size, in bytes.
+    pub fn line_size(&self) -> usize {
+        return 1 << self.line_shift;
+    }
+
+    /// Returns the address of the line holding a byte.
+    pub fn line_of(&self, byte_addr: u64) -> u64 {
+        return byte_addr >> self.line_shift;
+    }
+
+    /// Drops every entry, writing back dirty lines, as when the cache's geometry changes.
+    fn empty(&mut self) {
+        for set in &mut self.sets {
+            set.entries.fill(YACCEntry::INVALID);
+            set.lru_state.clear();
+            set.rrpv.fill(RRPV_MAX);
+        }
+        self.writebacks += self.dirty_lines.len() as u64;
+        self.dirty_lines.clear();
+    }
+
+    /// Returns the number of blocks per superblock.
+    pub fn superblock_blocks(&self) -> usize {
+        return 1 << self.sb_shift;
+    }
+
+    /// Returns the size of a superblock, in bytes.
+    pub fn superblock_bytes(&self) -> u64 {
+        return (self.line_size() as u64) << self.sb_shift;
+    }
+
+    /// Returns the double and quad co-allocation thresholds, in bytes.
+    pub fn coallocation_thresholds(&self) -> (u64, u64) {
+        return (self.double_threshold, self.quad_threshold);
+    }
+
+    /// Returns the bytes of ECC stored with every compressed block.
+    pub fn ecc_overhead(&self) -> u64 {
+        return self.ecc_bytes;
+    }
+
+    /// Reserves the given number of bytes of every compressed block for ECC, so that a block is only co-allocated if its
+    /// compressed size plus the ECC fits the threshold. With 2 bytes, the default thresholds effectively become 30 and 14 bytes.
+    pub fn set_ecc_overhead(&mut self, bytes: u64) {
+        self.ecc_bytes = bytes;
+    }
+
+    /// Re-encodes lines on every write from now on, as described by the defense.
+    pub fn set_reencoding_defense(&mut self, defense: ReencodingDefense) {
+        if let ReencodingDefense::PaddedSizes {pad_probability} = defense {
+            assert!((0.0..=1.0).contains(&pad_probability), "Padding probabilities must lie in [0, 1]");
+        }
+        self.reencoding = Some(defense);
+    }
+
+    /// Co-allocates a compressible block into an existing entry of its superblock only with the given probability
+    /// (drawn on every insertion), and otherwise gives it an entry of its own, as a defense. The default is 1.
+    pub fn set_admission_probability(&mut self, probability: f64) {
+        assert!((0.0..=1.0).contains(&probability), "Admission probabilities must lie in [0, 1]");
+        self.admission_probability = probability;
+    }
+
+    /// Quantizes every stored size to the defense's buckets from now on, as a defense (see defenses::SizeQuantization).
+    /// Padding is drawn whenever a line is written; lines not written since keep their unpadded size.
+    pub fn set_size_quantization(&mut self, defense: SizeQuantization) {
+        assert!(defense.bucket_bytes > 0, "Size buckets must be at least one byte");
+        assert!((0.0..=1.0).contains(&defense.pad_probability), "Padding probabilities must lie in [0, 1]");
+        self.quantization = Some(defense);
+    }
+
+    /// Co-allocates blocks as DISH does from now on: a block joins an entry of its superblock only if the words of all its
+    /// blocks fit one shared dictionary (see dish::shared_dictionary_fits). Used by dish::DISH.
+    pub fn share_dictionaries(&mut self) {
+        self.empty();
+        self.shared_dictionaries = true;
+    }
+
+    /// Returns whether blocks are co-allocated by shared dictionaries, as in DISH, rather than by compressed size.
+    pub fn shares_dictionaries(&self) -> bool {
+        return self.shared_dictionaries;
+    }
+
+    /// Makes every store fetch the line in exclusive state along with its neighbor (the adjacent line of the aligned
+    /// two-line pair), as write-combining buffers and adjacent-line prefetchers do. The model has no coherence states,
+    /// so this just means the neighbor is accessed right after the stored line, ending up most recently used.
+    pub fn enable_store_prefetch(&mut self) {
+        self.store_prefetch = true;
+    }
+
+    /// Maps superblocks to sets through a keyed cipher instead of their address, as a defense, with a key drawn from the
+    /// cache's generator and replaced as often as the defense says. Resident lines move to their sets under the key.
+    pub fn enable_randomized_indexing(&mut self, defense: RandomizedIndexing) {
+        self.randomizer = Some(IndexRandomizer::new(defense, self.rng.gen()));
+        self.remap();
+    }
+
+    /// Returns how many times randomized indexing replaced its key so far (0 without the defense).
+    pub fn index_remaps(&self) -> u64 {
+        return self.randomizer.as_ref().map_or(0, IndexRandomizer::remaps);
+    }
+
+    /// Counts an access against the randomized indexing's remap period, rekeying and remapping when it runs out.
+    fn tick_randomizer(&mut self) {
+        let Some(randomizer) = &mut self.randomizer else {return};
+        if !randomizer.tick() {return;}
+        randomizer.rekey(self.rng.gen());
+        self.remap();
+    }
+
+    /// Moves every resident line to the set it maps to now, oldest first, so that sets that end up over-full evict
+    /// as usual. Entries are rebuilt from scratch, so lines may end up co-allocated differently.
+    fn remap(&mut self) {
+        let mut lines: Vec<u64> = Vec::new();
+        for set in &mut self.sets {
+            for way in set.lru_state.iter() {lines.extend(set.entries[way].lines(self.sb_shift));}
+            set.entries.fill(YACCEntry::INVALID);
+            set.lru_state.clear();
+            set.rrpv.fill(RRPV_MAX);
+        }
+        for line in lines {self.access(line);}
+    }
+
+    /// Reseeds the cache's own random choices (admission, re-encoding and random replacement), so that they replay.
+    pub fn reseed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    /// Changes how full sets pick the entry to evict. The default is LRU.
+    pub fn set_replacement_policy(&mut self, policy: ReplacementPolicy) {
+        self.replacement = policy;
+    }
+
+    /// Changes when stores reach memory. The default is write-through.
+    pub fn set_write_policy(&mut self, policy: WritePolicy) {
+        self.write_policy = policy;
+    }
+
+    /// Returns the number of dirty lines written back to memory on eviction so far (always 0 under write-through).
+    pub fn writebacks(&self) -> u64 {
+        return self.writebacks;
+    }
+
+    /// Checks whether a line has been written since it was last in memory.
+    pub fn is_dirty(&self, line_addr: u64) -> bool {
+        return self.dirty_lines.contains(&line_addr);
+    }
+
+    /// Returns how full sets pick the entry to evict.
+    pub fn replacement_policy(&self) -> ReplacementPolicy {
+        return self.replacement;
+    }
+
+    /// Marks a range of byte addresses as never compressed, as a defense: their lines always take an entry of their own.
+    /// Lines already co-allocated stay that way until they are next filled.
+    pub fn mark_uncompressed(&mut self, bytes: Range<u64>) {
+        self.uncompressed_regions.push(bytes);
+    }
+
+    /// Lets every line be compressed again.
+    pub fn clear_uncompressed(&mut self) {
+        self.uncompressed_regions.clear();
+    }
+
+    /// Changes the cycle costs of accesses reported by read_byte_timed.
+    pub fn set_access_timing(&mut self, timing: AccessTiming) {
+        self.timing = timing;
+    }
+
+    /// Returns the cycles taken by a hit on a line stored alone, and by a hit on a line co-allocated with other blocks
+    /// (which has to be decompressed).
+    pub fn hit_latencies(&self) -> (u64, u64) {
+        return (self.timing.tag_lookup, self.timing.tag_lookup + self.compressor.decompression_cycles());
+    }
+
+    /// Reads a byte as read_byte does, also returning how many cycles the access took: the tag lookup, plus decompression
+    /// if it hit a line that shares its entry, or the memory latency if it missed. A line alone in its entry is stored
+    /// uncompressed, and a missing line is filled from memory without being decompressed.
+    pub fn read_byte_timed(&mut self, byte_addr: u64) -> (u8, AccessSpeed, u64) {
+        let (byte, speed) = self.read_byte(byte_addr);
+        let (uncompressed, compressed) = self.hit_latencies();
+        let cycles = match speed {
+            AccessSpeed::MISS => self.timing.tag_lookup + self.timing.memory,
+            AccessSpeed::HIT if self.is_stored_compressed(self.line_of(byte_addr)) => compressed,
+            AccessSpeed::HIT => uncompressed
+        };
+        return (byte, speed, cycles);
+    }
+
+    /// Whether a cached line shares its entry with other blocks of its superblock.
+    fn is_stored_compressed(&self, line_addr: u64) -> bool {
+        let Some(i) = self.is_line_cached(line_addr) else {return false};
+        return !matches!(self.sets[self.set_index(line_addr)].entries[i], YACCEntry::SINGLE {..});
+    }
+
+    /// Attaches a compressed metadata structure that tracks a write counter for every data line.
+    pub fn enable_metadata(&mut self, comp: Compressor) {
+        if self.metadata.is_none() {self.metadata = Some(Box::new(CompressedMetadata::new(comp)));}
+    }
+
+    /// Returns the attached metadata structure, if any.
+    pub fn metadata(&self) -> Option<&CompressedMetadata> {
+        return self.metadata.as_deref();
+    }
+
+    /// Starts recording the addresses of lines evicted from the cache, so an inclusive hierarchy can back-invalidate them.
+    pub fn track_evictions(&mut self) {
+        if self.evictions.is_none() {self.evictions = Some(Vec::new());}
+    }
+
+    /// Returns and clears the lines evicted since the last call. Always empty unless track_evictions was called.
+    pub fn take_evictions(&mut self) -> Vec<u64> {
+        return self.evictions.as_mut().map(std::mem::take).unwrap_or_default();
+    }
+
+    /// Starts recording every read and write into an access trace, for working out afterwards why an attack went wrong.
+    /// The trace grows with every access, so it is best kept to single attacks.
+    pub fn enable_access_trace(&mut self) {
+        if self.trace.is_none() {self.trace = Some(Vec::new());}
+    }
+
+    /// Returns and clears the accesses recorded since the last call. Always empty unless enable_access_trace was called.
+    pub fn take_access_trace(&mut self) -> Vec<AccessRecord> {
+        return self.trace.as_mut().map(std::mem::take).unwrap_or_default();
+    }
+
+    /// Adds an access that just happened to the trace, if there is one.
+    fn record_access(&mut self, byte_addr: u64, kind: AccessKind, hit: bool) {
+        if self.trace.is_none() {return;}
+        let line_addr = self.line_of(byte_addr);
+        let way = self.is_line_cached(line_addr);
+        let entry = way.map(|way| self.way_states(self.set_index(line_addr)).swap_remove(way));
+        let record = AccessRecord {byte_addr, kind, hit, way, entry, stored_bytes: self.stored_bytes(line_addr)};
+        if let Some(trace) = &mut self.trace {trace.push(record);}
+    }
+
     /// Checks whether a line is cached.
-    /// Returns the index in the entries array where the line is located, if it is cached.
+    /// Returns the index in its set's entries array where the line is located, if it is cached.
     fn is_line_cached(&self, requested_line_addr: u64) -> Option<usize> {
-        let requested_sb_addr = requested_line_addr >> 2;
-        let requested_block_number = requested_line_addr & 0b011;
-        for i in 0..ASSOCIATIVITY {
-            if match self.entries[i] {
-                YACCEntry::SINGLE { line_addr } => line_addr == requested_line_addr,
-                YACCEntry::DOUBLE { sb_addr, block0, block1 } => sb_addr == requested_sb_addr && (
-                    block0 == requested_block_number || block1 == requested_block_number
-                ),
-                YACCEntry::TRIO { sb_addr, block0, block1, block2 } => sb_addr == requested_sb_addr && (
-                    block0 == requested_block_number || block1 == requested_block_number || block2 == requested_block_number
-                ),
-                YACCEntry::QUAD { sb_addr } => sb_addr == requested_sb_addr,
-                YACCEntry::INVALID => false
-            } {
-                return Some(i);
-            }
-        }
-        return None;
+        let requested_sb_addr = requested_line_addr >> self.sb_shift;
+        let requested_block_number = requested_line_addr & ((1 << self.sb_shift) - 1);
+        let entries = &self.sets[self.set_index(requested_line_addr)].entries;
+        return (0..entries.len()).find(|&i| match entries[i] {
+            YACCEntry::SINGLE { line_addr } => line_addr == requested_line_addr,
+            YACCEntry::DOUBLE { sb_addr, block0, block1 } => sb_addr == requested_sb_addr && (
+                block0 == requested_block_number || block1 == requested_block_number
+            ),
+            YACCEntry::TRIO { sb_addr, block0, block1, block2 } => sb_addr == requested_sb_addr && (
+                block0 == requested_block_number || block1 == requested_block_number || block2 == requested_block_number
+            ),
+            YACCEntry::QUAD { sb_addr, block0, block1, block2, block3 } => sb_addr == requested_sb_addr && (
+                block0 == requested_block_number || block1 == requested_block_number || block2 == requested_block_number || block3 == requested_block_number
+            ),
+            YACCEntry::INVALID => false
+        });
     }
 
     /// Returns the compressed size of a line.
     pub fn compress_bytes(&self, line_addr: u64) -> u64 {
-        let line = self.memory.get_line(line_addr);
-        return match self.compressor {
-            Compressor::CPACK => cpack_bytes(line)
-        };
+        return self.compress_bits(line_addr).div_ceil(8);
     }
 
-    /// Returns the compressed size of a line, in bits.
-    #[allow(dead_code)]
+    /// Returns the compressed size of a line, in bits. Sizes are memoized until the line is next written.
     pub fn compress_bits(&self, line_addr: u64) -> u64 {
-        let line = self.memory.get_line(line_addr);
-        return match self.compressor {
-            Compressor::CPACK => cpack_bits(line)
+        return self.memory.compressed_bits(line_addr, |line| {
+            // The word-based compressors read words little-endian, so big-endian lines are handed to them with each word reversed.
+            let reordered;
+            let words = if self.endianness == Endianness::Little {line} else {reordered = self.endianness.line_bytes(line); &reordered[..]};
+            match self.compressor {
+                Compressor::CPACK => cpack_bits(words),
+                Compressor::BoundedCPACK {dict_entries, replacement} => bounded_cpack_bits(words, dict_entries, replacement),
+                Compressor::BDI => bdi_bits_in(line, self.endianness),
+                Compressor::FPC => fpc_bits(words),
+                Compressor::NONE => 8 * line.len() as u64
+            }
+        });
+    }
+
+    /// Returns the space a line takes up in a co-allocated entry: its compressed size plus the ECC overhead,
+    /// bumped up to the next size class if the re-encoding defense padded it, or the whole line if it is never compressed.
+    fn stored_bytes(&self, line_addr: u64) -> u64 {
+        if self.uncompressed_regions.iter().any(|region| region.contains(&(line_addr << self.line_shift))) {return self.line_size() as u64;}
+        let mut bytes = self.compress_bytes(line_addr) + self.ecc_bytes;
+        if let Some(quantization) = &self.quantization {bytes = quantization.quantize(bytes, self.quantized_padding.contains(&line_addr));}
+        if !self.padded_lines.contains(&line_addr) {return bytes;}
+        return if bytes <= self.quad_threshold {self.quad_threshold + 1} else if bytes <= self.double_threshold {self.double_threshold + 1} else {bytes};
+    }
+
+    /// Re-encodes a line that was just written, if the defense is enabled: draws a new nonce, and decides whether to pad it.
+    fn reencode(&mut self, line_addr: u64) {
+        let Some(defense) = self.reencoding else {return};
+        self.nonce = self.rng.gen();
+        let pad = match defense {
+            ReencodingDefense::RotatingNonce => false,
+            ReencodingDefense::PaddedSizes {pad_probability} => self.rng.gen_bool(pad_probability)
         };
+        if pad {self.padded_lines.insert(line_addr);} else {self.padded_lines.remove(&line_addr);}
+    }
+
+    /// Draws whether a line that was just written gets an extra bucket of padding, if size quantization is enabled.
+    fn requantize(&mut self, line_addr: u64) {
+        let Some(quantization) = self.quantization else {return};
+        let pad = quantization.pad_probability > 0.0 && self.rng.gen_bool(quantization.pad_probability);
+        if pad {self.quantized_padding.insert(line_addr);} else {self.quantized_padding.remove(&line_addr);}
+    }
+
+    /// Returns whether a line is small enough (ECC included) to share an entry with another block of its superblock, or
+    /// with shared dictionaries, whether its words fit a dictionary on their own.
+    pub fn fits_double(&self, line_addr: u64) -> bool {
+        if self.shared_dictionaries {return self.dictionary_fits(&[line_addr]);}
+        return self.stored_bytes(line_addr) <= self.double_threshold;
+    }
+
+    /// Returns whether a block of size stored bytes may join an entry holding entry_lines of its superblock. Under the
+    /// size thresholds, every block must fit the threshold of the grown entry (an entry of three already fits the quad
+    /// threshold); with shared dictionaries, the words of every block must fit one dictionary.
+    fn can_join(&self, entry_lines: &[u64], line_addr: u64, size: u64) -> bool {
+        if self.shared_dictionaries {
+            let mut lines = entry_lines.to_vec();
+            lines.push(line_addr);
+            return self.dictionary_fits(&lines);
+        }
+        let threshold = if entry_lines.len() == 1 {self.double_threshold} else {self.quad_threshold};
+        return size <= threshold && (entry_lines.len() == 3 || entry_lines.iter().all(|&line| self.stored_bytes(line) <= threshold));
+    }
+
+    /// Returns whether the lines' words fit one DISH dictionary together. Lines that are never compressed never fit.
+    fn dictionary_fits(&self, lines: &[u64]) -> bool {
+        if lines.iter().any(|&line| self.uncompressed_regions.iter().any(|region| region.contains(&(line << self.line_shift)))) {return false;}
+        let blocks: Vec<&[u8]> = lines.iter().map(|&line| self.memory.get_line(line)).collect();
+        return shared_dictionary_fits(&blocks, self.endianness);
+    }
+
+    /// Returns the number of entries in each set.
+    pub fn associativity(&self) -> usize {
+        return self.sets[0].entries.len();
+    }
+
+    /// Returns the number of sets.
+    pub fn set_count(&self) -> usize {
+        return self.sets.len();
+    }
+
+    /// Returns the set a line maps to.
+    pub fn set_index(&self, line_addr: u64) -> usize {
+        let superblock = line_addr >> self.sb_shift;
+        if let Some(randomizer) = &self.randomizer {return randomizer.set_of(superblock, self.sets.len());}
+        return (superblock % self.sets.len() as u64) as usize;
+    }
+
+    /// Returns the address of the k-th superblock at or after base (also a superblock address) that maps to the given set.
+    /// Under randomized indexing, this searches the current mapping, as an attacker that has just found an eviction set
+    /// would; the superblocks it returns stop sharing a set at the next remap.
+    pub fn superblock_in_set(&self, base: u64, k: u64, set: usize) -> u64 {
+        let sets = self.sets.len() as u64;
+        if let Some(randomizer) = &self.randomizer {
+            return (base..).filter(|&superblock| randomizer.set_of(superblock, sets as usize) == set).nth(k as usize).unwrap();
+        }
+        return base.next_multiple_of(sets) + k * sets + set as u64;
+    }
+
+    /// Returns the compressor the cache was built with.
+    pub fn compressor(&self) -> Compressor {
+        return self.compressor;
+    }
+
+    /// Checks whether a line is held by the cache, without accessing it.
+    pub fn is_cached(&self, line_addr: u64) -> bool {
+        return self.is_line_cached(line_addr).is_some();
+    }
+
+    /// Returns every way's LRU age and lines in the given set, indexed by way.
+    pub fn way_states(&self, set: usize) -> Vec<WayState> {
+        let set = &self.sets[set];
+        return set.entries.iter().enumerate().map(|(way, entry)| {
+            let lines = entry.lines(self.sb_shift);
+            let age = if lines.is_empty() {None} else {set.lru_state.age(way)};
+            WayState {age, lines}
+        }).collect();
+    }
+
+    /// Returns the number of lines currently held by the cache, counting every block of a co-allocated entry.
+    pub fn resident_lines(&self) -> usize {
+        return self.sets.iter().flat_map(|set| set.entries.iter()).map(|e| e.lines(self.sb_shift).len()).sum();
+    }
+
+    /// Returns how much memory the cache holds on to right now.
+    pub fn footprint(&self) -> Footprint {
+        let reserved_slots = self.memory.compressed_bits.borrow().capacity() + self.evictions.as_ref().map_or(0, Vec::capacity) + self.padded_lines.capacity() + self.quantized_padding.capacity() + self.uncompressed_regions.capacity()
+            + self.dirty_lines.capacity() + self.trace.as_ref().map_or(0, Vec::capacity);
+        return Footprint {memory_lines: self.memory.memory_map.len(), reserved_slots};
+    }
+
+    /// Checks the cache's bookkeeping for consistency: every set's LRU list and RRPVs cover its entries, every line sits
+    /// in the set it maps to, no line is held twice, and every dirty line is held. Returns the first inconsistency found.
+    pub fn check_invariants(&self) -> Result<(), String> {
+        let mut held: HashSet<u64> = HashSet::new();
+        for (i, set) in self.sets.iter().enumerate() {
+            let ways = set.entries.len();
+            if set.rrpv.len() != ways {return Err(format!("set {} has {} RRPVs for {} ways", i, set.rrpv.len(), ways));}
+            if let Err(e) = set.lru_state.check() {return Err(format!("set {}'s LRU list {}", i, e));}
+            if set.lru_state.len() > ways {return Err(format!("set {} has {} ways on its LRU list for {} ways", i, set.lru_state.len(), ways));}
+            for line in set.entries.iter().flat_map(|entry| entry.lines(self.sb_shift)) {
+                if self.set_index(line) != i {return Err(format!("line {:#x} is held in set {} but maps to set {}", line, i, self.set_index(line)));}
+                if !held.insert(line) {return Err(format!("line {:#x} is held twice", line));}
+            }
+        }
+        if let Some(line) = self.dirty_lines.iter().find(|line| !held.contains(line)) {return Err(format!("line {:#x} is dirty but not held", line));}
+        return Ok(());
+    }
+
+    /// Sets a line's contents in memory without an access, as if they had been written long ago and written back since.
+    /// If the line happens to be cached (and its contents change), it is dropped from the cache first.
+    pub fn initialize_line(&mut self, line_addr: u64, contents: &[u8]) {
+        assert_eq!(contents.len(), self.line_size(), "Line contents must fill exactly one line");
+        if self.memory.get_line(line_addr) == contents {return;}
+        self.remove_line(line_addr);
+        self.dirty_lines.remove(&line_addr);
+        self.memory.get_line_mut(line_addr).copy_from_slice(contents);
+        self.requantize(line_addr);
+    }
+
+    /// Returns a byte directly from memory, without an access.
+    pub fn peek_byte(&self, byte_addr: u64) -> u8 {
+        return self.memory.get_line(self.line_of(byte_addr))[(byte_addr & (self.line_size() as u64 - 1)) as usize];
     }
 
     /// Returns a line directly from memory. For debug purposes only.
-    #[allow(dead_code)]
-    pub fn peek_line(&self, line_addr: u64) -> &[u8;64] {
+    pub fn peek_line(&self, line_addr: u64) -> &[u8] {
         return self.memory.get_line(line_addr);
     }
 
     /// Accesses a line. Returns whether or not the access was a hit.
     /// This also updates the LRU state.
     fn access(&mut self, requested_line_addr: u64) -> AccessSpeed {
-        let requested_sb_addr = requested_line_addr >> 2;
-        let requested_sb_number = requested_line_addr & 0b011;
+        let requested_sb_addr = requested_line_addr >> self.sb_shift;
+        let requested_sb_number = requested_line_addr & ((1 << self.sb_shift) - 1);
+        let set = self.set_index(requested_line_addr);
 
         // Step 1: if the line is already there, return immediately.
         if let Some(i) = self.is_line_cached(requested_line_addr) {
-            self.update_lru_state(i);
+            self.touch(set, i, EntryAccess::Hit);
             return AccessSpeed::HIT;
         }
 
@@ -164,148 +1224,542 @@ impl YACC {
         let mut single_found: Option<usize> = None;
         let mut double_found: Option<usize> = None;
         let mut trio_found: Option<usize> = None;
-        let compressed_size = self.compress_bytes(requested_line_addr);
-        for i in 0..self.entries.len() {
-            match self.entries[i] {
+        let compressed_size = self.stored_bytes(requested_line_addr);
+        for i in 0..self.sets[set].entries.len() {
+            match self.sets[set].entries[i] {
                 YACCEntry::INVALID => {
                     empty_found = Some(i);
                 },
                 YACCEntry::SINGLE {line_addr} => {
-                    if (line_addr >> 2) == requested_sb_addr && compressed_size <= 32 && self.compress_bytes(line_addr) <= 32 {
+                    if (line_addr >> self.sb_shift) == requested_sb_addr && self.can_join(&[line_addr], requested_line_addr, compressed_size) {
                         single_found = Some(i);
                     }
                 },
                 YACCEntry::DOUBLE {sb_addr, block0, block1} => {
-                    if sb_addr == requested_sb_addr && compressed_size <= 16
-                        && self.compress_bytes((sb_addr << 2) | block0) <= 16
-                        && self.compress_bytes((sb_addr << 2) | block1) <= 16 {
+                    let lines = [(sb_addr << self.sb_shift) | block0, (sb_addr << self.sb_shift) | block1];
+                    if sb_addr == requested_sb_addr && self.can_join(&lines, requested_line_addr, compressed_size) {
                         double_found = Some(i);
                     }
                 },
-                YACCEntry::TRIO {sb_addr, block0:_, block1:_, block2:_} => {
-                    if sb_addr == requested_sb_addr && compressed_size <= 16 { // No need to check compressibility of preexisting blocks
+                YACCEntry::TRIO {sb_addr, block0, block1, block2} => {
+                    let lines = [(sb_addr << self.sb_shift) | block0, (sb_addr << self.sb_shift) | block1, (sb_addr << self.sb_shift) | block2];
+                    if sb_addr == requested_sb_addr && self.can_join(&lines, requested_line_addr, compressed_size) {
                         trio_found = Some(i);
                         break; // This is the best option, so break immediately
                     }
                 },
-                YACCEntry::QUAD {sb_addr: _} => ()
+                YACCEntry::QUAD {..} => ()
             }
         }
 
-        // Step 3: upgrade the slot that was found.
+        // Step 3: upgrade the slot that was found (unless the admission defense turns the block away).
+        if self.admission_probability < 1.0 && !self.rng.gen_bool(self.admission_probability) {
+            (trio_found, double_found, single_found) = (None, None, None);
+        }
         if let Some(i) = trio_found {
-            self.entries[i] = YACCEntry::QUAD {sb_addr: requested_sb_addr};
-            self.update_lru_state(i);
+            let (block0, block1, block2) = match self.sets[set].entries[i] {
+                YACCEntry::TRIO {sb_addr: _, block0, block1, block2} => (block0, block1, block2),
+                _ => unreachable!()
+            };
+            self.sets[set].entries[i] = YACCEntry::QUAD {sb_addr: requested_sb_addr, block0, block1, block2, block3: requested_sb_number};
+            self.touch(set, i, EntryAccess::Join);
             return AccessSpeed::MISS;
         }
         if let Some(i) = double_found {
-            let (b0, b1) = match self.entries[i] {
+            let (b0, b1) = match self.sets[set].entries[i] {
                 YACCEntry::DOUBLE { sb_addr: _, block0, block1} => (block0, block1),
                 _ => unreachable!()
             };
-            self.entries[i] = YACCEntry::TRIO {
+            self.sets[set].entries[i] = YACCEntry::TRIO {
                 sb_addr: requested_sb_addr,
                 block0: b0,
                 block1: b1,
                 block2: requested_sb_number
             };
-            self.update_lru_state(i);
+            self.touch(set, i, EntryAccess::Join);
             return AccessSpeed::MISS;
         }
         if let Some(i) = single_found {
-            let b0 = match self.entries[i] {
-                YACCEntry::SINGLE {line_addr} => line_addr & 0b011,
+            let b0 = match self.sets[set].entries[i] {
+                YACCEntry::SINGLE {line_addr} => line_addr & ((1 << self.sb_shift) - 1),
                 _ => unreachable!()
             };
-            self.entries[i] = YACCEntry::DOUBLE {
-                sb_addr: requested_sb_addr,
-                block0: b0,
-                block1: requested_sb_number
-            };
-            self.update_lru_state(i);
+            // The nonce only decides which half of the entry each block takes.
+            let (block0, block1) = if self.nonce & 1 == 0 {(b0, requested_sb_number)} else {(requested_sb_number, b0)};
+            self.sets[set].entries[i] = YACCEntry::DOUBLE {sb_addr: requested_sb_addr, block0, block1};
+            self.touch(set, i, EntryAccess::Join);
             return AccessSpeed::MISS;
         }
         if let Some(i) = empty_found {
-            self.entries[i] = YACCEntry::SINGLE {line_addr: requested_line_addr};
-            self.update_lru_state(i);
+            self.sets[set].entries[i] = YACCEntry::SINGLE {line_addr: requested_line_addr};
+            self.touch(set, i, EntryAccess::Fill);
             return AccessSpeed::MISS;
         }
 
         // Step 4: evict some space for the new line, then insert it.
-        let freed_index = self.lru_state[0];
-        self.entries[freed_index] = YACCEntry::SINGLE {line_addr: requested_line_addr};
-        self.update_lru_state(freed_index);
+        let freed_index = self.replacement_victim(set);
+        let evicted = self.sets[set].entries[freed_index].lines(self.sb_shift);
+        for line in &evicted {
+            if self.dirty_lines.remove(line) {self.writebacks += 1;}
+        }
+        if let Some(evictions) = &mut self.evictions {evictions.extend(evicted);}
+        self.sets[set].entries[freed_index] = YACCEntry::SINGLE {line_addr: requested_line_addr};
+        self.touch(set, freed_index, EntryAccess::Fill);
         return AccessSpeed::MISS;
     }
 
     /// Removes a line from the cache so that it can be re-inserted properly.
     /// This function is designed to quickly take out the line, without computing compressibilities.
     fn remove_line(&mut self, modified_line: u64) {
-        let modified_sb = modified_line >> 2;
-        let modified_block = modified_line & 0b11;
-        for i in 0..ASSOCIATIVITY {
+        let modified_sb = modified_line >> self.sb_shift;
+        let modified_block = modified_line & ((1 << self.sb_shift) - 1);
+        let set = self.set_index(modified_line);
+        for i in 0..self.sets[set].entries.len() {
             let mut replacement: Option<YACCEntry> = None;
-            match self.entries[i] {
-                YACCEntry::SINGLE {line_addr} => {
-                    if line_addr == modified_line {
-                        replacement = Some(YACCEntry::INVALID);
-                    }
+            match self.sets[set].entries[i] {
+                YACCEntry::SINGLE {line_addr} if line_addr == modified_line => {
+                    replacement = Some(YACCEntry::INVALID);
                 },
-                YACCEntry::DOUBLE {sb_addr, block0, block1} => {
-                    if modified_sb == sb_addr {
-                        if modified_block == block0 {
-                            replacement = Some(YACCEntry::SINGLE {line_addr: (sb_addr << 2) | block1});
-                        } else if modified_block == block1 {
-                            replacement = Some(YACCEntry::SINGLE {line_addr: (sb_addr << 2) | block0});
-                        }
+                YACCEntry::DOUBLE {sb_addr, block0, block1} if modified_sb == sb_addr => {
+                    if modified_block == block0 {
+                        replacement = Some(YACCEntry::SINGLE {line_addr: (sb_addr << self.sb_shift) | block1});
+                    } else if modified_block == block1 {
+                        replacement = Some(YACCEntry::SINGLE {line_addr: (sb_addr << self.sb_shift) | block0});
                     }
                 },
-                YACCEntry::TRIO {sb_addr, block0, block1, block2} => {
-                    if modified_sb == sb_addr {
-                        if modified_block == block0 {
-                            replacement = Some(YACCEntry::DOUBLE {sb_addr, block0: block2, block1});
-                        } else if modified_block == block1 {
-                            replacement = Some(YACCEntry::DOUBLE {sb_addr, block0, block1: block2});
-                        } else if modified_block == block2 {
-                            replacement = Some(YACCEntry::DOUBLE {sb_addr, block0, block1});
-                        }
+                YACCEntry::TRIO {sb_addr, block0, block1, block2} if modified_sb == sb_addr => {
+                    if modified_block == block0 {
+                        replacement = Some(YACCEntry::DOUBLE {sb_addr, block0: block2, block1});
+                    } else if modified_block == block1 {
+                        replacement = Some(YACCEntry::DOUBLE {sb_addr, block0, block1: block2});
+                    } else if modified_block == block2 {
+                        replacement = Some(YACCEntry::DOUBLE {sb_addr, block0, block1});
                     }
                 },
-                YACCEntry::QUAD {sb_addr} => {
-                    if modified_sb == sb_addr {
-                        let remnants: Vec<u64> = (0..=3).filter(|&x| x != modified_block).collect();
+                YACCEntry::QUAD {sb_addr, block0, block1, block2, block3} if modified_sb == sb_addr => {
+                    let remnants: Vec<u64> = [block0, block1, block2, block3].into_iter().filter(|&x| x != modified_block).collect();
+                    if remnants.len() == 3 {
                         replacement = Some(YACCEntry::TRIO {sb_addr, block0: remnants[0], block1: remnants[1], block2: remnants[2]});
                     }
                 },
                 _ => ()
             }
             if let Some(rep) = replacement {
-                self.entries[i] = rep;
+                self.sets[set].entries[i] = rep;
                 return;
             }
         }
     }
 
-    /// Bumps an index to the back of the LRU list so that it is the most recently accessed.
-    fn update_lru_state(&mut self, accessed_index: usize) {
-        self.lru_state = self.lru_state.iter().filter_map(|&x| if x != accessed_index {Some(x)} else {None}).collect();
-        self.lru_state.push(accessed_index);
+    /// Checks whether a cached line still fits the entry it is in after a store changed its contents.
+    fn still_fits(&self, line_addr: u64, index: usize) -> bool {
+        let entry = self.sets[self.set_index(line_addr)].entries[index];
+        if self.shared_dictionaries {
+            return matches!(entry, YACCEntry::INVALID | YACCEntry::SINGLE {..}) || self.dictionary_fits(&entry.lines(self.sb_shift));
+        }
+        let size = self.stored_bytes(line_addr);
+        return match entry {
+            YACCEntry::DOUBLE {..} => size <= self.double_threshold,
+            YACCEntry::TRIO {..} | YACCEntry::QUAD {..} => size <= self.quad_threshold,
+            _ => true
+        };
+    }
+
+    /// Picks the entry of a full set to evict, by the replacement policy.
+    fn replacement_victim(&mut self, set: usize) -> usize {
+        return match self.replacement {
+            ReplacementPolicy::Lru => self.sets[set].lru_state.lru().expect("A full set has touched every way"),
+            ReplacementPolicy::Random => self.rng.gen_range(0..self.sets[set].entries.len()),
+            ReplacementPolicy::Srrip | ReplacementPolicy::Brrip => {
+                let rrpv = &mut self.sets[set].rrpv;
+                loop {
+                    if let Some(i) = rrpv.iter().position(|&x| x == RRPV_MAX) {return i;}
+                    for x in rrpv.iter_mut() {*x += 1;}
+                }
+            }
+        };
+    }
+
+    /// Updates an entry's replacement state after an access. A block joining an entry keeps the entry's prediction if
+    /// it was nearer than a fill's, since the entry's other blocks are still there.
+    fn touch(&mut self, set: usize, index: usize, access: EntryAccess) {
+        self.update_lru_state(set, index);
+        let fill_rrpv = match self.replacement {
+            ReplacementPolicy::Brrip if !self.rng.gen_ratio(1, BRRIP_LONG_FILLS) => RRPV_MAX,
+            _ => RRPV_MAX - 1
+        };
+        let rrpv = &mut self.sets[set].rrpv[index];
+        *rrpv = match access {
+            EntryAccess::Hit => 0,
+            EntryAccess::Join => (*rrpv).min(fill_rrpv),
+            EntryAccess::Fill => fill_rrpv
+        };
+    }
+
+    /// Bumps an index to the back of its set's LRU list so that it is the most recently accessed.
+    fn update_lru_state(&mut self, set: usize, accessed_index: usize) {
+        self.sets[set].lru_state.touch(accessed_index);
     }
 }
 
 impl Cache for YACC {
     fn read_byte(&mut self, byte_addr: u64) -> (u8, AccessSpeed) {
-        let requested_line_addr = byte_addr >> 6;
-        let requested_byte_offset = (byte_addr & 0b0111111) as usize;
+        let requested_line_addr = self.line_of(byte_addr);
+        let requested_byte_offset = (byte_addr & (self.line_size() as u64 - 1)) as usize;
         let speed = self.access(requested_line_addr);
-        return (self.memory.get_line(requested_line_addr)[requested_byte_offset],speed);
+        if speed == AccessSpeed::MISS {
+            if let Some(metadata) = &mut self.metadata {metadata.touch(requested_line_addr);}
+        }
+        self.record_access(byte_addr, AccessKind::Read, speed == AccessSpeed::HIT);
+        let byte = self.memory.get_line(requested_line_addr)[requested_byte_offset];
+        self.tick_randomizer();
+        return (byte, speed);
     }
 
     fn write_byte(&mut self, byte_addr: u64, data: u8) {
-        let requested_line_addr = byte_addr >> 6;
-        let requested_byte_offset = (byte_addr & 0b0111111) as usize;
+        let requested_line_addr = self.line_of(byte_addr);
+        let requested_byte_offset = (byte_addr & (self.line_size() as u64 - 1)) as usize;
+        let hit = self.trace.is_some() && self.is_line_cached(requested_line_addr).is_some();
         self.memory.get_line_mut(requested_line_addr)[requested_byte_offset] = data;
-        self.remove_line(requested_line_addr);
-        self.access(requested_line_addr);
+        self.reencode(requested_line_addr);
+        self.requantize(requested_line_addr);
+        match (self.write_policy, self.is_line_cached(requested_line_addr)) {
+            (WritePolicy::WriteBack, Some(i)) if self.still_fits(requested_line_addr, i) => {
+                self.touch(self.set_index(requested_line_addr), i, EntryAccess::Hit);
+            },
+            _ => {
+                self.remove_line(requested_line_addr);
+                self.access(requested_line_addr);
+            }
+        }
+        if self.write_policy == WritePolicy::WriteBack {self.dirty_lines.insert(requested_line_addr);}
+        if self.store_prefetch {self.access(requested_line_addr ^ 1);}
+        if let Some(metadata) = &mut self.metadata {metadata.record_write(requested_line_addr);}
+        self.record_access(byte_addr, AccessKind::Write, hit);
+        self.tick_randomizer();
+    }
+}
+
+/// A compressed structure outside the data array: per-line 32-bit write counters (as used by memory encryption),
+/// packed 16 to a 64-byte metadata block and cached in their own compressed set.
+/// Every store to a data line bumps its counter, and every data miss touches the line's metadata block.
+/// The counters are not attacker-readable, but their values determine how well a metadata block compresses.
+pub struct CompressedMetadata {
+    cache: YACC
+}
+
+impl CompressedMetadata {
+    pub fn new(comp: Compressor) -> CompressedMetadata {
+        CompressedMetadata {cache: YACC::new(comp, ASSOCIATIVITY)}
+    }
+
+    /// Returns the metadata line holding a data line's counter, along with the counter's byte offset in that line.
+    fn locate(data_line_addr: u64) -> (u64, u64) {
+        return (data_line_addr >> 4, (data_line_addr & 0b1111) * 4);
+    }
+
+    /// Returns the write counter of a data line.
+    pub fn counter(&self, data_line_addr: u64) -> u32 {
+        let (meta_line, offset) = CompressedMetadata::locate(data_line_addr);
+        let line = self.cache.peek_line(meta_line);
+        let offset = offset as usize;
+        return u32::from_le_bytes([line[offset], line[offset+1], line[offset+2], line[offset+3]]);
+    }
+
+    /// Bumps the write counter of a data line, which also brings its metadata block into the metadata cache.
+    pub fn record_write(&mut self, data_line_addr: u64) {
+        let (meta_line, offset) = CompressedMetadata::locate(data_line_addr);
+        let counter = self.counter(data_line_addr).wrapping_add(1);
+        for (i, byte) in counter.to_le_bytes().into_iter().enumerate() {
+            self.cache.write_byte((meta_line << 6) + offset + i as u64, byte);
+        }
+    }
+
+    /// Touches the metadata block of a data line. Returns whether the block was already cached.
+    pub fn touch(&mut self, data_line_addr: u64) -> AccessSpeed {
+        return self.cache.access(CompressedMetadata::locate(data_line_addr).0);
+    }
+
+    /// Returns the compressed size, in bits, of the metadata block holding a data line's counter.
+    pub fn block_bits(&self, data_line_addr: u64) -> u64 {
+        return self.cache.compress_bits(CompressedMetadata::locate(data_line_addr).0);
+    }
+}
+
+/// A small private (per-core), uncompressed, fully associative LRU cache in front of the shared compressed cache.
+/// It only tracks which lines it holds; data always comes from the shared cache's backing memory,
+/// so it only affects timing and which accesses reach the shared cache.
+pub struct PrivateCache {
+    capacity: usize,
+    lines: Vec<u64> // Least recently used first
+}
+
+impl PrivateCache {
+    pub fn new(capacity: usize) -> PrivateCache {
+        PrivateCache {
+            capacity,
+            lines: Vec::with_capacity(capacity)
+        }
+    }
+
+    /// Touches a line. Returns HIT if the line was present; otherwise inserts it (evicting the LRU line if full) and returns MISS.
+    pub fn access(&mut self, line_addr: u64) -> AccessSpeed {
+        if let Some(i) = self.lines.iter().position(|&x| x == line_addr) {
+            self.lines.remove(i);
+            self.lines.push(line_addr);
+            return AccessSpeed::HIT;
+        }
+        if self.capacity == 0 {return AccessSpeed::MISS;}
+        if self.lines.len() >= self.capacity {self.lines.remove(0);}
+        self.lines.push(line_addr);
+        return AccessSpeed::MISS;
+    }
+
+    /// Drops a line, if present.
+    pub fn invalidate(&mut self, line_addr: u64) {
+        self.lines.retain(|&x| x != line_addr);
+    }
+
+    /// Drops every line.
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+
+    pub fn capacity(&self) -> usize {return self.capacity;}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a line from its words, least significant byte first.
+    fn line(words: &[u32]) -> Vec<u8> {
+        return words.iter().flat_map(|word| word.to_le_bytes()).collect();
+    }
+
+    #[test]
+    fn large_bounded_dictionary_matches_cpack() {
+        let words = line(&[0x12345678, 0x12345678, 0x123456AA, 0x1234BBBB, 0, 0x05]);
+        let patterns = vec![PatternCode::XXXX, PatternCode::MMMM, PatternCode::MMMX, PatternCode::MMXX, PatternCode::ZZZZ, PatternCode::ZZZX];
+        assert_eq!(cpack_words(&words), patterns);
+        for replacement in [DictionaryReplacement::Fifo, DictionaryReplacement::Lru] {
+            assert_eq!(bounded_cpack_words(&words, 16, replacement), patterns);
+            assert_eq!(bounded_cpack_bits(&words, 16, replacement), 34 + 6 + 16 + 24 + 2 + 12);
+        }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn full_fifo_dictionary_evicts_the_oldest_push() {
+        // A, B, A, C, A: the match on the second A does not save it from being evicted by C.
+        let words = line(&[0xAAAA0001, 0xBBBB0001, 0xAAAA0001, 0xCCCC0001, 0xAAAA0001]);
+        assert_eq!(bounded_cpack_words(&words, 2, DictionaryReplacement::Fifo),
+            vec![PatternCode::XXXX, PatternCode::XXXX, PatternCode::MMMM, PatternCode::XXXX, PatternCode::XXXX]);
+        assert_eq!(bounded_cpack_bits(&words, 2, DictionaryReplacement::Fifo), 4 * 34 + 6);
+    }
+
+    #[test]
+    fn full_lru_dictionary_evicts_the_least_recent_match() {
+        // A, B, A, C, A: the match on the second A refreshes it, so C evicts B instead.
+        let words = line(&[0xAAAA0001, 0xBBBB0001, 0xAAAA0001, 0xCCCC0001, 0xAAAA0001]);
+        assert_eq!(bounded_cpack_words(&words, 2, DictionaryReplacement::Lru),
+            vec![PatternCode::XXXX, PatternCode::XXXX, PatternCode::MMMM, PatternCode::XXXX, PatternCode::MMMM]);
+        assert_eq!(bounded_cpack_bits(&words, 2, DictionaryReplacement::Lru), 3 * 34 + 2 * 6);
+    }
+
+    #[test]
+    fn evicted_words_no_longer_give_partial_matches() {
+        // The unrelated second word evicts the first, so the third only partially matches without a bound.
+        let words = line(&[0x12345678, 0xAAAA0001, 0x123456FF]);
+        assert_eq!(cpack_bits(&words), 34 + 34 + 16);
+        for replacement in [DictionaryReplacement::Fifo, DictionaryReplacement::Lru] {
+            assert_eq!(bounded_cpack_bits(&words, 1, replacement), 3 * 34);
+        }
+    }
+
+    #[test]
+    fn zero_words_take_dictionary_entries() {
+        let words = line(&[0xAAAA0001, 0, 0xAAAA0001]);
+        assert_eq!(cpack_bits(&words), 34 + 2 + 6);
+        for replacement in [DictionaryReplacement::Fifo, DictionaryReplacement::Lru] {
+            assert_eq!(bounded_cpack_words(&words, 1, replacement), vec![PatternCode::XXXX, PatternCode::ZZZZ, PatternCode::XXXX]);
+        }
+    }
+
+    #[test]
+    fn bdi_sizes_special_lines() {
+        assert_eq!(bdi_bits(&[0u8; 64]), 8);
+        let repeated: Vec<u8> = (0..8).flat_map(|_| 0x0123456789ABCDEFu64.to_le_bytes()).collect();
+        assert_eq!(bdi_bits(&repeated), 64);
+    }
+
+    #[test]
+    fn bdi_picks_the_smallest_base_and_delta() {
+        // 8-byte values one apart from a large base: base8-delta1 takes 8 + 8 * 1 bytes and a bit per value.
+        let wide: Vec<u8> = (0..8u64).flat_map(|i| (0x1000000000000000 + i).to_le_bytes()).collect();
+        assert!(bdi_fits(&wide, 8, 1, Endianness::Little));
+        assert_eq!(bdi_bits(&wide), 8 * (8 + 8) + 8);
+        // 4-byte values three apart: the 8-byte values they pair into are too far apart, so base4-delta1 wins.
+        let narrow = line(&(0..16).map(|i| 0x12345600 + 3 * i).collect::<Vec<u32>>());
+        assert!(!bdi_fits(&narrow, 8, 4, Endianness::Little));
+        assert!(bdi_fits(&narrow, 4, 1, Endianness::Little));
+        assert_eq!(bdi_bits(&narrow), 8 * (4 + 16) + 16);
+    }
+
+    #[test]
+    fn bdi_stores_values_near_zero_as_immediates() {
+        // Small words fit a delta from zero, so only the large words need to be near the explicit base.
+        let mixed = line(&(0..16).map(|i| if i % 2 == 0 {i} else {0xABCD0000 + i}).collect::<Vec<u32>>());
+        assert!(bdi_fits(&mixed, 4, 1, Endianness::Little));
+        assert_eq!(bdi_bits(&mixed), 8 * (4 + 16) + 16);
+    }
+
+    #[test]
+    fn bdi_stores_incompressible_lines_uncompressed() {
+        let mut state = 0x9E3779B97F4A7C15u64;
+        let random: Vec<u8> = (0..64).map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        }).collect();
+        assert!(BDI_ENCODINGS.iter().all(|&(base, delta)| !bdi_fits(&random, base, delta, Endianness::Little)));
+        assert_eq!(bdi_bits(&random), 512);
+    }
+
+    #[test]
+    fn bdi_reads_values_in_the_given_byte_order() {
+        // In memory, every word is 10 00 00 i. Big-endian, the words are 0x10000000 + i, one delta byte from the first;
+        // little-endian, the varying byte is the most significant one and no encoding fits.
+        let bytes: Vec<u8> = (0..16).flat_map(|i| [0x10, 0, 0, i]).collect();
+        assert_eq!(bdi_bits_in(&bytes, Endianness::Big), 8 * (4 + 16) + 16);
+        assert_eq!(bdi_bits_in(&bytes, Endianness::Little), 512);
+        assert_eq!(bdi_bits(&bytes), 512);
+    }
+
+    #[test]
+    fn fpc_zero_runs_hold_up_to_eight_words() {
+        // Two runs of 8 zero words, each stored once.
+        assert_eq!(fpc_bits(&[0u8; 64]), 2 * 6);
+        let patterns = fpc_words(&line(&[0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0]));
+        assert_eq!(patterns[..3], [FpcPattern::ZeroRun, FpcPattern::ZeroRunContinued, FpcPattern::SignExtended4]);
+        // A nonzero word ends a run, and a ninth zero word starts a new one.
+        assert_eq!(patterns[3..].iter().filter(|&&p| p == FpcPattern::ZeroRun).count(), 2);
+        assert_eq!(fpc_bits(&line(&[0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0])), 6 + 7 + 6 + 6);
+    }
+
+    #[test]
+    fn fpc_picks_the_smallest_pattern_for_each_word() {
+        let words = [3, -3i32 as u32, 100, -100i32 as u32, 0x1234, 0xFFFF8000, 0x56780000, 0x00050003, 0x7F7F7F7F, 0x12345678];
+        assert_eq!(fpc_words(&line(&words)), vec![
+            FpcPattern::SignExtended4, FpcPattern::SignExtended4, FpcPattern::SignExtended8, FpcPattern::SignExtended8,
+            FpcPattern::SignExtended16, FpcPattern::SignExtended16, FpcPattern::PaddedHalfword,
+            FpcPattern::TwoSignExtendedBytes, FpcPattern::RepeatedBytes, FpcPattern::Uncompressed
+        ]);
+        assert_eq!(fpc_bits(&line(&words)), 2 * 7 + 2 * 11 + 4 * 19 + 11 + 35);
+    }
+
+    #[test]
+    fn fpc_stores_incompressible_lines_uncompressed() {
+        let words: Vec<u32> = (0..16).map(|i| 0x12345678u32.wrapping_mul(i + 1) | 0x01010101).collect();
+        assert!(fpc_words(&line(&words)).iter().all(|&p| p == FpcPattern::Uncompressed));
+        assert_eq!(fpc_bits(&line(&words)), 16 * 35);
+        assert_eq!(fpc_bytes(&line(&words)), 70);
+    }
+
+    #[test]
+    fn endianness_converts_words() {
+        let word = [0x78, 0x56, 0x34, 0x12];
+        assert_eq!(Endianness::Little.word_bytes(word), word);
+        assert_eq!(Endianness::Big.word_bytes(word), [0x12, 0x34, 0x56, 0x78]);
+        assert_eq!(Endianness::Big.word_bytes(Endianness::Big.word_bytes(word)), word);
+        assert_eq!(Endianness::Big.line_bytes(&[1, 2, 3, 4, 5, 6, 7, 8]), vec![4, 3, 2, 1, 8, 7, 6, 5]);
+        assert_eq!(Endianness::Little.line_bytes(&[1, 2, 3, 4, 5, 6, 7, 8]), vec![1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!((0..4).map(|i| Endianness::Little.byte_offset(i)).collect::<Vec<usize>>(), vec![0, 1, 2, 3]);
+        assert_eq!((0..4).map(|i| Endianness::Big.byte_offset(i)).collect::<Vec<usize>>(), vec![3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn cpack_reads_words_in_the_cache_byte_order() {
+        // 0x12345678 and 0x123456AA stored big-endian: read in that order, the second word matches the first's upper three
+        // bytes; read little-endian, the words are 0x78563412 and 0xAA563412, whose upper bytes differ.
+        let stored = [0x12, 0x34, 0x56, 0x78, 0x12, 0x34, 0x56, 0xAA];
+        for (endianness, bits) in [(Endianness::Big, 34 + 16 + 14 * 2), (Endianness::Little, 34 + 34 + 14 * 2)] {
+            let mut cache = YACC::new(Compressor::CPACK, ASSOCIATIVITY);
+            cache.set_endianness(endianness);
+            for (addr, &byte) in stored.iter().enumerate() {cache.write_byte(addr as u64, byte);}
+            assert_eq!(cache.compress_bits(0), bits);
+        }
+        assert_eq!(cpack_words(&Endianness::Big.line_bytes(&stored)), vec![PatternCode::XXXX, PatternCode::MMMX]);
+    }
+
+    /// Builds an LRU list of the given ways by touching them in order, so the first is the least recently used.
+    fn lru_list(ways: usize, touched: &[usize]) -> LruList {
+        let mut list = LruList::new(ways);
+        for &way in touched {list.touch(way);}
+        assert_eq!(list.check(), Ok(()));
+        return list;
+    }
+
+    #[test]
+    fn lru_list_keeps_touch_order() {
+        let list = lru_list(8, &[3, 1, 4]);
+        assert_eq!(list.iter().collect::<Vec<usize>>(), vec![3, 1, 4]);
+        assert_eq!((list.lru(), list.len()), (Some(3), 3));
+        assert_eq!((list.age(3), list.age(4), list.age(0)), (Some(2), Some(0), None));
+        assert_eq!(LruList::new(8).lru(), None);
+    }
+
+    #[test]
+    fn touching_the_head_makes_the_next_way_least_recent() {
+        let mut list = lru_list(4, &[0, 1, 2, 3]);
+        list.touch(0);
+        assert_eq!(list.check(), Ok(()));
+        assert_eq!(list.iter().collect::<Vec<usize>>(), vec![1, 2, 3, 0]);
+        assert_eq!((list.lru(), list.len()), (Some(1), 4));
+    }
+
+    #[test]
+    fn touching_the_tail_changes_nothing() {
+        let mut list = lru_list(4, &[0, 1, 2, 3]);
+        list.touch(3);
+        assert_eq!(list.check(), Ok(()));
+        assert_eq!(list.iter().collect::<Vec<usize>>(), vec![0, 1, 2, 3]);
+        assert_eq!(list.len(), 4);
+    }
+
+    #[test]
+    fn touching_a_middle_way_moves_it_to_the_tail() {
+        let mut list = lru_list(4, &[0, 1, 2, 3]);
+        list.touch(2);
+        assert_eq!(list.check(), Ok(()));
+        assert_eq!(list.iter().collect::<Vec<usize>>(), vec![0, 1, 3, 2]);
+    }
+
+    #[test]
+    fn evicting_through_the_lru_list_replaces_ways_oldest_first() {
+        // A full set evicts its least recently used way and touches it for the incoming line.
+        let mut list = lru_list(3, &[0, 1, 2]);
+        list.touch(0);
+        let mut evicted = Vec::new();
+        for _ in 0..4 {
+            let victim = list.lru().unwrap();
+            evicted.push(victim);
+            list.touch(victim);
+            assert_eq!(list.check(), Ok(()));
+        }
+        assert_eq!(evicted, vec![1, 2, 0, 1]);
+    }
+
+    #[test]
+    fn a_single_way_list_is_both_head_and_tail() {
+        let mut list = lru_list(1, &[0]);
+        list.touch(0);
+        assert_eq!(list.check(), Ok(()));
+        assert_eq!((list.lru(), list.len(), list.age(0)), (Some(0), 1, Some(0)));
+        list.clear();
+        assert_eq!(list.check(), Ok(()));
+        assert_eq!((list.lru(), list.len()), (None, 0));
+        list.touch(0);
+        assert_eq!(list.iter().collect::<Vec<usize>>(), vec![0]);
+    }
+}
@@ -0,0 +1,1657 @@
+//! The experiments the simulator was built for, one function each, along with the work behind the command line.
+
+use crate::attacker::{AttackPipeline, KnowledgeState, LastByteStep, PartialWord, PipelineControl, SecondByteStep, attack_yacc_cpack_4byte_secret, attack_yacc_cpack_8byte_secret, attack_yacc_cpack_with_retries, evict_and_reload_yacc_cpack_secret, AttackConfig, AttackLayout, AttackPhase, AttackStats, FillerStrategy, FlushLineContents, FlushSet, InterferenceMitigation, summarize, Summary, PrimeMethod, ProbeAddressPolicy, ProbeDiagnosis, ProbeOracle, ProbeOutcomes, ProbeStrategy, ProbeVoting, ReloadMethod, RetryPolicy, SecretStructure, StealthBudget, ThresholdSource};
+#[cfg(not(feature = "no-oracle"))]
+use crate::attacker::{attack_brute_force, attack_ground_truth};
+use crate::structures::{ASSOCIATIVITY, Compressor, DictionaryReplacement, Endianness, LINE_SIZE, LINE_SIZES, ReencodingDefense, RegionGranularity, ReplacementPolicy, WritePolicy, YACC};
+use crate::victim::{BackgroundNoiseConfig, BufferLayout, CoTenantConfig, CrossCoreConfig, HeaderLine, HeapConfig, ScheduledActivity, SecretBytes, SecretDistribution, SecretPlacement, VictimActivity, VictimCacheStats, VictimInterface, VictimProgramYACC, BUFFER_SIZE};
+use crate::clock::LatencyModel;
+use crate::scenarios::{metadata_counter_leak, CrossCoreScenario, HeapScenario, MultiTenantCampaign};
+use crate::detector::MissRateDetector;
+use crate::cost::CostModel;
+use crate::aliasing::CacheGeometry;
+use crate::bundle::ResultBundle;
+use crate::experiment::{Experiment, IterationPlan};
+use crate::config::{load_config, write_results, CacheConfig, ExperimentConfig, VictimConfig};
+use crate::traces::{load_trace, replay_on, report_trace_performance, BenignWorkload};
+use crate::fingerprint::FingerprintDb;
+use crate::output::{export_access_trace, write_output};
+use crate::determinism::check_determinism;
+use crate::heatmap::LeakageHeatmap;
+use crate::defenses::{RandomizedIndexing, SizeQuantization};
+use crate::dish::{CacheArchitecture, DISH};
+use crate::sweep::{export_sweep, run_sweep, SweepGrid};
+use crate::soak::soak;
+use crate::predictor::PredictorReport;
+use crate::session::AttackSession;
+use crate::replay::{export_cast, export_frames};
+use crate::statistics::StoppingRule;
+use crate::conformance::{check_conformance, ConformanceSpec};
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+/// Attacks iterations random secrets and draws where the probes and failures fall across the secret's bytes.
+pub fn map_leakage(secret_size: usize, iterations: usize, out: &str) {
+    let config = ExperimentConfig {iterations, victim: VictimConfig {secret_size, ..VictimConfig::default()}, ..ExperimentConfig::default()};
+    let heatmap = LeakageHeatmap::from_rows(&Experiment::from_config(&config).run().rows);
+    print!("{}", heatmap.render());
+    match heatmap.export(out) {
+        Ok(()) => println!("Wrote the heatmap to {}", out),
+        Err(e) => println!("Could not write the heatmap to {}: {}", out, e)
+    }
+}
+
+/// Checks that a seeded campaign replays bit for bit, serially and in parallel, and panics on the first iteration that does not.
+pub fn self_check(config: ExperimentConfig, seed: u64, iterations: usize) {
+    let config = ExperimentConfig {iterations, ..config};
+    let mismatches = check_determinism(&config, seed);
+    if let Some(first) = mismatches.first() {
+        println!("Iteration {} ran serially:\n{}\nand in parallel:\n{}", first.iteration, first.serial, first.parallel);
+    }
+    assert!(mismatches.is_empty(), "{} of {} iterations from seed {} did not replay identically", mismatches.len(), iterations, seed);
+    println!("All {} iterations from seed {} replayed identically", iterations, seed);
+}
+
+/// Runs a seeded campaign for a long time, printing a health check every check_every iterations and writing them all to out,
+/// then panics if any invariant failed, any iteration did not replay, or memory grew.
+pub fn soak_test(config: ExperimentConfig, seed: u64, iterations: usize, check_every: usize, out: &str) {
+    let report = soak(&config, seed, iterations, check_every, |check| println!("{}", check.render()));
+    match report.export(out) {
+        Ok(()) => println!("Wrote the health report to {}", out),
+        Err(e) => println!("Could not write the health report to {}: {}", out, e)
+    }
+    let problems = report.problems();
+    for problem in &problems {
+        println!("Problem: {}", problem);
+    }
+    assert!(problems.is_empty(), "{} problems in {} iterations from seed {}", problems.len(), iterations, seed);
+    println!("All {} iterations from seed {} stayed healthy", iterations, seed);
+}
+
+pub fn test_4_byte_attack() {
+    let mut victim = VictimProgramYACC::new(4, Compressor::CPACK, true);
+    let results = attack_yacc_cpack_4byte_secret(&mut victim, true);
+    println!("{:#?}", results);
+}
+
+pub fn test_8_byte_attack() {
+    let mut victim = VictimProgramYACC::new(8, Compressor::CPACK, true);
+    let results = attack_yacc_cpack_8byte_secret(&mut victim, true);
+    println!("{:#?}", results);
+}
+
+/// Runs a campaign (100 default iterations unless a results file supplies the config) and writes a bundle of its config,
+/// per-iteration results and summary to out.
+pub fn export_bundle(config_from: Option<&str>, out: &str, redact_secrets: bool) {
+    let config = match config_from {
+        Some(path) => match load_config(path) {
+            Ok(config) => config,
+            Err(e) => {
+                println!("Could not read a configuration from {}: {}", path, e);
+                return;
+            }
+        },
+        None => ExperimentConfig {iterations: 100, ..ExperimentConfig::default()}
+    };
+    let bundle = ResultBundle::record(&config, redact_secrets);
+    match bundle.write(out) {
+        Ok(()) => println!("Bundled {} iterations ({} recovered) into {}", bundle.iterations.len(), bundle.summary.successes, out),
+        Err(e) => println!("Could not write {}: {}", out, e)
+    }
+}
+
+/// Parses a secret of whole words (4 to 64 bytes) written as hex, in memory order (so "DEADBEEF" puts 0xDE at the
+/// lowest address).
+pub fn parse_hex_secret(hex: &str) -> Result<Vec<u8>, String> {
+    let hex = hex.trim().trim_start_matches("0x");
+    if hex.is_empty() || hex.len() > 128 || !hex.len().is_multiple_of(8) {
+        return Err(format!("expected a multiple of 8 hex digits, up to 128, got {}", hex.len()));
+    }
+    return (0..hex.len()).step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i+2], 16).map_err(|e| format!("{:?} is not a hex byte: {}", &hex[i..i+2], e)))
+        .collect();
+}
+
+/// Runs one verbose attack (with the default retries) against a chosen secret, to reproduce a suspected corner case.
+pub fn attack_custom_secret(secret: Vec<u8>) {
+    let secret_size = secret.len();
+    let mut victim = VictimProgramYACC::new_with_custom_secret(secret, Compressor::CPACK, true);
+    let results = attack_yacc_cpack_with_retries(&mut victim, secret_size, &RetryPolicy::default(), true);
+    println!("{:#?}", results);
+}
+
+/// Attacks every secret in a file of hex secrets (one per line, in the format taken by attack --secret; blank lines and
+/// lines starting with '#' are skipped), and writes one CSV row per secret to out_path.
+/// Lines that do not parse are reported and left out.
+pub fn attack_secrets_file(path: &str, out_path: &str) -> std::io::Result<()> {
+    let text = std::fs::read_to_string(path)?;
+    let mut secrets: Vec<Vec<u8>> = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {continue;}
+        match parse_hex_secret(line) {
+            Ok(secret) => secrets.push(secret),
+            Err(e) => println!("Skipping line {} of {}: {}", number + 1, path, e)
+        }
+    }
+    let count = secrets.len();
+    let experiment = Experiment::new(CacheConfig::default(), RetryPolicy::default(), IterationPlan::single(count), move |cache, i| {
+        let mut victim = VictimProgramYACC::with_secret(secrets[i].clone(), cache.build_cache(), false);
+        cache.configure(&mut victim);
+        victim
+    });
+    let result = experiment.run();
+    let mut out = File::create(out_path)?;
+    writeln!(out, "secret,success,recovered,attempts,guesses_needed,victim_interactions")?;
+    let hex = |bytes: &[u8]| bytes.iter().map(|x| format!("{:02X}", x)).collect::<String>();
+    for row in &result.rows {
+        let stats = &row.stats;
+        writeln!(out, "{},{},{},{},{},{}", hex(&row.secret), stats.success, hex(&stats.secret), stats.attempts, stats.guesses_needed, stats.victim_interactions)?;
+    }
+    println!("Attacked {} secrets from {}: {} recovered, results written to {}", count, path, result.summary.successes, out_path);
+    Ok(())
+}
+
+/// Fingerprints a memory dump, writes the fingerprints to out_path and prints the top lines that could hide a secret
+/// of secret_size bytes.
+pub fn fingerprint_dump(path: &str, base: u64, secret_size: usize, top: usize, out_path: &str) -> std::io::Result<()> {
+    let db = FingerprintDb::load_dump(path, base)?;
+    db.export(out_path)?;
+    let candidates = db.search(secret_size);
+    println!("{} of {} lines could hide a {}-byte secret; fingerprints written to {}", candidates.len(), db.lines.len(), secret_size, out_path);
+    for (record, slack) in candidates.iter().take(top) {
+        let f = record.fingerprint;
+        println!("{:#X}: C-PACK {} bits ({} bits of slack), BDI {} bits, FPC {} bits", record.address, f.cpack, slack, f.bdi, f.fpc);
+    }
+    Ok(())
+}
+
+pub fn print_summary(results: &Summary) {
+    println!("Iterations: {}", results.iterations);
+    println!("Successes: {} ({:.2}%)", results.successes, results.success_rate() * 100.0);
+    println!("First-try successes: {}", results.first_try_successes);
+    println!("Successes after a retry: {}", results.retry_successes);
+    println!("Guesses needed: {}", results.guesses_needed);
+    println!("Bytes written to the victim buffer: {}", results.bytes_written_to_victim);
+    println!("Bytes read from the victim buffer: {}", results.bytes_read_from_victim);
+    println!("Lines loaded directly by the attacker: {}", results.attacker_cache_lines_loaded);
+    println!("Number of set evictions performed by the attacker: {}", results.set_evictions);
+    for (name, spread) in results.distributions.named() {
+        println!("{} per attack: mean {:.2} (95% CI {:.2} to {:.2}), median {:.1}, stddev {:.2}, min {}, max {}",
+                 name, spread.mean, spread.ci95.0, spread.ci95.1, spread.median, spread.stddev, spread.min, spread.max);
+    }
+    println!("Flush accesses that hit leftover lines: {}", results.flush_hits);
+    println!("Victim API interactions: {} ({} of them writes)", results.victim_interactions, results.victim_write_calls);
+    println!("Ticks spent idle to stay stealthy: {}", results.idle_ticks);
+    println!("Probes skipped for read-only bytes: {}", results.unwritable_probes);
+    println!("Heap chunks allocated while grooming: {}", results.heap_allocations);
+    println!("Dirty lines written back: {}", results.writebacks);
+    if results.early_aborts > 0 {println!("Attempts given up after the leading shorts: {}", results.early_aborts);}
+    if results.budget_exhaustions > 0 {println!("Attacks stopped by the victim's access budget: {}", results.budget_exhaustions);}
+    let oracles = &results.oracle_report;
+    if oracles.probes > 0 {
+        println!("Probes read through both oracles: {} ({} disagreements)", oracles.probes, oracles.disagreements);
+        println!("Latency oracle: {:?} (accuracy {:.4}%)", oracles.latency, oracles.latency.accuracy() * 100.0);
+        println!("Residency oracle: {:?} (accuracy {:.4}%)", oracles.residency, oracles.residency.accuracy() * 100.0);
+    }
+    for phase in [AttackPhase::LeadingShort, AttackPhase::SecondByte, AttackPhase::LastByte] {
+        let outcomes = &results.probe_outcomes[phase as usize];
+        println!("{:?} probes: {:?} (false-positive rate {:.4}%)", phase, outcomes, outcomes.false_positive_rate() * 100.0);
+        let cost = &results.phase_costs[phase as usize];
+        println!("{:?} cost: {} bytes written in {} write calls, {} bytes read, {} set evictions, {} attacker lines loaded",
+                 phase, cost.bytes_written_to_victim, cost.victim_write_calls, cost.bytes_read_from_victim, cost.set_evictions, cost.attacker_cache_lines_loaded);
+        let sizes = &results.size_histograms[phase as usize];
+        println!("{:?} secret line sizes in bits (size: probes): {:?}", phase, sizes.counts);
+        if sizes.outside_window > 0 {
+            println!("WARNING: {} of {} {:?} probes left the secret line outside its intended size window", sizes.outside_window, sizes.samples(), phase);
+        }
+    }
+    let validation = &results.phase_costs[AttackPhase::Validation as usize];
+    println!("Validation cost: {} guesses in {} victim calls", validation.guesses, validation.victim_interactions);
+    for model in CostModel::ALL {
+        let cost = model.total(results);
+        let per_attack = results.iterations.max(1) as f64;
+        println!("Cost per attack for a {} attacker: {:.0} cycles, {:.1} syscalls, {:.1} network round trips",
+                 model.name, cost.cycles as f64 / per_attack, cost.syscalls as f64 / per_attack, cost.round_trips as f64 / per_attack);
+    }
+}
+
+/// Runs the campaign at every point of the grid in parallel, prints a line per point and writes them all to out as CSV.
+pub fn sweep_attacks(grid: &SweepGrid, out: &str) {
+    let points = grid.points().len();
+    println!("Sweeping {} points...", points);
+    let rows = run_sweep(grid);
+    for row in &rows {
+        let point = &row.point;
+        println!("{}-byte secrets, {:?}, {} ways, {:?}: {} of {} attacks succeeded, {:.1} probes per attack",
+                 point.secret_size, point.compressor, point.associativity, point.replacement, row.successes, point.iterations, row.probes_per_attack);
+    }
+    match export_sweep(out, &rows) {
+        Ok(()) => println!("Wrote {} rows to {}", rows.len(), out),
+        Err(e) => println!("Could not write {}: {}", out, e)
+    }
+}
+
+/// Runs a campaign in parallel groups, prints its summary and timing, and writes the configuration and summary to
+/// results_path so the campaign can be re-run with the rerun subcommand. With an output path, every run's stats go there
+/// too, as write_output does.
+pub fn run_campaign(config: &ExperimentConfig, subdivisions: usize, window: usize, stop: Option<StoppingRule>, results_path: &str, output: Option<&str>) {
+    let mut experiment = Experiment::from_config(config);
+    experiment.plan.groups = subdivisions;
+    println!("Running {} iterations in {} parallel groups...", config.iterations, subdivisions);
+    let result = experiment.run_windowed(window, stop, |progress| println!("{}", progress.render()));
+    println!();
+    print_summary(&result.summary);
+    result.metadata.print();
+    if let Err(e) = result.metadata.export("campaign_metadata.csv") {println!("Could not write campaign_metadata.csv: {}", e);}
+    if let Err(e) = write_results(results_path, config, &result.summary) {println!("Could not write {}: {}", results_path, e);}
+    if let Some(path) = output {
+        if let Err(e) = write_output(path, &result) {println!("Could not write {}: {}", path, e);}
+    }
+}
+
+/// Runs the campaign saved in the session file at path, or starts one from config and seed if there is none, and prints
+/// its summary once every iteration has finished.
+pub fn run_session(path: &str, config: ExperimentConfig, seed: u64, output: Option<&str>) {
+    let mut session = if Path::new(path).exists() {
+        match AttackSession::load(path) {
+            Ok(session) => session,
+            Err(e) => return println!("Could not read the session in {}: {}", path, e)
+        }
+    } else {AttackSession::new(config, seed)};
+    if !session.finished.is_empty() || session.in_flight.is_some() {
+        println!("Resuming the session in {} at iteration {} of {}", path, session.finished.len(), session.config.iterations);
+    }
+    let every = (session.config.iterations / 10).max(1);
+    let result = match session.run(path, |done, total| if done % every == 0 {println!("{} of {} iterations finished", done, total)}) {
+        Ok(result) => result,
+        Err(e) => return println!("Could not save the session to {}: {}", path, e)
+    };
+    print_summary(&result.summary);
+    result.metadata.print();
+    if let Some(output) = output {
+        if let Err(e) = write_output(output, &result) {println!("Could not write {}: {}", output, e);}
+    }
+}
+
+/// Attacks the victim built from seed with LRU snapshots turned on, and writes its probes as replay frames: an asciinema
+/// recording if out ends in .cast, and JSON otherwise.
+pub fn export_replay(secret_size: usize, seed: u64, out: &str, seconds_per_frame: f64) {
+    let mut victim = VictimConfig {secret_size, ..VictimConfig::default()}.build_seeded(&CacheConfig::default(), seed);
+    let policy = RetryPolicy {first: AttackConfig {record_lru: true, ..AttackConfig::default()}, alternates: Vec::new()};
+    let stats = attack_yacc_cpack_with_retries(&mut victim, secret_size, &policy, false);
+    if stats.probe_trace.is_empty() {return println!("The attack recorded no probes (snapshots are ground truth, which no-oracle builds leave out)");}
+    let written = if out.ends_with(".cast") {export_cast(out, &stats.probe_trace, seconds_per_frame)} else {export_frames(out, &stats.probe_trace)};
+    match written {
+        Ok(()) => println!("Wrote {} frames to {}", stats.probe_trace.len(), out),
+        Err(e) => println!("Could not write {}: {}", out, e)
+    }
+}
+
+/// Attacks the victim built from seed with the cache access trace on, and writes every access to out as
+/// newline-delimited JSON, for working out why that attack went the way it did.
+pub fn trace_attack(secret_size: usize, seed: u64, out: &str) {
+    let config = ExperimentConfig::default();
+    let mut victim = VictimConfig {secret_size, ..config.victim}.build_seeded(&config.cache, seed);
+    victim.enable_cache_trace();
+    let stats = attack_yacc_cpack_with_retries(&mut victim, secret_size, &config.attack, false);
+    let trace = victim.take_cache_trace();
+    match export_access_trace(out, &trace) {
+        Ok(()) => println!("The attack {} after {} probes; wrote {} accesses to {}", if stats.success {"succeeded"} else {"failed"}, stats.set_evictions, trace.len(), out),
+        Err(e) => println!("Could not write {}: {}", out, e)
+    }
+}
+
+/// Runs the conformance checks on every compressor and replacement policy the simulator models, with one set and with
+/// several, and panics if any model fails.
+pub fn check_cache_models() {
+    let mut failed = Vec::new();
+    for compressor in [Compressor::CPACK, Compressor::BDI, Compressor::FPC, Compressor::NONE] {
+        for policy in [ReplacementPolicy::Lru, ReplacementPolicy::Random, ReplacementPolicy::Srrip, ReplacementPolicy::Brrip] {
+            for sets in [1, 4] {
+                let make = || {
+                    let mut cache = YACC::new_with_geometry(compressor, sets, ASSOCIATIVITY);
+                    cache.set_replacement_policy(policy);
+                    cache.reseed(0);
+                    cache
+                };
+                let report = check_conformance(&ConformanceSpec::of(&make()), make);
+                println!("{:?} with {:?} replacement, {} sets:", compressor, policy, sets);
+                report.print();
+                if !report.passed() {failed.push((format!("{:?}", compressor), policy, sets));}
+            }
+        }
+    }
+    for policy in [ReplacementPolicy::Lru, ReplacementPolicy::Random, ReplacementPolicy::Srrip, ReplacementPolicy::Brrip] {
+        for sets in [1, 4] {
+            let make = || {
+                let mut cache = DISH::new_with_geometry(sets, ASSOCIATIVITY).into_cache();
+                cache.set_replacement_policy(policy);
+                cache.reseed(0);
+                cache
+            };
+            let report = check_conformance(&ConformanceSpec::of(&make()), make);
+            println!("DISH with {:?} replacement, {} sets:", policy, sets);
+            report.print();
+            if !report.passed() {failed.push(("DISH".to_string(), policy, sets));}
+        }
+    }
+    assert!(failed.is_empty(), "Models failed the conformance checks: {:?}", failed);
+    println!("Every cache model passed the conformance checks");
+}
+
+/// Runs 1000 attacks with each way of generating the attack strings' filler values, and reports how many succeeded.
+pub fn compare_filler_strategies(secret_size: usize) {
+    let iterations = 1000;
+    let strategies = [
+        FillerStrategy::SequentialLow,
+        FillerStrategy::HighEntropyRandom,
+        FillerStrategy::ExcludedRange {start: 0xE0, end: 0xFF}
+    ];
+    for filler in strategies {
+        let policy = RetryPolicy {first: AttackConfig {filler, ..AttackConfig::default()}, alternates: Vec::new()};
+        let successes = (0..iterations).into_par_iter().filter(|_|
+            attack_yacc_cpack_with_retries(&mut VictimProgramYACC::new(secret_size, Compressor::CPACK, false), secret_size, &policy, false).success
+        ).count();
+        println!("{:?}: {} of {} attacks succeeded", filler, successes, iterations);
+    }
+}
+
+/// Runs a batch of attacks and writes how the surviving candidate set shrinks with every probe.
+/// decay_runs.csv holds one row per (run, probe); decay_average.csv holds the population mean per probe,
+/// where runs that have already finished contribute their final value.
+pub fn simulate_candidate_decay(secret_size: usize, runs: usize) -> std::io::Result<()> {
+    let policy = RetryPolicy::no_retries();
+    let results: Vec<AttackStats> = (0..runs).into_par_iter().map(|_|
+        attack_yacc_cpack_with_retries(&mut VictimProgramYACC::new(secret_size, Compressor::CPACK, false), secret_size, &policy, false)
+    ).collect();
+    let mut per_run = File::create("decay_runs.csv")?;
+    writeln!(per_run, "run,probe,log2_candidates")?;
+    for (run, stats) in results.iter().enumerate() {
+        for (probe, log2) in stats.candidate_log2.0.iter().enumerate() {
+            writeln!(per_run, "{},{},{}", run, probe + 1, log2)?;
+        }
+    }
+    let longest = results.iter().map(|x| x.candidate_log2.0.len()).max().unwrap_or(0);
+    let mut average = File::create("decay_average.csv")?;
+    writeln!(average, "probe,mean_log2_candidates")?;
+    for probe in 0..longest {
+        let total: f64 = results.iter().map(|x| {
+            let series = &x.candidate_log2.0;
+            *series.get(probe).or(series.last()).unwrap_or(&0.0)
+        }).sum();
+        writeln!(average, "{},{}", probe + 1, total / runs as f64)?;
+    }
+    println!("Wrote candidate decay for {} runs ({} probes max) to decay_runs.csv and decay_average.csv", runs, longest);
+    Ok(())
+}
+
+/// Runs a single attack with the victim's audit log enabled and writes the log to audit_log.csv.
+pub fn record_audit_trace(secret_size: usize) -> std::io::Result<()> {
+    let mut victim = VictimProgramYACC::new(secret_size, Compressor::CPACK, false);
+    victim.enable_audit_log();
+    let results = attack_yacc_cpack_with_retries(&mut victim, secret_size, &RetryPolicy::no_retries(), false);
+    victim.export_audit_log(&mut File::create("audit_log.csv")?)?;
+    println!("Attack {} after {} victim API calls; audit log written to audit_log.csv",
+             if results.success {"succeeded"} else {"failed"}, victim.audit_log().len());
+    Ok(())
+}
+
+/// Runs one attack with the secret line's compressibility timeline enabled and writes it to compressibility_timeline.csv,
+/// showing the attack string pushing the line back and forth across the co-allocation threshold.
+pub fn record_compressibility_timeline(secret_size: usize) -> std::io::Result<()> {
+    let mut victim = VictimProgramYACC::new(secret_size, Compressor::CPACK, false);
+    victim.enable_compressibility_timeline();
+    let results = attack_yacc_cpack_with_retries(&mut victim, secret_size, &RetryPolicy::no_retries(), false);
+    victim.export_compressibility_timeline(&mut File::create("compressibility_timeline.csv")?)?;
+    let timeline = victim.compressibility_timeline();
+    let crossings = timeline.windows(2).filter(|w| w[0].fits_double != w[1].fits_double).count();
+    println!("Attack {} after {} writes, which crossed the co-allocation threshold {} times; timeline written to compressibility_timeline.csv",
+             if results.success {"succeeded"} else {"failed"}, timeline.len(), crossings);
+    Ok(())
+}
+
+/// Runs the cross-core scenario with and without inclusion and the attacker's L1 eviction set.
+pub fn simulate_cross_core() {
+    for inclusive in [true, false] {
+        for evict_private_cache in [true, false] {
+            CrossCoreScenario {
+                caches: CrossCoreConfig {l1_lines: 8, inclusive},
+                evict_private_cache,
+                iterations: 20,
+                ..CrossCoreScenario::default()
+            }.run_and_report();
+        }
+    }
+}
+
+/// Estimates attack throughput against many tenants at once, with more and more tenants crowding the same cache sets.
+pub fn simulate_multi_tenant_campaigns() {
+    for tenants in [4, 16, 64] {
+        for cost in [CostModel::CO_RESIDENT, CostModel::REMOTE] {
+            MultiTenantCampaign {tenants, sets: 16, cost, ..MultiTenantCampaign::default()}.run_and_report();
+        }
+    }
+}
+
+/// Attacks victims whose machine does its own work on a timer, with the clock running in cycles: a victim that rotates
+/// its buffer every period cycles, and one that reads its secret line every period cycles while serving other clients.
+pub fn simulate_scheduled_activity(secret_size: usize, iterations: usize) {
+    for activity in [VictimActivity::Relocate, VictimActivity::ReadSecret] {
+        for period in [1_000_000, 10_000_000, 100_000_000, 1_000_000_000] {
+            let config = ExperimentConfig {
+                iterations,
+                victim: VictimConfig {
+                    secret_size,
+                    latency: LatencyModel::CYCLES,
+                    activities: vec![ScheduledActivity {period, activity}],
+                    ..VictimConfig::default()
+                },
+                ..ExperimentConfig::default()
+            };
+            let summary = summarize(&config.run());
+            println!("{:?} every {} cycles: {} of {} attacks succeeded", activity, period, summary.successes, summary.iterations);
+        }
+    }
+}
+
+/// Attacks 4- and 8-byte secrets on a cache that re-encodes lines on writeback: with a rotating nonce alone, and with
+/// compressed sizes also padded to the next size class with increasing probability.
+pub fn attack_reencoding_defense(iterations: usize) {
+    let defenses = [None, Some(ReencodingDefense::RotatingNonce), Some(ReencodingDefense::PaddedSizes {pad_probability: 0.01}),
+                    Some(ReencodingDefense::PaddedSizes {pad_probability: 0.1}), Some(ReencodingDefense::PaddedSizes {pad_probability: 0.5})];
+    for secret_size in [4, 8] {
+        for reencoding in defenses {
+            let config = ExperimentConfig {
+                iterations,
+                cache: CacheConfig {reencoding, ..CacheConfig::default()},
+                victim: VictimConfig {secret_size, ..VictimConfig::default()},
+                attack: RetryPolicy::default()
+            };
+            let summary = summarize(&config.run());
+            let false_negatives: usize = summary.probe_outcomes.iter().map(|x| x.false_negatives).sum();
+            println!("{}-byte secrets, {:?}: {} of {} attacks succeeded, {:.1} probes per attack, {} false negatives",
+                     secret_size, reencoding, summary.successes, summary.iterations,
+                     summary.set_evictions as f64 / iterations as f64, false_negatives);
+        }
+    }
+}
+
+/// Attacks victims that clamp their secret line's compressibility with canaries, next to victims that do not.
+/// A clamped secret line never drops under the co-allocation threshold, so the attack should do no better than chance, i.e.
+/// guessing one of the 255!/(255-n)! possible secrets. Also prints what clamping costs the victim.
+pub fn attack_clamped_victim(iterations: usize) {
+    for secret_size in [4, 8] {
+        let chance = 1.0 / (0..secret_size).map(|i| (255 - i) as f64).product::<f64>();
+        for clamp_compressibility in [false, true] {
+            let config = ExperimentConfig {
+                iterations,
+                victim: VictimConfig {secret_size, clamp_compressibility, ..VictimConfig::default()},
+                ..ExperimentConfig::default()
+            };
+            let summary = summarize(&config.run());
+            println!("{}-byte secrets, clamped: {}: {} of {} attacks succeeded (chance: {:.1e}), {:.1} probes per attack, {} unwritable",
+                     secret_size, clamp_compressibility, summary.successes, summary.iterations, chance,
+                     summary.set_evictions as f64 / iterations as f64, summary.unwritable_probes);
+        }
+        let mut victim = VictimProgramYACC::new(secret_size, Compressor::CPACK, false);
+        victim.set_latency_model(LatencyModel::CYCLES);
+        let start = victim.now();
+        let words = victim.clamp_compressibility();
+        println!("  Overhead: {} canary words, {} fewer buffer bytes for the victim's data, {} stores taking {} cycles",
+                 words, words * 4, words * 4, victim.now() - start);
+    }
+}
+
+/// Attacks a cache that only co-allocates blocks with probability p, with an attacker that re-probes every negative
+/// result about 5/p times, so that a matching candidate is missed with probability about e^-5. The probe count should grow
+/// as 1/p. Also replays the benign workloads on the defended cache to show what the defense costs.
+pub fn attack_probabilistic_admission(iterations: usize) {
+    for p in [1.0f64, 0.5, 0.25, 0.1] {
+        let attempts = (5.0 / p).ceil() as usize;
+        let config = ExperimentConfig {
+            iterations,
+            cache: CacheConfig {admission_probability: Some(p), ..CacheConfig::default()},
+            attack: RetryPolicy {
+                first: AttackConfig {interference: InterferenceMitigation::ProbeRetries {attempts}, ..AttackConfig::default()},
+                alternates: Vec::new()
+            },
+            ..ExperimentConfig::default()
+        };
+        let summary = summarize(&config.run());
+        let probes = summary.set_evictions as f64 / iterations as f64;
+        println!("Admission probability {}: {} of {} attacks succeeded with {} attempts per probe, {:.0} probes per attack ({:.0} x p)",
+                 p, summary.successes, summary.iterations, attempts, probes, probes * p);
+        for workload in BenignWorkload::ALL {
+            let trace = workload.generate(16, 100000);
+            let mut cache = YACC::new(Compressor::CPACK, ASSOCIATIVITY);
+            cache.set_admission_probability(p);
+            let report = replay_on(&trace, cache, 16 * 64);
+            println!("  {:?}: hit rate {:.2}%, effective capacity {:.2} lines", workload, report.hit_rate() * 100.0, report.effective_capacity());
+        }
+    }
+}
+
+/// Attacks victims that mark the memory around their secret line as never compressed, at line, superblock and page
+/// granularity, and replays the benign workloads with the same units marked around one line of their working set, to
+/// give what each granularity costs against what it buys. Every granularity covers the secret line, so each one should
+/// stop the attack; coarser units only cost more capacity.
+pub fn sweep_uncompressed_regions(secret_size: usize, iterations: usize) {
+    let secret_line = 15 * 64;
+    for uncompressed_region in std::iter::once(None).chain(RegionGranularity::ALL.map(Some)) {
+        let config = ExperimentConfig {iterations, victim: VictimConfig {secret_size, uncompressed_region, ..VictimConfig::default()}, ..ExperimentConfig::default()};
+        let summary = summarize(&config.run());
+        println!("Uncompressed region {:?}: {} of {} attacks succeeded, {:.1} probes per attack",
+                 uncompressed_region, summary.successes, summary.iterations, summary.set_evictions as f64 / iterations as f64);
+        for workload in BenignWorkload::ALL {
+            let trace = workload.generate(16, 100000);
+            let mut cache = YACC::new(Compressor::CPACK, ASSOCIATIVITY);
+            let baseline = replay_on(&trace, YACC::new(Compressor::CPACK, ASSOCIATIVITY), 16 * 64);
+            if let Some(granularity) = uncompressed_region {
+                let region = granularity.cover(secret_line..secret_line + cache.line_size() as u64, &cache);
+                cache.mark_uncompressed(region);
+            }
+            let report = replay_on(&trace, cache, 16 * 64);
+            println!("  {:?}: hit rate {:.2}%, effective capacity {:.2} lines ({:.1}% lost)", workload, report.hit_rate() * 100.0,
+                     report.effective_capacity(), (1.0 - report.effective_capacity() / baseline.effective_capacity()) * 100.0);
+        }
+    }
+}
+
+/// Attacks secrets placed at the start, middle and end of every line of the superblock, to check that the attack does not
+/// depend on the secret ending the buffer.
+pub fn sweep_secret_offsets(secret_size: usize, iterations: usize) {
+    for line in (0..BUFFER_SIZE).step_by(64) {
+        for offset in [line, line + (64 - secret_size) / 8 * 4, line + 64 - secret_size] {
+            let config = ExperimentConfig {iterations, victim: VictimConfig {secret_size, secret_offset: Some(offset), ..VictimConfig::default()}, ..ExperimentConfig::default()};
+            let summary = summarize(&config.run());
+            println!("Secret at offset {:>3} (line {}, byte {:>2}): {} of {} attacks succeeded, {:.1} probes and {:.1} guesses per attack",
+                     offset, offset / 64, offset % 64, summary.successes, summary.iterations,
+                     summary.set_evictions as f64 / iterations as f64, summary.distributions.guesses_needed.mean);
+        }
+    }
+}
+
+/// Attacks heap-model victims end to end, grooming the heap first, with chunks landing next to the secret less and less often.
+pub fn simulate_heap_grooming(secret_size: usize, iterations: usize) {
+    for adjacency_probability in [0.5, 0.1, 0.02, 0.005] {
+        HeapScenario {secret_size, heap: HeapConfig {adjacency_probability}, iterations, ..HeapScenario::default()}.run_and_report();
+    }
+}
+
+/// Prints how the compressed size of a metadata block responds to the attacker's counter guesses.
+pub fn simulate_metadata_leak() {
+    let secret_writes = 37;
+    println!("Victim counter: {}", secret_writes);
+    for (guess, bits) in metadata_counter_leak(Compressor::CPACK, secret_writes, 48) {
+        println!("Attacker counter {:>2}: metadata block compresses to {} bits{}", guess, bits, if guess == secret_writes {" <- match"} else {""});
+    }
+}
+
+/// Attacks store-only victims, which never return buffer contents, by reloading lines with stores instead of reads.
+pub fn simulate_store_only_victims(secret_size: usize) {
+    let iterations = 100;
+    let policy = RetryPolicy {first: AttackConfig {reload: ReloadMethod::Store, ..AttackConfig::default()}, alternates: Vec::new()};
+    let results: Vec<AttackStats> = (0..iterations).into_par_iter().map(|_| {
+        let mut victim = VictimProgramYACC::new(secret_size, Compressor::CPACK, false);
+        victim.set_interface(VictimInterface::StoreOnly);
+        attack_yacc_cpack_with_retries(&mut victim, secret_size, &policy, false)
+    }).collect();
+    let successes = results.iter().filter(|x| x.success).count();
+    let reads: usize = results.iter().map(|x| x.bytes_read_from_victim).sum();
+    let writes: usize = results.iter().map(|x| x.bytes_written_to_victim).sum();
+    println!("Store-only victims: {} of {} attacks succeeded ({} bytes read, {:.1} bytes written on average)",
+             successes, iterations, reads, writes as f64 / iterations as f64);
+}
+
+/// Compares the number of victim interactions needed when attack strings are written byte by byte
+/// versus handed to the victim as a single message that it copies into the line.
+pub fn compare_prime_methods(secret_size: usize) {
+    let iterations = 100;
+    for prime in [PrimeMethod::ByteWrites, PrimeMethod::Memcpy] {
+        let policy = RetryPolicy {first: AttackConfig {prime, ..AttackConfig::default()}, alternates: Vec::new()};
+        let results: Vec<AttackStats> = (0..iterations).into_par_iter().map(|_|
+            attack_yacc_cpack_with_retries(&mut VictimProgramYACC::new(secret_size, Compressor::CPACK, false), secret_size, &policy, false)
+        ).collect();
+        let successes = results.iter().filter(|x| x.success).count();
+        let interactions: usize = results.iter().map(|x| x.victim_interactions).sum();
+        println!("{:?}: {} of {} attacks succeeded, {:.1} victim interactions on average",
+                 prime, successes, iterations, interactions as f64 / iterations as f64);
+    }
+}
+
+/// Sweeps how many non-secret words of the secret line hold leftover victim data rather than zeros.
+pub fn sweep_residual_data(secret_size: usize) {
+    let iterations = 100;
+    let policy = RetryPolicy::no_retries();
+    for patterned_words in [0, 1, 2, 4, 8, (LINE_SIZE - secret_size) / 4] {
+        let successes = (0..iterations).into_par_iter().filter(|_| {
+            let mut victim = VictimProgramYACC::new(secret_size, Compressor::CPACK, false);
+            victim.fill_residual_data(patterned_words);
+            attack_yacc_cpack_with_retries(&mut victim, secret_size, &policy, false).success
+        }).count();
+        println!("{:>2} patterned words: {} of {} attacks succeeded", patterned_words, successes, iterations);
+    }
+}
+
+/// Runs the 4-byte attack once for every leading short in shorts, with the two low bytes of the secret fixed,
+/// and reports every secret that was not recovered. Secrets outside the victim's usual assumptions
+/// (a zero byte or a repeated byte) are attacked too, but their failures are reported separately.
+/// Returns the secrets that failed even though they satisfy the assumptions.
+pub fn validate_exhaustive_shorts(low_bytes: [u8; 2], shorts: std::ops::RangeInclusive<u16>) -> Vec<Vec<u8>> {
+    let policy = RetryPolicy::no_retries();
+    let secrets: Vec<Vec<u8>> = shorts.map(|short| vec![low_bytes[0], low_bytes[1], (short & 0xFF) as u8, (short >> 8) as u8]).collect();
+    let failures: Vec<Vec<u8>> = secrets.par_iter().filter(|secret| {
+        let mut victim = VictimProgramYACC::new_with_custom_secret(secret.to_vec(), Compressor::CPACK, false);
+        let stats = attack_yacc_cpack_with_retries(&mut victim, 4, &policy, false);
+        !stats.success || stats.secret != **secret
+    }).cloned().collect();
+    let (expected, unexpected): (Vec<Vec<u8>>, Vec<Vec<u8>>) = failures.into_iter().partition(|secret| {
+        let unique: std::collections::HashSet<&u8> = secret.iter().collect();
+        secret.contains(&0) || unique.len() < secret.len()
+    });
+    println!("Attacked {} secrets: {} recovered, {} failures outside the victim's assumptions, {} unexpected failures",
+             secrets.len(), secrets.len() - expected.len() - unexpected.len(), expected.len(), unexpected.len());
+    for secret in &unexpected {println!("Unexpected failure: {:X?}", secret);}
+    return unexpected;
+}
+
+/// Attacks victims whose caches co-allocate at thresholds other than the stock 32 bytes, with an attacker that
+/// probes for the threshold first, and reports the thresholds it found and how often it recovered the secret.
+pub fn attack_unknown_thresholds(secret_size: usize, iterations: usize) {
+    let policy = RetryPolicy {
+        first: AttackConfig {threshold: ThresholdSource::Probed, ..AttackConfig::default()},
+        alternates: Vec::new()
+    };
+    for threshold in [24, 28, 32, 36, 40] {
+        let results: Vec<AttackStats> = (0..iterations).into_par_iter().map(|_| {
+            let mut victim = VictimProgramYACC::new(secret_size, Compressor::CPACK, false);
+            victim.set_coallocation_thresholds(threshold, threshold / 2);
+            attack_yacc_cpack_with_retries(&mut victim, secret_size, &policy, false)
+        }).collect();
+        let successes = results.iter().filter(|x| x.success).count();
+        let probed: std::collections::BTreeSet<Option<u64>> = results.iter().map(|x| x.probed_threshold).collect();
+        println!("{}B threshold: probed {:?}, {} of {} attacks succeeded", threshold, probed, successes, iterations);
+    }
+}
+
+/// Attacks victims on caches of other sizes of set and other thresholds than the stock 8 ways and 32 bytes, once with an
+/// attacker assuming the stock parameters and once with one that calibrates both first, and reports what the calibrating
+/// attacker found and how often each recovered the secret.
+pub fn attack_uncalibrated_caches(secret_size: usize, iterations: usize) {
+    let assuming = AttackConfig {threshold: ThresholdSource::Assumed(32), associativity: Some(ASSOCIATIVITY), ..AttackConfig::default()};
+    let calibrating = AttackConfig {threshold: ThresholdSource::Calibrated, ..AttackConfig::default()};
+    for associativity in [4, 8, 16] {
+        for threshold in [24, 28, 32] {
+            let cache = CacheConfig {associativity, double_threshold: threshold, quad_threshold: threshold / 2, ..CacheConfig::default()};
+            for attack in [&assuming, &calibrating] {
+                let config = ExperimentConfig {
+                    iterations,
+                    cache: cache.clone(),
+                    victim: VictimConfig {secret_size, ..VictimConfig::default()},
+                    attack: RetryPolicy {first: attack.clone(), alternates: Vec::new()}
+                };
+                let results = config.run();
+                let found: std::collections::BTreeSet<(Option<usize>, Option<u64>)> = results.iter().map(|x| (x.probed_capacity, x.probed_threshold)).collect();
+                println!("{} ways, {}B threshold, threshold {:?}: found (ways, bytes) {:?}, {} of {} attacks succeeded",
+                         associativity, threshold, attack.threshold, found, results.iter().filter(|x| x.success).count(), iterations);
+            }
+        }
+    }
+}
+
+/// Attacks victims whose cache reserves space for ECC in every compressed block, with an attacker that assumes the
+/// stock 32-byte threshold and with one that probes for it, and reports the probed thresholds and success rates.
+pub fn attack_ecc_overhead(secret_size: usize, iterations: usize) {
+    for threshold in [ThresholdSource::Assumed(32), ThresholdSource::Probed] {
+        let policy = RetryPolicy {first: AttackConfig {threshold, ..AttackConfig::default()}, alternates: Vec::new()};
+        for ecc_bytes in [0, 1, 2, 4, 8] {
+            let results: Vec<AttackStats> = (0..iterations).into_par_iter().map(|_| {
+                let mut victim = VictimProgramYACC::new(secret_size, Compressor::CPACK, false);
+                victim.set_ecc_overhead(ecc_bytes);
+                attack_yacc_cpack_with_retries(&mut victim, secret_size, &policy, false)
+            }).collect();
+            let successes = results.iter().filter(|x| x.success).count();
+            let probed: std::collections::BTreeSet<Option<u64>> = results.iter().map(|x| x.probed_threshold).collect();
+            println!("{}B of ECC, threshold {:?}: probed {:?}, {} of {} attacks succeeded",
+                     ecc_bytes, threshold, probed, successes, iterations);
+        }
+    }
+}
+
+/// Attacks victims that keep protocol header fields in their secret line read-only, and reports for each mask how often
+/// the secret is recovered and how many probes had to be skipped because no attack string could be written around it.
+pub fn attack_read_only_masks(secret_size: usize, iterations: usize) {
+    let masks: [(&str, Vec<(usize, u8)>); 5] = [
+        ("no read-only bytes", Vec::new()),
+        ("zero length field at 192", vec![(192, 0), (193, 0)]),
+        ("zero reserved word at 220", (220..224).map(|i| (i, 0)).collect()),
+        ("16 zero header bytes at 192", (192..208).map(|i| (i, 0)).collect()),
+        ("version byte 0x01 at 200", vec![(200, 1)])
+    ];
+    for (name, mask) in masks {
+        let results: Vec<AttackStats> = (0..iterations).into_par_iter().map(|_| {
+            let mut victim = VictimProgramYACC::new(secret_size, Compressor::CPACK, false);
+            victim.protect_bytes(&mask);
+            attack_yacc_cpack_with_retries(&mut victim, secret_size, &RetryPolicy::default(), false)
+        }).collect();
+        let summary = summarize(&results);
+        let blocked = results.iter().filter(|x| x.unwritable_probes > 0).count();
+        println!("{}: {} of {} attacks succeeded, {} blocked by the mask ({} probes skipped){}",
+                 name, summary.successes, iterations, blocked, summary.unwritable_probes,
+                 if blocked == iterations {", infeasible"} else {""});
+    }
+}
+
+/// Attacks a cache that prefetches the neighboring line on every store, with each way of priming and reloading, and reports
+/// how often the extra lines break the probes.
+pub fn attack_store_prefetch(secret_size: usize, iterations: usize) {
+    for store_prefetch in [false, true] {
+        for (prime, reload) in [(PrimeMethod::ByteWrites, ReloadMethod::Read), (PrimeMethod::Memcpy, ReloadMethod::Read), (PrimeMethod::ByteWrites, ReloadMethod::Store)] {
+            let config = ExperimentConfig {
+                iterations,
+                cache: CacheConfig {store_prefetch, ..CacheConfig::default()},
+                victim: VictimConfig {secret_size, ..VictimConfig::default()},
+                attack: RetryPolicy {first: AttackConfig {prime, reload, ..AttackConfig::default()}, alternates: Vec::new()}
+            };
+            let summary = summarize(&config.run());
+            let false_positives: usize = summary.probe_outcomes.iter().map(|x| x.false_positives).sum();
+            let false_negatives: usize = summary.probe_outcomes.iter().map(|x| x.false_negatives).sum();
+            println!("Prefetch on store: {}, {:?} and {:?}: {} of {} attacks succeeded, {} false positives, {} false negatives",
+                     store_prefetch, prime, reload, summary.successes, summary.iterations, false_positives, false_negatives);
+        }
+    }
+}
+
+/// Runs the C-PACK attack against cache sets of 2 to 16 ways, with the attacker flushing one line per way.
+pub fn sweep_associativity(secret_size: usize, iterations: usize) {
+    for associativity in [2, 4, 6, 8, 12, 16] {
+        let config = ExperimentConfig {
+            iterations,
+            cache: CacheConfig {associativity, ..CacheConfig::default()},
+            victim: VictimConfig {secret_size, ..VictimConfig::default()},
+            ..ExperimentConfig::default()
+        };
+        let summary = summarize(&config.run());
+        let false_positives: usize = summary.probe_outcomes.iter().map(|x| x.false_positives).sum();
+        let false_negatives: usize = summary.probe_outcomes.iter().map(|x| x.false_negatives).sum();
+        println!("{} ways: {} of {} attacks succeeded, {:.1} probes per attack, {} false positives, {} false negatives", associativity,
+                 summary.successes, summary.iterations, summary.set_evictions as f64 / iterations as f64, false_positives, false_negatives);
+    }
+}
+
+/// Runs the C-PACK attack against an LRU cache and caches with random and RRIP replacement, probing once and putting each
+/// probe to a vote over repeated rounds. A round under random replacement comes back positive about half the time when
+/// the victim's lines were co-allocated and about an eighth of the time when they were not, so the vote needs many rounds.
+/// Under BRRIP the victim's lines are filled as distant, so an uncompressed second line evicts the first, and even the
+/// vote cannot tell the two cases apart.
+pub fn compare_replacement_policies(secret_size: usize, iterations: usize) {
+    let voting = Some(ProbeVoting {rounds: 32, min_hits: 12});
+    let mut runs = vec![(ReplacementPolicy::Lru, None)];
+    for replacement in [ReplacementPolicy::Random, ReplacementPolicy::Srrip, ReplacementPolicy::Brrip] {
+        runs.push((replacement, None));
+        runs.push((replacement, voting));
+    }
+    for (replacement, voting) in runs {
+        let attack = AttackConfig {voting, ..AttackConfig::default()};
+        let config = ExperimentConfig {
+            iterations,
+            cache: CacheConfig {replacement, ..CacheConfig::default()},
+            victim: VictimConfig {secret_size, ..VictimConfig::default()},
+            attack: RetryPolicy {first: attack, alternates: Vec::new()}
+        };
+        let summary = summarize(&config.run());
+        let false_positives: usize = summary.probe_outcomes.iter().map(|x| x.false_positives).sum();
+        let false_negatives: usize = summary.probe_outcomes.iter().map(|x| x.false_negatives).sum();
+        println!("{:?} replacement, voting {:?}: {} of {} attacks succeeded, {:.1} probes per attack, {} false positives, {} false negatives",
+                 replacement, voting, summary.successes, summary.iterations, summary.set_evictions as f64 / iterations as f64,
+                 false_positives, false_negatives);
+    }
+}
+
+/// Attacks secrets of every whole number of words up to a line, for as long as the default threshold leaves room for
+/// the attack strings, and reports how the cost grows with the secret.
+pub fn sweep_secret_sizes(iterations: usize) {
+    for secret_size in (4..=64).step_by(4) {
+        let Some(layout) = AttackLayout::derive(32, secret_size) else {
+            println!("{}-byte secrets: no attack string layout fits", secret_size);
+            continue;
+        };
+        let config = ExperimentConfig {iterations, victim: VictimConfig {secret_size, ..VictimConfig::default()}, ..ExperimentConfig::default()};
+        let summary = summarize(&config.run());
+        println!("{}-byte secrets ({}, {} and {} test words): {} of {} attacks succeeded, {:.1} probes and {:.1} guesses per attack",
+                 secret_size, layout.leading_short.tests, layout.second_byte.tests, layout.last_byte.tests, summary.successes, summary.iterations,
+                 summary.set_evictions as f64 / iterations as f64, summary.guesses_needed as f64 / iterations as f64);
+    }
+}
+
+/// Attacks victims whose 16-byte secret is a key schedule, once treating the words as independent and once exploiting
+/// the schedule, which only needs one word recovered before guessing the rest.
+pub fn attack_key_schedule(iterations: usize) {
+    for structure in [SecretStructure::Independent, SecretStructure::KeySchedule] {
+        let config = ExperimentConfig {
+            iterations,
+            victim: VictimConfig {secret_size: 16, key_schedule: true, ..VictimConfig::default()},
+            attack: RetryPolicy {first: AttackConfig {structure, ..AttackConfig::default()}, alternates: Vec::new()},
+            ..ExperimentConfig::default()
+        };
+        let summary = summarize(&config.run());
+        println!("{:?} attack: {} of {} attacks succeeded, {:.1} probes and {:.1} guesses per attack",
+                 structure, summary.successes, summary.iterations, summary.set_evictions as f64 / iterations as f64,
+                 summary.guesses_needed as f64 / iterations as f64);
+    }
+}
+
+/// Attacks secrets kept two bytes to a word, with the attacker writing the other half of every word, next to the same
+/// secrets kept in whole words. Writing the leading shorts skips the costliest phase, and the shorts tell the words
+/// apart, so an interleaved secret is found with one guess. It also takes twice the words, though, and every one of them
+/// counts against the threshold, so no layout fits a 12-byte interleaved secret.
+pub fn attack_interleaved_secrets(iterations: usize) {
+    for secret_size in [2usize, 4, 6, 8, 12] {
+        for placement in [SecretPlacement::Contiguous, SecretPlacement::Interleaved] {
+            if placement == SecretPlacement::Contiguous && !secret_size.is_multiple_of(4) {continue;}
+            let config = ExperimentConfig {iterations, victim: VictimConfig {secret_size, placement, ..VictimConfig::default()}, ..ExperimentConfig::default()};
+            let summary = summarize(&config.run());
+            println!("{}-byte secret, {:?}: {} of {} attacks succeeded, {:.1} probes, {:.1} bytes written and {:.1} guesses per attack",
+                     secret_size, placement, summary.successes, summary.iterations, summary.set_evictions as f64 / iterations as f64,
+                     summary.bytes_written_to_victim as f64 / iterations as f64, summary.guesses_needed as f64 / iterations as f64);
+        }
+    }
+}
+
+/// Runs the C-PACK attack against caches with more than one set, with the attacker building its flush sets out of lines
+/// that map to the victim's set. Also replays the benign workloads on each geometry, where lines spread across the sets.
+pub fn sweep_set_counts(secret_size: usize, iterations: usize) {
+    for (sets, associativity) in [(1, 8), (16, 8), (1024, 8), (1024, 16)] {
+        let config = ExperimentConfig {
+            iterations,
+            cache: CacheConfig {sets, associativity, ..CacheConfig::default()},
+            victim: VictimConfig {secret_size, ..VictimConfig::default()},
+            ..ExperimentConfig::default()
+        };
+        let summary = summarize(&config.run());
+        let mut victim = config.victim.build(&config.cache);
+        println!("{} sets of {} ways: {} of {} attacks succeeded, {:.1} probes per attack (a sample secret line sits in set {} of {})",
+                 sets, associativity, summary.successes, summary.iterations, summary.set_evictions as f64 / iterations as f64,
+                 victim.buffer_set(BUFFER_SIZE - 1), victim.cache().set_count());
+        for workload in BenignWorkload::ALL {
+            let report = replay_on(&workload.generate(16 * sets as u64, 100000), config.cache.build_cache(), 16 * 64 * sets);
+            println!("    {:?}: {:.2}% read hit rate", workload, report.hit_rate() * 100.0);
+        }
+    }
+}
+
+/// Runs a campaign against a noisy co-tenant, then rebuilds the victim of every failed attack from its seed and attacks
+/// it again, checking that the failure replays exactly (same outcome and same number of probes).
+pub fn replay_failed_attacks(secret_size: usize, iterations: usize) {
+    let config = ExperimentConfig {
+        iterations,
+        victim: VictimConfig {secret_size, co_tenant: Some(CoTenantConfig {coverage: 1.0, access_probability: 0.05}), ..VictimConfig::default()},
+        ..ExperimentConfig::default()
+    };
+    let failures: Vec<AttackStats> = config.run().into_iter().filter(|stats| !stats.success).collect();
+    let mut replayed = 0;
+    for original in &failures {
+        let seed = original.victim_seed.expect("Attacks record their victim's seed");
+        let mut victim = config.victim.build_seeded(&config.cache, seed);
+        let stats = attack_yacc_cpack_with_retries(&mut victim, secret_size, &config.attack, false);
+        if stats.success == original.success && stats.set_evictions == original.set_evictions {replayed += 1;}
+        else {println!("Seed {} did not replay: {} probes the first time, {} the second", seed, original.set_evictions, stats.set_evictions);}
+    }
+    println!("{} of {} attacks failed; {} of the failures replayed exactly from their seeds", failures.len(), iterations, replayed);
+}
+
+/// Records the set's LRU state at every probe in setups where probes go wrong, and sorts the failed probes by cause:
+/// the check line evicted despite compression, or compression that never happened (and the reverse for false positives).
+pub fn diagnose_failed_probes(secret_size: usize, iterations: usize) {
+    let attack = |config: AttackConfig| RetryPolicy {first: AttackConfig {record_lru: true, ..config}, alternates: Vec::new()};
+    let setups = [
+        ("co-tenant", ExperimentConfig {
+            victim: VictimConfig {secret_size, co_tenant: Some(CoTenantConfig {coverage: 1.0, access_probability: 0.05}), ..VictimConfig::default()},
+            attack: attack(AttackConfig::default()),
+            ..ExperimentConfig::default()
+        }),
+        ("store prefetch", ExperimentConfig {
+            cache: CacheConfig {store_prefetch: true, ..CacheConfig::default()},
+            victim: VictimConfig {secret_size, ..VictimConfig::default()},
+            attack: attack(AttackConfig {reload: ReloadMethod::Store, ..AttackConfig::default()}),
+            ..ExperimentConfig::default()
+        }),
+        ("packed flush set", ExperimentConfig {
+            victim: VictimConfig {secret_size, ..VictimConfig::default()},
+            attack: attack(AttackConfig {flush: FlushSet {lines_per_superblock: 2, ..FlushSet::default()}, ..AttackConfig::default()}),
+            ..ExperimentConfig::default()
+        })
+    ];
+    for (name, config) in setups {
+        let config = ExperimentConfig {iterations, ..config};
+        let results = config.run();
+        let mut diagnoses: BTreeMap<ProbeDiagnosis, usize> = BTreeMap::new();
+        let mut example = None;
+        for record in results.iter().flat_map(|stats| stats.probe_trace.iter()) {
+            let diagnosis = record.diagnosis();
+            *diagnoses.entry(diagnosis).or_insert(0) += 1;
+            if diagnosis != ProbeDiagnosis::Correct && example.is_none() {example = Some(record.clone());}
+        }
+        println!("{}: {} of {} attacks succeeded, probes by diagnosis: {:?}", name, results.iter().filter(|x| x.success).count(), iterations, diagnoses);
+        if let Some(record) = example {
+            println!("    First failed probe ({:?}, {:?}): check line age {:?}, probed line (age, shared) {:?}",
+                     record.phase, record.diagnosis(), record.lru.check_age, record.lru.probed_line);
+            for (way, state) in record.lru.ways.iter().enumerate() {
+                println!("        way {}: age {:?}, lines {:X?}", way, state.age, state.lines);
+            }
+        }
+    }
+}
+
+/// Reads every probe through both the latency and the residency oracle, under increasing timing noise and with and
+/// without a co-tenant, and reports how often they disagree and how reliable each one is.
+pub fn compare_oracles(secret_size: usize, iterations: usize) {
+    let co_tenant = CoTenantConfig {coverage: 1.0, access_probability: 0.05};
+    for (timing_noise, co_tenant) in [(0.0, None), (0.01, None), (0.05, None), (0.0, Some(co_tenant)), (0.01, Some(co_tenant))] {
+        let config = ExperimentConfig {
+            iterations,
+            victim: VictimConfig {secret_size, timing_noise, co_tenant, ..VictimConfig::default()},
+            attack: RetryPolicy {first: AttackConfig {oracle: ProbeOracle::Dual, ..AttackConfig::default()}, alternates: Vec::new()},
+            ..ExperimentConfig::default()
+        };
+        let summary = summarize(&config.run());
+        let report = summary.oracle_report;
+        println!("Timing noise {}, co-tenant {}: {} of {} attacks succeeded, {} of {} probes disagreed", timing_noise, co_tenant.is_some(),
+                 summary.successes, summary.iterations, report.disagreements, report.probes);
+        for (name, outcomes) in [("latency", report.latency), ("residency", report.residency)] {
+            println!("    {} oracle: accuracy {:.4}%, {} false positives, {} false negatives", name, outcomes.accuracy() * 100.0,
+                     outcomes.false_positives, outcomes.false_negatives);
+        }
+    }
+}
+
+/// Attacks a victim whose clock charges the cache's own cycles, once with the usual flush and reload and once timing
+/// the victim's read of the companion line, which takes C-PACK's decompression cycles longer when the secret line shares
+/// its entry. The timing probe needs no flush lines at all.
+pub fn compare_decompression_timing(secret_size: usize, iterations: usize) {
+    for oracle in [ProbeOracle::Latency, ProbeOracle::DecompressionLatency] {
+        let config = ExperimentConfig {
+            iterations,
+            victim: VictimConfig {secret_size, latency: LatencyModel::CACHE_CYCLES, ..VictimConfig::default()},
+            attack: RetryPolicy {first: AttackConfig {oracle, ..AttackConfig::default()}, alternates: Vec::new()},
+            ..ExperimentConfig::default()
+        };
+        let summary = summarize(&config.run());
+        println!("{:?} oracle: {} of {} attacks succeeded, {:.1} victim calls and {:.1} attacker lines per attack",
+                 oracle, summary.successes, summary.iterations,
+                 summary.victim_interactions as f64 / iterations as f64, summary.attacker_cache_lines_loaded as f64 / iterations as f64);
+    }
+}
+
+/// Reads probes through the latency oracle and through the miss counter under each kind of noise, and reports how
+/// reliable each one is: timing noise misleads only the latency oracle, counter noise only the miss counter.
+pub fn compare_miss_counter(secret_size: usize, iterations: usize) {
+    let co_tenant = Some(CoTenantConfig {coverage: 1.0, access_probability: 0.05});
+    let noises = [("no noise", VictimConfig::default()), ("timing noise 0.01", VictimConfig {timing_noise: 0.01, ..VictimConfig::default()}),
+                  ("counter noise 0.01", VictimConfig {counter_noise: 0.01, ..VictimConfig::default()}), ("co-tenant", VictimConfig {co_tenant, ..VictimConfig::default()})];
+    for (name, victim) in noises {
+        for oracle in [ProbeOracle::Latency, ProbeOracle::MissCounter] {
+            let config = ExperimentConfig {
+                iterations,
+                victim: VictimConfig {secret_size, ..victim.clone()},
+                attack: RetryPolicy {first: AttackConfig {oracle, ..AttackConfig::default()}, alternates: Vec::new()},
+                ..ExperimentConfig::default()
+            };
+            let summary = summarize(&config.run());
+            let mut outcomes = ProbeOutcomes::default();
+            for phase_outcomes in summary.probe_outcomes {outcomes += phase_outcomes;}
+            println!("{}, {:?} oracle: {} of {} attacks succeeded, {} false positives and {} false negatives in {} probes", name, oracle,
+                     summary.successes, summary.iterations, outcomes.false_positives, outcomes.false_negatives, summary.set_evictions);
+        }
+    }
+}
+
+/// Attacks victims with the standard pipeline, stopping it once the leading shorts are known and handing the words over
+/// to a second pipeline that finds the remaining bytes with voted probes, as an external driver switching strategies
+/// would. Prints the mean of what the attacker knew after each phase.
+pub fn hand_off_after_leading_shorts(secret_size: usize, iterations: usize) {
+    let Some(layout) = AttackLayout::derive(32, secret_size) else {return println!("No attack string layout fits a {}-byte secret", secret_size)};
+    let voted = AttackConfig {voting: Some(ProbeVoting {rounds: 3, min_hits: 2}), ..AttackConfig::default()};
+    let runs: Vec<(AttackStats, Vec<KnowledgeState>)> = (0..iterations).into_par_iter().map(|_| {
+        let mut victim = VictimConfig {secret_size, ..VictimConfig::default()}.build(&CacheConfig::default());
+        let mut stats = AttackStats::default();
+        stats.attempts = 1;
+        let mut buffer_state = vec![0u8; 64 - secret_size];
+        let mut trail = Vec::new();
+        AttackPipeline::standard().run_observed(&mut victim, &AttackConfig::default(), &layout, &mut buffer_state, &mut stats, false, |state| {
+            trail.push(state.clone());
+            if state.phase == AttackPhase::LeadingShort {PipelineControl::Stop} else {PipelineControl::Continue}
+        });
+        let words = stats.recovered_words.clone();
+        AttackPipeline::empty().register(SecondByteStep).register(LastByteStep).starting_from(words)
+            .run_observed(&mut victim, &voted, &layout, &mut buffer_state, &mut stats, false, |state| {
+                trail.push(state.clone());
+                PipelineControl::Continue
+            });
+        victim.into_judge().score(&mut stats);
+        (stats, trail)
+    }).collect();
+    for phase in [AttackPhase::LeadingShort, AttackPhase::SecondByte, AttackPhase::LastByte] {
+        let states: Vec<&KnowledgeState> = runs.iter().flat_map(|(_, trail)| trail.iter().filter(|state| state.phase == phase)).collect();
+        let mean = |value: fn(&KnowledgeState) -> f64| states.iter().map(|state| value(state)).sum::<f64>() / states.len().max(1) as f64;
+        println!("After {:?}: {:.1} bytes pinned, {:.2} bits of candidates left, {:.1} probes spent (mean of {} attacks)",
+                 phase, mean(|state| state.pinned_bytes as f64), mean(|state| state.candidate_log2), mean(|state| state.probes as f64), states.len());
+    }
+    let stats: Vec<AttackStats> = runs.into_iter().map(|(stats, _)| stats).collect();
+    let summary = summarize(&stats);
+    println!("{} of {} attacks succeeded after the hand-off", summary.successes, summary.iterations);
+}
+
+/// An attack that takes a victim, its secret size and whether to be verbose, like evict_and_reload_yacc_cpack_secret.
+type AttackEntryPoint = fn(&mut VictimProgramYACC, usize, bool) -> AttackStats;
+
+/// Attacks victims with prime and probe and with Evict+Reload (see evict_and_reload_yacc_cpack_secret) under each kind
+/// of interference, to compare how robust the two channels are.
+pub fn compare_evict_reload(secret_size: usize, iterations: usize) {
+    let co_tenant = Some(CoTenantConfig {coverage: 1.0, access_probability: 0.05});
+    let noises = [("no noise", VictimConfig::default()), ("timing noise 0.001", VictimConfig {timing_noise: 0.001, ..VictimConfig::default()}),
+                  ("co-tenant", VictimConfig {co_tenant, ..VictimConfig::default()})];
+    for (name, victim) in noises {
+        let victim = VictimConfig {secret_size, ..victim};
+        let cache = CacheConfig::default();
+        let channels: [(&str, AttackEntryPoint); 2] =
+            [("prime and probe", |victim, size, verbose| attack_yacc_cpack_with_retries(victim, size, &RetryPolicy::default(), verbose)),
+             ("Evict+Reload", evict_and_reload_yacc_cpack_secret)];
+        for (channel, attack) in channels {
+            let results: Vec<AttackStats> = (0..iterations).into_par_iter().map(|_| {
+                let mut target = victim.build(&cache);
+                let mut stats = attack(&mut target, secret_size, false);
+                target.into_judge().score(&mut stats);
+                stats
+            }).collect();
+            let summary = summarize(&results);
+            println!("{}, {}: {} of {} attacks succeeded, {:.1} probes and {:.1} attacker lines per attack", name, channel,
+                     summary.successes, summary.iterations, summary.set_evictions as f64 / iterations as f64,
+                     summary.attacker_cache_lines_loaded as f64 / iterations as f64);
+        }
+    }
+}
+
+/// Runs the same seeded campaign with and without each source of noise. Since noise draws from its own stream, both
+/// runs attack the same secrets at the same buffer bases, so the outcomes can be compared attack by attack: the attacks
+/// that only one of the two runs got right are what the noise changed.
+pub fn paired_noise_comparison(secret_size: usize, iterations: usize, seed: u64) {
+    let clean = ExperimentConfig {iterations, victim: VictimConfig {secret_size, ..VictimConfig::default()}, ..ExperimentConfig::default()};
+    let baseline = Experiment::from_config_seeded(&clean, seed).run();
+    let co_tenant = Some(CoTenantConfig {coverage: 1.0, access_probability: 0.05});
+    let noises = [("timing noise 0.001", VictimConfig {timing_noise: 0.001, ..clean.victim.clone()}),
+                  ("co-tenant", VictimConfig {co_tenant, ..clean.victim.clone()})];
+    for (name, victim) in noises {
+        let noisy = Experiment::from_config_seeded(&ExperimentConfig {victim, ..clean.clone()}, seed).run();
+        let pairs: Vec<(bool, bool)> = baseline.rows.iter().zip(&noisy.rows).map(|(a, b)| {
+            assert_eq!(a.secret, b.secret, "Iteration {} attacked different secrets with and without {}", a.iteration, name);
+            (a.stats.success, b.stats.success)
+        }).collect();
+        let count = |clean: bool, noisy: bool| pairs.iter().filter(|&&pair| pair == (clean, noisy)).count();
+        println!("{}: {} attacks succeeded either way, {} failed either way, {} only failed with it and {} only succeeded with it",
+                 name, count(true, true), count(false, false), count(true, false), count(false, true));
+    }
+}
+
+/// Runs the attack against a write-through and a write-back cache. Under write-back, a store recompresses its line in
+/// place instead of re-inserting it, so the line only leaves a co-allocated entry when it outgrows it.
+pub fn compare_write_policies(secret_size: usize, iterations: usize) {
+    for write_policy in [WritePolicy::WriteThrough, WritePolicy::WriteBack] {
+        let config = ExperimentConfig {
+            iterations,
+            cache: CacheConfig {write_policy, ..CacheConfig::default()},
+            victim: VictimConfig {secret_size, ..VictimConfig::default()},
+            ..ExperimentConfig::default()
+        };
+        let summary = summarize(&config.run());
+        println!("{:?}: {} of {} attacks succeeded, {:.1} probes and {:.1} writebacks per attack", write_policy, summary.successes,
+                 summary.iterations, summary.set_evictions as f64 / iterations as f64, summary.writebacks as f64 / iterations as f64);
+    }
+}
+
+/// Runs campaigns under increasing timing noise, first to see how well predict_success foresees each attack's outcome
+/// after the leading shorts, then with attempts predicted to have less than an even chance given up early (and retried
+/// with the next configuration), to see what that saves.
+pub fn predict_attack_outcomes(secret_size: usize, iterations: usize) {
+    for timing_noise in [0.0, 0.0005, 0.002] {
+        let victim = VictimConfig {secret_size, timing_noise, ..VictimConfig::default()};
+        for abort_below in [None, Some(0.5)] {
+            let mut attack = RetryPolicy::default();
+            for config in std::iter::once(&mut attack.first).chain(attack.alternates.iter_mut()) {config.abort_below = abort_below;}
+            let config = ExperimentConfig {iterations, victim: victim.clone(), attack, ..ExperimentConfig::default()};
+            let results = config.run();
+            let summary = summarize(&results);
+            let report = PredictorReport::of(&results);
+            print!("Timing noise {}, {}: {} of {} attacks succeeded, {:.1} probes per attack", timing_noise,
+                   if abort_below.is_some() {"giving up below 50%"} else {"never giving up"}, summary.successes, summary.iterations,
+                   summary.set_evictions as f64 / iterations as f64);
+            if abort_below.is_some() {
+                println!(", {} attempts given up", summary.early_aborts);
+            } else {
+                println!(", Brier score {:.4} over {} predictions, {:.1}% called right", report.brier_score, report.predicted, report.outcomes.accuracy() * 100.0);
+            }
+        }
+    }
+}
+
+/// Runs the attack against victims making more and more background accesses to their own data in the secret line's
+/// set, probing once, retrying negative probes, or putting every probe to a majority vote, to quantify how much noise
+/// each mode tolerates.
+pub fn quantify_background_noise(secret_size: usize, iterations: usize) {
+    let modes = [("single probes", AttackConfig::default()),
+                 ("3 tries per negative probe", AttackConfig {interference: InterferenceMitigation::ProbeRetries {attempts: 3}, ..AttackConfig::default()}),
+                 ("2 of 3 votes", AttackConfig {voting: Some(ProbeVoting {rounds: 3, min_hits: 2}), ..AttackConfig::default()})];
+    for access_probability in [0.0, 0.01, 0.05, 0.2] {
+        let background_noise = Some(BackgroundNoiseConfig {access_probability, write_fraction: 0.5, lines: 4});
+        let victim = VictimConfig {secret_size, background_noise, ..VictimConfig::default()};
+        for (name, config) in &modes {
+            let policy = RetryPolicy {first: config.clone(), alternates: Vec::new()};
+            let results: Vec<(AttackStats, u64)> = (0..iterations).into_par_iter().map(|_| {
+                let mut target = victim.build(&CacheConfig::default());
+                let mut stats = attack_yacc_cpack_with_retries(&mut target, secret_size, &policy, false);
+                let accesses = target.background_accesses();
+                target.into_judge().score(&mut stats);
+                (stats, accesses)
+            }).collect();
+            let accesses: u64 = results.iter().map(|(_, accesses)| accesses).sum();
+            let summary = summarize(&results.into_iter().map(|(stats, _)| stats).collect::<Vec<AttackStats>>());
+            println!("Background access probability {}, {}: {} of {} attacks succeeded, {:.1} flushes per attack, {:.2} background accesses per flush",
+                     access_probability, name, summary.successes, summary.iterations, summary.set_evictions as f64 / iterations as f64,
+                     accesses as f64 / summary.set_evictions.max(1) as f64);
+        }
+    }
+}
+
+/// Attacks secrets drawn from each restricted alphabet, once by an attacker assuming any byte and once by one that knows
+/// the alphabet and never tests values outside it, to show how much a password-like secret helps the attacker.
+pub fn compare_secret_distributions(secret_size: usize, iterations: usize) {
+    for distribution in [SecretDistribution::UniformBytes, SecretDistribution::PrintableAscii, SecretDistribution::Base64, SecretDistribution::HexDigits] {
+        for known_distribution in [SecretDistribution::UniformBytes, distribution] {
+            let config = ExperimentConfig {
+                iterations,
+                victim: VictimConfig {secret_size, secret_distribution: distribution, ..VictimConfig::default()},
+                attack: RetryPolicy {first: AttackConfig {known_distribution, ..AttackConfig::default()}, alternates: Vec::new()},
+                ..ExperimentConfig::default()
+            };
+            let summary = summarize(&config.run());
+            println!("{:?} secrets, attacker assuming {:?}: {} of {} attacks succeeded, {:.1} probes and {:.2} guesses per attack",
+                     distribution, known_distribution, summary.successes, summary.iterations,
+                     summary.set_evictions as f64 / iterations as f64, summary.guesses_needed as f64 / iterations as f64);
+            if distribution == SecretDistribution::UniformBytes {break;}
+        }
+    }
+}
+
+/// Attacks secrets of unique non-zero bytes and secrets of arbitrary bytes drawn from the given distribution, each by an
+/// attacker assuming unique non-zero bytes and by one allowing for arbitrary secrets, to show how much the restriction
+/// flatters the attack's success rate and what handling zero and repeated bytes costs.
+pub fn compare_secret_bytes(distribution: SecretDistribution, secret_size: usize, iterations: usize) {
+    for secret_bytes in [SecretBytes::UniqueNonZero, SecretBytes::Arbitrary] {
+        for assumed in [SecretBytes::UniqueNonZero, SecretBytes::Arbitrary] {
+            let config = ExperimentConfig {
+                iterations,
+                victim: VictimConfig {secret_size, secret_distribution: distribution, secret_bytes, ..VictimConfig::default()},
+                attack: RetryPolicy {first: AttackConfig {known_distribution: distribution, secret_bytes: assumed, ..AttackConfig::default()}, alternates: Vec::new()},
+                ..ExperimentConfig::default()
+            };
+            let summary = summarize(&config.run());
+            println!("{:?} secrets of {:?} bytes, attacker assuming {:?}: {} of {} attacks succeeded, {:.1} probes and {:.2} guesses per attack",
+                     secret_bytes, distribution, assumed, summary.successes, summary.iterations,
+                     summary.set_evictions as f64 / iterations as f64, summary.guesses_needed as f64 / iterations as f64);
+        }
+    }
+}
+
+/// Runs the C-PACK attack against caches with 2-, 4- and 8-block superblocks, after checking that each still conforms.
+/// The attack assumes nothing about the superblock size beyond what the victim's cache reports.
+pub fn compare_superblock_sizes(secret_size: usize, iterations: usize) {
+    for superblock_blocks in [2, 4, 8] {
+        let cache = CacheConfig {superblock_blocks, ..CacheConfig::default()};
+        let report = check_conformance(&ConformanceSpec::of(&cache.build_cache()), || cache.build_cache());
+        let config = ExperimentConfig {
+            iterations,
+            cache,
+            victim: VictimConfig {secret_size, ..VictimConfig::default()},
+            ..ExperimentConfig::default()
+        };
+        let summary = summarize(&config.run());
+        println!("{}-block superblocks ({}): {} of {} attacks succeeded, {:.1} probes per attack",
+                 superblock_blocks, if report.passed() {"conformant"} else {"NOT conformant"}, summary.successes, summary.iterations,
+                 summary.set_evictions as f64 / iterations as f64);
+    }
+}
+
+/// Runs the C-PACK attack against caches with 32-, 64- and 128-byte lines, with the co-allocation thresholds scaled to
+/// half and a quarter of a line and the attacker assuming the scaled double threshold.
+pub fn compare_line_sizes(secret_size: usize, iterations: usize) {
+    for line_size in LINE_SIZES {
+        let threshold = line_size as u64 / 2;
+        let config = ExperimentConfig {
+            iterations,
+            cache: CacheConfig {line_size, double_threshold: threshold, quad_threshold: threshold / 2, ..CacheConfig::default()},
+            victim: VictimConfig {secret_size, ..VictimConfig::default()},
+            attack: RetryPolicy {first: AttackConfig {threshold: ThresholdSource::Assumed(threshold), ..AttackConfig::default()}, alternates: Vec::new()}
+        };
+        let summary = summarize(&config.run());
+        println!("{}-byte lines ({}-byte threshold): {} of {} attacks succeeded, {:.1} probes per attack",
+                 line_size, threshold, summary.successes, summary.iterations, summary.set_evictions as f64 / iterations as f64);
+    }
+}
+
+/// Runs the C-PACK attack against YACC and DISH caches of the same geometry, after checking that each conforms.
+/// DISH co-allocates blocks only when their words fit a shared dictionary of 8 whole words (or of 4 upper 28-bit
+/// values), so the partial matches the C-PACK attack builds on change nothing, and a probe can only confirm a guess of
+/// a whole word (or its upper 28 bits) at a time.
+pub fn compare_cache_architectures(secret_size: usize, iterations: usize) {
+    for architecture in [CacheArchitecture::Yacc, CacheArchitecture::Dish] {
+        let cache = CacheConfig {architecture, ..CacheConfig::default()};
+        let report = check_conformance(&ConformanceSpec::of(&cache.build_cache()), || cache.build_cache());
+        let config = ExperimentConfig {
+            iterations,
+            cache,
+            victim: VictimConfig {secret_size, ..VictimConfig::default()},
+            ..ExperimentConfig::default()
+        };
+        let summary = summarize(&config.run());
+        println!("{:?} ({} conformance): {} of {} attacks succeeded, {:.1} probes per attack", architecture,
+                 if report.passed() {"passes"} else {"fails"}, summary.successes, summary.iterations, summary.set_evictions as f64 / iterations as f64);
+    }
+}
+
+/// Runs the C-PACK attack against little- and big-endian caches, with the stock threshold assumed and with the cache
+/// calibrated by probing. The attacker builds its strings for the target's byte order, so both should leak alike.
+pub fn compare_endianness(secret_size: usize, iterations: usize) {
+    for endianness in [Endianness::Little, Endianness::Big] {
+        for threshold in [ThresholdSource::Assumed(32), ThresholdSource::Calibrated] {
+            let config = ExperimentConfig {
+                iterations,
+                cache: CacheConfig {endianness, ..CacheConfig::default()},
+                victim: VictimConfig {secret_size, ..VictimConfig::default()},
+                attack: RetryPolicy {first: AttackConfig {threshold, ..AttackConfig::default()}, alternates: Vec::new()}
+            };
+            let summary = summarize(&config.run());
+            println!("{:?}-endian, {:?} threshold: {} of {} attacks succeeded, {:.1} probes per attack", endianness, threshold,
+                     summary.successes, summary.iterations, summary.set_evictions as f64 / iterations as f64);
+        }
+    }
+}
+
+/// Runs the C-PACK attack against caches whose C-PACK dictionary holds only so many words, replaced first in first out
+/// or least recently used, next to an unbounded dictionary. The attacker lays its strings out for the dictionary's size
+/// (see AttackLayout::with_dictionary), so every size should leak; smaller ones only leave room for fewer test words
+/// per probe, and so cost more probes.
+pub fn compare_cpack_dictionaries(secret_size: usize, iterations: usize) {
+    let mut compressors = vec![Compressor::CPACK];
+    for dict_entries in [16, 8, 4] {
+        for replacement in [DictionaryReplacement::Fifo, DictionaryReplacement::Lru] {
+            compressors.push(Compressor::BoundedCPACK {dict_entries, replacement});
+        }
+    }
+    for compressor in compressors {
+        let config = ExperimentConfig {
+            iterations,
+            cache: CacheConfig {compressor, ..CacheConfig::default()},
+            victim: VictimConfig {secret_size, ..VictimConfig::default()},
+            ..ExperimentConfig::default()
+        };
+        let summary = summarize(&config.run());
+        println!("{:?}: {} of {} attacks succeeded, {:.1} probes per attack", compressor, summary.successes, summary.iterations,
+                 summary.set_evictions as f64 / iterations as f64);
+    }
+}
+
+/// Attacks victims that re-allocate their buffer at a random base every so many attacker operations, with attackers that
+/// ignore the moves and attackers that read back their string after each probe to catch them and prime again. Reports
+/// the success rate, how many moves happened and were caught per attack, and what catching them cost in probes.
+pub fn attack_rebasing_victim(secret_size: usize, iterations: usize) {
+    for rebase_every in [None, Some(1_000_000), Some(100_000), Some(10_000), Some(1_000)] {
+        for detect_relocation in [false, true] {
+            let config = ExperimentConfig {
+                iterations,
+                victim: VictimConfig {secret_size, rebase_every, ..VictimConfig::default()},
+                attack: RetryPolicy {first: AttackConfig {detect_relocation, ..AttackConfig::default()}, alternates: Vec::new()},
+                ..ExperimentConfig::default()
+            };
+            let experiment = Experiment::from_config(&config);
+            let runs: Vec<(AttackStats, u64)> = (0..iterations).into_par_iter().map(|iteration| {
+                let mut relocations = 0;
+                let row = experiment.run_inspected(iteration, |victim| relocations = victim.relocations());
+                (row.stats, relocations)
+            }).collect();
+            let relocations: u64 = runs.iter().map(|(_, relocations)| relocations).sum();
+            let summary = summarize(&runs.into_iter().map(|(stats, _)| stats).collect::<Vec<_>>());
+            let per_attack = |total: f64| total / iterations.max(1) as f64;
+            let period = rebase_every.map_or("never".to_string(), |operations| format!("every {} operations", operations));
+            println!("Rebasing {}, detection {}: {} of {} attacks succeeded, {:.1} moves and {:.1} caught per attack, {:.0} probes per attack",
+                     period, detect_relocation, summary.successes, iterations, per_attack(relocations as f64),
+                     per_attack(summary.relocations_detected as f64), per_attack(summary.set_evictions as f64));
+        }
+    }
+}
+
+/// Attacks victims that only serve the attacker a fixed number of buffer writes and reads, set at fractions of what an
+/// unlimited attack spends on average, and reports how many attacks still succeed, how many ran out of budget, and what
+/// the attacks that ran out had found by then: leading shorts, secret bytes, and the bits of the secret still unknown.
+pub fn attack_under_access_budget(secret_size: usize, iterations: usize) {
+    let run = |max_attacker_writes: Option<u64>, max_attacker_reads: Option<u64>| {
+        let config = ExperimentConfig {
+            iterations,
+            victim: VictimConfig {secret_size, max_attacker_writes, max_attacker_reads, ..VictimConfig::default()},
+            ..ExperimentConfig::default()
+        };
+        return config.run();
+    };
+    let baseline = summarize(&run(None, None));
+    let writes = baseline.victim_write_calls as f64 / iterations as f64;
+    let reads = baseline.bytes_read_from_victim as f64 / iterations as f64;
+    println!("Unlimited: {} of {} attacks succeeded, {:.0} writes and {:.0} reads per attack", baseline.successes, iterations, writes, reads);
+    for fraction in [1.0, 0.5, 0.25, 0.1] {
+        let results = run(Some((writes * fraction) as u64), Some((reads * fraction) as u64));
+        let summary = summarize(&results);
+        let exhausted: Vec<&AttackStats> = results.iter().filter(|stats| stats.budget_exhausted).collect();
+        let shorts: usize = exhausted.iter().map(|stats| stats.recovered_words.iter().filter(|word| word.short.is_some()).count()).sum();
+        let recovered: usize = exhausted.iter()
+            .map(|stats| stats.recovered_words.iter().map(PartialWord::known_bytes).sum::<usize>().min(secret_size)).sum();
+        // Attacks that ran out before confirming a single short still ruled candidates out, which the entropy left shows.
+        let bits_left: f64 = exhausted.iter().map(|stats| stats.candidate_log2.0.last().copied().unwrap_or(8.0 * secret_size as f64)).sum();
+        let per_exhaustion = |total: f64| total / exhausted.len().max(1) as f64;
+        println!("{:.0}% of the average budget: {} of {} attacks succeeded, {} ran out, finding {:.2} leading shorts and {:.2} secret bytes each, with {:.1} bits of the secret left",
+                 fraction * 100.0, summary.successes, iterations, summary.budget_exhaustions, per_exhaustion(shorts as f64), per_exhaustion(recovered as f64),
+                 per_exhaustion(bits_left));
+    }
+}
+
+/// Re-runs the standard attack against a 16-set cache with randomized set indexing (see defenses::RandomizedIndexing),
+/// with a fixed key and with shorter and shorter remap periods, and reports how far the success rate and the guesses
+/// per attack degrade from the same cache without the defense.
+pub fn evaluate_randomized_indexing(secret_size: usize, iterations: usize) {
+    let run = |randomized_indexing: Option<RandomizedIndexing>| {
+        let config = ExperimentConfig {
+            iterations,
+            cache: CacheConfig {sets: 16, randomized_indexing, ..CacheConfig::default()},
+            victim: VictimConfig {secret_size, ..VictimConfig::default()},
+            ..ExperimentConfig::default()
+        };
+        summarize(&config.run())
+    };
+    let baseline = run(None);
+    let guesses = |summary: &Summary| summary.guesses_needed as f64 / summary.successes.max(1) as f64;
+    println!("No randomization: {} of {} attacks succeeded, {:.2} guesses per success", baseline.successes, iterations, guesses(&baseline));
+    for remap_period in [None, Some(100000), Some(10000), Some(1000), Some(100)] {
+        let summary = run(Some(RandomizedIndexing {remap_period}));
+        let period = remap_period.map_or("a fixed key".to_string(), |period| format!("a remap every {} accesses", period));
+        println!("Randomized indexing with {}: {} of {} attacks succeeded ({:+.1} points), {:.2} guesses per success ({:+.2})",
+                 period, summary.successes, iterations, 100.0 * (summary.success_rate() - baseline.success_rate()),
+                 guesses(&summary), guesses(&summary) - guesses(&baseline));
+    }
+}
+
+/// Re-runs the standard attack against caches that quantize stored sizes to buckets of 8 to 32 bytes, with and without
+/// random padding, and breaks the probe outcomes down by phase. Buckets that divide both co-allocation thresholds keep
+/// every threshold crossing the attack relies on, so the attack should only break down once the buckets straddle a
+/// threshold or the padding starts moving lines across them.
+pub fn evaluate_size_quantization(secret_size: usize, iterations: usize) {
+    for bucket_bytes in [8, 16, 24, 32] {
+        for pad_probability in [0.0, 0.1, 0.5] {
+            let config = ExperimentConfig {
+                iterations,
+                cache: CacheConfig {size_quantization: Some(SizeQuantization {bucket_bytes, pad_probability}), ..CacheConfig::default()},
+                victim: VictimConfig {secret_size, ..VictimConfig::default()},
+                ..ExperimentConfig::default()
+            };
+            let summary = summarize(&config.run());
+            println!("{}-byte buckets, padding probability {}: {} of {} attacks succeeded, {:.1} probes per attack",
+                     bucket_bytes, pad_probability, summary.successes, summary.iterations, summary.set_evictions as f64 / iterations as f64);
+            for phase in [AttackPhase::LeadingShort, AttackPhase::SecondByte, AttackPhase::LastByte] {
+                let outcomes = &summary.probe_outcomes[phase as usize];
+                println!("  {:?}: {} true positives, {} false positives, {} false negatives (false-positive rate {:.2}%)", phase,
+                         outcomes.true_positives, outcomes.false_positives, outcomes.false_negatives, outcomes.false_positive_rate() * 100.0);
+            }
+        }
+    }
+}
+
+/// Runs the C-PACK attack against caches with each compressor, to compare how feasible it is under each.
+/// Also replays the benign workloads, to show what each compressor buys in effective capacity.
+pub fn compare_compressors(secret_size: usize, iterations: usize) {
+    for compressor in [Compressor::CPACK, Compressor::BDI, Compressor::FPC, Compressor::NONE] {
+        let config = ExperimentConfig {
+            iterations,
+            cache: CacheConfig {compressor, ..CacheConfig::default()},
+            victim: VictimConfig {secret_size, ..VictimConfig::default()},
+            ..ExperimentConfig::default()
+        };
+        let summary = summarize(&config.run());
+        println!("{:?}: {} of {} attacks succeeded, {:.1} probes per attack", compressor, summary.successes, summary.iterations,
+                 summary.set_evictions as f64 / iterations as f64);
+    }
+    for workload in BenignWorkload::ALL {
+        report_trace_performance(&format!("{:?}", workload), &workload.generate(16, 100000), &[Compressor::CPACK, Compressor::BDI, Compressor::FPC], 16 * 64);
+    }
+}
+
+/// Attacks victims whose secret shares its line with a known public prefix. The attack charges the prefix's words against
+/// the threshold when deriving its layouts, so the probes should keep landing in their size windows.
+pub fn attack_public_prefix(secret_size: usize, iterations: usize) {
+    let prefixes: [(&str, Vec<u8>); 5] = [
+        ("no prefix", Vec::new()),
+        ("version 1", vec![1, 0, 0, 0]),
+        ("magic number", b"YACC".to_vec()),
+        ("version 2 and a flags word", vec![2, 0, 0, 0, 0x10, 0x80, 0, 0]),
+        ("16-byte header", vec![0x01, 0, 0, 0, 0xEF, 0xBE, 0xAD, 0xDE, 0x40, 0, 0, 0, 0x34, 0x12, 0x00, 0x80])
+    ];
+    for (name, public_prefix) in prefixes {
+        let config = ExperimentConfig {
+            iterations,
+            victim: VictimConfig {secret_size, public_prefix, ..VictimConfig::default()},
+            ..ExperimentConfig::default()
+        };
+        let summary = summarize(&config.run());
+        let outside: usize = summary.size_histograms.iter().map(|h| h.outside_window).sum();
+        println!("{}: {} of {} attacks succeeded, {:.1} probes per attack, {} probes outside their size window, {} unwritable",
+                 name, summary.successes, summary.iterations, summary.set_evictions as f64 / iterations as f64, outside, summary.unwritable_probes);
+    }
+}
+
+/// Prints which attacker-writable bytes alias the secret for contiguous and striped buffers, in the simulator's
+/// single-set cache and in a cache with 1024 sets.
+pub fn report_buffer_aliasing(secret_size: usize) {
+    let layouts = [BufferLayout::Contiguous, BufferLayout::Striped {stride: 3, header: HeaderLine::Zeros}];
+    let geometries = [CacheGeometry::default(), CacheGeometry {sets: 1024, ..CacheGeometry::default()}];
+    for layout in layouts {
+        for geometry in geometries {
+            let mut victim = VictimProgramYACC::new(secret_size, Compressor::CPACK, false);
+            victim.set_layout(layout);
+            println!("{:?} buffer, {} sets:", layout, geometry.sets);
+            victim.aliasing(geometry).print();
+        }
+    }
+}
+
+/// Runs the prime+probe attack next to the two baselines (a cheating attacker that reads the secret, and a brute-force
+/// attacker that only guesses) and checks that the stats pipeline counts what each of them did.
+/// The brute-force attacker gets 4-byte victims whose top two bytes are 0x01, so it can finish within its guess budget.
+#[cfg(not(feature = "no-oracle"))]
+pub fn compare_baseline_attackers(iterations: usize) {
+    let secrets: Vec<Vec<u8>> = (0..iterations).map(|_| {
+        let low: [u8; 2] = [rand::random::<u8>().max(1), rand::random::<u8>().max(1)];
+        vec![low[0], low[1], 0x01, 0x01]
+    }).collect();
+    let prime_probe: Vec<AttackStats> = (0..iterations).into_par_iter().map(|_| {
+        attack_yacc_cpack_with_retries(&mut VictimProgramYACC::new(4, Compressor::CPACK, false), 4, &RetryPolicy::no_retries(), false)
+    }).collect();
+    let ground_truth: Vec<AttackStats> = (0..iterations).map(|_| attack_ground_truth(&mut VictimProgramYACC::new(4, Compressor::CPACK, false))).collect();
+    let brute_force: Vec<AttackStats> = secrets.par_iter().map(|secret| {
+        attack_brute_force(&mut VictimProgramYACC::new_with_custom_secret(secret.clone(), Compressor::CPACK, false), 4, 255 * 255)
+    }).collect();
+    for (name, results) in [("Prime+probe", &prime_probe), ("Ground truth", &ground_truth), ("Brute force", &brute_force)] {
+        let summary = summarize(results);
+        let probes: usize = summary.probe_outcomes.iter().map(|x| x.true_positives + x.false_positives + x.true_negatives + x.false_negatives).sum();
+        let candidate_points: usize = results.iter().map(|x| x.candidate_log2.0.len()).sum();
+        println!("{}: {} of {} succeeded, {:.1} guesses, {:.1} victim calls and {:.1} probes per attack",
+                 name, summary.successes, summary.iterations, summary.guesses_needed as f64 / iterations as f64,
+                 summary.victim_interactions as f64 / iterations as f64, summary.set_evictions as f64 / iterations as f64);
+        // Every probe is one set eviction and one candidate-tracking point, unless the attack only guessed.
+        let consistent = if summary.set_evictions == 0 {probes == 0 && summary.victim_interactions == summary.guesses_needed && candidate_points == summary.guesses_needed}
+            else {probes == summary.set_evictions && candidate_points == summary.set_evictions};
+        println!("  Stats pipeline {}", if consistent {"consistent"} else {"INCONSISTENT"});
+    }
+}
+
+/// Attacks victims whose buffer is striped across superblocks, so the secret line's companions are victim memory
+/// rather than attacker-controlled zeros, and reports how often the secret is recovered for each kind of header
+/// and each probe strategy.
+pub fn simulate_striped_buffers(secret_size: usize, iterations: usize) {
+    let layouts = [
+        BufferLayout::Contiguous,
+        BufferLayout::Striped {stride: 3, header: HeaderLine::Untouched},
+        BufferLayout::Striped {stride: 3, header: HeaderLine::Zeros},
+        BufferLayout::Striped {stride: 3, header: HeaderLine::Random}
+    ];
+    for layout in layouts {
+        for probe in [ProbeStrategy::SecretThenCompanion, ProbeStrategy::SecretOnly] {
+            let policy = RetryPolicy {first: AttackConfig {probe, ..AttackConfig::default()}, alternates: Vec::new()};
+            let successes = (0..iterations).into_par_iter().filter(|_| {
+                let mut victim = VictimProgramYACC::new(secret_size, Compressor::CPACK, false);
+                victim.set_layout(layout);
+                attack_yacc_cpack_with_retries(&mut victim, secret_size, &policy, false).success
+            }).count();
+            println!("{:?} with {:?}: {} of {} attacks succeeded", layout, probe, successes, iterations);
+        }
+    }
+}
+
+/// Compares probe address policies: how often flushes hit lines left over from earlier probes, and whether that
+/// changes the attack's success rate.
+pub fn compare_probe_addresses(secret_size: usize, iterations: usize) {
+    let policies = [
+        ProbeAddressPolicy::Fixed,
+        ProbeAddressPolicy::Fresh,
+        ProbeAddressPolicy::Pool {size: 8},
+        ProbeAddressPolicy::Pool {size: 12}
+    ];
+    for addresses in policies {
+        let policy = RetryPolicy {first: AttackConfig {addresses, ..AttackConfig::default()}, alternates: Vec::new()};
+        let results: Vec<AttackStats> = (0..iterations).into_par_iter().map(|_|
+            attack_yacc_cpack_with_retries(&mut VictimProgramYACC::new(secret_size, Compressor::CPACK, false), secret_size, &policy, false)
+        ).collect();
+        let summary = summarize(&results);
+        println!("{:?}: {} of {} attacks succeeded, {:.2} flush hits per probe", addresses, summary.successes, iterations,
+                 summary.flush_hits as f64 / summary.set_evictions.max(1) as f64);
+    }
+}
+
+/// Studies how the flush set's shape interacts with set occupancy: flush lines that share superblocks and compress well
+/// get co-allocated with each other, so the same number of flush lines fills fewer entries and may leave victim lines
+/// cached. Reports success rates, flush hits and how the probes went wrong.
+pub fn compare_flush_sets(secret_size: usize, iterations: usize) {
+    let contents = [FlushLineContents::Zeros, FlushLineContents::Sized {bits: 128}, FlushLineContents::Sized {bits: 256}, FlushLineContents::Sized {bits: 512}];
+    for lines_per_superblock in [1, 2, 4] {
+        for lines in [8, 16] {
+            for contents in contents {
+                let flush = FlushSet {lines: Some(lines), lines_per_superblock, contents};
+                let policy = RetryPolicy {first: AttackConfig {flush, ..AttackConfig::default()}, alternates: Vec::new()};
+                let results: Vec<AttackStats> = (0..iterations).into_par_iter().map(|_|
+                    attack_yacc_cpack_with_retries(&mut VictimProgramYACC::new(secret_size, Compressor::CPACK, false), secret_size, &policy, false)
+                ).collect();
+                let summary = summarize(&results);
+                let false_positives: usize = summary.probe_outcomes.iter().map(|x| x.false_positives).sum();
+                let false_negatives: usize = summary.probe_outcomes.iter().map(|x| x.false_negatives).sum();
+                println!("{} lines, {} per superblock, {:?}: {} of {} attacks succeeded, {:.2} flush hits per probe, {} false positives, {} false negatives",
+                         lines, lines_per_superblock, contents, summary.successes, iterations,
+                         summary.flush_hits as f64 / summary.set_evictions.max(1) as f64, false_positives, false_negatives);
+            }
+        }
+    }
+}
+
+/// Measures the collateral damage an attack does to the victim's own cache performance: the victim's load hit rate
+/// and misses during the attack, against a replay of the same victim accesses with no attacker in the cache.
+pub fn measure_victim_disturbance(secret_size: usize, iterations: usize) {
+    let policy = RetryPolicy::default();
+    let results: Vec<(VictimCacheStats, VictimCacheStats)> = (0..iterations).into_par_iter().map(|_| {
+        let mut victim = VictimProgramYACC::new(secret_size, Compressor::CPACK, false);
+        victim.enable_access_trace();
+        attack_yacc_cpack_with_retries(&mut victim, secret_size, &policy, false);
+        let baseline = victim.baseline_cache_stats().unwrap();
+        (victim.cache_stats(), VictimCacheStats {loads: baseline.reads as u64, load_hits: baseline.hits as u64})
+    }).collect();
+    let total = |stats: &mut dyn Iterator<Item = VictimCacheStats>| stats.fold(VictimCacheStats::default(),
+        |x, y| VictimCacheStats {loads: x.loads + y.loads, load_hits: x.load_hits + y.load_hits});
+    let attacked = total(&mut results.iter().map(|x| x.0));
+    let baseline = total(&mut results.iter().map(|x| x.1));
+    println!("Victim loads: {} under attack, {} in the baseline", attacked.loads, baseline.loads);
+    println!("Victim hit rate: {:.2}% under attack, {:.2}% without the attacker", attacked.hit_rate() * 100.0, baseline.hit_rate() * 100.0);
+    let (attacked_misses, baseline_misses) = (attacked.loads - attacked.load_hits, baseline.loads - baseline.load_hits);
+    println!("Victim misses: {} under attack, {} without the attacker ({:.1} extra misses per attack)",
+             attacked_misses, baseline_misses, (attacked_misses as f64 - baseline_misses as f64) / iterations as f64);
+}
+
+/// The stealth/cost tradeoff: attacks victims watched by a miss-rate detector, with the attacker pacing itself to
+/// successively tighter miss budgets, and reports detector alarms and how much longer the attack takes in simulated time.
+pub fn stealth_tradeoff(secret_size: usize, iterations: usize) {
+    let (window, max_misses) = (1000, 20);
+    let mut baseline_time: Option<f64> = None;
+    for budget in [None, Some(20), Some(10), Some(4)] {
+        let stealth = budget.map(|max_victim_misses| StealthBudget {window, max_victim_misses});
+        let policy = RetryPolicy {first: AttackConfig {stealth, ..AttackConfig::default()}, alternates: Vec::new()};
+        let results: Vec<(bool, u64, u64)> = (0..iterations).into_par_iter().map(|_| {
+            let mut victim = VictimProgramYACC::new(secret_size, Compressor::CPACK, false);
+            victim.attach_detector(MissRateDetector::new(window, max_misses));
+            let stats = attack_yacc_cpack_with_retries(&mut victim, secret_size, &policy, false);
+            (stats.success, victim.now(), victim.detector().unwrap().alarms())
+        }).collect();
+        let successes = results.iter().filter(|x| x.0).count();
+        let mean_time = results.iter().map(|x| x.1 as f64).sum::<f64>() / iterations as f64;
+        let alarms: u64 = results.iter().map(|x| x.2).sum();
+        let slowdown = mean_time / *baseline_time.get_or_insert(mean_time);
+        println!("Budget {:?} misses per {} ticks: {} of {} attacks succeeded, {} alarms, {:.0} ticks per attack ({:.1}x slowdown)",
+                 budget, window, successes, iterations, alarms, mean_time, slowdown);
+    }
+}
+
+/// Attacks victims that share their cache set with a noisy co-tenant, and reports how each interference mitigation holds up:
+/// success rate, probes that came back negative despite testing the right candidates, and buffer relocations.
+pub fn simulate_co_tenant_interference(secret_size: usize, iterations: usize) {
+    let mitigations = [
+        InterferenceMitigation::None,
+        InterferenceMitigation::ProbeRetries {attempts: 3},
+        InterferenceMitigation::SetMigration {canaries: 16, max_migrations: 8}
+    ];
+    for access_probability in [0.0, 0.02, 0.1] {
+        let co_tenant = CoTenantConfig {access_probability, coverage: 0.25};
+        for interference in mitigations {
+            let policy = RetryPolicy {first: AttackConfig {interference, ..AttackConfig::default()}, alternates: Vec::new()};
+            let results: Vec<AttackStats> = (0..iterations).into_par_iter().map(|_| {
+                let mut victim = VictimProgramYACC::new(secret_size, Compressor::CPACK, false);
+                victim.add_co_tenant(co_tenant);
+                attack_yacc_cpack_with_retries(&mut victim, secret_size, &policy, false)
+            }).collect();
+            let summary = summarize(&results);
+            let false_negatives: usize = summary.probe_outcomes.iter().map(|x| x.false_negatives).sum();
+            let migrations: usize = results.iter().map(|x| x.migrations).sum();
+            println!("Co-tenant access probability {} with {:?}: {} of {} attacks succeeded, {} false negatives, {} migrations",
+                     access_probability, interference, summary.successes, iterations, false_negatives, migrations);
+        }
+    }
+}
+
+/// Reports what compression buys on benign workloads: hit rate, hit-rate uplift over an uncompressed cache,
+/// effective capacity, and compressed size distribution. Replays the trace file at trace_path if given,
+/// otherwise each synthetic workload over a working set of 16 lines (twice the uncompressed capacity).
+pub fn evaluate_benign_performance(trace_path: Option<&str>) {
+    if let Some(path) = trace_path {
+        match load_trace(path) {
+            Ok(trace) => report_trace_performance(path, &trace, &[Compressor::CPACK], 0),
+            Err(e) => println!("Could not load trace {}: {}", path, e)
+        }
+        return;
+    }
+    for workload in BenignWorkload::ALL {
+        let trace = workload.generate(16, 100000);
+        report_trace_performance(&format!("{:?}", workload), &trace, &[Compressor::CPACK], 16 * 64);
+    }
+}
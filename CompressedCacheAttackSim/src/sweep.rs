@@ -0,0 +1,112 @@
+use std::io;
+use std::time::Instant;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::attacker::summarize;
+use crate::config::{CacheConfig, ExperimentConfig, VictimConfig};
+use crate::output::write_csv_rows;
+use crate::structures::{ASSOCIATIVITY, Compressor, ReplacementPolicy};
+
+/// The values to sweep each parameter over. A sweep runs every combination of them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SweepGrid {
+    pub secret_sizes: Vec<usize>,
+    pub compressors: Vec<Compressor>,
+    pub associativities: Vec<usize>,
+    pub replacements: Vec<ReplacementPolicy>,
+    pub iterations: Vec<usize>
+}
+
+impl Default for SweepGrid {
+    /// 10000 attacks each on 4- and 8-byte secrets, on the default C-PACK cache.
+    fn default() -> SweepGrid {
+        SweepGrid {
+            secret_sizes: vec![4, 8],
+            compressors: vec![Compressor::CPACK],
+            associativities: vec![ASSOCIATIVITY],
+            replacements: vec![ReplacementPolicy::Lru],
+            iterations: vec![10000]
+        }
+    }
+}
+
+impl SweepGrid {
+    /// Every combination of the grid's values, varying the last parameter fastest.
+    pub fn points(&self) -> Vec<SweepPoint> {
+        let mut points = Vec::new();
+        for &secret_size in &self.secret_sizes {
+            for &compressor in &self.compressors {
+                for &associativity in &self.associativities {
+                    for &replacement in &self.replacements {
+                        for &iterations in &self.iterations {
+                            points.push(SweepPoint {secret_size, compressor, associativity, replacement, iterations});
+                        }
+                    }
+                }
+            }
+        }
+        return points;
+    }
+}
+
+/// One combination of a sweep's parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SweepPoint {
+    pub secret_size: usize,
+    pub compressor: Compressor,
+    pub associativity: usize,
+    pub replacement: ReplacementPolicy,
+    pub iterations: usize
+}
+
+impl SweepPoint {
+    /// The campaign this point stands for: the default attack on a default victim, with the point's parameters.
+    pub fn config(&self) -> ExperimentConfig {
+        return ExperimentConfig {
+            iterations: self.iterations,
+            cache: CacheConfig {compressor: self.compressor, associativity: self.associativity, replacement: self.replacement, ..CacheConfig::default()},
+            victim: VictimConfig {secret_size: self.secret_size, ..VictimConfig::default()},
+            ..ExperimentConfig::default()
+        };
+    }
+}
+
+/// One row of a sweep's results: a point and how its campaign went, with costs averaged per attack.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SweepRow {
+    #[serde(flatten)]
+    pub point: SweepPoint,
+    pub successes: usize,
+    pub success_rate: f64,
+    pub guesses_per_attack: f64,
+    pub probes_per_attack: f64,
+    pub victim_interactions_per_attack: f64,
+    pub bytes_written_per_attack: f64,
+    pub attacker_lines_per_attack: f64,
+    pub seconds: f64
+}
+
+/// Runs the campaign at every point of the grid, the points in parallel, and returns a row for each in grid order.
+pub fn run_sweep(grid: &SweepGrid) -> Vec<SweepRow> {
+    return grid.points().into_par_iter().map(|point| {
+        let start = Instant::now();
+        let summary = summarize(&point.config().run());
+        let per_attack = |total: usize| total as f64 / point.iterations.max(1) as f64;
+        SweepRow {
+            point,
+            successes: summary.successes,
+            success_rate: summary.success_rate(),
+            guesses_per_attack: per_attack(summary.guesses_needed),
+            probes_per_attack: per_attack(summary.set_evictions),
+            victim_interactions_per_attack: per_attack(summary.victim_interactions),
+            bytes_written_per_attack: per_attack(summary.bytes_written_to_victim),
+            attacker_lines_per_attack: per_attack(summary.attacker_cache_lines_loaded),
+            seconds: start.elapsed().as_secs_f64()
+        }
+    }).collect();
+}
+
+/// Writes a sweep's rows as CSV, one row per point with a column for each parameter and each result.
+pub fn export_sweep(path: &str, rows: &[SweepRow]) -> io::Result<()> {
+    return write_csv_rows(path, rows);
+}
@@ -0,0 +1,161 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use rand::Rng;
+use crate::structures::{AccessSpeed, ASSOCIATIVITY, Cache, Compressor, YACC};
+
+/// A single memory operation in a trace.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TraceOp {
+    Read(u64),
+    Write(u64, u8)
+}
+
+/// Parses a text trace. Each non-empty line is either `R <addr>` or `W <addr> <byte>`, with hex operands.
+/// Lines starting with '#' are comments.
+pub fn parse_trace(text: &str) -> Result<Vec<TraceOp>, String> {
+    let mut ops = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {continue;}
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let hex = |s: &str| u64::from_str_radix(s.trim_start_matches("0x"), 16)
+            .map_err(|e| format!("line {}: bad operand {:?}: {}", number + 1, s, e));
+        let op = match fields.as_slice() {
+            ["R", addr] | ["r", addr] => TraceOp::Read(hex(addr)?),
+            ["W", addr, value] | ["w", addr, value] => {
+                let value = hex(value)?;
+                if value > 0xFF {return Err(format!("line {}: value {:#x} does not fit in a byte", number + 1, value));}
+                TraceOp::Write(hex(addr)?, value as u8)
+            },
+            _ => return Err(format!("line {}: expected \"R <addr>\" or \"W <addr> <byte>\"", number + 1))
+        };
+        ops.push(op);
+    }
+    return Ok(ops);
+}
+
+/// Reads and parses a trace file.
+pub fn load_trace(path: impl AsRef<Path>) -> io::Result<Vec<TraceOp>> {
+    let text = fs::read_to_string(path)?;
+    return parse_trace(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e));
+}
+
+/// Synthetic benign workloads, for when no recorded trace is at hand.
+/// Each one initializes a working set of lines with data of a given compressibility, then reads and
+/// occasionally rewrites random bytes of it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BenignWorkload {
+    /// Mostly zero-initialized memory with a few scattered nonzero bytes.
+    ZeroHeavy,
+    /// Arrays of small 32-bit integers, whose upper bytes are zero.
+    SmallIntegers,
+    /// Uniformly random bytes, which C-PACK cannot compress.
+    RandomBytes
+}
+
+impl BenignWorkload {
+    pub const ALL: [BenignWorkload; 3] = [BenignWorkload::ZeroHeavy, BenignWorkload::SmallIntegers, BenignWorkload::RandomBytes];
+
+    fn initial_byte(&self, offset: usize, rng: &mut impl Rng) -> u8 {
+        return match self {
+            BenignWorkload::ZeroHeavy => if rng.gen_ratio(1, 16) {rng.gen()} else {0},
+            BenignWorkload::SmallIntegers => if offset.is_multiple_of(4) {rng.gen()} else {0},
+            BenignWorkload::RandomBytes => rng.gen()
+        };
+    }
+
+    /// Generates a trace over working_set_lines consecutive lines, with the given number of accesses after initialization.
+    /// One access in eight is a write of a byte consistent with the workload's data pattern.
+    pub fn generate(&self, working_set_lines: u64, accesses: usize) -> Vec<TraceOp> {
+        let mut rng = rand::thread_rng();
+        let mut ops = Vec::new();
+        for addr in 0..working_set_lines * 64 {
+            ops.push(TraceOp::Write(addr, self.initial_byte((addr % 64) as usize, &mut rng)));
+        }
+        for _ in 0..accesses {
+            let addr = rng.gen_range(0..working_set_lines * 64);
+            if rng.gen_ratio(1, 8) {
+                ops.push(TraceOp::Write(addr, self.initial_byte((addr % 64) as usize, &mut rng)));
+            } else {
+                ops.push(TraceOp::Read(addr));
+            }
+        }
+        return ops;
+    }
+}
+
+/// Performance of one cache configuration on one trace.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayReport {
+    pub compressor: Compressor,
+    pub accesses: usize,
+    /// Writes carry no timing in this model, so the hit rate is measured over reads only.
+    pub reads: usize,
+    pub hits: usize,
+    /// Lines resident in the cache, summed after every access. Divide by accesses for the average.
+    pub resident_line_sum: usize,
+    /// Compressed size classes of the lines touched: at most 16 bytes, at most 32 bytes, and larger.
+    /// These are the thresholds at which YACC co-allocates four and two blocks per entry.
+    pub size_classes: [usize; 3]
+}
+
+impl ReplayReport {
+    pub fn hit_rate(&self) -> f64 {
+        return self.hits as f64 / self.reads.max(1) as f64;
+    }
+
+    /// Average number of lines held by the cache, i.e. its effective capacity in lines.
+    pub fn effective_capacity(&self) -> f64 {
+        return self.resident_line_sum as f64 / self.accesses.max(1) as f64;
+    }
+}
+
+/// Replays a trace against a fresh cache using the given compressor. Only the accesses after the first
+/// warmup operations are counted, so that initialization writes do not dominate the statistics.
+pub fn replay(trace: &[TraceOp], compressor: Compressor, warmup: usize) -> ReplayReport {
+    return replay_on(trace, YACC::new(compressor, ASSOCIATIVITY), warmup);
+}
+
+/// Same as replay, but on a cache that has already been configured (e.g. with a defense enabled).
+pub fn replay_on(trace: &[TraceOp], mut cache: YACC, warmup: usize) -> ReplayReport {
+    let compressor = cache.compressor();
+    let mut report = ReplayReport {compressor, accesses: 0, reads: 0, hits: 0, resident_line_sum: 0, size_classes: [0; 3]};
+    for (i, op) in trace.iter().enumerate() {
+        let (addr, speed) = match *op {
+            TraceOp::Read(addr) => (addr, Some(cache.read_byte(addr).1)),
+            TraceOp::Write(addr, value) => {
+                cache.write_byte(addr, value);
+                (addr, None)
+            }
+        };
+        if i < warmup {continue;}
+        report.accesses += 1;
+        if let Some(speed) = speed {
+            report.reads += 1;
+            if speed == AccessSpeed::HIT {report.hits += 1;}
+        }
+        report.resident_line_sum += cache.resident_lines();
+        let size = cache.compress_bytes(addr >> 6);
+        report.size_classes[if size <= 16 {0} else if size <= 32 {1} else {2}] += 1;
+    }
+    return report;
+}
+
+/// Replays a trace on an uncompressed baseline and on each given compressor, and prints hit rate, hit-rate uplift over
+/// the baseline, effective capacity, and the compressed size distribution of the lines touched.
+pub fn report_trace_performance(name: &str, trace: &[TraceOp], compressors: &[Compressor], warmup: usize) {
+    let baseline = replay(trace, Compressor::NONE, warmup);
+    println!("{} ({} measured accesses):", name, baseline.accesses);
+    for report in std::iter::once(baseline.clone()).chain(compressors.iter().map(|&c| replay(trace, c, warmup))) {
+        let total = report.accesses.max(1) as f64;
+        println!("  {:?}: hit rate {:.2}% (uplift {:+.2}%), effective capacity {:.2} lines, size classes <=16B {:.1}% / <=32B {:.1}% / >32B {:.1}%",
+                 report.compressor,
+                 report.hit_rate() * 100.0,
+                 (report.hit_rate() - baseline.hit_rate()) * 100.0,
+                 report.effective_capacity(),
+                 report.size_classes[0] as f64 / total * 100.0,
+                 report.size_classes[1] as f64 / total * 100.0,
+                 report.size_classes[2] as f64 / total * 100.0);
+    }
+}
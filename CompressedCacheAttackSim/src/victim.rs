@@ -1,102 +1,1518 @@
+//! The victim: a program keeping a secret at the end of a buffer on the shared cache, and the calls it lets attackers
+//! make. Ground truth about the secret is only reachable through a Judge, or in builds without the no-oracle feature.
+
 use std::collections::HashSet;
-use crate::structures::{Cache, Compressor, YACC};
-use rand::random;
+use crate::structures::{AccessRecord, AccessSpeed, ASSOCIATIVITY, Cache, Compressor, Endianness, Footprint, PatternCode, PrivateCache, ReencodingDefense, RegionGranularity, ReplacementPolicy, WritePolicy, YACC};
+#[cfg(not(feature = "no-oracle"))]
+use crate::structures::{cpack_words, WayState};
+use crate::traces::{replay, ReplayReport, TraceOp};
+use crate::detector::MissRateDetector;
+use crate::attacker::AttackStats;
+use crate::keyschedule;
+use crate::aliasing::{analyze_aliasing, AliasingReport, CacheGeometry};
+use crate::clock::{LatencyModel, SimClock};
+use crate::streams::RngStream;
+use crate::defenses::{RandomizedIndexing, SizeQuantization};
+use rand::{random, Rng, SeedableRng};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+
+pub const BUFFER_SIZE: usize = 256;
+
+/// The attacker-facing victim API calls that can show up in the audit log.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum AuditOp {WRITE, READ, MEMCPY, VALIDATE, RELOCATE, ALLOCATE}
+
+/// One attacker-facing API call made against the victim.
+/// timestamp counts API calls since the victim was created.
+/// value is the byte written or returned, if any. accepted is false if the victim refused the call.
+#[derive(Debug, Clone, Copy)]
+pub struct AuditEntry {
+    pub timestamp: u64,
+    pub op: AuditOp,
+    pub offset: usize,
+    pub value: Option<u8>,
+    pub accepted: bool
+}
+
+/// Private cache configuration for the cross-core threat model, where the victim and the attacker run on different cores,
+/// each with its own L1, and only share the compressed cache.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CrossCoreConfig {
+    /// Number of lines held by each core's private L1.
+    pub l1_lines: usize,
+    /// Whether the shared cache is inclusive of the L1s, i.e. evicting a line from it also evicts the line from both L1s.
+    pub inclusive: bool
+}
+
+/// The private L1s of the victim's core and the attacker's core.
+pub struct PrivateCaches {
+    victim_l1: PrivateCache,
+    attacker_l1: PrivateCache,
+    inclusive: bool
+}
+
+impl PrivateCaches {
+    /// Enforces inclusion after an access to the shared cache.
+    fn back_invalidate(&mut self, cache: &mut YACC) {
+        if !self.inclusive {return;}
+        for line in cache.take_evictions() {
+            self.victim_l1.invalidate(line);
+            self.attacker_l1.invalidate(line);
+        }
+    }
+}
+
+/// A per-core performance counter of the attacker's loads that miss in the shared cache, as perf would read it.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MissCounter {
+    misses: u64,
+    /// The probability that something else on the attacker's core (an interrupt, a page walk) adds a miss between two reads.
+    noise: f64
+}
+
+/// The attacker's view of the memory system: its own core's private cache (if any) in front of the shared cache.
+/// Its accesses take simulated time just like the victim's.
+pub struct AttackerPort<'a> {
+    cache: &'a mut YACC,
+    private_caches: Option<&'a mut PrivateCaches>,
+    clock: &'a mut SimClock,
+    timing_noise: f64,
+    miss_counter: &'a mut MissCounter,
+    rng: &'a mut StdRng
+}
+
+impl AttackerPort<'_> {
+    /// Evicts the attacker's own private cache by walking an eviction set of lines that share its L1 set
+    /// but map to other sets of the shared cache, so the simulated shared set is left untouched.
+    /// Returns the number of lines loaded (zero if there is no private cache).
+    pub fn evict_private_cache(&mut self) -> usize {
+        return match &mut self.private_caches {
+            Some(caches) => {
+                caches.attacker_l1.clear();
+                caches.attacker_l1.capacity()
+            },
+            None => 0
+        };
+    }
+
+    /// Fills one of the attacker's own lines with the given contents before using it, without touching the cache
+    /// (as an attacker would by setting up its buffers long before the attack).
+    pub fn initialize_line(&mut self, line_addr: u64, contents: &[u8]) {
+        self.cache.initialize_line(line_addr, contents);
+    }
+
+    /// Returns the number of entries in each of the shared cache's sets, which the attacker is assumed to know.
+    pub fn associativity(&self) -> usize {
+        return self.cache.associativity();
+    }
+
+    /// Returns the number of sets in the shared cache, which the attacker is assumed to know.
+    pub fn set_count(&self) -> usize {
+        return self.cache.set_count();
+    }
+
+    /// Returns the address of the k-th superblock at or after base that maps to the given set, as YACC::superblock_in_set.
+    pub fn superblock_in_set(&self, base: u64, k: u64, set: usize) -> u64 {
+        return self.cache.superblock_in_set(base, k, set);
+    }
+
+    /// Returns the size of the shared cache's superblocks in bytes, which the attacker is assumed to know.
+    pub fn superblock_bytes(&self) -> u64 {
+        return self.cache.superblock_bytes();
+    }
+
+    /// Returns the shared cache's line size in bytes, which the attacker is assumed to know.
+    pub fn line_size(&self) -> usize {
+        return self.cache.line_size();
+    }
+
+    /// Returns the address of the line holding a byte, in the shared cache's line size.
+    pub fn line_of(&self, byte_addr: u64) -> u64 {
+        return self.cache.line_of(byte_addr);
+    }
+
+    /// Whether a superblock may hold the victim's or its co-tenant's memory, as in_victim_memory on the shared cache.
+    pub fn in_victim_memory(&self, superblock: u64) -> bool {
+        return in_victim_memory(superblock, self.cache.set_count(), self.cache.superblock_bytes());
+    }
+
+    /// Checks whether the shared cache holds a byte's line, as a residency side channel (such as a cache occupancy
+    /// counter) would tell, without loading it or taking any time.
+    pub fn is_resident(&self, byte_addr: u64) -> bool {
+        return self.cache.is_cached(self.cache.line_of(byte_addr));
+    }
+
+    /// Returns the cycles a victim load takes when it hits a line stored alone and when it hits a co-allocated one, as
+    /// YACC::hit_latencies. The attacker is assumed to know them, as it would after timing hits on its own lines.
+    pub fn hit_latencies(&self) -> (u64, u64) {
+        return self.cache.hit_latencies();
+    }
+
+    /// Reads the attacker's miss counter. Unlike load timings, the count itself is exact, but other events on the core
+    /// can add to it between reads.
+    pub fn read_miss_counter(&mut self) -> u64 {
+        if self.miss_counter.noise > 0.0 && self.rng.gen_bool(self.miss_counter.noise) {self.miss_counter.misses += 1;}
+        return self.miss_counter.misses;
+    }
+
+    /// Misreads the timing of a load with the victim's timing noise probability. The cache itself is not affected.
+    fn observe(&mut self, speed: AccessSpeed) -> AccessSpeed {
+        if self.timing_noise == 0.0 || !self.rng.gen_bool(self.timing_noise) {return speed;}
+        return if speed == AccessSpeed::HIT {AccessSpeed::MISS} else {AccessSpeed::HIT};
+    }
+}
+
+impl Cache for AttackerPort<'_> {
+    fn read_byte(&mut self, byte_addr: u64) -> (u8, AccessSpeed) {
+        if let Some(caches) = &mut self.private_caches {
+            if caches.attacker_l1.access(self.cache.line_of(byte_addr)) == AccessSpeed::HIT {
+                self.clock.load(AccessSpeed::HIT);
+                return (self.cache.peek_byte(byte_addr), self.observe(AccessSpeed::HIT));
+            }
+        }
+        let (byte, speed, cycles) = self.cache.read_byte_timed(byte_addr);
+        self.clock.load_timed(speed, cycles);
+        if speed == AccessSpeed::MISS {self.miss_counter.misses += 1;}
+        if let Some(caches) = &mut self.private_caches {caches.back_invalidate(self.cache);}
+        return (byte, self.observe(speed));
+    }
 
-const BUFFER_SIZE: usize = 256;
+    fn write_byte(&mut self, byte_addr: u64, data: u8) {
+        // The private caches are write-through, so every store reaches the shared cache.
+        self.cache.write_byte(byte_addr, data);
+        self.clock.store();
+        if let Some(caches) = &mut self.private_caches {
+            caches.back_invalidate(self.cache);
+            caches.attacker_l1.access(self.cache.line_of(byte_addr));
+        }
+    }
+}
+
+/// The secret line's compressed size right after an attacker write.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimelinePoint {
+    /// The API call (as counted by the audit log) that made the write.
+    pub timestamp: u64,
+    /// The buffer offset written, or the start of the message for whole-message stores.
+    pub offset: usize,
+    pub compressed_bits: u64,
+    /// Whether the line is small enough to share a cache entry with a companion block.
+    pub fits_double: bool
+}
+
+/// Which buffer operations the victim offers to the attacker.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum VictimInterface {
+    /// The attacker may read and write any non-secret byte of the buffer.
+    ReadWrite,
+    /// A compression-oracle service: the attacker may store data, but the victim never returns any.
+    /// All leakage has to come through the cache timing channel.
+    StoreOnly
+}
+
+/// What the victim keeps in the first block of its secret line's superblock when its buffer is striped.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum HeaderLine {
+    /// The block is an unused hole that the victim never touches.
+    Untouched,
+    /// The block is a zero-initialized header (e.g. ring buffer indices) that the victim reads on every API call.
+    Zeros,
+    /// The block is a header full of incompressible victim data that the victim reads on every API call.
+    Random
+}
+
+/// How the victim's 256-byte buffer is laid out in memory.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum BufferLayout {
+    /// The buffer fills one whole superblock, so the attacker controls every companion of the secret line.
+    Contiguous,
+    /// Each 64-byte line of the buffer sits in its own superblock, stride superblocks apart, at the block it would have
+    /// in a contiguous buffer. The rest of each superblock is victim memory the attacker cannot write, so the secret
+    /// line's only possible companions are the victim's own blocks, such as its header.
+    Striped {stride: u64, header: HeaderLine}
+}
+
+/// Where the secret's bytes sit in the secret line.
+#[derive(Debug, Default, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum SecretPlacement {
+    /// The secret fills whole words of the line.
+    #[default]
+    Contiguous,
+    /// Every word of the secret's span holds two secret bytes in its low half, and the attacker may write its high half
+    /// (the word's leading short), as with a packed array of 2-byte secrets and 2-byte attacker-chosen tags.
+    Interleaved
+}
+
+impl SecretPlacement {
+    /// The number of bytes of the line taken up by the words holding a secret of secret_size bytes.
+    pub fn span(&self, secret_size: usize) -> usize {
+        return match self {
+            SecretPlacement::Contiguous => secret_size,
+            SecretPlacement::Interleaved => 2 * secret_size
+        };
+    }
+
+    /// The buffer offset of byte i of a secret whose words start at buffer offset start.
+    pub fn offset(&self, start: usize, i: usize) -> usize {
+        return match self {
+            SecretPlacement::Contiguous => start + i,
+            SecretPlacement::Interleaved => start + (i / 2) * 4 + i % 2
+        };
+    }
+}
+
+/// The values a secret's bytes are drawn from. Whatever the alphabet, bytes are never zero and never repeat, unless the
+/// secret is drawn with SecretBytes::Arbitrary.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum SecretDistribution {
+    /// Any byte, as for a random key.
+    #[default]
+    UniformBytes,
+    /// Printable ASCII (0x20 to 0x7E), as for a password.
+    PrintableAscii,
+    /// Lowercase hex digits, as for a hex-encoded token.
+    HexDigits,
+    /// The standard base64 alphabet, as for an encoded API key.
+    Base64
+}
+
+impl SecretDistribution {
+    /// Whether a secret byte can take this value.
+    pub fn allows(&self, byte: u8) -> bool {
+        return byte != 0 && match self {
+            SecretDistribution::UniformBytes => true,
+            SecretDistribution::PrintableAscii => (0x20..=0x7E).contains(&byte),
+            SecretDistribution::HexDigits => byte.is_ascii_digit() || (b'a'..=b'f').contains(&byte),
+            SecretDistribution::Base64 => byte.is_ascii_alphanumeric() || byte == b'+' || byte == b'/'
+        };
+    }
+
+    /// Every value a secret byte can take, in increasing order.
+    pub fn alphabet(&self) -> Vec<u8> {
+        return (0x01..=0xFF).filter(|&byte| self.allows(byte)).collect();
+    }
+
+    /// Same as allows, when the secret's bytes are restricted as given. Only uniform bytes can be zero.
+    pub fn allows_under(&self, byte: u8, bytes: SecretBytes) -> bool {
+        if byte == 0 {return bytes == SecretBytes::Arbitrary && *self == SecretDistribution::UniformBytes;}
+        return self.allows(byte);
+    }
+
+    /// Same as alphabet, when the secret's bytes are restricted as given.
+    pub fn alphabet_under(&self, bytes: SecretBytes) -> Vec<u8> {
+        return (0x00..=0xFF).filter(|&byte| self.allows_under(byte, bytes)).collect();
+    }
+}
+
+/// Which secrets a victim draws from its distribution. The attack's bit budgets were worked out for secrets of unique
+/// non-zero bytes, whose words never compress on their own or against each other, so drawing only those biases results.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum SecretBytes {
+    /// No byte is zero, and no two bytes are the same.
+    #[default]
+    UniqueNonZero,
+    /// Every byte is drawn independently, so bytes may repeat, and uniform bytes may be zero.
+    Arbitrary
+}
+
+/// The victim's own loads and how many of them hit, counting hits in its private cache if it has one.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct VictimCacheStats {
+    pub loads: u64,
+    pub load_hits: u64
+}
+
+impl VictimCacheStats {
+    pub fn hit_rate(&self) -> f64 {
+        return self.load_hits as f64 / self.loads.max(1) as f64;
+    }
+}
+
+/// A co-tenant process that uses some of the cache's sets for its own, unrelated data.
+/// Whenever the victim serves an API call while its buffer sits in a set the co-tenant uses, the co-tenant touches one
+/// of its own lines in that set with probability access_probability.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CoTenantConfig {
+    pub access_probability: f64,
+    /// The fraction of sets the co-tenant uses, i.e. the chance that a relocated buffer still shares a set with it.
+    pub coverage: f64
+}
+
+/// Start of the co-tenant's address space, away from both victim buffers and the attacker's lines.
+const CO_TENANT_REGION: u64 = 3 << 48;
+
+/// Start of the victim's own data that its background accesses touch, away from its buffers and everyone else's lines.
+const BACKGROUND_REGION: u64 = 4 << 48;
+
+/// The most lines per set that background accesses spread over.
+pub const MAX_BACKGROUND_LINES: u64 = 16;
+
+/// Victim buffers and heap chunks start below this address.
+const VICTIM_REGION_END: u64 = 1 << 48;
+
+/// The largest stride of a striped buffer, in superblocks, which bounds how far past its base a buffer can reach.
+pub const MAX_STRIDE: u64 = 1 << 16;
+
+/// Whether a superblock lies in memory the victim or its co-tenant may ever use, on a cache with the given number of sets.
+/// This is the machine's memory map, not where the victim happens to be, so an attacker can rely on it to keep its own
+/// lines apart from the victim's.
+pub fn in_victim_memory(superblock: u64, sets: usize, superblock_bytes: u64) -> bool {
+    let buffers = superblock * superblock_bytes < VICTIM_REGION_END + 4 * MAX_STRIDE * 256;
+    let co_tenant_start = (CO_TENANT_REGION / superblock_bytes).next_multiple_of(sets as u64);
+    let co_tenant = (CO_TENANT_REGION / superblock_bytes..co_tenant_start + 16 * sets as u64).contains(&superblock);
+    let background_start = (BACKGROUND_REGION / superblock_bytes).next_multiple_of(sets as u64);
+    let background = (BACKGROUND_REGION / superblock_bytes..background_start + MAX_BACKGROUND_LINES * sets as u64).contains(&superblock);
+    return buffers || co_tenant || background;
+}
+
+struct CoTenant {
+    config: CoTenantConfig,
+    shares_set: bool
+}
+
+/// Caps on the buffer writes and reads the victim serves, and the calls served and refused so far.
+#[derive(Debug, Clone, Copy, Default)]
+struct AccessBudget {
+    max_writes: Option<u64>,
+    max_reads: Option<u64>,
+    writes: u64,
+    reads: u64,
+    refused: u64
+}
+
+impl AccessBudget {
+    /// Takes one call out of the budget, or refuses it if the given count has reached its cap.
+    fn spend(used: &mut u64, max: Option<u64>, refused: &mut u64) -> bool {
+        if max.is_some_and(|max| *used >= max) {
+            *refused += 1;
+            return false;
+        }
+        *used += 1;
+        return true;
+    }
+
+    fn spend_write(&mut self) -> bool {
+        return AccessBudget::spend(&mut self.writes, self.max_writes, &mut self.refused);
+    }
+
+    fn spend_read(&mut self) -> bool {
+        return AccessBudget::spend(&mut self.reads, self.max_reads, &mut self.refused);
+    }
+}
+
+/// Work the victim does for itself (serving other requests, bookkeeping) in between the attacker's calls, on data of
+/// its own that sits in the same set as the secret line. On every API call, the victim makes a background access with
+/// probability access_probability, to a random byte of one of lines lines (each in its own superblock).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BackgroundNoiseConfig {
+    pub access_probability: f64,
+    /// The fraction of background accesses that write a random value (changing how well their line compresses) instead of reading.
+    pub write_fraction: f64,
+    /// How many lines the accesses spread over, up to MAX_BACKGROUND_LINES.
+    pub lines: u64
+}
+
+/// Work the victim's machine does on its own schedule, whatever the attacker is doing.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum VictimActivity {
+    /// The co-tenant, if there is one, gets a chance to touch its line (as it also does on every API call).
+    CoTenantAccess,
+    /// The victim reads its secret line, e.g. while serving another client.
+    ReadSecret,
+    /// The victim moves its buffer elsewhere in memory, as a buffer rotation defense would.
+    Relocate
+}
+
+/// A victim that keeps its secret in a heap allocation and the attacker's data in chunks of the same heap, instead of
+/// handing the attacker a buffer that is known to hold the secret. Every chunk the attacker allocates covers the writable
+/// bytes of a buffer-shaped region, but only lands next to the secret allocation (sharing its line and superblock)
+/// with probability adjacency_probability; otherwise it lands in an unrelated region of the heap.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HeapConfig {
+    pub adjacency_probability: f64
+}
+
+/// A victim activity that recurs every period ticks of simulated time, starting period ticks after it is scheduled.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ScheduledActivity {
+    pub period: u64,
+    pub activity: VictimActivity
+}
+
+/// Returns the physical address of byte index of a buffer at base with the given layout.
+pub fn buffer_address(base: u64, layout: BufferLayout, index: usize) -> u64 {
+    return match layout {
+        BufferLayout::Contiguous => base + index as u64,
+        BufferLayout::Striped {stride, ..} => base + (index / 64) as u64 * stride * 256 + index as u64
+    };
+}
+
+/// Holds a dead victim's secret, to score the answers an attack submitted while the victim was alive.
+pub struct Judge {
+    secret: Vec<u8>
+}
+
+impl Judge {
+    pub fn secret(&self) -> &[u8] {
+        return &self.secret;
+    }
+
+    /// Marks stats as a success if one of the attack's answers is the secret, counting the answers up to it as guesses.
+    /// Stats without answers (from attacks that validated their own guesses) are left alone.
+    pub fn score(&self, stats: &mut AttackStats) {
+        if stats.answers.is_empty() {return;}
+        let correct = stats.answers.iter().position(|a| a.len() >= self.secret.len() && a[..self.secret.len()] == self.secret[..]);
+        stats.guesses_needed += correct.map_or(stats.answers.len(), |i| i + 1);
+        if let Some(i) = correct {
+            stats.success = true;
+            stats.secret = stats.answers[i].clone();
+        }
+    }
+}
 
 pub struct VictimProgramYACC {
     cache: YACC, // Probably needs to be RefCell since both attacker and victim will modify
     secret: Vec<u8>,
+    placement: SecretPlacement,
+    /// The buffer offset of the secret's first word, if it was moved away from the end of the buffer.
+    secret_offset: Option<usize>,
     buffer_base: u64,
-    verbose: bool
+    verbose: bool,
+    api_calls: u64,
+    audit_log: Option<Vec<AuditEntry>>,
+    private_caches: Option<PrivateCaches>,
+    interface: VictimInterface,
+    layout: BufferLayout,
+    cache_stats: VictimCacheStats,
+    /// The victim's own accesses, if tracing is enabled, starting with writes that recreate its buffer.
+    access_trace: Option<Vec<TraceOp>>,
+    trace_warmup: usize,
+    clock: SimClock,
+    /// Each scheduled activity and the time it is next due.
+    activities: Vec<(ScheduledActivity, u64)>,
+    detector: Option<MissRateDetector>,
+    co_tenant: Option<CoTenant>,
+    background_noise: Option<BackgroundNoiseConfig>,
+    background_accesses: u64,
+    /// Buffer offsets the attacker cannot write, and the values the victim keeps there.
+    read_only: Vec<(usize, u8)>,
+    compressibility_timeline: Option<Vec<TimelinePoint>>,
+    /// The heap model, if enabled, and the base of the region holding the attacker's current chunk.
+    heap: Option<(HeapConfig, u64)>,
+    /// Canary bytes kept just before the secret by clamp_compressibility, as private as the secret itself.
+    canaries: Vec<u8>,
+    /// The granularity at which the memory around the secret line is marked as never compressed, if it is.
+    uncompressed_region: Option<RegionGranularity>,
+    /// The probability that the attacker misreads the timing of any one of its loads.
+    timing_noise: f64,
+    miss_counter: MissCounter,
+    /// Drives the victim's own random choices, so that victims built from the same seed behave the same.
+    rng: StdRng,
+    /// Drives the noise in what the attacker observes, apart from rng so that enabling noise leaves the victim's choices alone.
+    noise_rng: StdRng,
+    /// Drives the co-tenant, for the same reason.
+    co_tenant_rng: StdRng,
+    /// Drives the background accesses, for the same reason.
+    background_rng: StdRng,
+    access_budget: AccessBudget,
+    /// Relocate the buffer after every this many buffer operations from the attacker, if set (see set_rebase_period).
+    rebase_period: Option<u64>,
+    attacker_operations: u64,
+    relocations: u64,
+    seed: u64
 }
 
 impl VictimProgramYACC {
-    /// Makes a new victim program.
+    /// Makes a new victim program, running on an LRU cache.
     pub fn new(secret_length: usize, compressor: Compressor, verbose: bool) -> VictimProgramYACC {
-        let mut victim = VictimProgramYACC {
-            cache: YACC::new(compressor),
-            secret: Vec::new(),
-            buffer_base: random::<u64>() & 0x0000FFFF_FFFF0000u64,
-            verbose
-        };
+        return VictimProgramYACC::new_with_associativity(secret_length, compressor, ASSOCIATIVITY, verbose);
+    }
+
+    /// Makes a new victim program, running on a cache with the given replacement policy.
+    pub fn new_with_replacement(secret_length: usize, compressor: Compressor, replacement: ReplacementPolicy, verbose: bool) -> VictimProgramYACC {
+        let mut victim = VictimProgramYACC::new(secret_length, compressor, verbose);
+        victim.set_replacement_policy(replacement);
+        return victim;
+    }
+
+    /// Makes a new victim program running on a cache set with the given number of entries.
+    pub fn new_with_associativity(secret_length: usize, compressor: Compressor, associativity: usize, verbose: bool) -> VictimProgramYACC {
+        return VictimProgramYACC::new_with_geometry(secret_length, compressor, 1, associativity, verbose);
+    }
+
+    /// Makes a new victim program running on a cache of sets sets with ways entries each, as YACC::new_with_geometry.
+    pub fn new_with_geometry(secret_length: usize, compressor: Compressor, sets: usize, ways: usize, verbose: bool) -> VictimProgramYACC {
+        return VictimProgramYACC::new_seeded_with_geometry(secret_length, compressor, sets, ways, random(), verbose);
+    }
+
+    /// Makes a new victim program whose secret, buffer base and later random choices all come from the given seed.
+    pub fn new_seeded(secret_length: usize, compressor: Compressor, seed: u64, verbose: bool) -> VictimProgramYACC {
+        return VictimProgramYACC::new_seeded_with_geometry(secret_length, compressor, 1, ASSOCIATIVITY, seed, verbose);
+    }
+
+    /// Same as new_seeded, on a cache of sets sets with ways entries each.
+    pub fn new_seeded_with_geometry(secret_length: usize, compressor: Compressor, sets: usize, ways: usize, seed: u64, verbose: bool) -> VictimProgramYACC {
+        return VictimProgramYACC::new_seeded_on(secret_length, YACC::new_with_geometry(compressor, sets, ways), seed, verbose);
+    }
+
+    /// Same as new_seeded, on the given (empty) cache: a YACC, or a DISH.
+    pub fn new_seeded_on(secret_length: usize, cache: impl Into<YACC>, seed: u64, verbose: bool) -> VictimProgramYACC {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut secret: Vec<u8> = Vec::new();
         let mut used_bytes: HashSet<u8> = HashSet::new();
-        for i in 0..secret_length {
-            let mut byte: u8 = random();
-            while byte == 0 || used_bytes.contains(&byte) {byte = random();} // Assume the secret has no zero bytes and only unique bytes
+        for _ in 0..secret_length {
+            let mut byte: u8 = rng.gen();
+            while byte == 0 || used_bytes.contains(&byte) {byte = rng.gen();} // Assume the secret has no zero bytes and only unique bytes
             used_bytes.insert(byte);
-            victim.secret.push(byte);
-            victim.cache.write_byte(victim.buffer_base + (BUFFER_SIZE - secret_length + i) as u64, byte);
+            secret.push(byte);
         }
-        if victim.verbose {
-            println!("Victim has picked the following secret: {:X?}", victim.secret);
-        }
-        return victim;
+        return VictimProgramYACC::with_rng(secret, cache.into(), rng, seed, verbose);
     }
 
     /// Makes a new victim program.
-    #[allow(dead_code)]
     pub fn new_with_custom_secret(secret: Vec<u8>, compressor: Compressor, verbose: bool) -> VictimProgramYACC {
+        return VictimProgramYACC::with_secret(secret, YACC::new(compressor, ASSOCIATIVITY), verbose);
+    }
+
+    /// Makes a new victim program holding the given secret, running on the given (empty) cache: a YACC, or a DISH.
+    pub fn with_secret(secret: Vec<u8>, cache: impl Into<YACC>, verbose: bool) -> VictimProgramYACC {
+        return VictimProgramYACC::with_secret_seeded(secret, cache, random(), verbose);
+    }
+
+    /// Same as with_secret, with the victim's random choices (starting with its buffer base) coming from the given seed.
+    pub fn with_secret_seeded(secret: Vec<u8>, cache: impl Into<YACC>, seed: u64, verbose: bool) -> VictimProgramYACC {
+        return VictimProgramYACC::with_rng(secret, cache.into(), StdRng::seed_from_u64(seed), seed, verbose);
+    }
+
+    fn with_rng(secret: Vec<u8>, cache: YACC, mut rng: StdRng, seed: u64, verbose: bool) -> VictimProgramYACC {
         let mut victim = VictimProgramYACC {
-            cache: YACC::new(compressor),
+            cache,
             secret,
-            buffer_base: random::<u64>() & 0x0000FFFF_FFFF0000u64,
-            verbose
+            placement: SecretPlacement::Contiguous,
+            secret_offset: None,
+            buffer_base: rng.gen::<u64>() & 0x0000FFFF_FFFF0000u64,
+            verbose,
+            api_calls: 0,
+            audit_log: None,
+            private_caches: None,
+            interface: VictimInterface::ReadWrite,
+            layout: BufferLayout::Contiguous,
+            cache_stats: VictimCacheStats::default(),
+            access_trace: None,
+            trace_warmup: 0,
+            clock: SimClock::default(),
+            activities: Vec::new(),
+            detector: None,
+            co_tenant: None,
+            background_noise: None,
+            background_accesses: 0,
+            read_only: Vec::new(),
+            compressibility_timeline: None,
+            heap: None,
+            canaries: Vec::new(),
+            uncompressed_region: None,
+            timing_noise: 0.0,
+            miss_counter: MissCounter::default(),
+            rng,
+            noise_rng: RngStream::Noise.rng(seed),
+            co_tenant_rng: RngStream::CoTenant.rng(seed),
+            background_rng: RngStream::Background.rng(seed),
+            access_budget: AccessBudget::default(),
+            rebase_period: None,
+            attacker_operations: 0,
+            relocations: 0,
+            seed
         };
+        victim.cache.reseed(RngStream::Cache.seed(seed));
         for i in 0..victim.secret.len() {
-            victim.cache.write_byte(victim.buffer_base + (BUFFER_SIZE - victim.secret.len() + i) as u64, victim.secret[i]);
+            victim.cache.write_byte(victim.address(victim.secret_byte_offset(i)), victim.secret[i]);
         }
         if victim.verbose {
-            println!("Victim has picked the following secret: {:X?}", victim.secret);
+            println!("Victim has picked the following secret (seed {}): {:X?}", victim.seed, victim.secret);
         }
         return victim;
     }
 
+    /// Returns the master seed every random stream of the victim (and of attacks on it) derives from. Building a victim the
+    /// same way from the same seed replays them.
+    pub fn seed(&self) -> u64 {
+        return self.seed;
+    }
+
+    /// Returns the length of the secret, which the attacker is assumed to know.
+    pub fn secret_size(&self) -> usize {
+        return self.secret.len();
+    }
+
+    /// Limits the attacker to max_writes calls that write to the buffer (byte writes and message stores) and max_reads
+    /// byte reads from now on, after which the victim refuses them. None leaves that kind of call unlimited.
+    pub fn set_access_budget(&mut self, max_writes: Option<u64>, max_reads: Option<u64>) {
+        self.access_budget = AccessBudget {max_writes, max_reads, ..AccessBudget::default()};
+    }
+
+    /// Returns whether the victim has refused a call for going over its access budget.
+    pub fn access_budget_exhausted(&self) -> bool {
+        return self.access_budget.refused > 0;
+    }
+
+    /// Returns the buffer writes and reads the victim has served since its access budget was set.
+    pub fn budget_spent(&self) -> (u64, u64) {
+        return (self.access_budget.writes, self.access_budget.reads);
+    }
+
     /// Writes a byte to the victim's buffer.
-    /// Returns false if the index provided lands out of bounds or on top of the victim's secret.
+    /// Returns false if the index provided lands out of bounds, on top of the victim's secret, or on a read-only byte,
+    /// or if the victim's access budget has run out.
     /// Returns true otherwise, indicating that the write was successful.
     pub fn write_byte(&mut self, index: usize, byte: u8) -> bool {
-        if index >= BUFFER_SIZE - self.secret.len() {return false;}
-        self.cache.write_byte(self.buffer_base + (index as u64), byte);
+        if !self.access_budget.spend_write() || self.is_private(index) || self.is_read_only(index) {
+            self.audit(AuditOp::WRITE, index, Some(byte), false);
+            return false;
+        }
+        self.touch_header();
+        self.store(self.chunk_address(index), byte);
+        self.record_compressibility(index);
+        self.audit(AuditOp::WRITE, index, Some(byte), true);
+        self.count_attacker_operation();
+        return true;
+    }
+
+    /// Copies a whole message into the victim's buffer starting at index, in a single call.
+    /// Returns false (and copies nothing) if any part of the message lands out of bounds or on top of the victim's secret,
+    /// or if the access budget has run out.
+    /// Read-only bytes keep their values, as if the victim filled them in after the copy.
+    pub fn store_message(&mut self, index: usize, message: &[u8]) -> bool {
+        if !self.access_budget.spend_write() || (index..index + message.len()).any(|i| self.is_private(i)) {
+            self.audit(AuditOp::MEMCPY, index, None, false);
+            return false;
+        }
+        self.touch_header();
+        for (i, &byte) in message.iter().enumerate() {
+            if !self.is_read_only(index + i) {self.store(self.chunk_address(index + i), byte);}
+        }
+        self.record_compressibility(index);
+        self.audit(AuditOp::MEMCPY, index, None, true);
+        self.count_attacker_operation();
         return true;
     }
 
     /// Reads a byte from the victim's buffer.
-    /// Returns None if the index provided lands out of bounds or on top of the victim's secret.
+    /// Returns None if the index provided lands out of bounds or on top of the victim's secret, if the victim is store-only,
+    /// or if the access budget has run out.
     /// Returns Some with the data if the index is fine.
     pub fn read_byte(&mut self, index: usize) -> Option<u8> {
-        if index >= BUFFER_SIZE - self.secret.len() {return None;}
-        return Some(self.cache.read_byte(self.buffer_base + index as u64).0);
+        if !self.access_budget.spend_read() || self.is_private(index) || self.interface == VictimInterface::StoreOnly {
+            self.audit(AuditOp::READ, index, None, false);
+            return None;
+        }
+        self.touch_header();
+        let byte = self.load(self.chunk_address(index));
+        self.audit(AuditOp::READ, index, Some(byte), true);
+        self.count_attacker_operation();
+        return Some(byte);
+    }
+
+    /// Returns the address at which the attacker can load a buffer offset itself, as if the buffer were also mapped into
+    /// its address space (shared memory, or a deduplicated page), which Evict+Reload relies on. None for the secret and
+    /// anything else the attacker may not read.
+    pub fn shared_address(&self, index: usize) -> Option<u64> {
+        if self.is_private(index) {return None;}
+        return Some(self.chunk_address(index));
     }
 
-    /// Returns a reference to the cache, for the attacker to use.
+    /// Returns the attacker's port into the cache hierarchy, for the attacker to use.
     /// Note: the attacker cannot read the victim's entries directly.
     /// The attacker can only read and write to the attacker's own address space.
-    pub fn cache(&mut self) -> &mut YACC {return &mut self.cache;}
+    pub fn cache(&mut self) -> AttackerPort<'_> {
+        return AttackerPort {cache: &mut self.cache, private_caches: self.private_caches.as_mut(), clock: &mut self.clock, timing_noise: self.timing_noise, miss_counter: &mut self.miss_counter, rng: &mut self.noise_rng};
+    }
+
+    /// Fills patterned_words randomly chosen non-secret words of the secret line with leftover victim data
+    /// (random non-zero words, stored in the cache's byte order), instead of the zeros the attack's bit budgets assume it
+    /// starts from.
+    pub fn fill_residual_data(&mut self, patterned_words: usize) {
+        let secret_line_start = self.secret_line_start();
+        let endianness = self.endianness();
+        let free_words: Vec<usize> = (0..self.cache.line_size() / 4).filter(|&word| (0..4).all(|i| !self.is_private(secret_line_start + word * 4 + i))).collect();
+        let words: Vec<usize> = free_words.choose_multiple(&mut self.rng, patterned_words).copied().collect();
+        for word in words {
+            let mut value: u32 = self.rng.gen();
+            while value == 0 {value = self.rng.gen();}
+            for (i, byte) in endianness.word_bytes(value.to_le_bytes()).into_iter().enumerate() {
+                self.cache.write_byte(self.address(secret_line_start + word * 4 + i), byte);
+            }
+        }
+    }
+
+    /// Makes the given buffer offsets read-only (such as the fields of a protocol header), with the victim keeping the given
+    /// values there. Attacker writes to them are refused. The offsets and values are assumed to be public.
+    pub fn protect_bytes(&mut self, fields: &[(usize, u8)]) {
+        for &(index, value) in fields {
+            assert!(index < BUFFER_SIZE && !(self.private_start()..self.secret_end()).contains(&index), "Read-only bytes must lie outside the secret");
+            self.cache.write_byte(self.address(index), value);
+            self.read_only.retain(|&(i, _)| i != index);
+            self.read_only.push((index, value));
+        }
+    }
+
+    /// Stores fixed public data (such as a version field) right before the secret, read-only, so that the secret shares its
+    /// line with words the attacker knows but cannot write. The prefix must be made of whole words.
+    pub fn set_public_prefix(&mut self, prefix: &[u8]) {
+        assert!(prefix.len().is_multiple_of(4), "Public prefixes are made of whole words");
+        assert!(self.private_start() >= self.secret_line_start() + prefix.len(), "Public prefixes must fit in the secret line");
+        let start = self.private_start() - prefix.len();
+        let fields: Vec<(usize, u8)> = prefix.iter().enumerate().map(|(i, &value)| (start + i, value)).collect();
+        self.protect_bytes(&fields);
+    }
+
+    /// Returns the read-only offsets of the buffer and the values held there.
+    pub fn read_only_bytes(&self) -> &[(usize, u8)] {
+        return &self.read_only;
+    }
+
+    fn is_read_only(&self, index: usize) -> bool {
+        return self.read_only.iter().any(|&(i, _)| i == index);
+    }
+
+    /// Reports which of the attacker's writable buffer bytes alias the secret's line, superblock and set in the given geometry.
+    pub fn aliasing(&self, geometry: CacheGeometry) -> AliasingReport {
+        return analyze_aliasing(self.buffer_base, self.layout, self.private_start()..self.secret_end(), &self.read_only, geometry);
+    }
+
+    /// Changes which buffer operations the victim offers to the attacker.
+    pub fn set_interface(&mut self, interface: VictimInterface) {
+        self.interface = interface;
+    }
+
+    /// Moves the secret's bytes so that each of its words holds two of them, with the attacker free to write the rest of
+    /// the word (see SecretPlacement::Interleaved). Call this before the attack starts and before clamping the line's
+    /// compressibility. The secret must have an even length and fit in its line twice over.
+    pub fn interleave_secret(&mut self) {
+        assert!(self.placement == SecretPlacement::Contiguous, "The secret is already interleaved");
+        assert!(self.canaries.is_empty(), "Interleave the secret before clamping the line");
+        assert!(self.secret.len().is_multiple_of(2) && 2 * self.secret.len() <= self.cache.line_size(), "Interleaved secrets take an even number of bytes, up to half a line");
+        for i in 0..self.secret.len() {self.cache.write_byte(self.address(self.secret_byte_offset(i)), 0);}
+        self.placement = SecretPlacement::Interleaved;
+        assert!(self.secret_start() / self.cache.line_size() == (self.secret_end() - 1) / self.cache.line_size(), "The interleaved secret does not fit in its line");
+        assert!(self.read_only.iter().all(|&(index, _)| !(self.secret_start()..self.secret_end()).contains(&index)), "Read-only bytes cannot overlap the secret's words");
+        for i in 0..self.secret.len() {self.cache.write_byte(self.address(self.secret_byte_offset(i)), self.secret[i]);}
+    }
+
+    /// Returns where the secret's bytes sit in the secret line, which the attacker is assumed to know.
+    pub fn secret_placement(&self) -> SecretPlacement {
+        return self.placement;
+    }
+
+    /// Moves the secret so that its words start at the given buffer offset instead of ending the buffer, anywhere in the
+    /// superblock as long as they stay within one line (the secret line). The offset must be word-aligned. Call this before
+    /// the attack starts, before interleaving the secret and before clamping or protecting any bytes.
+    pub fn set_secret_offset(&mut self, offset: usize) {
+        assert!(self.canaries.is_empty() && self.read_only.is_empty(), "Move the secret before clamping or protecting any bytes");
+        let span = self.placement.span(self.secret.len());
+        assert!(offset.is_multiple_of(4) && offset + span <= BUFFER_SIZE && offset / self.cache.line_size() == (offset + span - 1) / self.cache.line_size(),
+                "The secret's words must start at a word and stay within one line of the buffer");
+        for i in 0..self.secret.len() {self.cache.write_byte(self.address(self.secret_byte_offset(i)), 0);}
+        self.secret_offset = Some(offset);
+        for i in 0..self.secret.len() {self.cache.write_byte(self.address(self.secret_byte_offset(i)), self.secret[i]);}
+        self.mark_uncompressed_region();
+    }
+
+    /// Returns the buffer offset of the secret's first word (by default, so that the secret ends the buffer), which the
+    /// attacker is assumed to know.
+    pub fn secret_start(&self) -> usize {
+        return self.secret_offset.unwrap_or(BUFFER_SIZE - self.placement.span(self.secret.len()));
+    }
+
+    /// Returns the buffer offset just past the secret's last word.
+    fn secret_end(&self) -> usize {
+        return self.secret_start() + self.placement.span(self.secret.len());
+    }
+
+    /// Returns the buffer offset of the line holding the secret.
+    fn secret_line_start(&self) -> usize {
+        return self.secret_start() / self.cache.line_size() * self.cache.line_size();
+    }
+
+    /// Returns the buffer offset of byte i of the secret.
+    fn secret_byte_offset(&self, i: usize) -> usize {
+        return self.placement.offset(self.secret_start(), i);
+    }
+
+    /// Moves the victim's buffer to a new layout, carrying the secret over (and wiping it from its old place).
+    /// Call this before the attack starts, since the rest of the buffer is not carried over.
+    pub fn set_layout(&mut self, layout: BufferLayout) {
+        if let BufferLayout::Striped {stride, ..} = layout {assert!((1..=MAX_STRIDE).contains(&stride), "Striped buffers need a stride of 1 to MAX_STRIDE");}
+        self.move_buffer(self.buffer_base, layout);
+    }
+
+    /// Gives the attacker a fresh buffer (as reconnecting to a server would), which lands somewhere else in memory and
+    /// so most likely in another cache set. The secret is copied to the same offset in the new buffer; everything else starts out zero.
+    /// If there is a co-tenant, whether it uses the new buffer's set is drawn again.
+    pub fn relocate_buffer(&mut self) {
+        self.relocate();
+        self.audit(AuditOp::RELOCATE, 0, None, true);
+    }
+
+    /// Has the victim re-allocate its buffer at a random new base (as ASLR on every re-allocation would) after every
+    /// operations buffer writes, message stores and reads the attacker makes, wiping whatever the attacker had written.
+    pub fn set_rebase_period(&mut self, operations: u64) {
+        assert!(operations > 0, "Rebase periods must be at least one operation long");
+        self.rebase_period = Some(operations);
+    }
+
+    /// Returns how many times the buffer has moved, whoever moved it.
+    pub fn relocations(&self) -> u64 {
+        return self.relocations;
+    }
+
+    /// Counts a buffer operation the attacker made, relocating the buffer if the rebase period ran out.
+    fn count_attacker_operation(&mut self) {
+        let Some(period) = self.rebase_period else {return};
+        self.attacker_operations += 1;
+        if self.attacker_operations.is_multiple_of(period) {self.relocate();}
+    }
+
+    /// Moves the buffer to a random new base, as relocate_buffer does, but without the attacker asking for it.
+    fn relocate(&mut self) {
+        self.relocations += 1;
+        let base = self.rng.gen::<u64>() & 0x0000FFFF_FFFF0000u64;
+        self.move_buffer(base, self.layout);
+        if let Some(co_tenant) = &mut self.co_tenant {
+            co_tenant.shares_set = self.co_tenant_rng.gen_bool(co_tenant.config.coverage);
+        }
+    }
+
+    /// Moves the secret (and header, if any) to a buffer at base with the given layout, wiping the secret from its old place.
+    /// Read-only bytes and canaries are carried over as well.
+    fn move_buffer(&mut self, base: u64, layout: BufferLayout) {
+        let private_start = self.private_start();
+        let private: Vec<(usize, u8)> = self.canaries.iter().enumerate().map(|(i, &byte)| (private_start + i, byte))
+            .chain(self.secret.iter().enumerate().map(|(i, &byte)| (self.secret_byte_offset(i), byte)))
+            .collect();
+        for &(index, _) in &private {self.cache.write_byte(self.address(index), 0);}
+        self.buffer_base = base;
+        self.layout = layout;
+        for &(index, byte) in &private {self.cache.write_byte(self.address(index), byte);}
+        for &(index, value) in &self.read_only {self.cache.write_byte(self.address(index), value);}
+        self.mark_uncompressed_region();
+        if let BufferLayout::Striped {header: HeaderLine::Random, ..} = layout {
+            let header = self.header_address().unwrap();
+            for i in 0..self.cache.line_size() as u64 {self.cache.write_byte(header + i, self.rng.gen::<u8>() | 1);}
+        }
+    }
+
+    /// Switches the victim to the heap model. The attacker starts out with a chunk somewhere else on the heap,
+    /// and has to allocate new chunks until one lands next to the secret.
+    pub fn enable_heap(&mut self, config: HeapConfig) {
+        assert!((0.0..=1.0).contains(&config.adjacency_probability), "Adjacency probabilities must lie in [0, 1]");
+        self.heap = Some((config, self.rng.gen::<u64>() & 0x0000FFFF_FFFF0000u64));
+    }
+
+    /// Frees the attacker's current chunk and allocates a new, zeroed one. The attacker is not told where it landed.
+    /// Does nothing unless the heap model is enabled.
+    pub fn allocate_chunk(&mut self) {
+        let Some((config, _)) = self.heap else {return};
+        let base = if self.rng.gen_bool(config.adjacency_probability) {self.buffer_base} else {self.rng.gen::<u64>() & 0x0000FFFF_FFFF0000u64};
+        self.heap = Some((config, base));
+        for index in (0..self.private_start()).chain(self.secret_end()..BUFFER_SIZE) {
+            if !self.is_read_only(index) {self.cache.write_byte(self.chunk_address(index), 0);}
+        }
+        self.audit(AuditOp::ALLOCATE, 0, None, true);
+    }
+
+    /// Replaces the 16-byte secret with an AES-style key schedule expanded from a fresh random 4-byte seed, as a victim
+    /// holding round keys would. Seeds are redrawn until the schedule has no zero bytes and only unique bytes.
+    pub fn refresh_key_schedule(&mut self) {
+        assert_eq!(self.secret.len(), keyschedule::SCHEDULE_SIZE, "Key schedules fill a 16-byte secret");
+        let schedule = loop {
+            let schedule = keyschedule::expand(self.rng.gen());
+            let unique: HashSet<u8> = schedule.iter().copied().collect();
+            if unique.len() == schedule.len() && !unique.contains(&0) {break schedule;}
+        };
+        for (i, &byte) in schedule.iter().enumerate() {self.store(self.address(self.secret_byte_offset(i)), byte);}
+        self.secret = schedule.to_vec();
+        if self.verbose {
+            println!("Victim has refreshed its secret to the key schedule {:X?}", self.secret);
+        }
+    }
+
+    /// Replaces the secret with one of the same size drawn from the given distribution, as a victim holding a password
+    /// or an encoded token would. With SecretBytes::UniqueNonZero, the alphabet must have a distinct value for every byte
+    /// of the secret.
+    pub fn redraw_secret(&mut self, distribution: SecretDistribution, bytes: SecretBytes) {
+        let mut alphabet = distribution.alphabet_under(bytes);
+        let secret = match bytes {
+            SecretBytes::UniqueNonZero => {
+                assert!(alphabet.len() >= self.secret.len(), "{:?} has too few values for a {}-byte secret of unique bytes", distribution, self.secret.len());
+                alphabet.shuffle(&mut self.rng);
+                alphabet[..self.secret.len()].to_vec()
+            },
+            SecretBytes::Arbitrary => (0..self.secret.len()).map(|_| alphabet[self.rng.gen_range(0..alphabet.len())]).collect()
+        };
+        for (i, &byte) in secret.iter().enumerate() {self.store(self.address(self.secret_byte_offset(i)), byte);}
+        self.secret = secret;
+        if self.verbose {
+            println!("Victim has redrawn its secret from {:?}: {:X?}", distribution, self.secret);
+        }
+    }
+
+    /// Clamps the compressibility of the secret line, as a software mitigation: before the attacker gets to interact with
+    /// it, the victim fills the words just before the secret with random canary words, which the attacker can neither read
+    /// nor write. Enough canaries are written that the line stays above the cache's double co-allocation threshold whatever
+    /// the attacker writes into the rest of it, even if every secret word compresses against the attacker's data.
+    /// The bound is worked out from C-PACK's pattern sizes, so the cache must compress with C-PACK.
+    /// Call this after the cache's thresholds and ECC overhead are set. Returns the number of canary words written.
+    pub fn clamp_compressibility(&mut self) -> usize {
+        assert!(self.canaries.is_empty(), "The secret line is already clamped");
+        assert!(matches!(self.cache.compressor(), Compressor::CPACK | Compressor::BoundedCPACK {..}), "Clamping bounds C-PACK sizes only");
+        let (double_threshold, _) = self.cache.coallocation_thresholds();
+        let span = self.placement.span(self.secret.len());
+        let secret_words = span.div_ceil(4);
+        let line_words = self.cache.line_size() / 4;
+        // Canaries only match nothing; at best, the secret words are fully matched and the rest are zero.
+        let words = (0..=(self.secret_start() - self.secret_line_start()) / 4)
+            .find(|&k| {
+                let bits = PatternCode::XXXX.bits() * k as u64 + PatternCode::MMMM.bits() * secret_words as u64 + PatternCode::ZZZZ.bits() * (line_words - k - secret_words) as u64;
+                return bits.div_ceil(8) + self.cache.ecc_overhead() > double_threshold;
+            })
+            .expect("The secret line cannot hold enough canaries to stay above the threshold");
+        // Canary bytes are non-zero and distinct from each other and from the secret's bytes, so no two words share a byte.
+        let mut used_bytes: HashSet<u8> = self.secret.iter().copied().collect();
+        let mut canaries = Vec::new();
+        for _ in 0..words * 4 {
+            let mut byte: u8 = self.rng.gen();
+            while byte == 0 || used_bytes.contains(&byte) {byte = self.rng.gen();}
+            used_bytes.insert(byte);
+            canaries.push(byte);
+        }
+        let start = self.secret_start() - canaries.len();
+        assert!(self.read_only.iter().all(|&(index, _)| index < start), "Canaries cannot overlap read-only bytes");
+        for (i, &byte) in canaries.iter().enumerate() {self.store(self.address(start + i), byte);}
+        self.canaries = canaries;
+        return words;
+    }
+
+    /// Marks the memory around the secret line as never compressed, in units of the given granularity, as a defense: the
+    /// secret line then never shares an entry, whatever the attacker writes next to the secret. Coarser units are easier
+    /// to mark (a page table bit instead of a tag bit per line) but take compression away from more of the victim's data.
+    /// The mark follows the buffer when it moves.
+    pub fn enable_uncompressed_region(&mut self, granularity: RegionGranularity) {
+        self.uncompressed_region = Some(granularity);
+        self.mark_uncompressed_region();
+    }
+
+    /// Marks the units of the configured granularity around the secret line as never compressed, unmarking any others.
+    fn mark_uncompressed_region(&mut self) {
+        let Some(granularity) = self.uncompressed_region else {return};
+        let line = self.address(self.secret_line_start());
+        self.cache.clear_uncompressed();
+        let region = granularity.cover(line..line + self.cache.line_size() as u64, &self.cache);
+        self.cache.mark_uncompressed(region);
+    }
+
+    /// Returns the first buffer offset the attacker cannot touch: the start of the canaries, if any, or else of the secret's words.
+    fn private_start(&self) -> usize {
+        return self.secret_start() - self.canaries.len();
+    }
+
+    /// Whether the attacker is kept away from a buffer offset: it is out of bounds, or holds a canary or a secret byte.
+    /// The high halves of an interleaved secret's words are the only offsets between private_start and secret_end the
+    /// attacker may touch.
+    fn is_private(&self, index: usize) -> bool {
+        let interleaved_region = self.secret_start()..self.secret_end();
+        return index >= BUFFER_SIZE || ((self.private_start()..self.secret_end()).contains(&index)
+            && !(self.placement == SecretPlacement::Interleaved && interleaved_region.contains(&index) && index % 4 >= 2));
+    }
+
+    /// Runs a co-tenant process next to the victim. It starts out sharing the victim buffer's set.
+    pub fn add_co_tenant(&mut self, config: CoTenantConfig) {
+        self.co_tenant = Some(CoTenant {config, shares_set: true});
+    }
+
+    /// Returns the current simulated time, as kept by the victim's latency model (by default, one tick per API call),
+    /// plus any time the attacker spent idle.
+    /// The attacker can always read this, just like a real attacker can read a timer.
+    pub fn now(&self) -> u64 {
+        return self.clock.now();
+    }
+
+    /// Lets simulated time pass without any API calls, e.g. while the attacker waits to stay under a detector's radar.
+    /// Scheduled activities that fall due in the meantime still run.
+    pub fn idle(&mut self, ticks: u64) {
+        self.clock.advance(ticks);
+        self.run_due_activities();
+    }
+
+    /// Changes how much simulated time each API call and cache access takes. Call this before the attack starts.
+    pub fn set_latency_model(&mut self, latency: LatencyModel) {
+        self.clock = SimClock::new(latency);
+    }
+
+    /// Makes the attacker misread the timing of each of its loads (a hit as a miss or the other way around) with the
+    /// given probability, as jitter from the rest of the system would.
+    pub fn set_timing_noise(&mut self, probability: f64) {
+        self.timing_noise = probability;
+    }
+
+    /// Makes something else on the attacker's core add a miss to its miss counter, with the given probability each time the
+    /// counter is read.
+    pub fn set_counter_noise(&mut self, probability: f64) {
+        self.miss_counter.noise = probability;
+    }
+
+    /// Returns the latency model the victim's clock runs on.
+    pub fn latency_model(&self) -> LatencyModel {
+        return self.clock.latency();
+    }
+
+    /// Makes the victim's machine do some work every so often, by simulated time rather than by API call.
+    pub fn schedule_activity(&mut self, activity: ScheduledActivity) {
+        assert!(activity.period > 0, "Scheduled activities need a non-zero period");
+        self.activities.push((activity, self.clock.now() + activity.period));
+    }
+
+    /// Runs every scheduled activity that has fallen due, catching up on any periods missed since it last ran.
+    /// The victim only gets to run at its next API call or while the attacker idles, so activities run then.
+    /// Their accesses go straight to the shared cache and take no simulated time, since they happen alongside the attacker.
+    fn run_due_activities(&mut self) {
+        let now = self.clock.now();
+        for i in 0..self.activities.len() {
+            while self.activities[i].1 <= now {
+                let (scheduled, due) = self.activities[i];
+                self.activities[i].1 = due + scheduled.period;
+                match scheduled.activity {
+                    VictimActivity::CoTenantAccess => self.co_tenant_access(),
+                    VictimActivity::ReadSecret => {self.cache.read_byte(self.address(self.secret_line_start()));},
+                    VictimActivity::Relocate => self.relocate()
+                }
+                if let Some(caches) = &mut self.private_caches {caches.back_invalidate(&mut self.cache);}
+            }
+        }
+    }
+
+    /// Gives the co-tenant, if any, its chance to touch one of its lines.
+    fn co_tenant_access(&mut self) {
+        if let Some(co_tenant) = &self.co_tenant {
+            if co_tenant.shares_set && self.co_tenant_rng.gen_bool(co_tenant.config.access_probability) {
+                // The co-tenant's lines sit in the same set as the victim's secret.
+                let set = self.cache.set_index(self.cache.line_of(self.address(self.secret_line_start())));
+                let superblock_bytes = self.cache.superblock_bytes();
+                let superblock = self.cache.superblock_in_set(CO_TENANT_REGION / superblock_bytes, self.co_tenant_rng.gen_range(0..16u64), set);
+                self.cache.read_byte(superblock * superblock_bytes);
+                if let Some(caches) = &mut self.private_caches {caches.back_invalidate(&mut self.cache);}
+            }
+        }
+    }
+
+    /// Has the victim make background accesses to its own data in the secret line's set (see BackgroundNoiseConfig).
+    pub fn enable_background_noise(&mut self, config: BackgroundNoiseConfig) {
+        assert!((0.0..=1.0).contains(&config.access_probability) && (0.0..=1.0).contains(&config.write_fraction), "Background noise rates must lie in [0, 1]");
+        assert!((1..=MAX_BACKGROUND_LINES).contains(&config.lines), "Background accesses spread over 1 to MAX_BACKGROUND_LINES lines");
+        self.background_noise = Some(config);
+    }
+
+    /// Returns the number of background accesses the victim has made so far.
+    pub fn background_accesses(&self) -> u64 {
+        return self.background_accesses;
+    }
+
+    /// Makes a background access, with the configured probability.
+    fn background_access(&mut self) {
+        let Some(noise) = self.background_noise else {return};
+        if !self.background_rng.gen_bool(noise.access_probability) {return;}
+        let set = self.cache.set_index(self.cache.line_of(self.address(self.secret_line_start())));
+        let superblock_bytes = self.cache.superblock_bytes();
+        let superblock = self.cache.superblock_in_set(BACKGROUND_REGION / superblock_bytes, self.background_rng.gen_range(0..noise.lines), set);
+        let address = superblock * superblock_bytes + self.background_rng.gen_range(0..self.cache.line_size() as u64);
+        if self.background_rng.gen_bool(noise.write_fraction) {
+            self.cache.write_byte(address, self.background_rng.gen());
+        } else {
+            self.cache.read_byte(address);
+        }
+        self.background_accesses += 1;
+        if let Some(caches) = &mut self.private_caches {caches.back_invalidate(&mut self.cache);}
+    }
+
+    /// Watches the victim's misses with an anomaly detector.
+    pub fn attach_detector(&mut self, detector: MissRateDetector) {
+        self.detector = Some(detector);
+    }
+
+    /// Returns the attached detector, if any.
+    pub fn detector(&self) -> Option<&MissRateDetector> {
+        return self.detector.as_ref();
+    }
+
+    /// Returns the victim's own load statistics so far.
+    pub fn cache_stats(&self) -> VictimCacheStats {
+        return self.cache_stats;
+    }
+
+    /// Starts recording the victim's own accesses, so that baseline_cache_stats can replay them without an attacker.
+    /// The trace starts with writes that recreate the current buffer contents (secret, header and any residual data).
+    pub fn enable_access_trace(&mut self) {
+        let mut trace: Vec<TraceOp> = Vec::new();
+        let header = self.header_address();
+        let line_bytes = self.cache.line_size() as u64;
+        for addr in (0..BUFFER_SIZE).map(|i| self.address(i)).chain(header.into_iter().flat_map(|h| h..h + line_bytes)) {
+            let byte = self.cache.peek_byte(addr);
+            if byte != 0 {trace.push(TraceOp::Write(addr, byte));}
+        }
+        self.trace_warmup = trace.len();
+        self.access_trace = Some(trace);
+    }
+
+    /// Starts recording every access the cache sees (the victim's and the attacker's) with the state it leaves behind.
+    pub fn enable_cache_trace(&mut self) {
+        self.cache.enable_access_trace();
+    }
+
+    /// Returns and clears the cache accesses recorded since enable_cache_trace.
+    pub fn take_cache_trace(&mut self) -> Vec<AccessRecord> {
+        return self.cache.take_access_trace();
+    }
+
+    /// Replays the victim's recorded accesses on a fresh cache with no attacker (and no private caches),
+    /// giving the hit rate the victim would have seen had it not been attacked. None if tracing was never enabled.
+    pub fn baseline_cache_stats(&self) -> Option<ReplayReport> {
+        return self.access_trace.as_ref().map(|trace| replay(trace, self.cache.compressor(), self.trace_warmup));
+    }
+
+    /// Runs the victim on a cache whose co-allocation thresholds differ from the YACC defaults.
+    /// The attacker is not told; it has to assume them or probe for them.
+    pub fn set_coallocation_thresholds(&mut self, double_bytes: u64, quad_bytes: u64) {
+        self.cache.set_coallocation_thresholds(double_bytes, quad_bytes);
+    }
+
+    /// Runs the victim on a cache with lines of the given size (see YACC::set_line_size). The buffer keeps its contents.
+    pub fn set_line_size(&mut self, bytes: usize) {
+        self.cache.set_line_size(bytes);
+        self.mark_uncompressed_region();
+    }
+
+    /// Runs the victim on a cache whose superblocks hold the given number of blocks (see YACC::set_superblock_blocks).
+    /// Anything the victim had cached is dropped, and brought back in by its next accesses.
+    pub fn set_superblock_blocks(&mut self, blocks: usize) {
+        self.cache.set_superblock_blocks(blocks);
+    }
+
+    /// Runs the victim on a cache that stores the given bytes of ECC with every compressed block, which tightens the
+    /// effective co-allocation thresholds. As with set_coallocation_thresholds, the attacker is not told.
+    pub fn set_ecc_overhead(&mut self, bytes: u64) {
+        self.cache.set_ecc_overhead(bytes);
+    }
+
+    /// Runs the victim on a cache that re-encodes every line it writes back, as a defense. The attacker is not told.
+    pub fn set_reencoding_defense(&mut self, defense: ReencodingDefense) {
+        self.cache.set_reencoding_defense(defense);
+    }
+
+    /// Runs the victim on a cache that maps superblocks to sets through a periodically rekeyed cipher, as a defense.
+    /// The attacker is not told the key, but finds the current mapping as it would with an eviction set search.
+    pub fn enable_randomized_indexing(&mut self, defense: RandomizedIndexing) {
+        self.cache.enable_randomized_indexing(defense);
+    }
+
+    /// Runs the victim on a cache whose compressor reads words in the given byte order.
+    pub fn set_endianness(&mut self, endianness: Endianness) {
+        self.cache.set_endianness(endianness);
+    }
+
+    /// Runs the victim on a cache that only tells compressed sizes apart in coarse, randomly padded buckets, as a defense.
+    pub fn set_size_quantization(&mut self, defense: SizeQuantization) {
+        self.cache.set_size_quantization(defense);
+    }
+
+    /// Runs the victim on a cache that only co-allocates blocks with the given probability, as a defense. The attacker is not told.
+    pub fn set_admission_probability(&mut self, probability: f64) {
+        self.cache.set_admission_probability(probability);
+    }
+
+    /// Runs the victim on a cache with the given replacement policy. The attacker is not told.
+    pub fn set_replacement_policy(&mut self, policy: ReplacementPolicy) {
+        self.cache.set_replacement_policy(policy);
+    }
+
+    /// Changes when the cache writes stores back to memory (see WritePolicy).
+    pub fn set_write_policy(&mut self, policy: WritePolicy) {
+        self.cache.set_write_policy(policy);
+    }
+
+    /// Returns the number of dirty lines the cache has written back to memory so far.
+    pub fn writebacks(&self) -> u64 {
+        return self.cache.writebacks();
+    }
+
+    /// Runs the victim on a cache where every store also brings in the neighboring line (see YACC::enable_store_prefetch).
+    pub fn enable_store_prefetch(&mut self) {
+        self.cache.enable_store_prefetch();
+    }
+
+    /// Puts the victim and the attacker on separate cores with private L1s in front of the shared compressed cache.
+    pub fn enable_cross_core(&mut self, config: CrossCoreConfig) {
+        if config.inclusive {self.cache.track_evictions();}
+        self.private_caches = Some(PrivateCaches {
+            victim_l1: PrivateCache::new(config.l1_lines),
+            attacker_l1: PrivateCache::new(config.l1_lines),
+            inclusive: config.inclusive
+        });
+    }
+
+    /// Returns the physical address of a byte of the buffer.
+    fn address(&self, index: usize) -> u64 {
+        return buffer_address(self.buffer_base, self.layout, index);
+    }
+
+    /// Returns the physical address that a buffer offset passed in by the attacker refers to: the buffer itself, or with
+    /// the heap model, the attacker's current chunk (which may or may not be next to the secret).
+    fn chunk_address(&self, index: usize) -> u64 {
+        return match self.heap {
+            Some((_, base)) => buffer_address(base, self.layout, index),
+            None => self.address(index)
+        };
+    }
+
+    /// Returns the address of the header the victim reads on every API call, if it has one.
+    fn header_address(&self) -> Option<u64> {
+        return match self.layout {
+            BufferLayout::Striped {header: HeaderLine::Zeros | HeaderLine::Random, ..} => Some(self.address(self.secret_line_start()) & !0xFF),
+            _ => None
+        };
+    }
+
+    /// Reads the victim's header, if it has one, as the victim does at the start of every API call.
+    fn touch_header(&mut self) {
+        if let Some(header) = self.header_address() {self.load(header);}
+    }
+
+    /// Loads a byte on the victim's core, going through its private cache if it has one.
+    fn load(&mut self, byte_addr: u64) -> u8 {
+        if let Some(trace) = &mut self.access_trace {trace.push(TraceOp::Read(byte_addr));}
+        self.cache_stats.loads += 1;
+        if let Some(caches) = &mut self.private_caches {
+            if caches.victim_l1.access(self.cache.line_of(byte_addr)) == AccessSpeed::HIT {
+                self.clock.load(AccessSpeed::HIT);
+                self.cache_stats.load_hits += 1;
+                return self.cache.peek_byte(byte_addr);
+            }
+        }
+        let (byte, speed, cycles) = self.cache.read_byte_timed(byte_addr);
+        self.clock.load_timed(speed, cycles);
+        if speed == AccessSpeed::HIT {
+            self.cache_stats.load_hits += 1;
+        } else if let Some(detector) = &mut self.detector {
+            detector.observe_miss(self.clock.now());
+        }
+        if let Some(caches) = &mut self.private_caches {caches.back_invalidate(&mut self.cache);}
+        return byte;
+    }
+
+    /// Stores a byte on the victim's core. Private caches are write-through, so the store always reaches the shared cache.
+    fn store(&mut self, byte_addr: u64, byte: u8) {
+        if let Some(trace) = &mut self.access_trace {trace.push(TraceOp::Write(byte_addr, byte));}
+        self.cache.write_byte(byte_addr, byte);
+        self.clock.store();
+        if let Some(caches) = &mut self.private_caches {
+            caches.back_invalidate(&mut self.cache);
+            caches.victim_l1.access(self.cache.line_of(byte_addr));
+        }
+    }
 
     /// Prints out the compressibility of the secret line to the console.
     /// This is purely for debugging and not used by the attack algorithm.
-    #[allow(dead_code)]
+    #[cfg(not(feature = "no-oracle"))]
     pub fn print_compressibility(&self) {
-        let c = self.cache.compress_bits((self.buffer_base >> 6) + 3);
+        let c = self.cache.compress_bits(self.cache.line_of(self.address(self.secret_line_start())));
         println!("Secret line compressibility: {} bits or {} bytes", c, (c + 7) >> 3);
+        let patterns = cpack_words(&self.endianness().line_bytes(self.cache.peek_line(self.cache.line_of(self.address(self.secret_line_start())))));
+        let breakdown: Vec<String> = patterns.iter().enumerate().map(|(i, p)| format!("{}:{:?}({})", i, p, p.bits())).collect();
+        println!("Secret line C-PACK patterns (word:pattern(bits)): {}", breakdown.join(" "));
     }
 
     /// Prints out the secret line.
     /// This is purely for debugging and not used by the attack algorithm.
-    #[allow(dead_code)]
+    #[cfg(not(feature = "no-oracle"))]
     pub fn print_secret_line(&self) {
-        println!("Secret line: {:X?}", self.cache.peek_line((self.buffer_base >> 6) + 3));
+        println!("Secret line: {:X?}", self.cache.peek_line(self.cache.line_of(self.address(self.secret_line_start()))));
+    }
+
+    /// Returns the secret itself. Only for instrumentation that labels what the attack observed against the truth
+    /// (and the cheating baseline attacker); a real attack must never use it to make decisions.
+    #[cfg(not(feature = "no-oracle"))]
+    pub fn ground_truth_secret(&self) -> &[u8] {
+        return &self.secret;
+    }
+
+    /// Returns the words of the secret line that hold the secret, as they sit in memory right now (for an
+    /// interleaved secret, with whatever the attacker wrote into their high halves). Like ground_truth_secret, this is
+    /// for instrumentation only.
+    #[cfg(not(feature = "no-oracle"))]
+    pub fn ground_truth_words(&self) -> Vec<[u8; 4]> {
+        let line = self.cache.peek_line(self.cache.line_of(self.address(self.secret_line_start())));
+        let start = self.secret_start() - self.secret_line_start();
+        return line[start..start + self.placement.span(self.secret.len())].chunks_exact(4).map(|word| word.try_into().unwrap()).collect();
+    }
+
+    /// Returns the secret line's compressed size in bits, straight from the compressor.
+    /// Like ground_truth_secret, this is for instrumentation only.
+    #[cfg(not(feature = "no-oracle"))]
+    pub fn secret_line_bits(&self) -> u64 {
+        return self.cache.compress_bits(self.cache.line_of(self.address(self.secret_line_start())));
+    }
+
+    /// Returns how much memory the victim and its cache hold on to right now, logs and traces included.
+    pub fn footprint(&self) -> Footprint {
+        let cache = self.cache.footprint();
+        let logs = self.audit_log.as_ref().map_or(0, Vec::capacity) + self.access_trace.as_ref().map_or(0, Vec::capacity)
+            + self.compressibility_timeline.as_ref().map_or(0, Vec::capacity);
+        let state = self.secret.capacity() + self.activities.capacity() + self.read_only.capacity() + self.canaries.capacity();
+        return Footprint {memory_lines: cache.memory_lines, reserved_slots: cache.reserved_slots + logs + state};
+    }
+
+    /// Checks the cache's invariants (see YACC::check_invariants), and that memory still holds the secret and canaries
+    /// where the victim put them. Returns the first inconsistency found, without revealing any secret bytes.
+    pub fn check_invariants(&self) -> Result<(), String> {
+        self.cache.check_invariants()?;
+        let stored = |index: usize| self.cache.peek_byte(self.address(index));
+        if let Some(i) = (0..self.secret.len()).find(|&i| stored(self.secret_byte_offset(i)) != self.secret[i]) {
+            return Err(format!("secret byte {} no longer matches memory", i));
+        }
+        let canary_start = self.private_start();
+        if let Some(i) = (0..self.canaries.len()).find(|&i| stored(canary_start + i) != self.canaries[i]) {
+            return Err(format!("canary {} no longer matches memory", i));
+        }
+        return Ok(());
+    }
+
+    /// Returns the index of the shared cache set holding a buffer index's line. The attacker is assumed to know it, as it
+    /// would after finding the set with an eviction set search (or from the buffer's page offset, for small caches).
+    pub fn buffer_set(&self, index: usize) -> usize {
+        return self.cache.set_index(self.cache.line_of(self.address(index)));
+    }
+
+    /// Returns the size of the shared cache's superblocks in bytes, which the attacker is assumed to know.
+    pub fn superblock_bytes(&self) -> u64 {
+        return self.cache.superblock_bytes();
+    }
+
+    /// Returns the shared cache's line size in bytes, which the attacker is assumed to know.
+    pub fn line_size(&self) -> usize {
+        return self.cache.line_size();
+    }
+
+    /// Returns the address of the line holding a byte, in the shared cache's line size.
+    pub fn line_of(&self, byte_addr: u64) -> u64 {
+        return self.cache.line_of(byte_addr);
+    }
+
+    /// Returns the byte order the shared cache's compressor reads words in, which the attacker is assumed to know (it is
+    /// the target architecture's).
+    pub fn endianness(&self) -> Endianness {
+        return self.cache.endianness();
+    }
+
+    /// Returns how many words the shared cache's C-PACK dictionary holds if it is bounded, which the attacker is assumed
+    /// to know, as it knows the line size.
+    pub fn dictionary_entries(&self) -> Option<usize> {
+        return self.cache.compressor().dictionary_entries();
+    }
+
+    /// Returns every way of the set holding a buffer index's line, as YACC::way_states does.
+    /// Like ground_truth_secret, this is for instrumentation only.
+    #[cfg(not(feature = "no-oracle"))]
+    pub fn way_states(&self, index: usize) -> Vec<WayState> {
+        return self.cache.way_states(self.cache.set_index(self.cache.line_of(self.address(index))));
+    }
+
+    /// Returns the address of the line holding a buffer index, which the attacker does not know. For instrumentation only.
+    #[cfg(not(feature = "no-oracle"))]
+    pub fn line_address(&self, index: usize) -> u64 {
+        return self.cache.line_of(self.address(index));
     }
 
     /// Returns whether or not a guess matches the victim's secret.
     /// This function should only be called when the attacker knows the victim's secret.
-    pub fn validate_secret(&self, guess: &Vec<u8>) -> bool {
-        for i in 0..self.secret.len() {
-            if let Some(&b) = guess.get(i) {
-                if b != self.secret[i] {return false;}
-            } else {return false;}
+    #[cfg(not(feature = "no-oracle"))]
+    pub fn validate_secret(&mut self, guess: &[u8]) -> bool {
+        let correct = guess.len() >= self.secret.len() && guess[..self.secret.len()] == self.secret[..];
+        self.audit(AuditOp::VALIDATE, 0, None, correct);
+        return correct;
+    }
+
+    /// Ends the victim's life and hands its secret to a judge, which scores attacks after the fact.
+    /// Attack code only ever borrows the victim, so it cannot reach the judge of the victim it is attacking;
+    /// this is the only way to learn the secret in a no-oracle build.
+    pub fn into_judge(self) -> Judge {
+        return Judge {secret: self.secret};
+    }
+
+    /// Starts recording every attacker-facing API call into the audit log (off by default, since it grows with every call).
+    pub fn enable_audit_log(&mut self) {
+        if self.audit_log.is_none() {self.audit_log = Some(Vec::new());}
+    }
+
+    /// Returns the audit log, or an empty slice if audit logging was never enabled.
+    pub fn audit_log(&self) -> &[AuditEntry] {
+        return self.audit_log.as_deref().unwrap_or(&[]);
+    }
+
+    /// Starts recording the secret line's compressed size after every attacker write (off by default, like the audit log).
+    pub fn enable_compressibility_timeline(&mut self) {
+        if self.compressibility_timeline.is_none() {self.compressibility_timeline = Some(Vec::new());}
+    }
+
+    /// Returns the compressibility timeline, or an empty slice if it was never enabled.
+    pub fn compressibility_timeline(&self) -> &[TimelinePoint] {
+        return self.compressibility_timeline.as_deref().unwrap_or(&[]);
+    }
+
+    /// Writes the compressibility timeline as CSV (timestamp,offset,compressed_bits,compressed_bytes,fits_double).
+    pub fn export_compressibility_timeline(&self, out: &mut impl Write) -> std::io::Result<()> {
+        writeln!(out, "timestamp,offset,compressed_bits,compressed_bytes,fits_double")?;
+        for point in self.compressibility_timeline() {
+            writeln!(out, "{},{},{},{},{}", point.timestamp, point.offset, point.compressed_bits, point.compressed_bits.div_ceil(8), point.fits_double)?;
+        }
+        Ok(())
+    }
+
+    /// Adds the secret line's current compressed size to the timeline, if it is being recorded.
+    fn record_compressibility(&mut self, offset: usize) {
+        if self.compressibility_timeline.is_none() {return;}
+        let line = self.cache.line_of(self.address(self.secret_line_start()));
+        let point = TimelinePoint {
+            timestamp: self.api_calls,
+            offset,
+            compressed_bits: self.cache.compress_bits(line),
+            fits_double: self.cache.fits_double(line)
+        };
+        self.compressibility_timeline.as_mut().unwrap().push(point);
+    }
+
+    /// Writes the audit log as CSV (timestamp,op,offset,value,accepted). Missing values are left blank.
+    pub fn export_audit_log(&self, out: &mut impl Write) -> std::io::Result<()> {
+        writeln!(out, "timestamp,op,offset,value,accepted")?;
+        for entry in self.audit_log() {
+            let value = entry.value.map(|x| x.to_string()).unwrap_or_default();
+            writeln!(out, "{},{:?},{},{},{}", entry.timestamp, entry.op, entry.offset, value, entry.accepted)?;
+        }
+        Ok(())
+    }
+
+    /// Advances the API call counter and the clock, and logs the call if auditing is enabled.
+    /// Also gives the co-tenant and the victim's background work their chance to run, along with any scheduled
+    /// activities that fell due.
+    fn audit(&mut self, op: AuditOp, offset: usize, value: Option<u8>, accepted: bool) {
+        let timestamp = self.api_calls;
+        self.api_calls += 1;
+        self.clock.call();
+        self.co_tenant_access();
+        self.background_access();
+        self.run_due_activities();
+        if let Some(log) = &mut self.audit_log {
+            log.push(AuditEntry {timestamp, op, offset, value, accepted});
         }
-        return true;
     }
 }
\ No newline at end of file